@@ -0,0 +1,170 @@
+//! Internationalized domain names.
+//!
+//! DNS, the `Host` header and SNI only carry ASCII, so a hostname such as
+//! `bücher.example` is sent as its punycode form, `xn--bcher-kva.example`,
+//! as RFC 3490 and RFC 3492 describe. Labels are lowercased, but not
+//! otherwise mapped.
+use std::num::Int;
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+/// Convert a domain to ASCII, encoding each label that is not ASCII with
+/// punycode and the `xn--` prefix.
+///
+/// Labels may also be separated by the dots of other scripts, such as `。`.
+///
+/// ```
+/// use hyper::client::idna::domain_to_ascii;
+///
+/// assert_eq!(domain_to_ascii("Bücher.example").unwrap()[], "xn--bcher-kva.example");
+/// assert_eq!(domain_to_ascii("example.domain").unwrap()[], "example.domain");
+/// ```
+pub fn domain_to_ascii(domain: &str) -> Option<String> {
+    let mut ascii = String::with_capacity(domain.len());
+    for (i, label) in domain.split(['.', '。', '．', '｡'][]).enumerate() {
+        if i > 0 {
+            ascii.push('.');
+        }
+        let label: Vec<char> = label.chars().map(|c| c.to_lowercase()).collect();
+        if label.iter().all(|&c| (c as u32) < 0x80) {
+            ascii.extend(label.into_iter());
+        } else {
+            match punycode_encode(label[]) {
+                Some(encoded) => {
+                    ascii.push_str("xn--");
+                    ascii.push_str(encoded[]);
+                },
+                None => return None
+            }
+        }
+    }
+    Some(ascii)
+}
+
+/// Convert the host of an absolute URL, or of a reference starting with
+/// `//`, to ASCII.
+///
+/// Anything else, or a host that can't be converted, is returned as it is,
+/// for the URL parser to reject.
+pub fn url_to_ascii(url: &str) -> String {
+    if url.bytes().all(|b| b < 0x80) {
+        return url.to_string();
+    }
+    let start = match url.find_str("://") {
+        Some(i) if !url[..i].contains("/") => i + 3,
+        _ if url.starts_with("//") => 2,
+        _ => return url.to_string()
+    };
+    let end = url[start..].find(['/', '?', '#'][]).map_or(url.len(), |i| start + i);
+    let host_start = url[start..end].rfind('@').map_or(start, |i| start + i + 1);
+    let host_end = url[host_start..end].find(':').map_or(end, |i| host_start + i);
+
+    match domain_to_ascii(url[host_start..host_end]) {
+        Some(host) => format!("{}{}{}", url[..host_start], host, url[host_end..]),
+        None => url.to_string()
+    }
+}
+
+/// Encode a label with punycode, per RFC 3492.
+fn punycode_encode(input: &[char]) -> Option<String> {
+    let mut output: String = input.iter().map(|&c| c).filter(|&c| (c as u32) < 0x80).collect();
+    let basic = output.len() as u32;
+    if basic > 0 {
+        output.push('-');
+    }
+
+    let mut n = INITIAL_N;
+    let mut delta = 0u32;
+    let mut bias = INITIAL_BIAS;
+    let mut handled = basic;
+    while (handled as uint) < input.len() {
+        // the smallest code point not handled yet
+        let m = input.iter().map(|&c| c as u32).filter(|&c| c >= n).min().unwrap();
+        delta = match (m - n).checked_mul(handled + 1).and_then(|d| d.checked_add(delta)) {
+            Some(delta) => delta,
+            None => return None
+        };
+        n = m;
+        for &c in input.iter() {
+            let c = c as u32;
+            if c < n {
+                delta = match delta.checked_add(1) {
+                    Some(delta) => delta,
+                    None => return None
+                };
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias {
+                        T_MIN
+                    } else if k >= bias + T_MAX {
+                        T_MAX
+                    } else {
+                        k - bias
+                    };
+                    if q < t {
+                        break;
+                    }
+                    output.push(digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(output)
+}
+
+fn adapt(delta: u32, points: u32, first: bool) -> u32 {
+    let mut delta = if first { delta / DAMP } else { delta / 2 };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn digit(d: u32) -> char {
+    match d {
+        0...25 => (b'a' + d as u8) as char,
+        _ => (b'0' + (d - 26) as u8) as char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{domain_to_ascii, url_to_ascii};
+
+    #[test]
+    fn test_domain_to_ascii() {
+        assert_eq!(domain_to_ascii("bücher").unwrap()[], "xn--bcher-kva");
+        assert_eq!(domain_to_ascii("München.example").unwrap()[], "xn--mnchen-3ya.example");
+        assert_eq!(domain_to_ascii("例え。テスト").unwrap()[], "xn--r8jz45g.xn--zckzah");
+        assert_eq!(domain_to_ascii("Example.Domain").unwrap()[], "example.domain");
+    }
+
+    #[test]
+    fn test_url_to_ascii() {
+        assert_eq!(url_to_ascii("http://user@bücher.example:8080/ü?q=ü")[],
+                   "http://user@xn--bcher-kva.example:8080/ü?q=ü");
+        assert_eq!(url_to_ascii("//bücher.example/path")[], "//xn--bcher-kva.example/path");
+        assert_eq!(url_to_ascii("/path?next=http://bücher")[], "/path?next=http://bücher");
+        assert_eq!(url_to_ascii("http://example.domain/")[], "http://example.domain/");
+    }
+}
@@ -22,22 +22,24 @@ use std::io::IoResult;
 use std::io::util::copy;
 use std::iter::Extend;
 
-use url::UrlParser;
 use url::ParseError as UrlError;
 
 use openssl::ssl::VerifyCallback;
 
 use header::{Headers, Header, HeaderFormat};
 use header::common::{ContentLength, Location};
+use http::{ChunkLimits, ParseMode};
 use method::Method;
 use net::{NetworkConnector, NetworkStream, HttpConnector};
 use status::StatusClass::Redirection;
+use uri::resolve;
 use {Url, Port, HttpResult};
 use HttpError::HttpUriError;
 
 pub use self::request::Request;
-pub use self::response::Response;
+pub use self::response::{InterimCallback, Response};
 
+pub mod idna;
 pub mod request;
 pub mod response;
 
@@ -47,6 +49,9 @@ pub mod response;
 pub struct Client<C> {
     connector: C,
     redirect_policy: RedirectPolicy,
+    parse_mode: ParseMode,
+    interim_callback: Option<InterimCallback>,
+    chunk_limits: ChunkLimits,
 }
 
 impl Client<HttpConnector> {
@@ -69,7 +74,10 @@ impl<C: NetworkConnector<S>, S: NetworkStream> Client<C> {
     pub fn with_connector(connector: C) -> Client<C> {
         Client {
             connector: connector,
-            redirect_policy: Default::default()
+            redirect_policy: Default::default(),
+            parse_mode: Default::default(),
+            interim_callback: None,
+            chunk_limits: Default::default()
         }
     }
 
@@ -78,6 +86,30 @@ impl<C: NetworkConnector<S>, S: NetworkStream> Client<C> {
         self.redirect_policy = policy;
     }
 
+    /// Set how strictly responses are parsed.
+    ///
+    /// `ParseMode::Lenient` accepts what servers commonly get wrong, such as
+    /// status lines without a reason phrase. Responses are parsed strictly
+    /// by default.
+    pub fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.parse_mode = mode;
+    }
+
+    /// Call `callback` for each informational response, such as `103 Early
+    /// Hints`, that a server sends before the final one.
+    pub fn set_interim_callback(&mut self, callback: InterimCallback) {
+        self.interim_callback = Some(callback);
+    }
+
+    /// Set how large the chunks of a chunked response body may be, and how
+    /// much they may add up to.
+    ///
+    /// By default, a chunk may be up to `http::DEFAULT_MAX_CHUNK_SIZE`, and
+    /// there is no limit on the whole body.
+    pub fn set_chunk_limits(&mut self, limits: ChunkLimits) {
+        self.chunk_limits = limits;
+    }
+
     /// Execute a Get request.
     pub fn get<U: IntoUrl>(&mut self, url: U) -> RequestBuilder<U, C, S> {
         self.request(Method::Get, url)
@@ -112,6 +144,7 @@ impl<C: NetworkConnector<S>, S: NetworkStream> Client<C> {
             url: url,
             body: None,
             headers: None,
+            asterisk: false,
         }
     }
 }
@@ -126,6 +159,7 @@ pub struct RequestBuilder<'a, U: IntoUrl, C: NetworkConnector<S> + 'a, S: Networ
     headers: Option<Headers>,
     method: Method,
     body: Option<Body<'a>>,
+    asterisk: bool,
 }
 
 impl<'a, U: IntoUrl, C: NetworkConnector<S>, S: NetworkStream> RequestBuilder<'a, U, C, S> {
@@ -158,9 +192,16 @@ impl<'a, U: IntoUrl, C: NetworkConnector<S>, S: NetworkStream> RequestBuilder<'a
         self
     }
 
+    /// Send `*` as the request target, for an `OPTIONS` request about the
+    /// whole server.
+    pub fn asterisk(mut self) -> RequestBuilder<'a, U, C, S> {
+        self.asterisk = true;
+        self
+    }
+
     /// Execute this request and receive a Response back.
     pub fn send(self) -> HttpResult<Response> {
-        let RequestBuilder { client, method, url, headers, body } = self;
+        let RequestBuilder { client, method, url, headers, body, asterisk } = self;
         let mut url = try!(url.into_url());
         debug!("client.request {} {}", method, url);
 
@@ -178,6 +219,13 @@ impl<'a, U: IntoUrl, C: NetworkConnector<S>, S: NetworkStream> RequestBuilder<'a
         loop {
             let mut req = try!(Request::with_connector(method.clone(), url.clone(), &mut client.connector));
             headers.as_ref().map(|headers| req.headers_mut().extend(headers.iter()));
+            req.set_parse_mode(client.parse_mode);
+            if asterisk {
+                req.set_asterisk_target();
+            }
+            if let Some(callback) = client.interim_callback {
+                req.set_interim_callback(callback);
+            }
 
             match (can_have_body, body.as_ref()) {
                 (true, Some(ref body)) => match body.size() {
@@ -189,7 +237,8 @@ impl<'a, U: IntoUrl, C: NetworkConnector<S>, S: NetworkStream> RequestBuilder<'a
             }
             let mut streaming = try!(req.start());
             body.take().map(|mut rdr| copy(&mut rdr, &mut streaming));
-            let res = try!(streaming.send());
+            let mut res = try!(streaming.send());
+            res.set_chunk_limits(client.chunk_limits);
             if res.status.class() != Redirection {
                 return Ok(res)
             }
@@ -199,7 +248,8 @@ impl<'a, U: IntoUrl, C: NetworkConnector<S>, S: NetworkStream> RequestBuilder<'a
                 // punching borrowck here
                 let loc = match res.headers.get::<Location>() {
                     Some(&Location(ref loc)) => {
-                        Some(UrlParser::new().base_url(&url).parse(loc[]))
+                        // a relative Location is relative to the URL requested
+                        Some(resolve(&url, idna::url_to_ascii(loc[])[]))
                     }
                     None => {
                         debug!("no Location header");
@@ -309,8 +359,9 @@ impl IntoUrl for Url {
 }
 
 impl<'a> IntoUrl for &'a str {
+    /// A hostname that is not ASCII is converted to punycode.
     fn into_url(self) -> Result<Url, UrlError> {
-        Url::parse(self)
+        Url::parse(idna::url_to_ascii(self)[])
     }
 }
 
@@ -355,7 +406,7 @@ fn get_host_and_port(url: &Url) -> HttpResult<(String, Port)> {
 #[cfg(test)]
 mod tests {
     use header::common::Server;
-    use super::{Client, RedirectPolicy};
+    use super::{Client, IntoUrl, RedirectPolicy};
     use url::Url;
 
     mock_connector!(MockRedirectPolicy {
@@ -375,6 +426,18 @@ mod tests {
                                     "
     });
 
+    mock_connector!(MockRelativeRedirect {
+        "http://127.0.0.4" =>       "HTTP/1.1 302 Found\r\n\
+                                     Location: //127.0.0.5/next\r\n\
+                                     Server: mock4\r\n\
+                                     \r\n\
+                                    "
+        "http://127.0.0.5" =>       "HTTP/1.1 200 OK\r\n\
+                                     Server: mock5\r\n\
+                                     \r\n\
+                                    "
+    });
+
     #[test]
     fn test_redirect_followall() {
         let mut client = Client::with_connector(MockRedirectPolicy);
@@ -403,4 +466,19 @@ mod tests {
         assert_eq!(res.headers.get(), Some(&Server("mock2".to_string())));
     }
 
+    #[test]
+    fn test_idna_into_url() {
+        let url = "http://user@Bücher.example:8080/ü".into_url().unwrap();
+        assert_eq!(url.domain(), Some("xn--bcher-kva.example"));
+        assert_eq!(url.port(), Some(8080));
+    }
+
+    #[test]
+    fn test_redirect_relative() {
+        let mut client = Client::with_connector(MockRelativeRedirect);
+        client.set_redirect_policy(RedirectPolicy::FollowAll);
+        let res = client.get("http://127.0.0.4/start").send().unwrap();
+        assert_eq!(res.headers.get(), Some(&Server("mock5".to_string())));
+    }
+
 }
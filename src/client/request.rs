@@ -1,5 +1,5 @@
 //! Client Requests
-use std::io::{BufferedWriter, IoResult};
+use std::io::{BufferedWriter, IoError, IoResult, InvalidInput};
 
 use url::Url;
 
@@ -8,11 +8,12 @@ use method::Method::{Get, Post, Delete, Put, Patch, Head, Options};
 use header::Headers;
 use header::common::{mod, Host};
 use net::{NetworkStream, NetworkConnector, HttpConnector, Fresh, Streaming};
-use http::{HttpWriter, LINE_ENDING};
+use http::{HttpWriter, ParseMode, LINE_ENDING};
 use http::HttpWriter::{ThroughWriter, ChunkedWriter, SizedWriter, EmptyWriter};
 use version;
 use HttpResult;
-use client::{Response, get_host_and_port};
+use HttpError::HttpIoError;
+use client::{InterimCallback, Response, get_host_and_port};
 
 
 /// A client request to a remote server.
@@ -26,6 +27,9 @@ pub struct Request<W> {
     body: HttpWriter<BufferedWriter<Box<NetworkStream + Send>>>,
     headers: Headers,
     method: method::Method,
+    parse_mode: ParseMode,
+    asterisk: bool,
+    interim_callback: Option<InterimCallback>,
 }
 
 impl<W> Request<W> {
@@ -54,17 +58,19 @@ impl Request<Fresh> {
         let stream = ThroughWriter(BufferedWriter::new(box stream as Box<NetworkStream + Send>));
 
         let mut headers = Headers::new();
-        headers.set(Host {
-            hostname: host,
-            port: Some(port),
-        });
+        if let Some(host) = Host::from_url(&url) {
+            headers.set(host);
+        }
 
         Ok(Request {
             method: method,
             headers: headers,
             url: url,
             version: version::HttpVersion::Http11,
-            body: stream
+            body: stream,
+            parse_mode: ParseMode::Strict,
+            asterisk: false,
+            interim_callback: None
         })
     }
 
@@ -105,14 +111,35 @@ impl Request<Fresh> {
 
     /// Consume a Fresh Request, writing the headers and method,
     /// returning a Streaming Request.
+    ///
+    /// A body without a `Content-Length` is sent chunked, which is an error
+    /// for versions without chunks, such as HTTP/1.0.
     pub fn start(mut self) -> HttpResult<Request<Streaming>> {
-        let mut uri = self.url.serialize_path().unwrap();
-        //TODO: this needs a test
-        if let Some(ref q) = self.url.query {
-            uri.push('?');
-            uri.push_str(q[]);
+        let has_body = match self.method {
+            Get | Head => false,
+            _ => true
+        };
+        if has_body && !self.version.supports_chunked() &&
+                !self.headers.has::<common::ContentLength>() {
+            return Err(HttpIoError(IoError {
+                kind: InvalidInput,
+                desc: "A body without a Content-Length can't be chunked in this version",
+                detail: Some(self.version.to_string())
+            }));
         }
 
+        let uri = if self.asterisk {
+            "*".to_string()
+        } else {
+            let mut uri = self.url.serialize_path().unwrap();
+            //TODO: this needs a test
+            if let Some(ref q) = self.url.query {
+                uri.push('?');
+                uri.push_str(q[]);
+            }
+            uri
+        };
+
         debug!("writing head: {} {} {}", self.method, uri, self.version);
         try!(write!(&mut self.body, "{} {} {}", self.method, uri, self.version));
         try!(self.body.write(LINE_ENDING));
@@ -121,7 +148,7 @@ impl Request<Fresh> {
         let stream = match self.method {
             Get | Head => {
                 debug!("headers [\n{}]", self.headers);
-                try!(write!(&mut self.body, "{}", self.headers));
+                try!(self.headers.write_to(&mut self.body));
                 try!(self.body.write(LINE_ENDING));
                 EmptyWriter(self.body.unwrap())
             },
@@ -155,7 +182,7 @@ impl Request<Fresh> {
                 }
 
                 debug!("headers [\n{}]", self.headers);
-                try!(write!(&mut self.body, "{}", self.headers));
+                try!(self.headers.write_to(&mut self.body));
                 try!(self.body.write(LINE_ENDING));
 
                 if chunked {
@@ -171,13 +198,36 @@ impl Request<Fresh> {
             headers: self.headers,
             url: self.url,
             version: self.version,
-            body: stream
+            body: stream,
+            parse_mode: self.parse_mode,
+            asterisk: self.asterisk,
+            interim_callback: self.interim_callback
         })
     }
 
     /// Get a mutable reference to the Request headers.
     #[inline]
     pub fn headers_mut(&mut self) -> &mut Headers { &mut self.headers }
+
+    /// Set how strictly the response is parsed. The default is
+    /// `ParseMode::Strict`.
+    pub fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.parse_mode = mode;
+    }
+
+    /// Send `*` as the request target, instead of the path of the url.
+    ///
+    /// This asks about the server as a whole, and is only meaningful for
+    /// `OPTIONS` requests, as in `OPTIONS * HTTP/1.1`.
+    pub fn set_asterisk_target(&mut self) {
+        self.asterisk = true;
+    }
+
+    /// Call `callback` for each informational response, such as `103 Early
+    /// Hints`, that the server sends before the final one.
+    pub fn set_interim_callback(&mut self, callback: InterimCallback) {
+        self.interim_callback = Some(callback);
+    }
 }
 
 impl Request<Streaming> {
@@ -186,7 +236,7 @@ impl Request<Streaming> {
     /// Consumes the Request.
     pub fn send(self) -> HttpResult<Response> {
         let raw = try!(self.body.end()).into_inner();
-        Response::new(raw)
+        Response::with_interim_callback(raw, self.parse_mode, self.interim_callback)
     }
 }
 
@@ -207,7 +257,9 @@ mod tests {
     use std::boxed::BoxAny;
     use std::str::from_utf8;
     use url::Url;
-    use method::Method::{Get, Head};
+    use header::common::{ContentLength, Host};
+    use method::Method::{Get, Head, Options, Post};
+    use version::HttpVersion::Http10;
     use mock::{MockStream, MockConnector};
     use super::Request;
 
@@ -236,4 +288,56 @@ mod tests {
         assert!(!s.contains("Content-Length:"));
         assert!(!s.contains("Transfer-Encoding:"));
     }
+
+    #[test]
+    fn test_host() {
+        let req = Request::with_connector(
+            Get, Url::parse("http://example.dom/path").unwrap(), &mut MockConnector
+        ).unwrap();
+        assert_eq!(req.headers().get(), Some(&Host::new("example.dom", None)));
+
+        let req = Request::with_connector(
+            Get, Url::parse("http://example.dom:8080/path").unwrap(), &mut MockConnector
+        ).unwrap();
+        let req = req.start().unwrap();
+        let stream = *req.body.end().unwrap().into_inner().downcast::<MockStream>().unwrap();
+        let bytes = stream.write.into_inner();
+        let s = from_utf8(bytes[]).unwrap();
+        assert!(s.contains("Host: example.dom:8080\r\n"));
+    }
+
+    #[test]
+    fn test_asterisk_target() {
+        let mut req = Request::with_connector(
+            Options, Url::parse("http://example.dom/path?q=1").unwrap(), &mut MockConnector
+        ).unwrap();
+        req.set_asterisk_target();
+        let req = req.start().unwrap();
+        let stream = *req.body.end().unwrap().into_inner().downcast::<MockStream>().unwrap();
+        let bytes = stream.write.into_inner();
+        let s = from_utf8(bytes[]).unwrap();
+        assert!(s.starts_with("OPTIONS * HTTP/1.1\r\n"));
+        assert!(s.contains("Host: example.dom\r\n"));
+    }
+
+    #[test]
+    fn test_http10_body() {
+        let mut req = Request::with_connector(
+            Post, Url::parse("http://example.dom").unwrap(), &mut MockConnector
+        ).unwrap();
+        req.version = Http10;
+        assert!(req.start().is_err());
+
+        let mut req = Request::with_connector(
+            Post, Url::parse("http://example.dom").unwrap(), &mut MockConnector
+        ).unwrap();
+        req.version = Http10;
+        req.headers_mut().set(ContentLength(0));
+        let req = req.start().unwrap();
+        let stream = *req.body.end().unwrap().into_inner().downcast::<MockStream>().unwrap();
+        let bytes = stream.write.into_inner();
+        let s = from_utf8(bytes[]).unwrap();
+        assert!(s.starts_with("POST / HTTP/1.0\r\n"));
+        assert!(!s.contains("Transfer-Encoding:"));
+    }
 }
@@ -1,4 +1,5 @@
 //! Client Responses
+use std::default::Default;
 use std::num::FromPrimitive;
 use std::io::{BufferedReader, IoResult};
 
@@ -6,13 +7,20 @@ use header;
 use header::common::{ContentLength, TransferEncoding};
 use header::common::transfer_encoding::Encoding::Chunked;
 use net::{NetworkStream, HttpStream};
-use http::{read_status_line, HttpReader, RawStatus};
-use http::HttpReader::{SizedReader, ChunkedReader, EofReader};
+use http::{check_framing, read_status_line_with, should_keep_alive, ChunkLimits, HttpReader,
+           ParseMode, RawStatus};
+use http::HttpReader::{SizedReader, ChunkedReader, EofReader, EmptyReader};
 use status;
+use status::StatusCode::{NoContent, NotModified};
 use version;
 use HttpResult;
 use HttpError::HttpStatusError;
 
+/// Called with the status and headers of each informational `1xx` response
+/// received before the final response, such as `103 Early Hints` with the
+/// `Link` headers of resources worth loading early.
+pub type InterimCallback = fn(status::StatusCode, &header::Headers);
+
 /// A response for a client request to a remote server.
 pub struct Response<S = HttpStream> {
     /// The status from the server.
@@ -29,18 +37,30 @@ impl Response {
 
     /// Creates a new response from a server.
     pub fn new(stream: Box<NetworkStream + Send>) -> HttpResult<Response> {
-        let mut stream = BufferedReader::new(stream);
-        let (version, raw_status) = try!(read_status_line(&mut stream));
-        let status = match FromPrimitive::from_u16(raw_status.0) {
-            Some(status) => status,
-            None => return Err(HttpStatusError)
-        };
-        debug!("{} {}", version, status);
+        Response::with_parse_mode(stream, ParseMode::Strict)
+    }
 
-        let headers = try!(header::Headers::from_raw(&mut stream));
-        debug!("Headers: [\n{}]", headers);
+    /// Creates a new response from a server, parsing its head as strictly as
+    /// `mode` says.
+    pub fn with_parse_mode(stream: Box<NetworkStream + Send>, mode: ParseMode) -> HttpResult<Response> {
+        Response::with_interim_callback(stream, mode, None)
+    }
+
+    /// Creates a new response from a server, calling `callback` for each
+    /// informational response that comes before it.
+    ///
+    /// Informational responses are skipped without a callback. `101
+    /// Switching Protocols` is a final response.
+    pub fn with_interim_callback(stream: Box<NetworkStream + Send>, mode: ParseMode,
+                                 callback: Option<InterimCallback>) -> HttpResult<Response> {
+        let mut stream = BufferedReader::new(stream);
+        let (version, raw_status, status, headers) = try!(read_final_head(&mut stream, mode, callback));
 
-        let body = if headers.has::<TransferEncoding>() {
+        // HTTP/1.0 has no Transfer-Encoding, so a body is framed by its
+        // Content-Length, or ends when the server closes the connection
+        let body = if status == NoContent || status == NotModified {
+            EmptyReader(stream)
+        } else if version.supports_chunked() && headers.has::<TransferEncoding>() {
             match headers.get::<TransferEncoding>() {
                 Some(&TransferEncoding(ref codings)) => {
                     if codings.len() > 1 {
@@ -48,7 +68,7 @@ impl Response {
                     };
 
                     if codings.contains(&Chunked) {
-                        ChunkedReader(stream, None)
+                        ChunkedReader(stream, None, Default::default())
                     } else {
                         debug!("not chuncked. read till eof");
                         EofReader(stream)
@@ -62,7 +82,7 @@ impl Response {
                 None => unreachable!()
             }
         } else {
-            debug!("neither Transfer-Encoding nor Content-Length");
+            debug!("neither Transfer-Encoding nor Content-Length, read till eof");
             EofReader(stream)
         };
 
@@ -80,12 +100,68 @@ impl Response {
         &self.status_raw
     }
 
+    /// The reason phrase the server sent, which may differ from the
+    /// canonical one of the status, or be empty.
+    pub fn reason(&self) -> &str {
+        self.status_raw.1[]
+    }
+
+    /// Whether the connection can be used for another request once the body
+    /// has been read.
+    ///
+    /// It can't if the server said it closes the connection, which HTTP/1.0
+    /// servers do unless they say `keep-alive`, or if the body has neither a
+    /// `Content-Length` nor chunks, as such a body ends when the connection
+    /// does.
+    pub fn is_reusable(&self) -> bool {
+        let delimited = match self.body {
+            EofReader(_) => false,
+            _ => true
+        };
+        should_keep_alive(self.version, &self.headers, delimited)
+    }
+
+    /// Set how large the chunks of a chunked body may be, and how much they
+    /// may add up to, before reading it.
+    ///
+    /// A chunk over the limits is an `InvalidInput` error when reading.
+    pub fn set_chunk_limits(&mut self, limits: ChunkLimits) {
+        if let ChunkedReader(_, _, ref mut current) = self.body {
+            *current = limits;
+        }
+    }
+
     /// Consumes the Request to return the NetworkStream underneath.
     pub fn into_inner(self) -> Box<NetworkStream + Send> {
         self.body.unwrap().into_inner()
     }
 }
 
+/// Read heads until the final one, passing informational ones to `callback`.
+fn read_final_head<R: Reader>(stream: &mut R, mode: ParseMode, callback: Option<InterimCallback>)
+        -> HttpResult<(version::HttpVersion, RawStatus, status::StatusCode, header::Headers)> {
+    loop {
+        let (version, raw_status) = try!(read_status_line_with(stream, mode));
+        let status: status::StatusCode = match FromPrimitive::from_u16(raw_status.0) {
+            Some(status) => status,
+            None => return Err(HttpStatusError)
+        };
+        debug!("{} {}", version, status);
+
+        let mut headers = try!(header::Headers::from_raw_with(stream, mode));
+        debug!("Headers: [\n{}]", headers);
+
+        if status.is_informational() && status != status::StatusCode::SwitchingProtocols {
+            if let Some(callback) = callback {
+                callback(status, &headers);
+            }
+            continue;
+        }
+        try!(check_framing(&mut headers));
+        return Ok((version, raw_status, status, headers));
+    }
+}
+
 impl Reader for Response {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
@@ -97,17 +173,20 @@ impl Reader for Response {
 mod tests {
     use std::borrow::Cow::Borrowed;
     use std::boxed::BoxAny;
-    use std::io::BufferedReader;
+    use std::io::{BufferedReader, InvalidInput};
 
     use header::Headers;
+    use http::ChunkLimits;
     use http::HttpReader::EofReader;
     use http::RawStatus;
+    use http::ParseMode::{Lenient, Strict};
     use mock::MockStream;
     use net::NetworkStream;
     use status;
     use version;
 
-    use super::Response;
+    use HttpError::HttpHeaderError;
+    use super::{InterimCallback, Response};
 
 
     #[test]
@@ -124,4 +203,127 @@ mod tests {
         assert_eq!(b, box MockStream::new());
 
     }
+
+    #[test]
+    fn test_http10_read_to_eof() {
+        let stream = MockStream::with_input(b"HTTP/1.0 200 OK\r\n\
+                                              Transfer-Encoding: chunked\r\n\
+                                              \r\n\
+                                              5\r\nhello");
+        let mut res = Response::new(box stream as Box<NetworkStream + Send>).unwrap();
+        assert_eq!(res.version, version::HttpVersion::Http10);
+        assert_eq!(res.read_to_string().unwrap()[], "5\r\nhello");
+    }
+
+    #[test]
+    fn test_lenient_status_line() {
+        let input = b"HTTP/1.1 200\r\nContent-Length: 2\n\nok";
+        let stream = box MockStream::with_input(input) as Box<NetworkStream + Send>;
+        assert!(Response::new(stream).is_err());
+
+        let stream = box MockStream::with_input(input) as Box<NetworkStream + Send>;
+        let mut res = Response::with_parse_mode(stream, Lenient).unwrap();
+        assert_eq!(res.status, status::StatusCode::Ok);
+        assert_eq!(res.read_to_string().unwrap()[], "ok");
+    }
+
+    #[test]
+    fn test_reason() {
+        let stream = MockStream::with_input(b"HTTP/1.1 200 Fine By Me\r\nContent-Length: 0\r\n\r\n");
+        let res = Response::new(box stream as Box<NetworkStream + Send>).unwrap();
+        assert_eq!(res.status, status::StatusCode::Ok);
+        assert_eq!(res.reason(), "Fine By Me");
+    }
+
+    #[test]
+    fn test_unregistered_status() {
+        let stream = MockStream::with_input(b"HTTP/1.1 499 Client Closed Request\r\n\
+                                              Content-Length: 0\r\n\
+                                              \r\n");
+        let res = Response::new(box stream as Box<NetworkStream + Send>).unwrap();
+        assert_eq!(res.status, status::StatusCode::Code499);
+        assert!(res.status.is_client_error());
+        assert!(!res.status.is_registered());
+        assert_eq!(res.reason(), "Client Closed Request");
+    }
+
+    #[test]
+    fn test_interim_callback() {
+        use std::sync::atomic::{AtomicUint, INIT_ATOMIC_UINT, SeqCst};
+        static HINTS: AtomicUint = INIT_ATOMIC_UINT;
+
+        fn hints(status: status::StatusCode, headers: &Headers) {
+            assert_eq!(status, status::StatusCode::Code103);
+            assert_eq!(headers.get_raw("Link"), Some([b"</style.css>; rel=preload".to_vec()][]));
+            HINTS.fetch_add(1, SeqCst);
+        }
+
+        let input = b"HTTP/1.1 103 Early Hints\r\n\
+                      Link: </style.css>; rel=preload\r\n\
+                      \r\n\
+                      HTTP/1.1 200 OK\r\n\
+                      Content-Length: 2\r\n\
+                      \r\n\
+                      ok";
+        let stream = box MockStream::with_input(input) as Box<NetworkStream + Send>;
+        let callback = Some(hints as InterimCallback);
+        let mut res = Response::with_interim_callback(stream, Strict, callback).unwrap();
+        assert_eq!(HINTS.load(SeqCst), 1);
+        assert_eq!(res.status, status::StatusCode::Ok);
+        assert_eq!(res.read_to_string().unwrap()[], "ok");
+
+        let stream = box MockStream::with_input(input) as Box<NetworkStream + Send>;
+        let res = Response::new(stream).unwrap();
+        assert_eq!(HINTS.load(SeqCst), 1);
+        assert_eq!(res.status, status::StatusCode::Ok);
+    }
+
+    #[test]
+    fn test_conflicting_framing() {
+        let stream = MockStream::with_input(b"HTTP/1.1 200 OK\r\n\
+                                              Content-Length: 5\r\n\
+                                              Transfer-Encoding: chunked\r\n\
+                                              \r\n");
+        let res = Response::new(box stream as Box<NetworkStream + Send>);
+        assert_eq!(res.err(), Some(HttpHeaderError));
+    }
+
+    #[test]
+    fn test_read_to_eof() {
+        let stream = MockStream::with_input(b"HTTP/1.1 200 OK\r\n\r\nuntil close");
+        let mut res = Response::new(box stream as Box<NetworkStream + Send>).unwrap();
+        assert!(!res.is_reusable());
+        assert_eq!(res.read_to_string().unwrap()[], "until close");
+
+        let stream = MockStream::with_input(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+        let res = Response::new(box stream as Box<NetworkStream + Send>).unwrap();
+        assert!(res.is_reusable());
+
+        let stream = MockStream::with_input(b"HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\nok");
+        let res = Response::new(box stream as Box<NetworkStream + Send>).unwrap();
+        assert!(!res.is_reusable());
+
+        let stream = MockStream::with_input(b"HTTP/1.1 304 Not Modified\r\n\r\n");
+        let mut res = Response::new(box stream as Box<NetworkStream + Send>).unwrap();
+        assert!(res.is_reusable());
+        assert_eq!(res.read_to_string().unwrap()[], "");
+    }
+
+    #[test]
+    fn test_chunk_limits() {
+        let stream = MockStream::with_input(b"HTTP/1.1 200 OK\r\n\
+                                              Transfer-Encoding: chunked\r\n\
+                                              \r\n\
+                                              ffffffffffffffffff\r\n");
+        let mut res = Response::new(box stream as Box<NetworkStream + Send>).unwrap();
+        assert_eq!(res.read_to_string().unwrap_err().kind, InvalidInput);
+
+        let stream = MockStream::with_input(b"HTTP/1.1 200 OK\r\n\
+                                              Transfer-Encoding: chunked\r\n\
+                                              \r\n\
+                                              6\r\nchunks\r\n0\r\n\r\n");
+        let mut res = Response::new(box stream as Box<NetworkStream + Send>).unwrap();
+        res.set_chunk_limits(ChunkLimits { max_chunk_size: 5, max_body_size: 10 });
+        assert_eq!(res.read_to_string().unwrap_err().kind, InvalidInput);
+    }
 }
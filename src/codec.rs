@@ -0,0 +1,68 @@
+//! Base64 and SHA-1, as the headers and handshakes of HTTP use them.
+//!
+//! Basic credentials, WebSocket keys and entity tags are all built from
+//! these, so they share the one configuration: standard base64 with padding
+//! and without line breaks, and SHA-1 from OpenSSL.
+//!
+//! ```
+//! use hyper::codec::{base64_encode, base64_decode, sha1_hex};
+//!
+//! assert_eq!(base64_encode(b"Aladdin:open sesame")[], "QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+//! assert_eq!(base64_decode("QWxhZGRpbjpvcGVuIHNlc2FtZQ==").unwrap(), b"Aladdin:open sesame".to_vec());
+//! assert_eq!(sha1_hex(b"abc")[], "a9993e364706816aba3e25717850c26c9cd0d89d");
+//! ```
+use openssl::crypto::hash::{hash, HashType};
+use serialize::base64::{ToBase64, FromBase64, FromBase64Error, Standard, Config, Newline};
+use serialize::hex::ToHex;
+
+/// Encode `bytes` as standard base64, padded, on one line.
+pub fn base64_encode(bytes: &[u8]) -> String {
+    bytes.to_base64(Config {
+        char_set: Standard,
+        newline: Newline::CRLF,
+        pad: true,
+        line_length: None
+    })
+}
+
+/// Decode standard base64. Line breaks in `s` are skipped.
+pub fn base64_decode(s: &str) -> Result<Vec<u8>, FromBase64Error> {
+    s.from_base64()
+}
+
+/// The SHA-1 hash of `bytes`, which is 20 bytes long.
+pub fn sha1(bytes: &[u8]) -> Vec<u8> {
+    hash(HashType::SHA1, bytes)
+}
+
+/// The SHA-1 hash of `bytes`, as lowercase hex.
+pub fn sha1_hex(bytes: &[u8]) -> String {
+    sha1(bytes).to_hex()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64_encode, base64_decode, sha1, sha1_hex};
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64_encode(b"")[], "");
+        assert_eq!(base64_encode(b"f")[], "Zg==");
+        assert_eq!(base64_encode(b"foob")[], "Zm9vYg==");
+        assert_eq!(base64_encode(b"foobar")[], "Zm9vYmFy");
+        // long input is not broken into lines
+        assert!(!base64_encode([0u8, ..100][])[].contains("\r\n"));
+
+        assert_eq!(base64_decode("Zm9vYg==").unwrap(), b"foob".to_vec());
+        assert_eq!(base64_decode("Zm9v\r\nYmFy").unwrap(), b"foobar".to_vec());
+        assert!(base64_decode("not base64!").is_err());
+    }
+
+    #[test]
+    fn test_sha1() {
+        assert_eq!(sha1(b"").len(), 20);
+        assert_eq!(sha1_hex(b"")[], "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(sha1_hex(b"The quick brown fox jumps over the lazy dog")[],
+                   "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12");
+    }
+}
@@ -0,0 +1,531 @@
+//! Encoding structs as `application/x-www-form-urlencoded` data, and back.
+//!
+//! The fields of a struct become the keys, so a query string or form body
+//! doesn't need to be built by hand. A field can be a number, a bool, a
+//! string, a unit-like enum variant, an `Option`, which is left out when it
+//! is `None`, or a sequence of those, which repeats its key.
+//!
+//! ```
+//! extern crate hyper;
+//! extern crate "rustc-serialize" as serialize;
+//!
+//! #[deriving(Encodable, Decodable, PartialEq, Show)]
+//! struct Search {
+//!     q: String,
+//!     page: Option<uint>,
+//!     tag: Vec<String>,
+//! }
+//!
+//! fn main() {
+//!     let search = Search {
+//!         q: "two words".to_string(),
+//!         page: None,
+//!         tag: vec!["a&b".to_string(), "c".to_string()]
+//!     };
+//!     let query = hyper::form::encode(&search).unwrap();
+//!     assert_eq!(query[], "q=two+words&tag=a%26b&tag=c");
+//!     assert_eq!(hyper::form::decode::<Search>(query[]).unwrap(), search);
+//! }
+//! ```
+use std::error::Error;
+use std::str::FromStr;
+
+use serialize::{Encodable, Encoder, Decodable, Decoder};
+
+use percent_encoding::{form_decode, form_encode};
+
+use self::FormError::{Unsupported, MissingField, InvalidValue};
+
+/// An error encoding or decoding form data.
+#[deriving(Clone, PartialEq, Show)]
+pub enum FormError {
+    /// The value has a shape that form data can't hold, such as a struct in
+    /// a field.
+    Unsupported(&'static str),
+    /// The data has no value for a field that isn't an `Option`.
+    MissingField(String),
+    /// A value could not be decoded as the type of its field.
+    InvalidValue(String),
+}
+
+impl Error for FormError {
+    fn description(&self) -> &str {
+        match *self {
+            Unsupported(_) => "Unsupported form value",
+            MissingField(_) => "Missing form field",
+            InvalidValue(_) => "Invalid form value"
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        match *self {
+            Unsupported(what) => Some(what.to_string()),
+            MissingField(ref name) | InvalidValue(ref name) => Some(name.clone())
+        }
+    }
+}
+
+/// A result of encoding or decoding form data.
+pub type FormResult<T> = Result<T, FormError>;
+
+/// Encode `value`, a struct or a map, as form data.
+pub fn encode<T: Encodable<FormEncoder, FormError>>(value: &T) -> FormResult<String> {
+    encode_pairs(value).map(|pairs| form_encode(pairs[]))
+}
+
+/// Encode `value`, a struct or a map, as key and value pairs.
+pub fn encode_pairs<T: Encodable<FormEncoder, FormError>>(value: &T) -> FormResult<Vec<(String, String)>> {
+    let mut encoder = FormEncoder {
+        pairs: Vec::new(),
+        key: None,
+        in_key: false
+    };
+    try!(value.encode(&mut encoder));
+    Ok(encoder.pairs)
+}
+
+/// Decode form data, such as a query, as a struct or a map.
+pub fn decode<T: Decodable<FormDecoder, FormError>>(data: &str) -> FormResult<T> {
+    decode_pairs(form_decode(data.as_bytes()))
+}
+
+/// Decode key and value pairs as a struct or a map.
+pub fn decode_pairs<T: Decodable<FormDecoder, FormError>>(pairs: Vec<(String, String)>) -> FormResult<T> {
+    Decodable::decode(&mut FormDecoder {
+        pairs: pairs,
+        target: Target::Top
+    })
+}
+
+/// The `Encoder` used by `encode`.
+pub struct FormEncoder {
+    pairs: Vec<(String, String)>,
+    key: Option<String>,
+    in_key: bool
+}
+
+impl FormEncoder {
+    fn scalar(&mut self, value: String) -> FormResult<()> {
+        if self.in_key {
+            self.key = Some(value);
+            return Ok(());
+        }
+        match self.key {
+            Some(ref key) => {
+                self.pairs.push((key.clone(), value));
+                Ok(())
+            },
+            None => Err(Unsupported("a value that isn't a struct or a map"))
+        }
+    }
+}
+
+impl Encoder<FormError> for FormEncoder {
+    fn emit_nil(&mut self) -> FormResult<()> { self.scalar(String::new()) }
+    fn emit_uint(&mut self, v: uint) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_u64(&mut self, v: u64) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_u32(&mut self, v: u32) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_u16(&mut self, v: u16) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_u8(&mut self, v: u8) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_int(&mut self, v: int) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_i64(&mut self, v: i64) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_i32(&mut self, v: i32) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_i16(&mut self, v: i16) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_i8(&mut self, v: i8) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_bool(&mut self, v: bool) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_f64(&mut self, v: f64) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_f32(&mut self, v: f32) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_char(&mut self, v: char) -> FormResult<()> { self.scalar(v.to_string()) }
+    fn emit_str(&mut self, v: &str) -> FormResult<()> { self.scalar(v.to_string()) }
+
+    fn emit_enum(&mut self, _name: &str,
+                 f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        f(self)
+    }
+
+    fn emit_enum_variant(&mut self, v_name: &str, _v_id: uint, len: uint,
+                         _f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        if len > 0 {
+            return Err(Unsupported("an enum variant with fields"));
+        }
+        self.scalar(v_name.to_string())
+    }
+
+    fn emit_enum_variant_arg(&mut self, _a_idx: uint,
+                             _f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        Err(Unsupported("an enum variant with fields"))
+    }
+
+    fn emit_enum_struct_variant(&mut self, _v_name: &str, _v_id: uint, _len: uint,
+                                _f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        Err(Unsupported("an enum variant with fields"))
+    }
+
+    fn emit_enum_struct_variant_field(&mut self, _f_name: &str, _f_idx: uint,
+                                      _f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        Err(Unsupported("an enum variant with fields"))
+    }
+
+    fn emit_struct(&mut self, _name: &str, _len: uint,
+                   f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        if self.key.is_some() || self.in_key {
+            return Err(Unsupported("a struct in a field"));
+        }
+        f(self)
+    }
+
+    fn emit_struct_field(&mut self, f_name: &str, _f_idx: uint,
+                         f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        self.key = Some(f_name.to_string());
+        let result = f(self);
+        self.key = None;
+        result
+    }
+
+    fn emit_tuple(&mut self, _len: uint,
+                  _f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        Err(Unsupported("a tuple"))
+    }
+
+    fn emit_tuple_arg(&mut self, _idx: uint,
+                      _f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        Err(Unsupported("a tuple"))
+    }
+
+    fn emit_tuple_struct(&mut self, _name: &str, _len: uint,
+                         _f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        Err(Unsupported("a tuple struct"))
+    }
+
+    fn emit_tuple_struct_arg(&mut self, _f_idx: uint,
+                             _f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        Err(Unsupported("a tuple struct"))
+    }
+
+    fn emit_option(&mut self, f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        f(self)
+    }
+
+    fn emit_option_none(&mut self) -> FormResult<()> {
+        // the field is left out
+        Ok(())
+    }
+
+    fn emit_option_some(&mut self, f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        f(self)
+    }
+
+    fn emit_seq(&mut self, _len: uint,
+                f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        if self.key.is_none() || self.in_key {
+            return Err(Unsupported("a sequence that isn't in a field"));
+        }
+        f(self)
+    }
+
+    fn emit_seq_elt(&mut self, _idx: uint,
+                    f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        // each element repeats the key of the field
+        f(self)
+    }
+
+    fn emit_map(&mut self, _len: uint,
+                f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        if self.key.is_some() || self.in_key {
+            return Err(Unsupported("a map in a field"));
+        }
+        let result = f(self);
+        self.key = None;
+        result
+    }
+
+    fn emit_map_elt_key(&mut self, _idx: uint,
+                        f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        self.in_key = true;
+        let result = f(self);
+        self.in_key = false;
+        result
+    }
+
+    fn emit_map_elt_val(&mut self, _idx: uint,
+                        f: |&mut FormEncoder| -> FormResult<()>) -> FormResult<()> {
+        f(self)
+    }
+}
+
+/// What a `FormDecoder` reads a value from next.
+enum Target {
+    /// The struct or map itself.
+    Top,
+    /// A value of a field, by its name and its index among those.
+    Field(String, uint),
+    /// The key of a pair, for a map.
+    Key(uint),
+    /// The value of a pair, for a map.
+    Value(uint),
+}
+
+/// The `Decoder` used by `decode`.
+pub struct FormDecoder {
+    pairs: Vec<(String, String)>,
+    target: Target
+}
+
+impl FormDecoder {
+    fn values(&self, name: &str) -> Vec<&str> {
+        self.pairs.iter().filter(|&&(ref key, _)| key[] == name).map(|&(_, ref value)| value[]).collect()
+    }
+
+    fn scalar(&mut self) -> FormResult<String> {
+        let value = match self.target {
+            Target::Top => return Err(Unsupported("a value that isn't a struct or a map")),
+            Target::Field(ref name, index) => match self.values(name[]).get(index) {
+                Some(value) => value.to_string(),
+                None => return Err(MissingField(name.clone()))
+            },
+            Target::Key(index) => self.pairs[index].0.clone(),
+            Target::Value(index) => self.pairs[index].1.clone()
+        };
+        Ok(value)
+    }
+
+    fn parse<T: FromStr>(&mut self) -> FormResult<T> {
+        let value = try!(self.scalar());
+        match value.parse() {
+            Some(value) => Ok(value),
+            None => Err(InvalidValue(value))
+        }
+    }
+}
+
+impl Decoder<FormError> for FormDecoder {
+    fn read_nil(&mut self) -> FormResult<()> { self.scalar().map(|_| ()) }
+    fn read_uint(&mut self) -> FormResult<uint> { self.parse() }
+    fn read_u64(&mut self) -> FormResult<u64> { self.parse() }
+    fn read_u32(&mut self) -> FormResult<u32> { self.parse() }
+    fn read_u16(&mut self) -> FormResult<u16> { self.parse() }
+    fn read_u8(&mut self) -> FormResult<u8> { self.parse() }
+    fn read_int(&mut self) -> FormResult<int> { self.parse() }
+    fn read_i64(&mut self) -> FormResult<i64> { self.parse() }
+    fn read_i32(&mut self) -> FormResult<i32> { self.parse() }
+    fn read_i16(&mut self) -> FormResult<i16> { self.parse() }
+    fn read_i8(&mut self) -> FormResult<i8> { self.parse() }
+    fn read_bool(&mut self) -> FormResult<bool> { self.parse() }
+    fn read_f64(&mut self) -> FormResult<f64> { self.parse() }
+    fn read_f32(&mut self) -> FormResult<f32> { self.parse() }
+    fn read_str(&mut self) -> FormResult<String> { self.scalar() }
+
+    fn read_char(&mut self) -> FormResult<char> {
+        let value = try!(self.scalar());
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(InvalidValue(value.clone()))
+        }
+    }
+
+    fn read_enum<T>(&mut self, _name: &str,
+                    f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        f(self)
+    }
+
+    fn read_enum_variant<T>(&mut self, names: &[&str],
+                            f: |&mut FormDecoder, uint| -> FormResult<T>) -> FormResult<T> {
+        let value = try!(self.scalar());
+        match names.iter().position(|name| *name == value[]) {
+            Some(index) => f(self, index),
+            None => Err(InvalidValue(value))
+        }
+    }
+
+    fn read_enum_variant_arg<T>(&mut self, _a_idx: uint,
+                                _f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        Err(Unsupported("an enum variant with fields"))
+    }
+
+    fn read_enum_struct_variant<T>(&mut self, _names: &[&str],
+                                   _f: |&mut FormDecoder, uint| -> FormResult<T>) -> FormResult<T> {
+        Err(Unsupported("an enum variant with fields"))
+    }
+
+    fn read_enum_struct_variant_field<T>(&mut self, _f_name: &str, _f_idx: uint,
+                                         _f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        Err(Unsupported("an enum variant with fields"))
+    }
+
+    fn read_struct<T>(&mut self, _s_name: &str, _len: uint,
+                      f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        match self.target {
+            Target::Top => f(self),
+            _ => Err(Unsupported("a struct in a field"))
+        }
+    }
+
+    fn read_struct_field<T>(&mut self, f_name: &str, _f_idx: uint,
+                            f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        self.target = Target::Field(f_name.to_string(), 0);
+        let result = f(self);
+        self.target = Target::Top;
+        result
+    }
+
+    fn read_tuple<T>(&mut self, _len: uint,
+                     _f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        Err(Unsupported("a tuple"))
+    }
+
+    fn read_tuple_arg<T>(&mut self, _a_idx: uint,
+                         _f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        Err(Unsupported("a tuple"))
+    }
+
+    fn read_tuple_struct<T>(&mut self, _s_name: &str, _len: uint,
+                            _f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        Err(Unsupported("a tuple struct"))
+    }
+
+    fn read_tuple_struct_arg<T>(&mut self, _a_idx: uint,
+                                _f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        Err(Unsupported("a tuple struct"))
+    }
+
+    fn read_option<T>(&mut self, f: |&mut FormDecoder, bool| -> FormResult<T>) -> FormResult<T> {
+        let present = match self.target {
+            Target::Field(ref name, index) => self.values(name[]).len() > index,
+            _ => true
+        };
+        f(self, present)
+    }
+
+    fn read_seq<T>(&mut self, f: |&mut FormDecoder, uint| -> FormResult<T>) -> FormResult<T> {
+        let len = match self.target {
+            Target::Field(ref name, _) => self.values(name[]).len(),
+            _ => return Err(Unsupported("a sequence that isn't in a field"))
+        };
+        f(self, len)
+    }
+
+    fn read_seq_elt<T>(&mut self, idx: uint,
+                       f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        if let Target::Field(_, ref mut index) = self.target {
+            *index = idx;
+        }
+        f(self)
+    }
+
+    fn read_map<T>(&mut self, f: |&mut FormDecoder, uint| -> FormResult<T>) -> FormResult<T> {
+        match self.target {
+            Target::Top => {
+                let len = self.pairs.len();
+                f(self, len)
+            },
+            _ => Err(Unsupported("a map in a field"))
+        }
+    }
+
+    fn read_map_elt_key<T>(&mut self, idx: uint,
+                           f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        self.target = Target::Key(idx);
+        let result = f(self);
+        self.target = Target::Top;
+        result
+    }
+
+    fn read_map_elt_val<T>(&mut self, idx: uint,
+                           f: |&mut FormDecoder| -> FormResult<T>) -> FormResult<T> {
+        self.target = Target::Value(idx);
+        let result = f(self);
+        self.target = Target::Top;
+        result
+    }
+
+    fn error(&mut self, err: &str) -> FormError {
+        InvalidValue(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use method::Method;
+    use super::{encode, decode, encode_pairs};
+    use super::FormError::{Unsupported, MissingField, InvalidValue};
+
+    #[deriving(Encodable, Decodable, PartialEq, Show)]
+    enum Sort {
+        Newest,
+        Oldest
+    }
+
+    #[deriving(Encodable, Decodable, PartialEq, Show)]
+    struct Params {
+        q: String,
+        page: uint,
+        exact: bool,
+        sort: Sort,
+        method: Method,
+        limit: Option<int>,
+        tag: Vec<String>,
+    }
+
+    #[deriving(Encodable, Decodable, PartialEq, Show)]
+    struct Nested {
+        params: Params
+    }
+
+    fn params() -> Params {
+        Params {
+            q: "two words".to_string(),
+            page: 2,
+            exact: false,
+            sort: Sort::Oldest,
+            method: Method::Get,
+            limit: None,
+            tag: vec!["a&b".to_string(), "c".to_string()]
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(&params()).unwrap()[],
+                   "q=two+words&page=2&exact=false&sort=Oldest&method=GET&tag=a%26b&tag=c");
+
+        let mut with_limit = params();
+        with_limit.limit = Some(-1);
+        with_limit.tag = vec![];
+        assert_eq!(encode_pairs(&with_limit).unwrap().into_iter().filter(|&(ref key, _)| {
+            key[] == "limit" || key[] == "tag"
+        }).collect::<Vec<(String, String)>>(), vec![("limit".to_string(), "-1".to_string())]);
+    }
+
+    #[test]
+    fn test_decode() {
+        let query = "tag=a%26b&q=two+words&page=2&exact=false&sort=Oldest&method=GET&tag=c";
+        assert_eq!(decode::<Params>(query).unwrap(), params());
+
+        let decoded = decode::<Params>("q=&page=1&exact=true&sort=Newest&method=PUT&limit=5").unwrap();
+        assert_eq!(decoded.limit, Some(5));
+        assert_eq!(decoded.tag, vec![]);
+
+        assert_eq!(decode::<Params>("q=x"), Err(MissingField("page".to_string())));
+        assert_eq!(decode::<Params>("q=x&page=two"), Err(InvalidValue("two".to_string())));
+    }
+
+    #[test]
+    fn test_map() {
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), "1".to_string());
+        map.insert("b c".to_string(), "2".to_string());
+        assert_eq!(encode(&map).unwrap()[], "a=1&b+c=2");
+        assert_eq!(decode::<BTreeMap<String, String>>("a=1&b+c=2").unwrap(), map);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert!(encode(&"not a struct".to_string()).is_err());
+        match encode(&Nested { params: params() }) {
+            Err(Unsupported(_)) => (),
+            other => panic!("expected Unsupported, got {}", other)
+        }
+    }
+}
@@ -1,29 +1,94 @@
 use header::{Header, HeaderFormat};
-use std::fmt::{mod, Show};
-use std::str::from_utf8;
+use header::shared::QualityItem;
+use std::fmt;
 use mime::Mime;
+use mime::TopLevel;
+use mime::SubLevel;
+use super::util::{from_comma_list, fmt_comma_delimited};
 
 /// The `Accept` header.
 ///
 /// The `Accept` header is used to tell a server which content-types the client
-/// is capable of using. It can be a comma-separated list of `Mime`s, and the
-/// priority can be indicated with a `q` parameter.
+/// is capable of using. It is a comma-separated list of media ranges, which
+/// can be `*/*` or `text/*`, and the priority of each can be set with a `q`
+/// parameter.
 ///
 /// Example:
 ///
 /// ```
 /// # use hyper::header::Headers;
 /// # use hyper::header::common::Accept;
+/// # use hyper::header::shared::{q, qitem};
 /// use hyper::mime::Mime;
 /// use hyper::mime::TopLevel::Text;
 /// use hyper::mime::SubLevel::{Html, Xml};
 /// # let mut headers = Headers::new();
-/// headers.set(Accept(vec![ Mime(Text, Html, vec![]), Mime(Text, Xml, vec![]) ]));
+/// headers.set(Accept(vec![
+///     qitem(Mime(Text, Html, vec![])),
+///     q(Mime(Text, Xml, vec![]), 0.5)
+/// ]));
 /// ```
 #[deriving(Clone, PartialEq, Show)]
-pub struct Accept(pub Vec<Mime>);
+pub struct Accept(pub Vec<QualityItem<Mime>>);
 
-deref!(Accept -> Vec<Mime>);
+deref!(Accept -> Vec<QualityItem<Mime>>);
+
+impl Accept {
+    /// The quality of a content type, from the most specific media range
+    /// that matches it, or `0` if none do.
+    pub fn quality(&self, mime: &Mime) -> f32 {
+        let mut best = None;
+        for range in self.iter() {
+            let specificity = match specificity(&range.item, mime) {
+                Some(specificity) => specificity,
+                None => continue
+            };
+            if best.map_or(true, |(most, _)| specificity > most) {
+                best = Some((specificity, range.quality));
+            }
+        }
+        best.map_or(0.0, |(_, quality)| quality)
+    }
+
+    /// Pick the content type the client prefers from those `available`.
+    ///
+    /// Returns `None` if none of them are acceptable. When several are
+    /// preferred equally, the first of them is picked, so `available` should
+    /// be in the order the server prefers.
+    pub fn negotiate<'a>(&self, available: &'a [Mime]) -> Option<&'a Mime> {
+        let mut best = None;
+        for mime in available.iter() {
+            let quality = self.quality(mime);
+            if quality <= 0.0 {
+                continue;
+            }
+            if best.map_or(true, |(_, most)| quality > most) {
+                best = Some((mime, quality));
+            }
+        }
+        best.map(|(mime, _)| mime)
+    }
+}
+
+/// How specifically a media range matches a content type, if it does.
+fn specificity(range: &Mime, mime: &Mime) -> Option<uint> {
+    let (&Mime(ref top, ref sub, ref params), &Mime(ref mime_top, ref mime_sub, ref mime_params)) =
+        (range, mime);
+    match (top, sub) {
+        (&TopLevel::Star, &SubLevel::Star) => Some(0),
+        (top, &SubLevel::Star) if top == mime_top => Some(1),
+        (top, sub) if top == mime_top && sub == mime_sub => {
+            if params.is_empty() {
+                Some(2)
+            } else if params.iter().all(|param| mime_params.contains(param)) {
+                Some(3)
+            } else {
+                None
+            }
+        },
+        _ => None
+    }
+}
 
 impl Header for Accept {
     fn header_name(_: Option<Accept>) -> &'static str {
@@ -31,43 +96,72 @@ impl Header for Accept {
     }
 
     fn parse_header(raw: &[Vec<u8>]) -> Option<Accept> {
-        let mut mimes: Vec<Mime> = vec![];
-        for mimes_raw in raw.iter() {
-            match from_utf8(mimes_raw.as_slice()) {
-                Ok(mimes_str) => {
-                    for mime_str in mimes_str.split(',') {
-                        match mime_str.trim().parse() {
-                            Some(mime) => mimes.push(mime),
-                            None => return None
-                        }
-                    }
-                },
-                Err(_) => return None
-            };
-        }
-
-        if !mimes.is_empty() {
-            Some(Accept(mimes))
-        } else {
-            // Currently is just a None, but later it can be Accept for */*
-            None
+        match from_comma_list(raw) {
+            Some(mimes) => if !mimes.is_empty() {
+                Some(Accept(mimes))
+            } else {
+                // Currently is just a None, but later it can be Accept for */*
+                None
+            },
+            None => None
         }
     }
 }
 
 impl HeaderFormat for Accept {
     fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let Accept(ref value) = *self;
-        let last = value.len() - 1;
-        for (i, mime) in value.iter().enumerate() {
-            try!(mime.fmt(fmt));
-            if i < last {
-                try!(", ".fmt(fmt));
-            }
-        }
-        Ok(())
+        fmt_comma_delimited(fmt, self[])
     }
 }
 
-bench_header!(bench, Accept, { vec![b"text/plain; q=0.5, text/html".to_vec()] });
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use header::shared::{QualityItem, qitem};
+    use mime::Mime;
+    use mime::TopLevel::{Text, Image};
+    use mime::SubLevel::{Html, Plain, Png, Star};
+    use super::Accept;
+
+    fn accept(s: &str) -> Accept {
+        Header::parse_header([s.as_bytes().to_vec()][]).unwrap()
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(accept("text/html, text/*; q=0.5"), Accept(vec![
+            qitem(Mime(Text, Html, vec![])),
+            QualityItem::new(Mime(Text, Star, vec![]), 0.5)
+        ]));
+        let bad: Option<Accept> = Header::parse_header([b"text/html; q=high".to_vec()][]);
+        assert_eq!(bad, None);
+    }
 
+    #[test]
+    fn test_fmt() {
+        let accept = accept("text/html, text/plain;q=0.5");
+        assert_eq!(format!("{}", HeaderFormatter(&accept))[], "text/html, text/plain; q=0.5");
+    }
+
+    #[test]
+    fn test_quality() {
+        let accept = accept("text/*; q=0.3, text/html; q=0.7, text/html; level=1, */*; q=0.1");
+        assert_eq!(accept.quality(&"text/html; level=1".parse().unwrap()), 1.0);
+        assert_eq!(accept.quality(&Mime(Text, Html, vec![])), 0.7);
+        assert_eq!(accept.quality(&Mime(Text, Plain, vec![])), 0.3);
+        assert_eq!(accept.quality(&Mime(Image, Png, vec![])), 0.1);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let available = [Mime(Text, Html, vec![]), Mime(Text, Plain, vec![])];
+        assert_eq!(accept("text/plain, text/html; q=0.9").negotiate(&available),
+                   Some(&available[1]));
+        assert_eq!(accept("*/*").negotiate(&available), Some(&available[0]));
+        assert_eq!(accept("text/*, text/html; q=0").negotiate(&available),
+                   Some(&available[1]));
+        assert_eq!(accept("image/*").negotiate(&available), None);
+    }
+}
+
+bench_header!(bench, Accept, { vec![b"text/plain; q=0.5, text/html".to_vec()] });
@@ -0,0 +1,124 @@
+use header::{Header, HeaderFormat};
+use header::shared::{Encoding, QualityItem};
+use header::shared::Encoding::{Identity, EncodingExt};
+use std::fmt;
+use super::util::{from_comma_list, fmt_comma_delimited};
+
+/// The `Accept-Encoding` header, telling which content codings the client
+/// can decode, with their `q` values.
+///
+/// The wildcard `*` is kept as `EncodingExt("*")`. The `identity` coding is
+/// acceptable unless it is excluded with a quality of `0`, even if it is not
+/// listed.
+///
+/// See https://tools.ietf.org/html/rfc7231#section-5.3.4
+#[deriving(Clone, PartialEq, Show)]
+pub struct AcceptEncoding(pub Vec<QualityItem<Encoding>>);
+
+deref!(AcceptEncoding -> Vec<QualityItem<Encoding>>);
+
+impl AcceptEncoding {
+    /// The quality of a coding, or `0` if it is not acceptable.
+    pub fn quality(&self, encoding: &Encoding) -> f32 {
+        let mut wildcard = None;
+        for item in self.iter() {
+            if item.item == *encoding {
+                return item.quality;
+            }
+            if let EncodingExt(ref s) = item.item {
+                if s[] == "*" {
+                    wildcard = Some(item.quality);
+                }
+            }
+        }
+        match wildcard {
+            Some(quality) => quality,
+            None if *encoding == Identity => 1.0,
+            None => 0.0
+        }
+    }
+
+    /// Pick the coding the client prefers from those `available`.
+    ///
+    /// Returns `None` if none of them are acceptable. When several are
+    /// preferred equally, the first of them is picked.
+    pub fn negotiate<'a>(&self, available: &'a [Encoding]) -> Option<&'a Encoding> {
+        let mut best = None;
+        for encoding in available.iter() {
+            let quality = self.quality(encoding);
+            if quality <= 0.0 {
+                continue;
+            }
+            if best.map_or(true, |(_, most)| quality > most) {
+                best = Some((encoding, quality));
+            }
+        }
+        best.map(|(encoding, _)| encoding)
+    }
+}
+
+impl Header for AcceptEncoding {
+    fn header_name(_: Option<AcceptEncoding>) -> &'static str {
+        "Accept-Encoding"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<AcceptEncoding> {
+        from_comma_list(raw).map(AcceptEncoding)
+    }
+}
+
+impl HeaderFormat for AcceptEncoding {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use header::shared::{QualityItem, qitem};
+    use header::shared::Encoding::{Gzip, Deflate, Identity, EncodingExt};
+    use super::AcceptEncoding;
+
+    fn accept(s: &str) -> AcceptEncoding {
+        Header::parse_header([s.as_bytes().to_vec()][]).unwrap()
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(accept("gzip, deflate;q=0.5, *; q=0"), AcceptEncoding(vec![
+            qitem(Gzip),
+            QualityItem::new(Deflate, 0.5),
+            QualityItem::new(EncodingExt("*".to_string()), 0.0)
+        ]));
+        assert_eq!(accept(""), AcceptEncoding(vec![]));
+    }
+
+    #[test]
+    fn test_fmt() {
+        let encodings = accept("gzip,deflate;q=0.5");
+        assert_eq!(format!("{}", HeaderFormatter(&encodings))[], "gzip, deflate; q=0.5");
+    }
+
+    #[test]
+    fn test_quality() {
+        let gzip = accept("gzip; q=0.8");
+        assert_eq!(gzip.quality(&Gzip), 0.8);
+        assert_eq!(gzip.quality(&Deflate), 0.0);
+        assert_eq!(gzip.quality(&Identity), 1.0);
+
+        assert_eq!(accept("gzip, *;q=0").quality(&Identity), 0.0);
+        assert_eq!(accept("*; q=0.5").quality(&Deflate), 0.5);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let available = [Gzip, Deflate, Identity];
+        assert_eq!(accept("deflate, gzip").negotiate(&available), Some(&Gzip));
+        assert_eq!(accept("gzip;q=0.5, deflate").negotiate(&available), Some(&Deflate));
+        assert_eq!(accept("").negotiate(&available), Some(&Identity));
+        assert_eq!(accept("identity;q=0").negotiate(&[Identity]), None);
+    }
+}
+
+bench_header!(bench, AcceptEncoding, { vec![b"gzip, deflate; q=0.5, identity; q=0.1".to_vec()] });
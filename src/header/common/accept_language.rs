@@ -0,0 +1,173 @@
+use header::{Header, HeaderFormat};
+use header::shared::QualityItem;
+use std::ascii::AsciiExt;
+use std::fmt::{mod, Show};
+use std::str::{FromStr, from_str};
+use super::content_language::LanguageTag;
+use super::util::{from_comma_list, fmt_comma_delimited};
+
+/// The `Accept-Language` header, listing the languages the client prefers,
+/// with their `q` values.
+///
+/// ```notrust
+/// Accept-Language: da, en-GB;q=0.8, en;q=0.7
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7231#section-5.3.5
+#[deriving(Clone, PartialEq, Show)]
+pub struct AcceptLanguage(pub Vec<QualityItem<LanguageRange>>);
+
+deref!(AcceptLanguage -> Vec<QualityItem<LanguageRange>>);
+
+/// A language range, which is a language tag like `en-GB`, or `*`.
+#[deriving(Clone, PartialEq)]
+pub struct LanguageRange(pub String);
+
+impl LanguageRange {
+    /// Whether a language tag is in this range, because it is the same tag,
+    /// or starts with it, ignoring case.
+    ///
+    /// The range `en` has `en` and `en-GB` in it, but not `eng`.
+    pub fn matches(&self, tag: &str) -> bool {
+        let range = self.0[];
+        range == "*" || range.eq_ignore_ascii_case(tag) ||
+            (tag.len() > range.len() && tag.as_bytes()[range.len()] == b'-' &&
+             range.eq_ignore_ascii_case(tag[..range.len()]))
+    }
+}
+
+impl FromStr for LanguageRange {
+    fn from_str(s: &str) -> Option<LanguageRange> {
+        if s == "*" || from_str::<LanguageTag>(s).is_some() {
+            Some(LanguageRange(s.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Show for LanguageRange {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+impl AcceptLanguage {
+    /// The quality of a language tag, from the longest range it is in, or `0`
+    /// if it is in none.
+    pub fn quality(&self, tag: &str) -> f32 {
+        let mut best = None;
+        for range in self.iter() {
+            if !range.item.matches(tag) {
+                continue;
+            }
+            // `*` is the least specific of all
+            let len = if range.item.0[] == "*" { 0 } else { range.item.0.len() };
+            if best.map_or(true, |(longest, _)| len > longest) {
+                best = Some((len, range.quality));
+            }
+        }
+        best.map_or(0.0, |(_, quality)| quality)
+    }
+
+    /// Pick the locale the client prefers from those `available`.
+    ///
+    /// When no locale is in any of the ranges, the ranges are shortened
+    /// instead, so a client asking for `en-GB` gets `en` if that is
+    /// available. Returns `None` if nothing matches either way. When several
+    /// locales are preferred equally, the first of them is picked.
+    pub fn negotiate<'a, S: Str>(&self, available: &'a [S]) -> Option<&'a S> {
+        let mut best = None;
+        for locale in available.iter() {
+            let quality = self.quality(locale.as_slice());
+            if quality > 0.0 && best.map_or(true, |(_, most)| quality > most) {
+                best = Some((locale, quality));
+            }
+        }
+        if best.is_none() {
+            for locale in available.iter() {
+                let locale_range = LanguageRange(locale.as_slice().to_string());
+                let quality = self.iter().filter(|range| locale_range.matches(range.item.0[]))
+                    .fold(0.0, |most, range| if range.quality > most { range.quality } else { most });
+                if quality > 0.0 && best.map_or(true, |(_, most)| quality > most) {
+                    best = Some((locale, quality));
+                }
+            }
+        }
+        best.map(|(locale, _)| locale)
+    }
+}
+
+impl Header for AcceptLanguage {
+    fn header_name(_: Option<AcceptLanguage>) -> &'static str {
+        "Accept-Language"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<AcceptLanguage> {
+        from_comma_list(raw).map(AcceptLanguage)
+    }
+}
+
+impl HeaderFormat for AcceptLanguage {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use header::shared::{QualityItem, qitem};
+    use super::{AcceptLanguage, LanguageRange};
+
+    fn accept(s: &str) -> AcceptLanguage {
+        Header::parse_header([s.as_bytes().to_vec()][]).unwrap()
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(accept("da, en-GB;q=0.8, en;q=0.7"), AcceptLanguage(vec![
+            qitem(LanguageRange("da".to_string())),
+            QualityItem::new(LanguageRange("en-GB".to_string()), 0.8),
+            QualityItem::new(LanguageRange("en".to_string()), 0.7)
+        ]));
+        let bad: Option<AcceptLanguage> = Header::parse_header([b"en_GB".to_vec()][]);
+        assert_eq!(bad, None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let languages = accept("da,en-GB;q=0.8");
+        assert_eq!(format!("{}", HeaderFormatter(&languages))[], "da, en-GB; q=0.8");
+    }
+
+    #[test]
+    fn test_matches() {
+        let en = LanguageRange("en".to_string());
+        assert!(en.matches("en"));
+        assert!(en.matches("EN-gb"));
+        assert!(!en.matches("eng"));
+        assert!(LanguageRange("*".to_string()).matches("fr"));
+    }
+
+    #[test]
+    fn test_quality() {
+        let languages = accept("en;q=0.5, en-GB, *;q=0.1");
+        assert_eq!(languages.quality("en-GB"), 1.0);
+        assert_eq!(languages.quality("en-US"), 0.5);
+        assert_eq!(languages.quality("fr"), 0.1);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let available = ["en-US", "fr"];
+        assert_eq!(accept("fr, en;q=0.8").negotiate(&available), Some(&"fr"));
+        assert_eq!(accept("en").negotiate(&available), Some(&"en-US"));
+        assert_eq!(accept("de").negotiate(&available), None);
+
+        let available = ["en", "de"];
+        assert_eq!(accept("en-GB, de;q=0.5").negotiate(&available), Some(&"en"));
+    }
+}
+
+bench_header!(bench, AcceptLanguage, { vec![b"da, en-GB;q=0.8, en;q=0.7".to_vec()] });
@@ -0,0 +1,79 @@
+use header::{Header, HeaderFormat};
+use std::fmt::{mod, Show};
+use std::str::FromStr;
+use super::util::{from_comma_delimited, fmt_comma_delimited};
+
+/// The `Accept-Ranges` header, telling which range units the server
+/// supports for the target.
+///
+/// See https://tools.ietf.org/html/rfc7233#section-2.3
+#[deriving(Clone, PartialEq, Show)]
+pub struct AcceptRanges(pub Vec<RangeUnit>);
+
+deref!(AcceptRanges -> Vec<RangeUnit>);
+
+/// A range unit in the `Accept-Ranges` header.
+#[deriving(Clone, PartialEq)]
+pub enum RangeUnit {
+    /// `bytes`, the only registered unit.
+    Bytes,
+    /// `none`, telling that no ranges are supported.
+    NoRanges,
+    /// Some other unit.
+    Unregistered(String)
+}
+
+impl FromStr for RangeUnit {
+    fn from_str(s: &str) -> Option<RangeUnit> {
+        match s {
+            "bytes" => Some(RangeUnit::Bytes),
+            "none" => Some(RangeUnit::NoRanges),
+            s => Some(RangeUnit::Unregistered(s.to_string()))
+        }
+    }
+}
+
+impl Show for RangeUnit {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RangeUnit::Bytes => "bytes",
+            RangeUnit::NoRanges => "none",
+            RangeUnit::Unregistered(ref s) => s[]
+        }.fmt(fmt)
+    }
+}
+
+impl Header for AcceptRanges {
+    fn header_name(_: Option<AcceptRanges>) -> &'static str {
+        "Accept-Ranges"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<AcceptRanges> {
+        from_comma_delimited(raw).map(AcceptRanges)
+    }
+}
+
+impl HeaderFormat for AcceptRanges {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use super::AcceptRanges;
+    use super::RangeUnit::{Bytes, NoRanges, Unregistered};
+
+    #[test]
+    fn test_parse_header() {
+        let ranges: Option<AcceptRanges> = Header::parse_header([b"bytes".to_vec()][]);
+        assert_eq!(ranges, Some(AcceptRanges(vec![Bytes])));
+        let ranges: Option<AcceptRanges> = Header::parse_header([b"none".to_vec()][]);
+        assert_eq!(ranges, Some(AcceptRanges(vec![NoRanges])));
+        let ranges: Option<AcceptRanges> = Header::parse_header([b"bytes, items".to_vec()][]);
+        assert_eq!(ranges, Some(AcceptRanges(vec![Bytes, Unregistered("items".to_string())])));
+    }
+}
+
+bench_header!(bench, AcceptRanges, { vec![b"bytes".to_vec()] });
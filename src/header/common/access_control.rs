@@ -0,0 +1,215 @@
+use header::{Header, HeaderFormat};
+use method::Method;
+use std::fmt::{mod, Show};
+use std::str::from_str;
+use super::util::{from_one_raw_str, from_comma_list, fmt_comma_delimited};
+
+/// The `Access-Control-Allow-Origin` header, telling which origin may read
+/// a response.
+///
+/// See https://fetch.spec.whatwg.org/#http-access-control-allow-origin
+#[deriving(Clone, PartialEq, Show)]
+pub enum AccessControlAllowOrigin {
+    /// `*`, any origin, for requests without credentials.
+    Any,
+    /// `null`, for requests from an opaque origin.
+    Null,
+    /// A single origin, such as `https://example.domain`.
+    Value(String)
+}
+
+impl Header for AccessControlAllowOrigin {
+    fn header_name(_: Option<AccessControlAllowOrigin>) -> &'static str {
+        "Access-Control-Allow-Origin"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<AccessControlAllowOrigin> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            match s[].trim() {
+                "" => None,
+                "*" => Some(AccessControlAllowOrigin::Any),
+                "null" => Some(AccessControlAllowOrigin::Null),
+                origin => Some(AccessControlAllowOrigin::Value(origin.to_string()))
+            }
+        })
+    }
+}
+
+impl HeaderFormat for AccessControlAllowOrigin {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccessControlAllowOrigin::Any => "*",
+            AccessControlAllowOrigin::Null => "null",
+            AccessControlAllowOrigin::Value(ref origin) => origin[]
+        }.fmt(fmt)
+    }
+}
+
+/// The `Access-Control-Allow-Credentials` header, which can only be `true`,
+/// letting a response to a request with credentials be read.
+#[deriving(Copy, Clone, PartialEq, Show)]
+pub struct AccessControlAllowCredentials;
+
+impl Header for AccessControlAllowCredentials {
+    fn header_name(_: Option<AccessControlAllowCredentials>) -> &'static str {
+        "Access-Control-Allow-Credentials"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<AccessControlAllowCredentials> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            // the value is case-sensitive
+            if s[].trim() == "true" {
+                Some(AccessControlAllowCredentials)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl HeaderFormat for AccessControlAllowCredentials {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write(b"true")
+    }
+}
+
+/// The `Access-Control-Max-Age` header, telling how many seconds the
+/// result of a preflight request can be cached.
+#[deriving(Copy, Clone, PartialEq, Show)]
+pub struct AccessControlMaxAge(pub u32);
+
+deref!(AccessControlMaxAge -> u32);
+
+impl Header for AccessControlMaxAge {
+    fn header_name(_: Option<AccessControlMaxAge>) -> &'static str {
+        "Access-Control-Max-Age"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<AccessControlMaxAge> {
+        from_one_raw_str::<String>(raw).and_then(|s| from_str(s[].trim())).map(AccessControlMaxAge)
+    }
+}
+
+impl HeaderFormat for AccessControlMaxAge {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+/// The `Access-Control-Request-Method` header of a preflight request, with
+/// the method of the actual request.
+#[deriving(Clone, PartialEq, Show)]
+pub struct AccessControlRequestMethod(pub Method);
+
+deref!(AccessControlRequestMethod -> Method);
+
+impl Header for AccessControlRequestMethod {
+    fn header_name(_: Option<AccessControlRequestMethod>) -> &'static str {
+        "Access-Control-Request-Method"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<AccessControlRequestMethod> {
+        from_one_raw_str::<String>(raw).and_then(|s| from_str(s[].trim()))
+            .map(AccessControlRequestMethod)
+    }
+}
+
+impl HeaderFormat for AccessControlRequestMethod {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+macro_rules! list_header(
+    ($(#[$attr:meta])* $name:ident, $header:expr, $item:ty) => {
+        $(#[$attr])*
+        #[deriving(Clone, PartialEq, Show)]
+        pub struct $name(pub Vec<$item>);
+
+        deref!($name -> Vec<$item>);
+
+        impl Header for $name {
+            fn header_name(_: Option<$name>) -> &'static str {
+                $header
+            }
+
+            fn parse_header(raw: &[Vec<u8>]) -> Option<$name> {
+                from_comma_list(raw).map($name)
+            }
+        }
+
+        impl HeaderFormat for $name {
+            fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+                fmt_comma_delimited(fmt, self[])
+            }
+        }
+    }
+);
+
+list_header!(
+    /// The `Access-Control-Allow-Methods` header of a preflight response,
+    /// with the methods allowed in cross-origin requests.
+    AccessControlAllowMethods, "Access-Control-Allow-Methods", Method);
+
+list_header!(
+    /// The `Access-Control-Allow-Headers` header of a preflight response,
+    /// with the request headers allowed in cross-origin requests.
+    AccessControlAllowHeaders, "Access-Control-Allow-Headers", String);
+
+list_header!(
+    /// The `Access-Control-Expose-Headers` header, with the response headers
+    /// scripts may read.
+    AccessControlExposeHeaders, "Access-Control-Expose-Headers", String);
+
+list_header!(
+    /// The `Access-Control-Request-Headers` header of a preflight request,
+    /// with the headers the actual request will have.
+    AccessControlRequestHeaders, "Access-Control-Request-Headers", String);
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use method::Method::{Get, Put};
+    use super::{AccessControlAllowOrigin, AccessControlAllowCredentials, AccessControlAllowMethods,
+                AccessControlRequestHeaders, AccessControlMaxAge};
+
+    #[test]
+    fn test_allow_origin() {
+        let origin: Option<AccessControlAllowOrigin> = Header::parse_header([b"*".to_vec()][]);
+        assert_eq!(origin, Some(AccessControlAllowOrigin::Any));
+        let origin: Option<AccessControlAllowOrigin> =
+            Header::parse_header([b"https://example.domain".to_vec()][]);
+        assert_eq!(origin, Some(AccessControlAllowOrigin::Value("https://example.domain".to_string())));
+    }
+
+    #[test]
+    fn test_allow_credentials() {
+        let credentials: Option<AccessControlAllowCredentials> =
+            Header::parse_header([b"true".to_vec()][]);
+        assert_eq!(credentials, Some(AccessControlAllowCredentials));
+        let credentials: Option<AccessControlAllowCredentials> =
+            Header::parse_header([b"True".to_vec()][]);
+        assert_eq!(credentials, None);
+    }
+
+    #[test]
+    fn test_lists() {
+        let methods: Option<AccessControlAllowMethods> = Header::parse_header([b"GET, PUT".to_vec()][]);
+        assert_eq!(methods, Some(AccessControlAllowMethods(vec![Get, Put])));
+        let headers: Option<AccessControlRequestHeaders> =
+            Header::parse_header([b"x-foo,content-type".to_vec()][]);
+        let headers = headers.unwrap();
+        assert_eq!(headers, AccessControlRequestHeaders(vec!["x-foo".to_string(),
+                                                             "content-type".to_string()]));
+        assert_eq!(format!("{}", HeaderFormatter(&headers))[], "x-foo, content-type");
+    }
+
+    #[test]
+    fn test_max_age() {
+        let max_age: Option<AccessControlMaxAge> = Header::parse_header([b"600".to_vec()][]);
+        assert_eq!(max_age, Some(AccessControlMaxAge(600)));
+    }
+}
+
+bench_header!(allow_origin, AccessControlAllowOrigin, { vec![b"https://example.domain".to_vec()] });
+bench_header!(allow_methods, AccessControlAllowMethods, { vec![b"GET, PUT, DELETE".to_vec()] });
@@ -0,0 +1,59 @@
+use std::fmt::{mod, Show};
+use std::str::from_str;
+use header::{Header, HeaderFormat};
+use super::util::from_one_raw_str;
+
+/// The `Age` header field, with how many seconds a response has been in
+/// a cache.
+///
+/// Ages too large to be represented are kept as `2147483648`, as the spec
+/// asks.
+///
+/// See https://tools.ietf.org/html/rfc7234#section-5.1
+#[deriving(Copy, PartialEq, Clone, Show)]
+pub struct Age(pub u64);
+
+deref!(Age -> u64);
+
+/// The largest `Age` that is sent, 2^31 seconds.
+pub const MAX_AGE: u64 = 2147483648;
+
+impl Header for Age {
+    fn header_name(_: Option<Age>) -> &'static str {
+        "Age"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<Age> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let s = s[].trim();
+            if s.is_empty() || !s.chars().all(|c| c.is_digit(10)) {
+                return None;
+            }
+            Some(Age(from_str::<u64>(s).map_or(MAX_AGE, |secs| secs.min(MAX_AGE))))
+        })
+    }
+}
+
+impl HeaderFormat for Age {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.min(MAX_AGE).fmt(fmt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use super::{Age, MAX_AGE};
+
+    #[test]
+    fn test_parse_header() {
+        let age: Option<Age> = Header::parse_header([b"60".to_vec()][]);
+        assert_eq!(age, Some(Age(60)));
+        let age: Option<Age> = Header::parse_header([b"99999999999999999999999".to_vec()][]);
+        assert_eq!(age, Some(Age(MAX_AGE)));
+        let age: Option<Age> = Header::parse_header([b"-1".to_vec()][]);
+        assert_eq!(age, None);
+    }
+}
+
+bench_header!(bench, Age, { vec![b"3600".to_vec()] });
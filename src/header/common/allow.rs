@@ -30,7 +30,7 @@ impl HeaderFormat for Allow {
 #[cfg(test)]
 mod tests {
     use super::Allow;
-    use header::Header;
+    use header::{Header, HeaderFormatter};
     use method::Method::{mod, Options, Get, Put, Post, Delete, Head, Trace, Connect, Patch, Extension};
 
     #[test]
@@ -42,6 +42,18 @@ mod tests {
 
         allow = Header::parse_header([b"".to_vec()].as_slice());
         assert_eq!(allow, Some(Allow(Vec::<Method>::new())));
+
+        allow = Header::parse_header([b"GET, PUT".to_vec(), b"POST".to_vec()].as_slice());
+        assert_eq!(allow, Some(Allow(vec![Get, Put, Post])));
+    }
+
+    #[test]
+    fn test_allow_fmt() {
+        let allow = Allow(vec![Get, Head, Extension("PURGE".to_string())]);
+        assert_eq!(format!("{}", HeaderFormatter(&allow))[], "GET, HEAD, PURGE");
+
+        let allow: Option<Allow> = Header::parse_header([b"GET, HEAD, PURGE".to_vec()].as_slice());
+        assert_eq!(allow, Some(Allow(vec![Get, Head, Extension("PURGE".to_string())])));
     }
 }
 
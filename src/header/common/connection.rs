@@ -1,6 +1,7 @@
-use header::{Header, HeaderFormat};
+use header::{Header, HeaderFormat, Headers};
+use std::ascii::AsciiExt;
 use std::fmt::{mod, Show};
-use std::str::FromStr;
+use std::str::{FromStr, from_str};
 use super::util::{from_comma_delimited, fmt_comma_delimited};
 
 pub use self::ConnectionOption::{KeepAlive, Close, ConnectionHeader};
@@ -11,6 +12,30 @@ pub struct Connection(pub Vec<ConnectionOption>);
 
 deref!(Connection -> Vec<ConnectionOption>);
 
+impl Connection {
+    /// Whether `token`, such as `upgrade` or `keep-alive`, is listed.
+    /// Tokens are compared ignoring case.
+    pub fn has(&self, token: &str) -> bool {
+        self.iter().any(|option| option.is(token))
+    }
+
+    /// Add a connection token, unless it is already there.
+    pub fn add(&mut self, token: &str) {
+        if !self.has(token) {
+            self.push(from_str(token).unwrap());
+        }
+    }
+
+    /// Add a connection token to the `Connection` header of `headers`,
+    /// setting one if there is none.
+    pub fn add_to(headers: &mut Headers, token: &str) {
+        if let Some(conn) = headers.get_mut::<Connection>() {
+            return conn.add(token);
+        }
+        headers.set(Connection(vec![from_str(token).unwrap()]));
+    }
+}
+
 /// Values that can be in the `Connection` header.
 #[deriving(Clone, PartialEq)]
 pub enum ConnectionOption {
@@ -29,12 +54,26 @@ pub enum ConnectionOption {
     ConnectionHeader(String),
 }
 
+impl ConnectionOption {
+    /// Whether this is the connection token `token`, ignoring case.
+    pub fn is(&self, token: &str) -> bool {
+        match *self {
+            KeepAlive => token.eq_ignore_ascii_case("keep-alive"),
+            Close => token.eq_ignore_ascii_case("close"),
+            ConnectionHeader(ref s) => s[].eq_ignore_ascii_case(token)
+        }
+    }
+}
+
 impl FromStr for ConnectionOption {
     fn from_str(s: &str) -> Option<ConnectionOption> {
-        match s {
-            "keep-alive" => Some(KeepAlive),
-            "close" => Some(Close),
-            s => Some(ConnectionHeader(s.to_string()))
+        // connection options are case-insensitive
+        if s.eq_ignore_ascii_case("keep-alive") {
+            Some(KeepAlive)
+        } else if s.eq_ignore_ascii_case("close") {
+            Some(Close)
+        } else {
+            Some(ConnectionHeader(s.to_string()))
         }
     }
 }
@@ -66,6 +105,37 @@ impl HeaderFormat for Connection {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers, HeaderFormatter};
+    use super::{Connection, KeepAlive, Close, ConnectionHeader};
+
+    #[test]
+    fn test_parse() {
+        let conn: Option<Connection> = Header::parse_header([b"Keep-Alive, Upgrade".to_vec()][]);
+        let conn = conn.unwrap();
+        assert_eq!(conn, Connection(vec![KeepAlive, ConnectionHeader("Upgrade".to_string())]));
+        assert!(conn.has("upgrade"));
+        assert!(conn.has("keep-alive"));
+        assert!(!conn.has("close"));
+
+        let conn: Option<Connection> = Header::parse_header([b"CLOSE".to_vec()][]);
+        assert_eq!(conn, Some(Connection(vec![Close])));
+    }
+
+    #[test]
+    fn test_add() {
+        let mut headers = Headers::new();
+        Connection::add_to(&mut headers, "upgrade");
+        Connection::add_to(&mut headers, "TE");
+        Connection::add_to(&mut headers, "Upgrade");
+        Connection::add_to(&mut headers, "close");
+        let conn = headers.get::<Connection>().unwrap();
+        assert_eq!(format!("{}", HeaderFormatter(conn))[], "upgrade, TE, close");
+        assert!(conn.contains(&Close));
+    }
+}
+
 bench_header!(close, Connection, { vec![b"close".to_vec()] });
 bench_header!(keep_alive, Connection, { vec![b"keep-alive".to_vec()] });
 bench_header!(header, Connection, { vec![b"authorization".to_vec()] });
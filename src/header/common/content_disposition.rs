@@ -0,0 +1,205 @@
+use header::{Header, HeaderFormat};
+use header::shared::Params;
+use std::ascii::AsciiExt;
+use std::fmt::{mod, Show};
+use std::str::FromStr;
+use super::util::{from_one_raw_str, split_unquoted, unquote, fmt_quoted};
+use super::util::{decode_ext_value, fmt_ext_value};
+
+use self::DispositionType::{Inline, Attachment, FormData, DispositionExt};
+
+/// The `Content-Disposition` header, telling whether a body is shown
+/// inline or downloaded, and its file name.
+///
+/// Parameters whose names end in `*`, like `filename*`, are extended values
+/// with a charset, as described in RFC 5987. They are kept decoded, and sent
+/// as percent-encoded UTF-8.
+///
+/// ```notrust
+/// Content-Disposition: attachment; filename="EURO rates"; filename*=UTF-8''%e2%82%ac%20rates
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc6266
+#[deriving(Clone, PartialEq, Show)]
+pub struct ContentDisposition {
+    /// How the body is shown.
+    pub disposition: DispositionType,
+    /// The parameters, with their values unquoted and decoded.
+    pub params: Params
+}
+
+/// A disposition type of the `Content-Disposition` header.
+#[deriving(Clone, PartialEq)]
+pub enum DispositionType {
+    /// `inline`, shown as part of the page.
+    Inline,
+    /// `attachment`, downloaded as a file.
+    Attachment,
+    /// `form-data`, a field of a `multipart/form-data` body.
+    FormData,
+    /// Some other type.
+    DispositionExt(String)
+}
+
+impl FromStr for DispositionType {
+    fn from_str(s: &str) -> Option<DispositionType> {
+        let lower = s.to_ascii_lower();
+        match lower[] {
+            "" => None,
+            "inline" => Some(Inline),
+            "attachment" => Some(Attachment),
+            "form-data" => Some(FormData),
+            _ => Some(DispositionExt(s.to_string()))
+        }
+    }
+}
+
+impl Show for DispositionType {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Inline => "inline",
+            Attachment => "attachment",
+            FormData => "form-data",
+            DispositionExt(ref s) => s[]
+        }.fmt(fmt)
+    }
+}
+
+impl ContentDisposition {
+    /// An `attachment` to be saved as `filename`.
+    ///
+    /// A name that is not printable ASCII is sent as `filename*`, with an
+    /// ASCII `filename` for older clients.
+    pub fn attachment(filename: &str) -> ContentDisposition {
+        let mut params = Params::new();
+        if filename.chars().all(is_printable) {
+            params.push("filename", filename);
+        } else {
+            let fallback: String = filename.chars().map(|c| if is_printable(c) { c } else { '_' }).collect();
+            params.push("filename", fallback[]);
+            params.push("filename*", filename);
+        }
+        ContentDisposition {
+            disposition: Attachment,
+            params: params
+        }
+    }
+
+    /// The value of a parameter, whose name is case-insensitive.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params.get(name)
+    }
+
+    /// The file name, preferring `filename*` over `filename`.
+    pub fn filename(&self) -> Option<&str> {
+        self.get_param("filename*").or_else(|| self.get_param("filename"))
+    }
+
+    /// The name of the form field, for `form-data`.
+    pub fn name(&self) -> Option<&str> {
+        self.get_param("name")
+    }
+}
+
+fn is_printable(c: char) -> bool {
+    c.is_ascii() && !c.is_control()
+}
+
+impl Header for ContentDisposition {
+    fn header_name(_: Option<ContentDisposition>) -> &'static str {
+        "Content-Disposition"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<ContentDisposition> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let mut parts = split_unquoted(s[], ';').into_iter();
+            let disposition = match parts.next().and_then(|s| s.trim().parse()) {
+                Some(disposition) => disposition,
+                None => return None
+            };
+            let mut params = Params::new();
+            for part in parts {
+                let part = part.trim();
+                if part.is_empty() {
+                    continue;
+                }
+                let (name, value) = match part.find('=') {
+                    Some(i) => (part[..i].trim(), part[i + 1..]),
+                    None => return None
+                };
+                let value = if name.ends_with("*") { decode_ext_value(value) } else { unquote(value) };
+                match value {
+                    Some(value) => params.push(name, value[]),
+                    None => return None
+                }
+            }
+            Some(ContentDisposition {
+                disposition: disposition,
+                params: params
+            })
+        })
+    }
+}
+
+impl HeaderFormat for ContentDisposition {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(self.disposition.fmt(fmt));
+        for &(ref name, ref value) in self.params.iter() {
+            try!(write!(fmt, "; {}=", name));
+            if name[].ends_with("*") {
+                try!(fmt_ext_value(fmt, value[]));
+            } else {
+                try!(fmt_quoted(fmt, value[]));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::ContentDisposition;
+    use super::DispositionType::{Attachment, FormData};
+
+    fn parse(s: &str) -> Option<ContentDisposition> {
+        Header::parse_header([s.as_bytes().to_vec()][])
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let disposition = parse("form-data; name=upload; filename=\"a; b.txt\"").unwrap();
+        assert_eq!(disposition.disposition, FormData);
+        assert_eq!(disposition.name(), Some("upload"));
+        assert_eq!(disposition.filename(), Some("a; b.txt"));
+
+        let disposition = parse("Attachment; filename=\"EURO rates\"; \
+                                 filename*=utf-8''%e2%82%ac%20rates").unwrap();
+        assert_eq!(disposition.disposition, Attachment);
+        assert_eq!(disposition.filename(), Some("€ rates"));
+
+        let disposition = parse("attachment; filename*=iso-8859-1'en'%A3%20rates").unwrap();
+        assert_eq!(disposition.filename(), Some("£ rates"));
+
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("attachment; filename"), None);
+        assert_eq!(parse("attachment; filename*=UTF-8''%ff"), None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let disposition = ContentDisposition::attachment("report.pdf");
+        assert_eq!(format!("{}", HeaderFormatter(&disposition))[],
+                   "attachment; filename=\"report.pdf\"");
+
+        let disposition = ContentDisposition::attachment("€ rates");
+        assert_eq!(format!("{}", HeaderFormatter(&disposition))[],
+                   "attachment; filename=\"_ rates\"; filename*=UTF-8''%E2%82%AC%20rates");
+        let formatted = format!("{}", HeaderFormatter(&disposition));
+        assert_eq!(parse(formatted[]), Some(disposition));
+    }
+}
+
+bench_header!(bench, ContentDisposition, {
+    vec![b"attachment; filename=\"EURO rates\"; filename*=UTF-8''%e2%82%ac%20rates".to_vec()]
+});
@@ -0,0 +1,50 @@
+use header::{Header, HeaderFormat};
+use header::shared::Encoding;
+use std::fmt;
+use super::util::{from_comma_delimited, fmt_comma_delimited};
+
+/// The `Content-Encoding` header, listing the codings applied to the body,
+/// in the order they were applied.
+///
+/// ```notrust
+/// Content-Encoding: gzip
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7231#section-3.1.2.2
+#[deriving(Clone, PartialEq, Show)]
+pub struct ContentEncoding(pub Vec<Encoding>);
+
+deref!(ContentEncoding -> Vec<Encoding>);
+
+impl Header for ContentEncoding {
+    fn header_name(_: Option<ContentEncoding>) -> &'static str {
+        "Content-Encoding"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<ContentEncoding> {
+        from_comma_delimited(raw).map(ContentEncoding)
+    }
+}
+
+impl HeaderFormat for ContentEncoding {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use header::shared::Encoding::{Gzip, EncodingExt};
+    use super::ContentEncoding;
+
+    #[test]
+    fn test_parse_header() {
+        let encoding: Option<ContentEncoding> = Header::parse_header([b"gzip".to_vec()][]);
+        assert_eq!(encoding, Some(ContentEncoding(vec![Gzip])));
+        let encoding: Option<ContentEncoding> = Header::parse_header([b"gzip, br".to_vec()][]);
+        assert_eq!(encoding, Some(ContentEncoding(vec![Gzip, EncodingExt("br".to_string())])));
+    }
+}
+
+bench_header!(bench, ContentEncoding, { vec![b"gzip".to_vec()] });
@@ -0,0 +1,97 @@
+use header::{Header, HeaderFormat};
+use std::ascii::AsciiExt;
+use std::fmt::{mod, Show};
+use std::str::FromStr;
+use super::util::{from_comma_delimited, fmt_comma_delimited};
+
+/// The `Content-Language` header, listing the languages of the intended
+/// audience of the body.
+///
+/// ```notrust
+/// Content-Language: mi, en
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7231#section-3.1.3.2
+#[deriving(Clone, PartialEq, Show)]
+pub struct ContentLanguage(pub Vec<LanguageTag>);
+
+deref!(ContentLanguage -> Vec<LanguageTag>);
+
+/// A language tag, like `en` or `zh-Hant-TW`, made of subtags of up to 8
+/// letters and digits.
+///
+/// Tags are compared ignoring case.
+#[deriving(Clone)]
+pub struct LanguageTag(pub String);
+
+impl LanguageTag {
+    /// The primary language subtag, like `zh` in `zh-Hant-TW`.
+    pub fn primary(&self) -> &str {
+        self.0[].split('-').next().unwrap_or("")
+    }
+}
+
+impl PartialEq for LanguageTag {
+    fn eq(&self, other: &LanguageTag) -> bool {
+        self.0[].eq_ignore_ascii_case(other.0[])
+    }
+}
+
+impl FromStr for LanguageTag {
+    fn from_str(s: &str) -> Option<LanguageTag> {
+        let valid = !s.is_empty() && s.split('-').all(|part| {
+            !part.is_empty() && part.len() <= 8 &&
+                part.chars().all(|c| c.is_alphanumeric() && c.is_ascii())
+        });
+        if valid {
+            Some(LanguageTag(s.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Show for LanguageTag {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+impl Header for ContentLanguage {
+    fn header_name(_: Option<ContentLanguage>) -> &'static str {
+        "Content-Language"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<ContentLanguage> {
+        from_comma_delimited(raw).and_then(|tags: Vec<LanguageTag>| {
+            if tags.is_empty() {
+                None
+            } else {
+                Some(ContentLanguage(tags))
+            }
+        })
+    }
+}
+
+impl HeaderFormat for ContentLanguage {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::{ContentLanguage, LanguageTag};
+
+    #[test]
+    fn test_parse_header() {
+        let language: ContentLanguage = Header::parse_header([b"mi, EN-nz".to_vec()][]).unwrap();
+        assert_eq!(language, ContentLanguage(vec![LanguageTag("mi".to_string()),
+                                                  LanguageTag("en-NZ".to_string())]));
+        assert_eq!(language[1].primary(), "EN");
+        assert_eq!(format!("{}", HeaderFormatter(&language))[], "mi, EN-nz");
+    }
+}
+
+bench_header!(bench, ContentLanguage, { vec![b"mi, en".to_vec()] });
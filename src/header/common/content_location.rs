@@ -0,0 +1,71 @@
+use header::{Header, HeaderFormat};
+use std::fmt::{mod, Show};
+use url::{Url, UrlParser};
+use super::util::from_one_raw_str;
+
+/// The `Content-Location` header, with a URI reference for the
+/// representation in the body.
+///
+/// The reference can be relative, so it is kept as it was sent, and can be
+/// resolved against the request URL with `resolve`.
+///
+/// See https://tools.ietf.org/html/rfc7231#section-3.1.4.2
+#[deriving(Clone, PartialEq, Show)]
+pub struct ContentLocation(pub String);
+
+deref!(ContentLocation -> String);
+
+impl ContentLocation {
+    /// The absolute URL of the reference, relative to `base`.
+    pub fn resolve(&self, base: &Url) -> Option<Url> {
+        UrlParser::new().base_url(base).parse(self.0[]).ok()
+    }
+}
+
+impl Header for ContentLocation {
+    fn header_name(_: Option<ContentLocation>) -> &'static str {
+        "Content-Location"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<ContentLocation> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let s = s[].trim();
+            if s.is_empty() || s.contains_char(' ') {
+                None
+            } else {
+                Some(ContentLocation(s.to_string()))
+            }
+        })
+    }
+}
+
+impl HeaderFormat for ContentLocation {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+    use header::Header;
+    use super::ContentLocation;
+
+    #[test]
+    fn test_parse_header() {
+        let location: Option<ContentLocation> = Header::parse_header([b"/docs/en".to_vec()][]);
+        assert_eq!(location, Some(ContentLocation("/docs/en".to_string())));
+        let location: Option<ContentLocation> = Header::parse_header([b"".to_vec()][]);
+        assert_eq!(location, None);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let base = Url::parse("http://example.domain/docs/index?lang=auto").unwrap();
+        let location = ContentLocation("en/index".to_string());
+        assert_eq!(location.resolve(&base).unwrap().to_string()[],
+                   "http://example.domain/docs/en/index");
+    }
+}
+
+bench_header!(bench, ContentLocation, { vec![b"/docs/en/index".to_vec()] });
@@ -0,0 +1,118 @@
+use header::{Header, HeaderFormat};
+use std::fmt;
+use std::str::from_str;
+use super::util::from_one_raw_str;
+
+/// The `Content-Range` header, of a `206 Partial Content` or
+/// `416 Range Not Satisfiable` response.
+///
+/// See https://tools.ietf.org/html/rfc7233#section-4.2
+#[deriving(Copy, Clone, PartialEq, Show)]
+pub struct ContentRange {
+    /// The first and last byte sent, inclusive, or `None` for `*`, when the
+    /// range was not satisfiable.
+    pub range: Option<(u64, u64)>,
+    /// The length of the whole representation, or `None` for `*`, if it is
+    /// not known.
+    pub length: Option<u64>
+}
+
+impl Header for ContentRange {
+    fn header_name(_: Option<ContentRange>) -> &'static str {
+        "Content-Range"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<ContentRange> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let s = s[].trim();
+            if !s.starts_with("bytes ") {
+                return None;
+            }
+            let mut parts = s[6..].splitn(1, '/');
+            let (range, length) = match (parts.next(), parts.next()) {
+                (Some(range), Some(length)) => (range.trim(), length.trim()),
+                _ => return None
+            };
+
+            let length = match length {
+                "*" => None,
+                length => match from_str(length) {
+                    Some(length) => Some(length),
+                    None => return None
+                }
+            };
+            let range = match range {
+                "*" => None,
+                range => {
+                    let mut bounds = range.splitn(1, '-').map(|n| from_str::<u64>(n));
+                    match (bounds.next(), bounds.next()) {
+                        (Some(Some(first)), Some(Some(last))) if first <= last => {
+                            Some((first, last))
+                        },
+                        _ => return None
+                    }
+                }
+            };
+
+            match (range, length) {
+                (None, None) => None,
+                (Some((_, last)), Some(length)) if last >= length => None,
+                _ => Some(ContentRange {
+                    range: range,
+                    length: length
+                })
+            }
+        })
+    }
+}
+
+impl HeaderFormat for ContentRange {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(fmt.write(b"bytes "));
+        match self.range {
+            Some((first, last)) => try!(write!(fmt, "{}-{}", first, last)),
+            None => try!(fmt.write(b"*"))
+        }
+        match self.length {
+            Some(length) => write!(fmt, "/{}", length),
+            None => fmt.write(b"/*")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::ContentRange;
+
+    fn parse(s: &str) -> Option<ContentRange> {
+        Header::parse_header([s.as_bytes().to_vec()][])
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(parse("bytes 0-499/1234"), Some(ContentRange {
+            range: Some((0, 499)),
+            length: Some(1234)
+        }));
+        assert_eq!(parse("bytes */1234"), Some(ContentRange { range: None, length: Some(1234) }));
+        assert_eq!(parse("bytes 0-499/*"), Some(ContentRange {
+            range: Some((0, 499)),
+            length: None
+        }));
+        assert_eq!(parse("bytes */*"), None);
+        assert_eq!(parse("bytes 0-1234/1234"), None);
+        assert_eq!(parse("bytes 5-1/10"), None);
+        assert_eq!(parse("items 0-1/10"), None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let range = ContentRange { range: Some((0, 499)), length: Some(1234) };
+        assert_eq!(format!("{}", HeaderFormatter(&range))[], "bytes 0-499/1234");
+        let range = ContentRange { range: None, length: Some(1234) };
+        assert_eq!(format!("{}", HeaderFormatter(&range))[], "bytes */1234");
+    }
+}
+
+bench_header!(bench, ContentRange, { vec![b"bytes 0-499/1234".to_vec()] });
@@ -0,0 +1,136 @@
+use header::{Header, HeaderFormat};
+use std::ascii::AsciiExt;
+use std::fmt;
+use super::util::from_one_raw_str;
+
+/// The `Content-Security-Policy` header, restricting where a page may load
+/// scripts, styles, frames and other resources from.
+///
+/// Each directive names a kind of resource and lists its allowed sources,
+/// in the order they were added.
+///
+/// ```notrust
+/// Content-Security-Policy: default-src 'self'; img-src 'self' https://img.example
+/// ```
+///
+/// See https://www.w3.org/TR/CSP2/#content-security-policy-header-field
+#[deriving(Clone, PartialEq, Show)]
+pub struct ContentSecurityPolicy {
+    directives: Vec<(String, Vec<String>)>
+}
+
+impl ContentSecurityPolicy {
+    /// A policy without directives.
+    pub fn new() -> ContentSecurityPolicy {
+        ContentSecurityPolicy {
+            directives: Vec::new()
+        }
+    }
+
+    /// Set the sources of the directive `name`, such as
+    /// `directive("script-src", &["'self'", "https://cdn.example"])`.
+    ///
+    /// Directive names are compared ignoring case, and setting a directive
+    /// again replaces its sources.
+    pub fn directive(mut self, name: &str, sources: &[&str]) -> ContentSecurityPolicy {
+        let name = name.to_ascii_lower();
+        let sources = sources.iter().map(|s| s.to_string()).collect();
+        match self.directives.iter().position(|&(ref n, _)| *n == name) {
+            Some(pos) => self.directives[pos].1 = sources,
+            None => self.directives.push((name, sources))
+        }
+        self
+    }
+
+    /// The sources of the directive `name`.
+    pub fn get_directive(&self, name: &str) -> Option<&[String]> {
+        self.directives.iter()
+            .find(|&&(ref n, _)| n[].eq_ignore_ascii_case(name))
+            .map(|&(_, ref sources)| sources[])
+    }
+
+    /// The directives, in order.
+    pub fn directives(&self) -> &[(String, Vec<String>)] {
+        self.directives[]
+    }
+}
+
+impl Header for ContentSecurityPolicy {
+    fn header_name(_: Option<ContentSecurityPolicy>) -> &'static str {
+        "Content-Security-Policy"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<ContentSecurityPolicy> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let mut directives: Vec<(String, Vec<String>)> = Vec::new();
+            for directive in s[].split(';').map(|d| d.trim()).filter(|d| !d.is_empty()) {
+                let mut tokens = directive.split(|c: char| c == ' ' || c == '\t')
+                    .filter(|t| !t.is_empty());
+                let name = match tokens.next() {
+                    Some(name) => name.to_ascii_lower(),
+                    None => continue
+                };
+                // only the first of repeated directives is used
+                if directives.iter().any(|&(ref n, _)| *n == name) {
+                    continue;
+                }
+                directives.push((name, tokens.map(|t| t.to_string()).collect()));
+            }
+            if directives.is_empty() {
+                None
+            } else {
+                Some(ContentSecurityPolicy { directives: directives })
+            }
+        })
+    }
+}
+
+impl HeaderFormat for ContentSecurityPolicy {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &(ref name, ref sources)) in self.directives.iter().enumerate() {
+            if i != 0 {
+                try!(fmt.write(b"; "));
+            }
+            try!(fmt.write_str(name[]));
+            for source in sources.iter() {
+                try!(write!(fmt, " {}", source));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::ContentSecurityPolicy;
+
+    #[test]
+    fn test_parse() {
+        let csp: Option<ContentSecurityPolicy> = Header::parse_header(
+            [b"default-src 'self'; IMG-SRC 'self'  https://img.example;; img-src *; upgrade-insecure-requests".to_vec()][]);
+        let csp = csp.unwrap();
+        assert_eq!(csp.get_directive("default-src"), Some(["'self'".to_string()][]));
+        assert_eq!(csp.get_directive("img-src"),
+                   Some(["'self'".to_string(), "https://img.example".to_string()][]));
+        assert_eq!(csp.get_directive("upgrade-insecure-requests"), Some([][]));
+        assert_eq!(csp.get_directive("script-src"), None);
+        assert_eq!(csp.directives().len(), 3);
+
+        let csp: Option<ContentSecurityPolicy> = Header::parse_header([b" ; ".to_vec()][]);
+        assert_eq!(csp, None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let csp = ContentSecurityPolicy::new()
+            .directive("default-src", &["'none'"])
+            .directive("Script-Src", &["'self'", "https://cdn.example"])
+            .directive("default-src", &["'self'"])
+            .directive("block-all-mixed-content", &[]);
+        assert_eq!(format!("{}", HeaderFormatter(&csp))[],
+                   "default-src 'self'; script-src 'self' https://cdn.example; block-all-mixed-content");
+    }
+}
+
+bench_header!(bench, ContentSecurityPolicy, { vec![b"default-src 'self'; img-src *".to_vec()] });
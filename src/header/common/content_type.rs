@@ -1,4 +1,5 @@
 use header::{Header, HeaderFormat};
+use std::ascii::AsciiExt;
 use std::fmt::{mod, Show};
 use super::util::from_one_raw_str;
 use mime::Mime;
@@ -7,6 +8,13 @@ use mime::Mime;
 ///
 /// Used to describe the MIME type of message body. Can be used with both
 /// requests and responses.
+///
+/// ```
+/// # use hyper::header::common::ContentType;
+/// let content_type = ContentType::multipart("x0x0").unwrap();
+/// assert_eq!(content_type.boundary(), Some("x0x0".to_string()));
+/// assert_eq!(content_type.charset(), None);
+/// ```
 #[deriving(Clone, PartialEq, Show)]
 pub struct ContentType(pub Mime);
 
@@ -22,6 +30,65 @@ impl Header for ContentType {
     }
 }
 
+impl ContentType {
+    /// `application/json`
+    pub fn json() -> ContentType {
+        ContentType("application/json".parse().unwrap())
+    }
+
+    /// `application/x-www-form-urlencoded`
+    pub fn form_urlencoded() -> ContentType {
+        ContentType("application/x-www-form-urlencoded".parse().unwrap())
+    }
+
+    /// `multipart/form-data` with a `boundary`.
+    ///
+    /// Returns `None` if the boundary is not made of `token` characters.
+    pub fn multipart(boundary: &str) -> Option<ContentType> {
+        if boundary.is_empty() {
+            return None;
+        }
+        let mime: Mime = match format!("multipart/form-data; boundary={}", boundary).parse() {
+            Some(mime) => mime,
+            None => return None
+        };
+        // a boundary with `;` or `=` would parse as other parameters
+        if mime.2.len() == 1 && mime.2[0].1.to_string()[] == boundary {
+            Some(ContentType(mime))
+        } else {
+            None
+        }
+    }
+
+    /// `text/plain; charset=utf-8`
+    pub fn plaintext() -> ContentType {
+        ContentType("text/plain; charset=utf-8".parse().unwrap())
+    }
+
+    /// `text/html; charset=utf-8`
+    pub fn html() -> ContentType {
+        ContentType("text/html; charset=utf-8".parse().unwrap())
+    }
+
+    /// Get the value of a parameter, with a case-insensitive name.
+    pub fn get_param(&self, name: &str) -> Option<String> {
+        let ContentType(ref mime) = *self;
+        mime.2.iter()
+            .find(|&&(ref attr, _)| attr.to_string()[].eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| value.to_string())
+    }
+
+    /// The `charset` parameter.
+    pub fn charset(&self) -> Option<String> {
+        self.get_param("charset")
+    }
+
+    /// The `boundary` parameter of a multipart type.
+    pub fn boundary(&self) -> Option<String> {
+        self.get_param("boundary")
+    }
+}
+
 impl HeaderFormat for ContentType {
     fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         let ContentType(ref value) = *self;
@@ -29,5 +96,32 @@ impl HeaderFormat for ContentType {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use super::ContentType;
+
+    #[test]
+    fn test_params() {
+        let content_type: ContentType = Header::parse_header(
+            [b"text/html; Charset=UTF-8; level=1".to_vec()][]).unwrap();
+        assert_eq!(content_type.charset().map(|c| c.to_ascii_lower()), Some("utf-8".to_string()));
+        assert_eq!(content_type.get_param("level"), Some("1".to_string()));
+        assert_eq!(content_type.get_param("boundary"), None);
+    }
+
+    #[test]
+    fn test_constructors() {
+        assert_eq!(ContentType::json().0.to_string()[], "application/json");
+        assert_eq!(ContentType::form_urlencoded().0.to_string()[],
+                   "application/x-www-form-urlencoded");
+        assert_eq!(ContentType::multipart("abc").unwrap().boundary(), Some("abc".to_string()));
+        assert_eq!(ContentType::multipart("a b"), None);
+        assert_eq!(ContentType::multipart("a; charset=utf-8"), None);
+        assert_eq!(ContentType::multipart(""), None);
+        assert_eq!(ContentType::html().charset().map(|c| c.to_ascii_lower()), Some("utf-8".to_string()));
+    }
+}
+
 bench_header!(bench, ContentType, { vec![b"application/json; charset=utf-8".to_vec()] });
 
@@ -1,5 +1,6 @@
 use header::{Header, HeaderFormat};
 use std::fmt::{mod};
+use std::str::{FromStr, from_str, from_utf8};
 use super::util::from_one_raw_str;
 
 /// The `Etag` header.
@@ -16,12 +17,22 @@ pub struct Etag {
     pub tag: String
 }
 
-impl Header for Etag {
-    fn header_name(_: Option<Etag>) -> &'static str {
-        "Etag"
+impl Etag {
+    /// The strong comparison: both tags are strong, and the same.
+    ///
+    /// See https://tools.ietf.org/html/rfc7232#section-2.3.2
+    pub fn strong_eq(&self, other: &Etag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> Option<Etag> {
+    /// The weak comparison: the tags are the same, even if either is weak.
+    pub fn weak_eq(&self, other: &Etag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl FromStr for Etag {
+    fn from_str(s: &str) -> Option<Etag> {
         // check that each char in the slice is either:
         // 1. %x21, or
         // 2. in the range %x23 to %x7E, or
@@ -36,46 +47,105 @@ impl Header for Etag {
             true
         }
 
+        let length: uint = s.len();
+        let slice = s;
 
-        from_one_raw_str(raw).and_then(|s: String| {
-            let length: uint = s.len();
-            let slice = s[];
+        // Early exits:
+        // 1. The string is empty, or,
+        // 2. it doesn't terminate in a DQUOTE.
+        if slice.is_empty() || !slice.ends_with("\"") {
+            return None;
+        }
 
-            // Early exits:
-            // 1. The string is empty, or,
-            // 2. it doesn't terminate in a DQUOTE.
-            if slice.is_empty() || !slice.ends_with("\"") {
+        // The etag is weak if its first char is not a DQUOTE.
+        if slice.char_at(0) == '"' {
+            // No need to check if the last char is a DQUOTE,
+            // we already did that above.
+            if check_slice_validity(slice.slice_chars(1, length-1)) {
+                return Some(Etag {
+                    weak: false,
+                    tag: slice.slice_chars(1, length-1).to_string()
+                });
+            } else {
                 return None;
             }
+        }
 
-            // The etag is weak if its first char is not a DQUOTE.
-            if slice.char_at(0) == '"' {
-                // No need to check if the last char is a DQUOTE,
-                // we already did that above.
-                if check_slice_validity(slice.slice_chars(1, length-1)) {
-                    return Some(Etag {
-                        weak: false,
-                        tag: slice.slice_chars(1, length-1).to_string()
-                    });
-                } else {
-                    return None;
-                }
+        if slice.starts_with("W/\"") {
+            if check_slice_validity(slice.slice_chars(3, length-1)) {
+                return Some(Etag {
+                    weak: true,
+                    tag: slice.slice_chars(3, length-1).to_string()
+                });
+            } else {
+                return None;
             }
+        }
 
-            if slice.slice_chars(0, 3) == "W/\"" {
-                if check_slice_validity(slice.slice_chars(3, length-1)) {
-                    return Some(Etag {
-                        weak: true,
-                        tag: slice.slice_chars(3, length-1).to_string()
-                    });
-                } else {
-                    return None;
-                }
+        None
+    }
+}
+
+impl Header for Etag {
+    fn header_name(_: Option<Etag>) -> &'static str {
+        "Etag"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<Etag> {
+        from_one_raw_str(raw)
+    }
+}
+
+/// Reads a comma-delimited list of entity tags, from any number of lines.
+///
+/// The tags are quoted strings that can contain commas, so they can't be
+/// split like other lists. An empty list is `None`.
+pub fn from_raw_list(raw: &[Vec<u8>]) -> Option<Vec<Etag>> {
+    let mut tags = Vec::new();
+    for line in raw.iter() {
+        let mut rest = match from_utf8(line[]) {
+            Ok(s) => s,
+            Err(_) => return None
+        };
+        loop {
+            rest = rest.trim_left_chars([',', ' ', '\t'][]);
+            if rest.is_empty() {
+                break;
+            }
+            let start = if rest.starts_with("W/\"") {
+                3
+            } else if rest.starts_with("\"") {
+                1
+            } else {
+                return None;
+            };
+            let end = match rest[start..].find('"') {
+                Some(i) => start + i + 1,
+                None => return None
+            };
+            match from_str::<Etag>(rest[..end]) {
+                Some(tag) => tags.push(tag),
+                None => return None
             }
+            rest = rest[end..];
+        }
+    }
+    if tags.is_empty() {
+        None
+    } else {
+        Some(tags)
+    }
+}
 
-            None
-        })
+/// Format a list of entity tags, separated by commas.
+pub fn fmt_list(fmt: &mut fmt::Formatter, tags: &[Etag]) -> fmt::Result {
+    for (i, tag) in tags.iter().enumerate() {
+        if i > 0 {
+            try!(fmt.write(b", "));
+        }
+        try!(tag.fmt_header(fmt));
     }
+    Ok(())
 }
 
 impl HeaderFormat for Etag {
@@ -89,7 +159,7 @@ impl HeaderFormat for Etag {
 
 #[cfg(test)]
 mod tests {
-    use super::Etag;
+    use super::{Etag, from_raw_list};
     use header::Header;
 
     #[test]
@@ -128,6 +198,32 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn test_etag_comparison() {
+        let strong = Etag { weak: false, tag: "1".to_string() };
+        let weak = Etag { weak: true, tag: "1".to_string() };
+        let other = Etag { weak: false, tag: "2".to_string() };
+        assert!(strong.strong_eq(&strong));
+        assert!(!strong.strong_eq(&weak));
+        assert!(!weak.strong_eq(&weak));
+        assert!(strong.weak_eq(&weak));
+        assert!(weak.weak_eq(&weak));
+        assert!(!strong.weak_eq(&other));
+    }
+
+    #[test]
+    fn test_from_raw_list() {
+        let tags = from_raw_list(&[b"\"a,b\", W/\"c\"".to_vec(), b"\"d\"".to_vec()]).unwrap();
+        assert_eq!(tags, vec![
+            Etag { weak: false, tag: "a,b".to_string() },
+            Etag { weak: true, tag: "c".to_string() },
+            Etag { weak: false, tag: "d".to_string() },
+        ]);
+        assert_eq!(from_raw_list(&[b"".to_vec()]), None);
+        assert_eq!(from_raw_list(&[b"\"a\", b".to_vec()]), None);
+        assert_eq!(from_raw_list(&[b"\"unterminated".to_vec()]), None);
+    }
+
     #[test]
     fn test_etag_failures() {
         // Expected failures
@@ -1,14 +1,26 @@
 use std::fmt::{mod, Show};
-use std::str::FromStr;
-use time::Tm;
+use std::str::{FromStr, from_str};
 use header::{Header, HeaderFormat};
-use super::util::{from_one_raw_str, tm_from_str};
+use super::HttpDate;
+use super::util::from_one_raw_str;
 
 /// The `Expires` header field.
+///
+/// An invalid date, such as `0`, means the response has already expired,
+/// so it parses as the Unix epoch instead of failing.
+///
+/// See https://tools.ietf.org/html/rfc7234#section-5.3
 #[deriving(Copy, PartialEq, Clone)]
-pub struct Expires(pub Tm);
+pub struct Expires(pub HttpDate);
 
-deref!(Expires -> Tm);
+deref!(Expires -> HttpDate);
+
+impl Expires {
+    /// Whether the response is stale at `now`, in seconds since the epoch.
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        self.seconds_from(now) <= 0
+    }
+}
 
 impl Header for Expires {
     fn header_name(_: Option<Expires>) -> &'static str {
@@ -23,21 +35,34 @@ impl Header for Expires {
 
 impl HeaderFormat for Expires {
     fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let tm = **self;
-        match tm.tm_utcoff {
-            0 => tm.rfc822().fmt(fmt),
-            _ => tm.to_utc().rfc822().fmt(fmt)
-        }
+        self.0.fmt(fmt)
     }
 }
 
 impl FromStr for Expires {
     fn from_str(s: &str) -> Option<Expires> {
-        tm_from_str(s).map(Expires)
+        Some(Expires(from_str(s).unwrap_or(HttpDate::from_seconds(0))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use super::Expires;
+
+    #[test]
+    fn test_parse_header() {
+        let expires: Expires = Header::parse_header([b"Sun, 06 Nov 1994 08:49:37 GMT".to_vec()][]).unwrap();
+        assert_eq!(expires.to_timespec().sec, 784111777);
+        assert!(!expires.is_expired_at(784111776));
+        assert!(expires.is_expired_at(784111777));
+
+        let expires: Expires = Header::parse_header([b"0".to_vec()][]).unwrap();
+        assert_eq!(expires.to_timespec().sec, 0);
+        assert!(expires.is_expired_at(0));
     }
 }
 
 bench_header!(imf_fixdate, Expires, { vec![b"Sun, 07 Nov 1994 08:48:37 GMT".to_vec()] });
 bench_header!(rfc_850, Expires, { vec![b"Sunday, 06-Nov-94 08:49:37 GMT".to_vec()] });
 bench_header!(asctime, Expires, { vec![b"Sun Nov  6 08:49:37 1994".to_vec()] });
-
@@ -0,0 +1,202 @@
+use header::{Header, HeaderFormat};
+use header::shared::Params;
+use std::fmt::{mod, Show};
+use std::io::net::ip::IpAddr;
+use std::str::{from_str, from_utf8};
+use super::util::{split_unquoted, unquote, fmt_quoted};
+
+/// The `Forwarded` header, describing the client and the proxies a request
+/// passed through, with the most recent last.
+///
+/// ```notrust
+/// Forwarded: for=192.0.2.60;proto=http;by=203.0.113.43, for="[2001:db8:cafe::17]:4711"
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7239
+#[deriving(Clone, PartialEq, Show)]
+pub struct Forwarded(pub Vec<ForwardedElement>);
+
+deref!(Forwarded -> Vec<ForwardedElement>);
+
+/// The parameters one proxy added to the `Forwarded` header.
+#[deriving(Clone, PartialEq)]
+pub struct ForwardedElement {
+    /// The parameters, such as `for` or `proto`, with their values unquoted.
+    pub params: Params
+}
+
+impl ForwardedElement {
+    /// An element without parameters.
+    pub fn new() -> ForwardedElement {
+        ForwardedElement {
+            params: Params::new()
+        }
+    }
+
+    /// Add a parameter.
+    pub fn param(mut self, name: &str, value: &str) -> ForwardedElement {
+        self.params.push(name, value);
+        self
+    }
+
+    /// The value of a parameter, whose name is case-insensitive.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params.get(name)
+    }
+
+    /// The node that made the request to the proxy, from `for`.
+    pub fn forwarded_for(&self) -> Option<&str> {
+        self.get_param("for")
+    }
+
+    /// The proxy's own node, from `by`.
+    pub fn by(&self) -> Option<&str> {
+        self.get_param("by")
+    }
+
+    /// The `Host` of the request the proxy received.
+    pub fn host(&self) -> Option<&str> {
+        self.get_param("host")
+    }
+
+    /// The scheme of the request the proxy received.
+    pub fn proto(&self) -> Option<&str> {
+        self.get_param("proto")
+    }
+}
+
+/// The address of a node in `Forwarded` or `X-Forwarded-For`, which can
+/// have a port, and brackets around an IPv6 address.
+///
+/// Returns `None` for `unknown` and obfuscated nodes like `_hidden`.
+pub fn node_ip(node: &str) -> Option<IpAddr> {
+    let node = node.trim();
+    if node.starts_with("[") {
+        return node.find(']').and_then(|end| from_str(node[1..end]));
+    }
+    from_str(node).or_else(|| match node.rfind(':') {
+        // an IPv4 address and port
+        Some(i) if node[..i].find(':').is_none() => from_str(node[..i]),
+        _ => None
+    })
+}
+
+impl Show for ForwardedElement {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &(ref name, ref value)) in self.params.iter().enumerate() {
+            if i > 0 {
+                try!(fmt.write(b";"));
+            }
+            try!(write!(fmt, "{}=", name));
+            let token = !value.is_empty() && value[].chars().all(|c| {
+                c.is_alphanumeric() && c.is_ascii() || "!#$%&'*+-.^_`|~".contains_char(c)
+            });
+            if token {
+                try!(fmt.write(value.as_bytes()));
+            } else {
+                try!(fmt_quoted(fmt, value[]));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Header for Forwarded {
+    fn header_name(_: Option<Forwarded>) -> &'static str {
+        "Forwarded"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<Forwarded> {
+        let mut elements = Vec::new();
+        for line in raw.iter() {
+            let s = match from_utf8(line[]) {
+                Ok(s) => s,
+                Err(_) => return None
+            };
+            for part in split_unquoted(s, ',').into_iter() {
+                if part.trim().is_empty() {
+                    continue;
+                }
+                let mut element = ForwardedElement::new();
+                for pair in split_unquoted(part, ';').into_iter() {
+                    let pair = pair.trim();
+                    if pair.is_empty() {
+                        continue;
+                    }
+                    let value = match pair.find('=') {
+                        Some(i) => unquote(pair[i + 1..]).map(|value| (pair[..i].trim(), value)),
+                        None => None
+                    };
+                    match value {
+                        Some((name, value)) => element.params.push(name, value[]),
+                        None => return None
+                    }
+                }
+                elements.push(element);
+            }
+        }
+        if elements.is_empty() {
+            None
+        } else {
+            Some(Forwarded(elements))
+        }
+    }
+}
+
+impl HeaderFormat for Forwarded {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (i, element) in self.iter().enumerate() {
+            if i > 0 {
+                try!(fmt.write(b", "));
+            }
+            try!(element.fmt(fmt));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::net::ip::{Ipv4Addr, Ipv6Addr};
+    use header::{Header, HeaderFormatter};
+    use super::{Forwarded, ForwardedElement, node_ip};
+
+    #[test]
+    fn test_parse_header() {
+        let forwarded: Forwarded = Header::parse_header([
+            b"for=192.0.2.60;proto=http;by=203.0.113.43, for=\"[2001:db8:cafe::17]:4711\"".to_vec()
+        ][]).unwrap();
+        assert_eq!(forwarded.len(), 2);
+        assert_eq!(forwarded[0].forwarded_for(), Some("192.0.2.60"));
+        assert_eq!(forwarded[0].proto(), Some("http"));
+        assert_eq!(forwarded[0].by(), Some("203.0.113.43"));
+        assert_eq!(forwarded[1].forwarded_for(), Some("[2001:db8:cafe::17]:4711"));
+
+        let bad: Option<Forwarded> = Header::parse_header([b"for".to_vec()][]);
+        assert_eq!(bad, None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let forwarded = Forwarded(vec![
+            ForwardedElement::new().param("for", "192.0.2.60").param("proto", "https"),
+            ForwardedElement::new().param("for", "[2001:db8:cafe::17]:4711")
+        ]);
+        assert_eq!(format!("{}", HeaderFormatter(&forwarded))[],
+                   "for=192.0.2.60;proto=https, for=\"[2001:db8:cafe::17]:4711\"");
+    }
+
+    #[test]
+    fn test_node_ip() {
+        assert_eq!(node_ip("192.0.2.60"), Some(Ipv4Addr(192, 0, 2, 60)));
+        assert_eq!(node_ip("192.0.2.60:8080"), Some(Ipv4Addr(192, 0, 2, 60)));
+        assert_eq!(node_ip("[2001:db8::17]:4711"), Some(Ipv6Addr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x17)));
+        assert_eq!(node_ip("2001:db8::17"), Some(Ipv6Addr(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x17)));
+        assert_eq!(node_ip("unknown"), None);
+        assert_eq!(node_ip("_hidden"), None);
+    }
+}
+
+bench_header!(bench, Forwarded, {
+    vec![b"for=192.0.2.60;proto=http;by=203.0.113.43, for=198.51.100.17".to_vec()]
+});
@@ -1,15 +1,21 @@
 use header::{Header, HeaderFormat};
 use Port;
+use std::ascii::AsciiExt;
 use std::fmt::{mod, Show};
+use std::str::from_str;
+use url::Url;
+use uri::RequestUri;
 use super::util::from_one_raw_str;
 
 /// The `Host` header.
 ///
 /// HTTP/1.1 requires that all requests include a `Host` header, and so hyper
-/// client requests add one automatically.
+/// client requests add one automatically, from the target URL. The port is
+/// left out when it is the default port of the scheme.
 ///
-/// Currently is just a String, but it should probably become a better type,
-/// like url::Host or something.
+/// An IPv6 address keeps its brackets in the `hostname`, like `[::1]`.
+///
+/// See https://tools.ietf.org/html/rfc7230#section-5.4
 #[deriving(Clone, PartialEq, Show)]
 pub struct Host {
     /// The hostname, such a example.domain.
@@ -18,55 +24,109 @@ pub struct Host {
     pub port: Option<Port>
 }
 
+impl Host {
+    /// A `Host` for `hostname` and an optional `port`.
+    pub fn new(hostname: &str, port: Option<Port>) -> Host {
+        Host {
+            hostname: hostname.to_string(),
+            port: port
+        }
+    }
+
+    /// The `Host` of a request to `url`, or `None` if it has no host.
+    pub fn from_url(url: &Url) -> Option<Host> {
+        url.serialize_host().map(|hostname| {
+            let port = url.port_or_default();
+            Host {
+                hostname: hostname,
+                port: if port == default_port(url.scheme[]) { None } else { port }
+            }
+        })
+    }
+
+    /// Whether this is the authority of a request target, as a server should
+    /// check for targets in absolute or authority form.
+    ///
+    /// Hostnames are compared ignoring case, and a missing port is the
+    /// default port of the target's scheme. Targets with only a path match
+    /// any `Host`.
+    pub fn matches_uri(&self, uri: &RequestUri) -> bool {
+        match *uri {
+            RequestUri::AbsoluteUri(ref url) => match url.serialize_host() {
+                Some(hostname) => {
+                    hostname[].eq_ignore_ascii_case(self.hostname[]) &&
+                        self.port.or(default_port(url.scheme[])) == url.port_or_default()
+                },
+                None => false
+            },
+            RequestUri::Authority(ref authority) => match parse_host(authority[]) {
+                Some(host) => {
+                    host.hostname[].eq_ignore_ascii_case(self.hostname[]) &&
+                        host.port == self.port
+                },
+                None => false
+            },
+            RequestUri::AbsolutePath(_) | RequestUri::Star => true
+        }
+    }
+}
+
+fn default_port(scheme: &str) -> Option<Port> {
+    match scheme {
+        "http" => Some(80),
+        "https" => Some(443),
+        _ => None
+    }
+}
+
+/// Parses `host [ ":" port ]`, where the host may be a bracketed IPv6
+/// address, and an empty port is the same as none.
+fn parse_host(s: &str) -> Option<Host> {
+    let s = s.trim();
+    let (hostname, port) = if s.starts_with("[") {
+        match s.find(']') {
+            Some(idx) => (s[..idx + 1], s[idx + 1..]),
+            None => return None // this is a bad ipv6 address...
+        }
+    } else {
+        match s.rfind(':') {
+            Some(idx) => (s[..idx], s[idx..]),
+            None => (s, "")
+        }
+    };
+
+    if hostname.is_empty() || hostname.contains_char(':') && !hostname.starts_with("[") {
+        return None;
+    }
+    let port = match port {
+        "" | ":" => None,
+        port if port.starts_with(":") => match from_str::<Port>(port[1..]) {
+            Some(port) => Some(port),
+            None => return None
+        },
+        _ => return None
+    };
+
+    Some(Host {
+        hostname: hostname.to_string(),
+        port: port
+    })
+}
+
 impl Header for Host {
     fn header_name(_: Option<Host>) -> &'static str {
         "Host"
     }
 
     fn parse_header(raw: &[Vec<u8>]) -> Option<Host> {
-        from_one_raw_str(raw).and_then(|mut s: String| {
-            // FIXME: use rust-url to parse this
-            // https://github.com/servo/rust-url/issues/42
-            let idx = {
-                let slice = s[];
-                if slice.char_at(1) == '[' {
-                    match slice.rfind(']') {
-                        Some(idx) => {
-                            if slice.len() > idx + 2 {
-                                Some(idx + 1)
-                            } else {
-                                None
-                            }
-                        }
-                        None => return None // this is a bad ipv6 address...
-                    }
-                } else {
-                    slice.rfind(':')
-                }
-            };
-
-            let port = match idx {
-                Some(idx) => s[].slice_from(idx + 1).parse(),
-                None => None
-            };
-
-            match idx {
-                Some(idx) => s.truncate(idx),
-                None => ()
-            }
-
-            Some(Host {
-                hostname: s,
-                port: port
-            })
-        })
+        from_one_raw_str::<String>(raw).and_then(|s| parse_host(s[]))
     }
 }
 
 impl HeaderFormat for Host {
     fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self.port {
-            None | Some(80) | Some(443) => self.hostname.fmt(fmt),
+            None => self.hostname.fmt(fmt),
             Some(port) => write!(fmt, "{}:{}", self.hostname, port)
         }
     }
@@ -74,8 +134,10 @@ impl HeaderFormat for Host {
 
 #[cfg(test)]
 mod tests {
+    use url::Url;
     use super::Host;
-    use header::Header;
+    use header::{Header, HeaderFormatter};
+    use uri::RequestUri::{AbsolutePath, AbsoluteUri, Authority};
 
 
     #[test]
@@ -93,7 +155,61 @@ mod tests {
             port: Some(8080)
         }));
     }
+
+    #[test]
+    fn test_host_ipv6() {
+        let host: Option<Host> = Header::parse_header([b"[::1]:8080".to_vec()][]);
+        assert_eq!(host, Some(Host::new("[::1]", Some(8080))));
+        let host: Option<Host> = Header::parse_header([b"[::1]".to_vec()][]);
+        assert_eq!(host, Some(Host::new("[::1]", None)));
+        let host: Option<Host> = Header::parse_header([b"[::1".to_vec()][]);
+        assert_eq!(host, None);
+    }
+
+    #[test]
+    fn test_host_invalid() {
+        let host: Option<Host> = Header::parse_header([b"foo.com:http".to_vec()][]);
+        assert_eq!(host, None);
+        let host: Option<Host> = Header::parse_header([b"foo.com:99999".to_vec()][]);
+        assert_eq!(host, None);
+        let host: Option<Host> = Header::parse_header([b":80".to_vec()][]);
+        assert_eq!(host, None);
+        let host: Option<Host> = Header::parse_header([b"".to_vec()][]);
+        assert_eq!(host, None);
+        let host: Option<Host> = Header::parse_header([b"foo.com:".to_vec()][]);
+        assert_eq!(host, Some(Host::new("foo.com", None)));
+    }
+
+    #[test]
+    fn test_fmt() {
+        assert_eq!(format!("{}", HeaderFormatter(&Host::new("foo.com", Some(443))))[], "foo.com:443");
+        assert_eq!(format!("{}", HeaderFormatter(&Host::new("foo.com", None)))[], "foo.com");
+    }
+
+    #[test]
+    fn test_from_url() {
+        let host = Host::from_url(&Url::parse("http://Example.domain/path").unwrap());
+        assert_eq!(host, Some(Host::new("example.domain", None)));
+        let host = Host::from_url(&Url::parse("https://example.domain:443/").unwrap());
+        assert_eq!(host, Some(Host::new("example.domain", None)));
+        let host = Host::from_url(&Url::parse("http://example.domain:8080/").unwrap());
+        assert_eq!(host, Some(Host::new("example.domain", Some(8080))));
+    }
+
+    #[test]
+    fn test_matches_uri() {
+        let host = Host::new("Example.domain", None);
+        assert!(host.matches_uri(&AbsoluteUri(Url::parse("http://example.domain/").unwrap())));
+        assert!(host.matches_uri(&AbsoluteUri(Url::parse("http://example.domain:80/").unwrap())));
+        assert!(!host.matches_uri(&AbsoluteUri(Url::parse("http://example.domain:8080/").unwrap())));
+        assert!(!host.matches_uri(&AbsoluteUri(Url::parse("http://other.domain/").unwrap())));
+        assert!(host.matches_uri(&AbsolutePath("/".to_string())));
+
+        let host = Host::new("example.domain", Some(443));
+        assert!(host.matches_uri(&Authority("example.domain:443".to_string())));
+        assert!(!host.matches_uri(&Authority("example.domain:8443".to_string())));
+        assert!(host.matches_uri(&AbsoluteUri(Url::parse("https://example.domain/").unwrap())));
+    }
 }
 
 bench_header!(bench, Host, { vec![b"foo.com:3000".to_vec()] });
-
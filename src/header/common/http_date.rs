@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+use std::fmt::{mod, Show};
+use std::str::FromStr;
+use time::{Tm, Timespec, at_utc, get_time, now_utc};
+use super::util::tm_from_str;
+
+/// A timestamp in a header field, such as `Last-Modified`.
+///
+/// All three formats HTTP allows are parsed: the IMF-fixdate
+/// `Sun, 06 Nov 1994 08:49:37 GMT`, the obsolete RFC 850 format
+/// `Sunday, 06-Nov-94 08:49:37 GMT`, and the asctime format
+/// `Sun Nov  6 08:49:37 1994`. It is always formatted as an IMF-fixdate.
+///
+/// Dates are equal and ordered by the instant they name, whatever offset
+/// their `Tm` has.
+///
+/// See https://tools.ietf.org/html/rfc7231#section-7.1.1.1
+#[deriving(Copy, Clone)]
+pub struct HttpDate(pub Tm);
+
+deref!(HttpDate -> Tm);
+
+impl HttpDate {
+    /// The current time.
+    pub fn now() -> HttpDate {
+        HttpDate(now_utc())
+    }
+
+    /// The date at `timespec`, in UTC.
+    pub fn from_timespec(timespec: Timespec) -> HttpDate {
+        HttpDate(at_utc(timespec))
+    }
+
+    /// The date `secs` seconds after the Unix epoch.
+    pub fn from_seconds(secs: i64) -> HttpDate {
+        HttpDate::from_timespec(Timespec::new(secs, 0))
+    }
+
+    /// The seconds from the Unix epoch to this date.
+    pub fn seconds(&self) -> i64 {
+        self.to_timespec().sec
+    }
+
+    /// The seconds from `now`, in seconds since the epoch, until this date,
+    /// which are negative if the date is before `now`.
+    pub fn seconds_from(&self, now: i64) -> i64 {
+        self.seconds() - now
+    }
+
+    /// The seconds from the current time until this date, negative if the
+    /// date has passed.
+    pub fn seconds_until_now(&self) -> i64 {
+        -self.seconds_since_now()
+    }
+
+    /// The seconds since this date until the current time, negative if the
+    /// date is yet to come.
+    pub fn seconds_since_now(&self) -> i64 {
+        get_time().sec - self.seconds()
+    }
+}
+
+impl PartialEq for HttpDate {
+    fn eq(&self, other: &HttpDate) -> bool {
+        self.to_timespec() == other.to_timespec()
+    }
+}
+
+impl Eq for HttpDate {}
+
+impl PartialOrd for HttpDate {
+    fn partial_cmp(&self, other: &HttpDate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HttpDate {
+    fn cmp(&self, other: &HttpDate) -> Ordering {
+        self.to_timespec().cmp(&other.to_timespec())
+    }
+}
+
+impl FromStr for HttpDate {
+    fn from_str(s: &str) -> Option<HttpDate> {
+        tm_from_str(s).map(HttpDate)
+    }
+}
+
+impl Show for HttpDate {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let tm = **self;
+        match tm.tm_utcoff {
+            0 => tm.rfc822().fmt(fmt),
+            _ => tm.to_utc().rfc822().fmt(fmt)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::from_str;
+    use time::Timespec;
+    use super::HttpDate;
+
+    #[test]
+    fn test_formats() {
+        let imf: HttpDate = from_str("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        let rfc850: HttpDate = from_str("Sunday, 06-Nov-94 08:49:37 GMT").unwrap();
+        let asctime: HttpDate = from_str("Sun Nov  6 08:49:37 1994").unwrap();
+        assert_eq!(imf.to_timespec(), rfc850.to_timespec());
+        assert_eq!(imf.to_timespec(), asctime.to_timespec());
+        for date in [imf, rfc850, asctime].iter() {
+            assert_eq!(date.to_string()[], "Sun, 06 Nov 1994 08:49:37 GMT");
+        }
+        assert!(from_str::<HttpDate>("yesterday").is_none());
+    }
+
+    #[test]
+    fn test_timespec() {
+        let date = HttpDate::from_timespec(Timespec::new(784111777, 0));
+        assert_eq!(date.to_string()[], "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(date.seconds(), 784111777);
+        assert_eq!(HttpDate::from_seconds(784111777), date);
+        assert_eq!(date.seconds_from(784111677), 100);
+        assert_eq!(date.seconds_from(784111877), -100);
+
+        let now = HttpDate::now();
+        assert!(now.seconds_since_now() >= 0 && now.seconds_since_now() < 5);
+        assert!(HttpDate::from_seconds(now.seconds() + 60).seconds_until_now() > 50);
+    }
+
+    #[test]
+    fn test_cmp() {
+        let imf: HttpDate = from_str("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        let asctime: HttpDate = from_str("Sun Nov  6 08:49:37 1994").unwrap();
+        let later: HttpDate = from_str("Sun, 06 Nov 1994 08:49:38 GMT").unwrap();
+        assert_eq!(imf, asctime);
+        assert!(imf < later && later > asctime);
+        assert_eq!(::std::cmp::max(imf, later), later);
+    }
+}
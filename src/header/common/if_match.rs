@@ -0,0 +1,87 @@
+use header::{Header, HeaderFormat};
+use std::fmt;
+use super::Etag;
+use super::etag::{from_raw_list, fmt_list};
+use super::util::from_one_raw_str;
+
+/// The `If-Match` header.
+///
+/// Makes a request conditional on the current representation of the target
+/// having one of the entity tags, compared with the strong comparison, or
+/// on there being any representation for `*`.
+///
+/// See https://tools.ietf.org/html/rfc7232#section-3.1
+#[deriving(Clone, PartialEq, Show)]
+pub enum IfMatch {
+    /// `*`, matching any current representation.
+    Any,
+    /// A list of entity tags.
+    Tags(Vec<Etag>)
+}
+
+impl IfMatch {
+    /// Whether the condition holds for a representation with `etag`, or for
+    /// none if `etag` is `None`.
+    pub fn matches(&self, etag: Option<&Etag>) -> bool {
+        match (self, etag) {
+            (&IfMatch::Any, Some(_)) => true,
+            (&IfMatch::Tags(ref tags), Some(etag)) => tags.iter().any(|tag| tag.strong_eq(etag)),
+            (_, None) => false
+        }
+    }
+}
+
+impl Header for IfMatch {
+    fn header_name(_: Option<IfMatch>) -> &'static str {
+        "If-Match"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<IfMatch> {
+        if from_one_raw_str::<String>(raw).map_or(false, |s| s[].trim() == "*") {
+            return Some(IfMatch::Any);
+        }
+        from_raw_list(raw).map(IfMatch::Tags)
+    }
+}
+
+impl HeaderFormat for IfMatch {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IfMatch::Any => fmt.write(b"*"),
+            IfMatch::Tags(ref tags) => fmt_list(fmt, tags[])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use header::common::Etag;
+    use super::IfMatch;
+
+    fn etag(weak: bool, tag: &str) -> Etag {
+        Etag { weak: weak, tag: tag.to_string() }
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let any: Option<IfMatch> = Header::parse_header([b"*".to_vec()][]);
+        assert_eq!(any, Some(IfMatch::Any));
+        let tags: Option<IfMatch> = Header::parse_header([b"\"a\", W/\"b\"".to_vec()][]);
+        assert_eq!(tags, Some(IfMatch::Tags(vec![etag(false, "a"), etag(true, "b")])));
+        let bad: Option<IfMatch> = Header::parse_header([b"a".to_vec()][]);
+        assert_eq!(bad, None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let tags = IfMatch::Tags(vec![etag(false, "a"), etag(true, "b")]);
+        assert!(tags.matches(Some(&etag(false, "a"))));
+        assert!(!tags.matches(Some(&etag(true, "a"))));
+        assert!(!tags.matches(Some(&etag(false, "b"))));
+        assert!(IfMatch::Any.matches(Some(&etag(true, "c"))));
+        assert!(!IfMatch::Any.matches(None));
+    }
+}
+
+bench_header!(bench, IfMatch, { vec![b"\"xyzzy\", \"r2d2xxxx\", \"c3piozzzz\"".to_vec()] });
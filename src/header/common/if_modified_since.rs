@@ -1,8 +1,9 @@
 use std::fmt::{mod, Show};
-use std::str::FromStr;
+use std::str::{FromStr, from_str};
 use time::Tm;
 use header::{Header, HeaderFormat};
-use super::util::{from_one_raw_str, tm_from_str};
+use super::HttpDate;
+use super::util::from_one_raw_str;
 
 /// The `If-Modified-Since` header field.
 #[deriving(Copy, PartialEq, Clone)]
@@ -10,6 +11,13 @@ pub struct IfModifiedSince(pub Tm);
 
 deref!(IfModifiedSince -> Tm);
 
+impl IfModifiedSince {
+    /// The date, as an `HttpDate`, to compare it with others.
+    pub fn date(&self) -> HttpDate {
+        HttpDate(self.0)
+    }
+}
+
 impl Header for IfModifiedSince {
     fn header_name(_: Option<IfModifiedSince>) -> &'static str {
         "If-Modified-Since"
@@ -23,21 +31,16 @@ impl Header for IfModifiedSince {
 
 impl HeaderFormat for IfModifiedSince {
     fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let tm = **self;
-        match tm.tm_utcoff {
-            0 => tm.rfc822().fmt(fmt),
-            _ => tm.to_utc().rfc822().fmt(fmt)
-        }
+        self.date().fmt(fmt)
     }
 }
 
 impl FromStr for IfModifiedSince {
     fn from_str(s: &str) -> Option<IfModifiedSince> {
-        tm_from_str(s).map(IfModifiedSince)
+        from_str(s).map(|HttpDate(tm)| IfModifiedSince(tm))
     }
 }
 
 bench_header!(imf_fixdate, IfModifiedSince, { vec![b"Sun, 07 Nov 1994 08:48:37 GMT".to_vec()] });
 bench_header!(rfc_850, IfModifiedSince, { vec![b"Sunday, 06-Nov-94 08:49:37 GMT".to_vec()] });
 bench_header!(asctime, IfModifiedSince, { vec![b"Sun Nov  6 08:49:37 1994".to_vec()] });
-
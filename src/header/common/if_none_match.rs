@@ -0,0 +1,87 @@
+use header::{Header, HeaderFormat};
+use std::fmt;
+use super::Etag;
+use super::etag::{from_raw_list, fmt_list};
+use super::util::from_one_raw_str;
+
+/// The `If-None-Match` header.
+///
+/// Makes a request conditional on the current representation of the target
+/// having none of the entity tags, compared with the weak comparison, or on
+/// there being no representation for `*`. A `GET` that fails the condition
+/// is answered with `304 Not Modified`.
+///
+/// See https://tools.ietf.org/html/rfc7232#section-3.2
+#[deriving(Clone, PartialEq, Show)]
+pub enum IfNoneMatch {
+    /// `*`, matching any current representation.
+    Any,
+    /// A list of entity tags.
+    Tags(Vec<Etag>)
+}
+
+impl IfNoneMatch {
+    /// Whether a representation with `etag`, or none if `etag` is `None`, is
+    /// matched, which means the condition does not hold.
+    pub fn matches(&self, etag: Option<&Etag>) -> bool {
+        match (self, etag) {
+            (&IfNoneMatch::Any, Some(_)) => true,
+            (&IfNoneMatch::Tags(ref tags), Some(etag)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+            (_, None) => false
+        }
+    }
+}
+
+impl Header for IfNoneMatch {
+    fn header_name(_: Option<IfNoneMatch>) -> &'static str {
+        "If-None-Match"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<IfNoneMatch> {
+        if from_one_raw_str::<String>(raw).map_or(false, |s| s[].trim() == "*") {
+            return Some(IfNoneMatch::Any);
+        }
+        from_raw_list(raw).map(IfNoneMatch::Tags)
+    }
+}
+
+impl HeaderFormat for IfNoneMatch {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IfNoneMatch::Any => fmt.write(b"*"),
+            IfNoneMatch::Tags(ref tags) => fmt_list(fmt, tags[])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use header::common::Etag;
+    use super::IfNoneMatch;
+
+    fn etag(weak: bool, tag: &str) -> Etag {
+        Etag { weak: weak, tag: tag.to_string() }
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let any: Option<IfNoneMatch> = Header::parse_header([b" * ".to_vec()][]);
+        assert_eq!(any, Some(IfNoneMatch::Any));
+        let tags: Option<IfNoneMatch> = Header::parse_header([b"W/\"a\"".to_vec(),
+                                                              b"\"b\"".to_vec()][]);
+        assert_eq!(tags, Some(IfNoneMatch::Tags(vec![etag(true, "a"), etag(false, "b")])));
+    }
+
+    #[test]
+    fn test_matches() {
+        let tags = IfNoneMatch::Tags(vec![etag(true, "a")]);
+        assert!(tags.matches(Some(&etag(false, "a"))));
+        assert!(tags.matches(Some(&etag(true, "a"))));
+        assert!(!tags.matches(Some(&etag(true, "b"))));
+        assert!(!tags.matches(None));
+        assert!(IfNoneMatch::Any.matches(Some(&etag(false, "c"))));
+    }
+}
+
+bench_header!(bench, IfNoneMatch, { vec![b"W/\"xyzzy\", \"r2d2xxxx\"".to_vec()] });
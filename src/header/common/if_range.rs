@@ -0,0 +1,123 @@
+use header::{Header, HeaderFormat, Headers};
+use std::fmt::{mod, Show};
+use std::str::from_str;
+use super::{Etag, HttpDate, LastModified};
+use super::util::from_one_raw_str;
+
+/// The `If-Range` header, making a `Range` request only apply if the
+/// representation is unchanged, and get the whole of it otherwise.
+///
+/// ```notrust
+/// If-Range: "xyzzy"
+/// If-Range: Sat, 29 Oct 1994 19:43:31 GMT
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7233#section-3.2
+#[deriving(Clone, PartialEq, Show)]
+pub enum IfRange {
+    /// The range applies if the representation has this entity tag.
+    EntityTag(Etag),
+    /// The range applies if the representation was last modified at this
+    /// date.
+    Date(HttpDate)
+}
+
+impl IfRange {
+    /// Whether a representation with an `etag` and `last_modified` date is
+    /// unchanged, so that the range should be sent.
+    ///
+    /// Entity tags must match with the strong comparison, and dates must be
+    /// exactly the same.
+    pub fn is_satisfied(&self, etag: Option<&Etag>, last_modified: Option<&HttpDate>) -> bool {
+        match *self {
+            IfRange::EntityTag(ref tag) => etag.map_or(false, |etag| etag.strong_eq(tag)),
+            IfRange::Date(ref date) => last_modified.map_or(false, |last_modified| {
+                last_modified.seconds() == date.seconds()
+            })
+        }
+    }
+
+    /// Whether the representation with the `Etag` and `Last-Modified` of
+    /// response `headers` is unchanged.
+    pub fn is_satisfied_by(&self, headers: &Headers) -> bool {
+        let last_modified = headers.get::<LastModified>().map(|last_modified| last_modified.date());
+        self.is_satisfied(headers.get::<Etag>(), last_modified.as_ref())
+    }
+}
+
+impl Header for IfRange {
+    fn header_name(_: Option<IfRange>) -> &'static str {
+        "If-Range"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<IfRange> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let s = s[].trim();
+            if s.starts_with("\"") || s.starts_with("W/") {
+                from_str(s).map(IfRange::EntityTag)
+            } else {
+                from_str(s).map(IfRange::Date)
+            }
+        })
+    }
+}
+
+impl HeaderFormat for IfRange {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IfRange::EntityTag(ref tag) => tag.fmt_header(fmt),
+            IfRange::Date(ref date) => date.fmt(fmt)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::from_str;
+    use header::{Header, Headers, HeaderFormatter};
+    use header::common::{Etag, LastModified, HttpDate};
+    use super::IfRange;
+
+    fn etag(tag: &str, weak: bool) -> Etag {
+        Etag { weak: weak, tag: tag.to_string() }
+    }
+
+    #[test]
+    fn test_parse() {
+        let if_range: Option<IfRange> = Header::parse_header([b"\"xyzzy\"".to_vec()][]);
+        assert_eq!(if_range, Some(IfRange::EntityTag(etag("xyzzy", false))));
+
+        let if_range: Option<IfRange> = Header::parse_header([b"Sat, 29 Oct 1994 19:43:31 GMT".to_vec()][]);
+        let date: HttpDate = from_str("Sat, 29 Oct 1994 19:43:31 GMT").unwrap();
+        assert_eq!(if_range, Some(IfRange::Date(date)));
+        assert_eq!(format!("{}", HeaderFormatter(&if_range.unwrap()))[],
+                   "Sat, 29 Oct 1994 19:43:31 GMT");
+
+        let if_range: Option<IfRange> = Header::parse_header([b"yesterday".to_vec()][]);
+        assert_eq!(if_range, None);
+    }
+
+    #[test]
+    fn test_is_satisfied() {
+        let date: HttpDate = from_str("Sat, 29 Oct 1994 19:43:31 GMT").unwrap();
+        let later: HttpDate = from_str("Sun, 30 Oct 1994 19:43:31 GMT").unwrap();
+
+        let by_tag = IfRange::EntityTag(etag("xyzzy", false));
+        assert!(by_tag.is_satisfied(Some(&etag("xyzzy", false)), None));
+        assert!(!by_tag.is_satisfied(Some(&etag("xyzzy", true)), None));
+        assert!(!by_tag.is_satisfied(Some(&etag("other", false)), None));
+        assert!(!by_tag.is_satisfied(None, Some(&date)));
+
+        let by_date = IfRange::Date(date);
+        assert!(by_date.is_satisfied(None, Some(&date)));
+        assert!(!by_date.is_satisfied(None, Some(&later)));
+        assert!(!by_date.is_satisfied(Some(&etag("xyzzy", false)), None));
+
+        let mut headers = Headers::new();
+        headers.set(LastModified(date.0));
+        assert!(by_date.is_satisfied_by(&headers));
+        assert!(!by_tag.is_satisfied_by(&headers));
+    }
+}
+
+bench_header!(bench, IfRange, { vec![b"\"xyzzy\"".to_vec()] });
@@ -0,0 +1,41 @@
+use std::fmt::{mod, Show};
+use std::str::{FromStr, from_str};
+use header::{Header, HeaderFormat};
+use super::HttpDate;
+use super::util::from_one_raw_str;
+
+/// The `If-Unmodified-Since` header field.
+///
+/// Makes a request conditional on the target not being modified since the
+/// date, which is compared to its `Last-Modified`.
+#[deriving(Copy, PartialEq, Clone)]
+pub struct IfUnmodifiedSince(pub HttpDate);
+
+deref!(IfUnmodifiedSince -> HttpDate);
+
+impl Header for IfUnmodifiedSince {
+    fn header_name(_: Option<IfUnmodifiedSince>) -> &'static str {
+        "If-Unmodified-Since"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<IfUnmodifiedSince> {
+        from_one_raw_str(raw)
+    }
+}
+
+
+impl HeaderFormat for IfUnmodifiedSince {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+impl FromStr for IfUnmodifiedSince {
+    fn from_str(s: &str) -> Option<IfUnmodifiedSince> {
+        from_str(s).map(IfUnmodifiedSince)
+    }
+}
+
+bench_header!(imf_fixdate, IfUnmodifiedSince, { vec![b"Sun, 07 Nov 1994 08:48:37 GMT".to_vec()] });
+bench_header!(rfc_850, IfUnmodifiedSince, { vec![b"Sunday, 06-Nov-94 08:49:37 GMT".to_vec()] });
+bench_header!(asctime, IfUnmodifiedSince, { vec![b"Sun Nov  6 08:49:37 1994".to_vec()] });
@@ -1,15 +1,23 @@
 use std::fmt::{mod, Show};
-use std::str::FromStr;
+use std::str::{FromStr, from_str};
 use time::Tm;
 use header::{Header, HeaderFormat};
-use super::util::{from_one_raw_str, tm_from_str};
+use super::HttpDate;
+use super::util::from_one_raw_str;
 
-/// The `LastModified` header field.
+/// The `Last-Modified` header field.
 #[deriving(Copy, PartialEq, Clone)]
 pub struct LastModified(pub Tm);
 
 deref!(LastModified -> Tm);
 
+impl LastModified {
+    /// The date, as an `HttpDate`, to compare it with others.
+    pub fn date(&self) -> HttpDate {
+        HttpDate(self.0)
+    }
+}
+
 impl Header for LastModified {
     fn header_name(_: Option<LastModified>) -> &'static str {
         "Last-Modified"
@@ -23,21 +31,16 @@ impl Header for LastModified {
 
 impl HeaderFormat for LastModified {
     fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let tm = **self;
-        match tm.tm_utcoff {
-            0 => tm.rfc822().fmt(fmt),
-            _ => tm.to_utc().rfc822().fmt(fmt)
-        }
+        self.date().fmt(fmt)
     }
 }
 
 impl FromStr for LastModified {
     fn from_str(s: &str) -> Option<LastModified> {
-        tm_from_str(s).map(LastModified)
+        from_str(s).map(|HttpDate(tm)| LastModified(tm))
     }
 }
 
 bench_header!(imf_fixdate, LastModified, { vec![b"Sun, 07 Nov 1994 08:48:37 GMT".to_vec()] });
 bench_header!(rfc_850, LastModified, { vec![b"Sunday, 06-Nov-94 08:49:37 GMT".to_vec()] });
 bench_header!(asctime, LastModified, { vec![b"Sun Nov  6 08:49:37 1994".to_vec()] });
-
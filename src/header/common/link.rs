@@ -0,0 +1,210 @@
+use header::{Header, HeaderFormat};
+use header::shared::Params;
+use std::ascii::AsciiExt;
+use std::fmt::{mod, Show};
+use std::str::from_utf8;
+use super::util::{split_unquoted, unquote, fmt_quoted, decode_ext_value, fmt_ext_value};
+
+/// The `Link` header, with links to related resources, such as the pages
+/// of a paginated API.
+///
+/// ```notrust
+/// Link: <https://api.example/items?page=3>; rel="next", <https://api.example/items?page=1>; rel="first"
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc5988#section-5
+#[deriving(Clone, PartialEq, Show)]
+pub struct Link(pub Vec<LinkValue>);
+
+deref!(Link -> Vec<LinkValue>);
+
+/// A link in the `Link` header.
+///
+/// Parameters whose names end in `*`, like `title*`, are extended values,
+/// and are kept decoded.
+#[deriving(Clone, PartialEq)]
+pub struct LinkValue {
+    /// The target of the link, as it was in the header.
+    pub uri: String,
+    /// The parameters, such as `rel`, `type` or `title`, with their values
+    /// unquoted.
+    pub params: Params
+}
+
+impl LinkValue {
+    /// A link to `uri`, without parameters.
+    pub fn new(uri: &str) -> LinkValue {
+        LinkValue {
+            uri: uri.to_string(),
+            params: Params::new()
+        }
+    }
+
+    /// Add a parameter.
+    pub fn param(mut self, name: &str, value: &str) -> LinkValue {
+        self.params.push(name, value);
+        self
+    }
+
+    /// The value of a parameter, whose name is case-insensitive.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params.get(name)
+    }
+
+    /// The relation types, which are separated by spaces in `rel`.
+    pub fn rel(&self) -> Vec<&str> {
+        self.get_param("rel").map_or(Vec::new(), |rel| {
+            rel.split(' ').filter(|rel| !rel.is_empty()).collect()
+        })
+    }
+
+    /// Whether this link has a relation type, ignoring case.
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rel().iter().any(|r| r.eq_ignore_ascii_case(rel))
+    }
+
+    /// The media type of the target, from `type`.
+    pub fn media_type(&self) -> Option<&str> {
+        self.get_param("type")
+    }
+
+    /// The title, preferring `title*` over `title`.
+    pub fn title(&self) -> Option<&str> {
+        self.get_param("title*").or_else(|| self.get_param("title"))
+    }
+}
+
+impl Link {
+    /// The first link with a relation type, such as `next`.
+    pub fn find_rel(&self, rel: &str) -> Option<&LinkValue> {
+        self.iter().find(|link| link.has_rel(rel))
+    }
+}
+
+impl Show for LinkValue {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "<{}>", self.uri));
+        for &(ref name, ref value) in self.params.iter() {
+            try!(write!(fmt, "; {}=", name));
+            if name[].ends_with("*") {
+                try!(fmt_ext_value(fmt, value[]));
+            } else {
+                try!(fmt_quoted(fmt, value[]));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_link(s: &str) -> Option<LinkValue> {
+    let s = s.trim();
+    let end = match s.find('>') {
+        Some(end) if s.starts_with("<") => end,
+        _ => return None
+    };
+    let mut link = LinkValue::new(s[1..end].trim());
+    for part in split_unquoted(s[end + 1..], ';').into_iter().skip(1) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (name, value) = match part.find('=') {
+            Some(i) => (part[..i].trim(), part[i + 1..]),
+            // a parameter without a value, like `crossorigin`
+            None => (part, "")
+        };
+        let value = if name.ends_with("*") { decode_ext_value(value) } else { unquote(value) };
+        match value {
+            Some(value) => link.params.push(name, value[]),
+            None => return None
+        }
+    }
+    Some(link)
+}
+
+impl Header for Link {
+    fn header_name(_: Option<Link>) -> &'static str {
+        "Link"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<Link> {
+        let mut links = Vec::new();
+        for line in raw.iter() {
+            let s = match from_utf8(line[]) {
+                Ok(s) => s,
+                Err(_) => return None
+            };
+            for part in split_unquoted(s, ',').into_iter() {
+                if part.trim().is_empty() {
+                    continue;
+                }
+                match parse_link(part) {
+                    Some(link) => links.push(link),
+                    None => return None
+                }
+            }
+        }
+        if links.is_empty() {
+            None
+        } else {
+            Some(Link(links))
+        }
+    }
+}
+
+impl HeaderFormat for Link {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (i, link) in self.iter().enumerate() {
+            if i > 0 {
+                try!(fmt.write(b", "));
+            }
+            try!(link.fmt(fmt));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::{Link, LinkValue};
+
+    fn parse(s: &str) -> Option<Link> {
+        Header::parse_header([s.as_bytes().to_vec()][])
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let link = parse("<https://api.example/items?page=3&a=1,2>; rel=\"next last\", \
+                          <https://api.example/items?page=1>; rel=first; type=\"text/html\"; \
+                          title*=UTF-8'de'n%c3%a4chstes").unwrap();
+        assert_eq!(link.len(), 2);
+        assert_eq!(link[0].uri[], "https://api.example/items?page=3&a=1,2");
+        assert_eq!(link[0].rel(), vec!["next", "last"]);
+        assert_eq!(link[1].media_type(), Some("text/html"));
+        assert_eq!(link[1].title(), Some("nächstes"));
+        assert_eq!(link.find_rel("LAST").map(|link| link.uri[]),
+                   Some("https://api.example/items?page=3&a=1,2"));
+        assert!(link.find_rel("prev").is_none());
+
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("https://api.example; rel=next"), None);
+        assert_eq!(parse("<https://api.example>; title=\"open"), None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let link = Link(vec![
+            LinkValue::new("/items?page=2").param("rel", "next"),
+            LinkValue::new("/items?page=1").param("rel", "prev").param("title*", "zurück")
+        ]);
+        let formatted = format!("{}", HeaderFormatter(&link));
+        assert_eq!(formatted[], "</items?page=2>; rel=\"next\", \
+                                 </items?page=1>; rel=\"prev\"; title*=UTF-8''zur%C3%BCck");
+        assert_eq!(parse(formatted[]), Some(link));
+    }
+}
+
+bench_header!(bench, Link, {
+    vec![b"<https://api.example/items?page=3>; rel=\"next\", <https://api.example/items?page=1>; rel=\"first\"".to_vec()]
+});
@@ -7,24 +7,58 @@
 //! is used, such as `ContentType(pub Mime)`.
 
 pub use self::accept::Accept;
+pub use self::accept_encoding::AcceptEncoding;
+pub use self::accept_language::AcceptLanguage;
+pub use self::accept_ranges::AcceptRanges;
+pub use self::access_control::{AccessControlAllowOrigin, AccessControlAllowCredentials};
+pub use self::access_control::{AccessControlAllowMethods, AccessControlAllowHeaders};
+pub use self::access_control::{AccessControlExposeHeaders, AccessControlMaxAge};
+pub use self::access_control::{AccessControlRequestMethod, AccessControlRequestHeaders};
+pub use self::age::Age;
 pub use self::allow::Allow;
-pub use self::authorization::Authorization;
+pub use self::authorization::{Authorization, ProxyAuthorization};
 pub use self::cache_control::CacheControl;
 pub use self::cookie::Cookies;
 pub use self::connection::Connection;
+pub use self::content_disposition::ContentDisposition;
+pub use self::content_encoding::ContentEncoding;
+pub use self::content_language::ContentLanguage;
 pub use self::content_length::ContentLength;
+pub use self::content_location::ContentLocation;
+pub use self::content_range::ContentRange;
+pub use self::content_security_policy::ContentSecurityPolicy;
 pub use self::content_type::ContentType;
 pub use self::date::Date;
 pub use self::etag::Etag;
 pub use self::expires::Expires;
+pub use self::forwarded::Forwarded;
 pub use self::host::Host;
+pub use self::http_date::HttpDate;
 pub use self::last_modified::LastModified;
+pub use self::if_match::IfMatch;
 pub use self::if_modified_since::IfModifiedSince;
+pub use self::if_none_match::IfNoneMatch;
+pub use self::if_range::IfRange;
+pub use self::if_unmodified_since::IfUnmodifiedSince;
+pub use self::link::Link;
 pub use self::location::Location;
+pub use self::pragma::Pragma;
+pub use self::range::Range;
+pub use self::strict_transport_security::StrictTransportSecurity;
+pub use self::te::Te;
+pub use self::trailer::Trailer;
 pub use self::transfer_encoding::TransferEncoding;
 pub use self::upgrade::Upgrade;
 pub use self::user_agent::UserAgent;
 pub use self::vary::Vary;
+pub use self::warning::{Warning, WarningValue};
+pub use self::websocket::{SecWebSocketKey, SecWebSocketAccept, SecWebSocketVersion};
+pub use self::websocket::{SecWebSocketProtocol, SecWebSocketExtensions};
+pub use self::www_authenticate::{WwwAuthenticate, ProxyAuthenticate};
+pub use self::x_content_type_options::XContentTypeOptions;
+pub use self::x_forwarded::{XForwardedFor, XForwardedProto, XForwardedHost};
+pub use self::x_frame_options::XFrameOptions;
+pub use self::retry_after::RetryAfter;
 pub use self::server::Server;
 pub use self::set_cookie::SetCookie;
 
@@ -76,6 +110,21 @@ macro_rules! deref(
 /// Exposes the Accept header.
 pub mod accept;
 
+/// Exposes the AcceptEncoding header.
+pub mod accept_encoding;
+
+/// Exposes the AcceptLanguage header.
+pub mod accept_language;
+
+/// Exposes the AcceptRanges header.
+pub mod accept_ranges;
+
+/// Exposes the Access-Control-* headers.
+pub mod access_control;
+
+/// Exposes the Age header.
+pub mod age;
+
 /// Exposes the Allow header.
 pub mod allow;
 
@@ -91,9 +140,27 @@ pub mod cookie;
 /// Exposes the Connection header.
 pub mod connection;
 
+/// Exposes the ContentDisposition header.
+pub mod content_disposition;
+
+/// Exposes the ContentEncoding header.
+pub mod content_encoding;
+
+/// Exposes the ContentLanguage header.
+pub mod content_language;
+
 /// Exposes the ContentLength header.
 pub mod content_length;
 
+/// Exposes the ContentLocation header.
+pub mod content_location;
+
+/// Exposes the ContentRange header.
+pub mod content_range;
+
+/// Exposes the Content-Security-Policy header.
+pub mod content_security_policy;
+
 /// Exposes the ContentType header.
 pub mod content_type;
 
@@ -106,24 +173,63 @@ pub mod etag;
 /// Exposes the Expires header.
 pub mod expires;
 
+/// Exposes the Forwarded header.
+pub mod forwarded;
+
 /// Exposes the Host header.
 pub mod host;
 
 /// Exposes the LastModified header.
 pub mod last_modified;
 
+/// Exposes the HttpDate type of date header fields.
+pub mod http_date;
+
+/// Exposes the If-Match header.
+pub mod if_match;
+
 /// Exposes the If-Modified-Since header.
 pub mod if_modified_since;
 
+/// Exposes the If-None-Match header.
+pub mod if_none_match;
+
+/// Exposes the If-Range header.
+pub mod if_range;
+
+/// Exposes the If-Unmodified-Since header.
+pub mod if_unmodified_since;
+
+/// Exposes the Link header.
+pub mod link;
+
 /// Exposes the Location header.
 pub mod location;
 
+/// Exposes the Pragma header.
+pub mod pragma;
+
+/// Exposes the Range header.
+pub mod range;
+
+/// Exposes the RetryAfter header.
+pub mod retry_after;
+
 /// Exposes the Server header.
 pub mod server;
 
 /// Exposes the Set-Cookie header.
 pub mod set_cookie;
 
+/// Exposes the StrictTransportSecurity header.
+pub mod strict_transport_security;
+
+/// Exposes the Te header.
+pub mod te;
+
+/// Exposes the Trailer header.
+pub mod trailer;
+
 /// Exposes the TransferEncoding header.
 pub mod transfer_encoding;
 
@@ -136,4 +242,23 @@ pub mod user_agent;
 /// Exposes the Vary header.
 pub mod vary;
 
+/// Exposes the Warning header.
+pub mod warning;
+
+/// Exposes the Sec-WebSocket-Key, Sec-WebSocket-Accept, Sec-WebSocket-Version,
+/// Sec-WebSocket-Protocol and Sec-WebSocket-Extensions headers.
+pub mod websocket;
+
+/// Exposes the WwwAuthenticate and ProxyAuthenticate headers.
+pub mod www_authenticate;
+
+/// Exposes the X-Content-Type-Options header.
+pub mod x_content_type_options;
+
+/// Exposes the XForwardedFor, XForwardedProto and XForwardedHost headers.
+pub mod x_forwarded;
+
+/// Exposes the X-Frame-Options header.
+pub mod x_frame_options;
+
 pub mod util;
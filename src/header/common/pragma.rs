@@ -0,0 +1,111 @@
+use std::ascii::AsciiExt;
+use std::fmt::{mod, Show};
+use std::str::FromStr;
+use header::{Header, HeaderFormat};
+use super::util::{from_comma_list, fmt_comma_delimited};
+
+/// The `Pragma` header, from HTTP/1.0.
+///
+/// Only `no-cache` has a meaning, the same as `Cache-Control: no-cache`,
+/// for caches that don't know `Cache-Control`. It is ignored in requests
+/// that have a `Cache-Control` header.
+///
+/// See https://tools.ietf.org/html/rfc7234#section-5.4
+#[deriving(PartialEq, Clone, Show)]
+pub struct Pragma(pub Vec<PragmaDirective>);
+
+deref!(Pragma -> Vec<PragmaDirective>);
+
+impl Pragma {
+    /// Whether there is a `no-cache` directive.
+    pub fn is_no_cache(&self) -> bool {
+        self.iter().any(|directive| *directive == PragmaDirective::NoCache)
+    }
+}
+
+impl Header for Pragma {
+    fn header_name(_: Option<Pragma>) -> &'static str {
+        "Pragma"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<Pragma> {
+        from_comma_list(raw).and_then(|directives: Vec<PragmaDirective>| {
+            if directives.is_empty() {
+                None
+            } else {
+                Some(Pragma(directives))
+            }
+        })
+    }
+}
+
+impl HeaderFormat for Pragma {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+/// A directive of a `Pragma` header.
+#[deriving(PartialEq, Clone)]
+pub enum PragmaDirective {
+    /// "no-cache"
+    NoCache,
+    /// Extension directives. Optionally include an argument.
+    Extension(String, Option<String>)
+}
+
+impl Show for PragmaDirective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PragmaDirective::NoCache => f.write(b"no-cache"),
+            PragmaDirective::Extension(ref name, None) => name.fmt(f),
+            PragmaDirective::Extension(ref name, Some(ref arg)) => write!(f, "{}={}", name, arg)
+        }
+    }
+}
+
+impl FromStr for PragmaDirective {
+    fn from_str(s: &str) -> Option<PragmaDirective> {
+        if s.eq_ignore_ascii_case("no-cache") {
+            return Some(PragmaDirective::NoCache);
+        }
+        match s.find('=') {
+            Some(0) => None,
+            Some(idx) => Some(PragmaDirective::Extension(s[..idx].trim().to_string(),
+                                                         Some(s[idx + 1..].trim().to_string()))),
+            None => Some(PragmaDirective::Extension(s.to_string(), None))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::Pragma;
+    use super::PragmaDirective::{NoCache, Extension};
+
+    #[test]
+    fn test_parse() {
+        let pragma: Option<Pragma> = Header::parse_header([b"no-cache".to_vec()][]);
+        assert_eq!(pragma, Some(Pragma(vec![NoCache])));
+        assert!(pragma.unwrap().is_no_cache());
+
+        let pragma: Option<Pragma> = Header::parse_header([b"foo, No-Cache".to_vec(), b"bar=baz".to_vec()][]);
+        assert_eq!(pragma, Some(Pragma(vec![Extension("foo".to_string(), None), NoCache,
+                                            Extension("bar".to_string(), Some("baz".to_string()))])));
+
+        let pragma: Option<Pragma> = Header::parse_header([b"x-foo".to_vec()][]);
+        assert!(!pragma.unwrap().is_no_cache());
+
+        let pragma: Option<Pragma> = Header::parse_header([b"".to_vec()][]);
+        assert_eq!(pragma, None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let pragma = Pragma(vec![NoCache, Extension("bar".to_string(), Some("baz".to_string()))]);
+        assert_eq!(format!("{}", HeaderFormatter(&pragma))[], "no-cache, bar=baz");
+    }
+}
+
+bench_header!(bench, Pragma, { vec![b"no-cache".to_vec()] });
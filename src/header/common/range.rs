@@ -0,0 +1,173 @@
+use header::{Header, HeaderFormat};
+use std::fmt::{mod, Show};
+use std::str::{FromStr, from_str};
+use super::util::{from_one_raw_str, fmt_comma_delimited};
+
+use self::ByteRangeSpec::{FromTo, AllFrom, Last};
+
+/// The `Range` header, asking for parts of a representation in bytes.
+///
+/// Ranges in other units are not understood, and parse as `None`, which a
+/// server answers by ignoring the header.
+///
+/// See https://tools.ietf.org/html/rfc7233#section-3.1
+#[deriving(Clone, PartialEq, Show)]
+pub struct Range(pub Vec<ByteRangeSpec>);
+
+deref!(Range -> Vec<ByteRangeSpec>);
+
+/// A range of bytes in a `Range` header.
+#[deriving(Copy, Clone, PartialEq)]
+pub enum ByteRangeSpec {
+    /// The bytes from the first to the last offset, inclusive, like `0-499`.
+    FromTo(u64, u64),
+    /// The bytes from an offset to the end, like `9500-`.
+    AllFrom(u64),
+    /// The last bytes, like `-500`.
+    Last(u64)
+}
+
+impl ByteRangeSpec {
+    /// The first and last offset this range covers, inclusive, in a
+    /// representation `length` bytes long.
+    ///
+    /// Returns `None` if the range is not satisfiable, because it starts
+    /// after the end, or asks for the last zero bytes.
+    pub fn to_satisfiable_range(&self, length: u64) -> Option<(u64, u64)> {
+        if length == 0 {
+            return None;
+        }
+        match *self {
+            FromTo(first, _) | AllFrom(first) if first >= length => None,
+            FromTo(first, last) => Some((first, if last < length { last } else { length - 1 })),
+            AllFrom(first) => Some((first, length - 1)),
+            Last(0) => None,
+            Last(n) => Some((if n < length { length - n } else { 0 }, length - 1))
+        }
+    }
+}
+
+impl Range {
+    /// The satisfiable ranges, for a representation `length` bytes long.
+    ///
+    /// If this is empty, the request is answered with
+    /// `416 Range Not Satisfiable`.
+    pub fn satisfiable_ranges(&self, length: u64) -> Vec<(u64, u64)> {
+        self.iter().filter_map(|spec| spec.to_satisfiable_range(length)).collect()
+    }
+}
+
+impl FromStr for ByteRangeSpec {
+    fn from_str(s: &str) -> Option<ByteRangeSpec> {
+        let s = s.trim();
+        match s.find('-') {
+            Some(0) => from_str(s[1..]).map(Last),
+            Some(i) => {
+                let first = match from_str(s[..i]) {
+                    Some(first) => first,
+                    None => return None
+                };
+                if i + 1 == s.len() {
+                    return Some(AllFrom(first));
+                }
+                match from_str(s[i + 1..]) {
+                    Some(last) if last >= first => Some(FromTo(first, last)),
+                    _ => None
+                }
+            },
+            None => None
+        }
+    }
+}
+
+impl Show for ByteRangeSpec {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FromTo(first, last) => write!(fmt, "{}-{}", first, last),
+            AllFrom(first) => write!(fmt, "{}-", first),
+            Last(n) => write!(fmt, "-{}", n)
+        }
+    }
+}
+
+impl Header for Range {
+    fn header_name(_: Option<Range>) -> &'static str {
+        "Range"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<Range> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let s = s[].trim();
+            if !s.starts_with("bytes=") {
+                return None;
+            }
+            // every spec must parse, and only commas separate them
+            let mut specs: Vec<ByteRangeSpec> = Vec::new();
+            for spec in s[6..].split(',') {
+                let spec = spec.trim();
+                if spec.is_empty() {
+                    continue;
+                }
+                match from_str(spec) {
+                    Some(spec) => specs.push(spec),
+                    None => return None
+                }
+            }
+            if specs.is_empty() {
+                None
+            } else {
+                Some(Range(specs))
+            }
+        })
+    }
+}
+
+impl HeaderFormat for Range {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(fmt.write(b"bytes="));
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::Range;
+    use super::ByteRangeSpec::{FromTo, AllFrom, Last};
+
+    #[test]
+    fn test_parse_header() {
+        let range: Option<Range> = Header::parse_header([b"bytes=0-499, 500-, -200".to_vec()][]);
+        assert_eq!(range, Some(Range(vec![FromTo(0, 499), AllFrom(500), Last(200)])));
+
+        let range: Option<Range> = Header::parse_header([b"bytes=9-5".to_vec()][]);
+        assert_eq!(range, None);
+        let range: Option<Range> = Header::parse_header([b"bytes=".to_vec()][]);
+        assert_eq!(range, None);
+        let range: Option<Range> = Header::parse_header([b"items=0-5".to_vec()][]);
+        assert_eq!(range, None);
+        let range: Option<Range> = Header::parse_header([b"bytes=0-5,x".to_vec()][]);
+        assert_eq!(range, None);
+        let range: Option<Range> = Header::parse_header([b"bytes=0 -5".to_vec()][]);
+        assert_eq!(range, None);
+        let range: Option<Range> = Header::parse_header([b"bytes= 0-5 ,\t-3".to_vec()][]);
+        assert_eq!(range, Some(Range(vec![FromTo(0, 5), Last(3)])));
+    }
+
+    #[test]
+    fn test_fmt() {
+        let range = Range(vec![FromTo(0, 499), AllFrom(500), Last(200)]);
+        assert_eq!(format!("{}", HeaderFormatter(&range))[], "bytes=0-499, 500-, -200");
+    }
+
+    #[test]
+    fn test_satisfiable_ranges() {
+        let range = Range(vec![FromTo(0, 499), FromTo(900, 2000), AllFrom(1000), Last(100),
+                               Last(0)]);
+        assert_eq!(range.satisfiable_ranges(1000), vec![(0, 499), (900, 999), (900, 999)]);
+        assert_eq!(range.satisfiable_ranges(50), vec![(0, 49), (0, 49)]);
+        assert!(range.satisfiable_ranges(0).is_empty());
+    }
+}
+
+bench_header!(bench, Range, { vec![b"bytes=0-499, 500-999, -500".to_vec()] });
@@ -0,0 +1,108 @@
+use header::{Header, HeaderFormat};
+use std::fmt::{mod, Show};
+use std::str::from_str;
+use std::time::Duration;
+use time::{Tm, now_utc};
+use super::HttpDate;
+use super::util::from_one_raw_str;
+
+/// The `Retry-After` header, telling a client how long to wait before
+/// making another request, as a number of seconds or a date.
+///
+/// ```notrust
+/// Retry-After: 120
+/// Retry-After: Fri, 31 Dec 1999 23:59:59 GMT
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7231#section-7.1.3
+#[deriving(Copy, Clone, PartialEq, Show)]
+pub enum RetryAfter {
+    /// Retry after a delay, which is sent as whole seconds.
+    Delay(Duration),
+    /// Retry after a date.
+    DateTime(HttpDate)
+}
+
+impl RetryAfter {
+    /// How long to wait from `now`, whichever form the header has.
+    ///
+    /// A date in the past gives a zero duration.
+    pub fn delay_from(&self, now: Tm) -> Duration {
+        let delay = match *self {
+            RetryAfter::Delay(delay) => delay,
+            RetryAfter::DateTime(date) => {
+                Duration::seconds(date.seconds_from(now.to_timespec().sec))
+            }
+        };
+        if delay < Duration::zero() { Duration::zero() } else { delay }
+    }
+
+    /// How long to wait from the current time.
+    pub fn delay(&self) -> Duration {
+        self.delay_from(now_utc())
+    }
+}
+
+impl Header for RetryAfter {
+    fn header_name(_: Option<RetryAfter>) -> &'static str {
+        "Retry-After"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<RetryAfter> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let s = s[].trim();
+            match from_str::<u32>(s) {
+                Some(secs) => Some(RetryAfter::Delay(Duration::seconds(secs as i64))),
+                None => from_str(s).map(RetryAfter::DateTime)
+            }
+        })
+    }
+}
+
+impl HeaderFormat for RetryAfter {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RetryAfter::Delay(delay) => delay.num_seconds().fmt(fmt),
+            RetryAfter::DateTime(date) => date.fmt(fmt)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::from_str;
+    use std::time::Duration;
+    use header::{Header, HeaderFormatter, HttpDate};
+    use super::RetryAfter;
+
+    fn parse(s: &str) -> Option<RetryAfter> {
+        Header::parse_header([s.as_bytes().to_vec()][])
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(parse("120"), Some(RetryAfter::Delay(Duration::seconds(120))));
+        assert_eq!(parse("Fri, 31 Dec 1999 23:59:59 GMT"),
+                   Some(RetryAfter::DateTime(from_str("Fri, 31 Dec 1999 23:59:59 GMT").unwrap())));
+        assert_eq!(parse("-1"), None);
+        assert_eq!(parse("soon"), None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let retry = RetryAfter::Delay(Duration::milliseconds(120500));
+        assert_eq!(format!("{}", HeaderFormatter(&retry))[], "120");
+    }
+
+    #[test]
+    fn test_delay_from() {
+        let now = from_str::<HttpDate>("Fri, 31 Dec 1999 23:57:59 GMT").unwrap().0;
+        assert_eq!(parse("Fri, 31 Dec 1999 23:59:59 GMT").unwrap().delay_from(now),
+                   Duration::seconds(120));
+        assert_eq!(parse("Fri, 31 Dec 1999 23:00:00 GMT").unwrap().delay_from(now),
+                   Duration::zero());
+        assert_eq!(parse("30").unwrap().delay_from(now), Duration::seconds(30));
+    }
+}
+
+bench_header!(bench, RetryAfter, { vec![b"120".to_vec()] });
@@ -5,6 +5,8 @@ use std::str::from_utf8;
 use cookie::Cookie;
 use cookie::CookieJar;
 
+use super::HttpDate;
+
 /// The `Set-Cookie` header
 ///
 /// Informally, the Set-Cookie response header contains the header name
@@ -45,15 +47,25 @@ impl Header for SetCookie {
 
 impl HeaderFormat for SetCookie {
 
+    /// Writes the cookies separated by `, `. Each is sent on its own
+    /// `Set-Cookie` line, though, as cookies can't be folded into one.
     fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for (i, cookie) in self.0.iter().enumerate() {
             if i != 0 {
-                try!(f.write(b"\r\nSet-Cookie: "));
+                try!(f.write(b", "));
             }
             try!(cookie.fmt(f));
         }
         Ok(())
     }
+
+    fn line_count(&self) -> uint {
+        self.0.len()
+    }
+
+    fn fmt_line(&self, index: uint, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0[index].fmt(f)
+    }
 }
 
 
@@ -73,6 +85,74 @@ impl SetCookie {
     }
 }
 
+/// A builder of a `Cookie` to send with a `SetCookie` header.
+///
+/// Example:
+///
+/// ```
+/// # use hyper::header::common::set_cookie::CookieBuilder;
+/// let cookie = CookieBuilder::new("session", "abc123")
+///     .path("/")
+///     .domain("example.domain")
+///     .max_age(3600)
+///     .secure()
+///     .http_only()
+///     .finish();
+/// assert!(cookie.httponly);
+/// ```
+pub struct CookieBuilder {
+    cookie: Cookie
+}
+
+impl CookieBuilder {
+    /// Start building a cookie with a name and value.
+    pub fn new(name: &str, value: &str) -> CookieBuilder {
+        CookieBuilder {
+            cookie: Cookie::new(name.to_string(), value.to_string())
+        }
+    }
+
+    /// Set the `Path` attribute.
+    pub fn path(mut self, path: &str) -> CookieBuilder {
+        self.cookie.path = Some(path.to_string());
+        self
+    }
+
+    /// Set the `Domain` attribute.
+    pub fn domain(mut self, domain: &str) -> CookieBuilder {
+        self.cookie.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Set the `Expires` attribute.
+    pub fn expires(mut self, expires: HttpDate) -> CookieBuilder {
+        self.cookie.expires = Some(expires.0);
+        self
+    }
+
+    /// Set the `Max-Age` attribute, in seconds.
+    pub fn max_age(mut self, secs: u64) -> CookieBuilder {
+        self.cookie.max_age = Some(secs);
+        self
+    }
+
+    /// Set the `Secure` attribute.
+    pub fn secure(mut self) -> CookieBuilder {
+        self.cookie.secure = true;
+        self
+    }
+
+    /// Set the `HttpOnly` attribute.
+    pub fn http_only(mut self) -> CookieBuilder {
+        self.cookie.httponly = true;
+        self
+    }
+
+    /// Finish building, returning the `Cookie`.
+    pub fn finish(self) -> Cookie {
+        self.cookie
+    }
+}
 
 #[test]
 fn test_parse() {
@@ -95,6 +175,12 @@ fn test_fmt() {
     headers.set(cookies);
 
     assert_eq!(headers.to_string()[], "Set-Cookie: foo=bar; HttpOnly; Path=/p\r\nSet-Cookie: baz=quux; Path=/\r\n");
+    assert_eq!(headers.get_raw("Set-Cookie").unwrap(),
+               [b"foo=bar; HttpOnly; Path=/p".to_vec(), b"baz=quux; Path=/".to_vec()][]);
+
+    let mut w = ::std::io::MemWriter::new();
+    headers.write_to(&mut w).unwrap();
+    assert_eq!(w.get_ref(), headers.to_string().as_bytes());
 }
 
 #[test]
@@ -112,3 +198,25 @@ fn cookie_jar() {
     assert_eq!(jar.iter().collect::<Vec<Cookie>>(), new_jar.iter().collect::<Vec<Cookie>>());
 }
 
+#[test]
+fn test_cookie_builder() {
+    use header::{Headers, HttpDate};
+
+    let cookie = CookieBuilder::new("foo", "bar")
+        .path("/p")
+        .domain("example.domain")
+        .expires(HttpDate::from_seconds(784111777))
+        .secure()
+        .http_only()
+        .finish();
+    let mut headers = Headers::new();
+    headers.set(SetCookie(vec![cookie]));
+
+    let s = headers.to_string();
+    assert!(s[].starts_with("Set-Cookie: foo=bar"));
+    assert!(s[].contains("HttpOnly"));
+    assert!(s[].contains("Secure"));
+    assert!(s[].contains("Path=/p"));
+    assert!(s[].contains("Domain=example.domain"));
+    assert!(s[].contains("Expires=Sun, 06 Nov 1994 08:49:37"));
+}
@@ -0,0 +1,136 @@
+use header::{Header, HeaderFormat};
+use std::ascii::AsciiExt;
+use std::fmt;
+use std::str::from_str;
+use super::util::{from_one_raw_str, unquote};
+
+/// The `Strict-Transport-Security` header, telling a client to only use
+/// HTTPS for the host for a while.
+///
+/// ```notrust
+/// Strict-Transport-Security: max-age=31536000; includeSubDomains
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc6797#section-6.1
+#[deriving(Copy, Clone, PartialEq, Show)]
+pub struct StrictTransportSecurity {
+    /// How long to only use HTTPS, in seconds. `0` makes the client forget
+    /// the host.
+    pub max_age: u64,
+    /// Whether the subdomains of the host are included.
+    pub include_subdomains: bool,
+    /// Whether the host asks to be in the browser preload lists.
+    pub preload: bool
+}
+
+impl StrictTransportSecurity {
+    /// Only use HTTPS for `max_age` seconds, for the host and its subdomains.
+    pub fn including_subdomains(max_age: u64) -> StrictTransportSecurity {
+        StrictTransportSecurity {
+            max_age: max_age,
+            include_subdomains: true,
+            preload: false
+        }
+    }
+
+    /// Only use HTTPS for `max_age` seconds, for the host only.
+    pub fn excluding_subdomains(max_age: u64) -> StrictTransportSecurity {
+        StrictTransportSecurity {
+            max_age: max_age,
+            include_subdomains: false,
+            preload: false
+        }
+    }
+}
+
+impl Header for StrictTransportSecurity {
+    fn header_name(_: Option<StrictTransportSecurity>) -> &'static str {
+        "Strict-Transport-Security"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<StrictTransportSecurity> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let mut max_age = None;
+            let mut include_subdomains = false;
+            let mut preload = false;
+            for directive in s[].split(';').map(|d| d.trim()).filter(|d| !d.is_empty()) {
+                let (name, value) = match directive.find('=') {
+                    Some(i) => (directive[..i].trim(), Some(directive[i + 1..])),
+                    None => (directive, None)
+                };
+                let name = name.to_ascii_lower();
+                match (name[], value) {
+                    // each directive must not appear more than once
+                    ("max-age", Some(value)) if max_age.is_none() => {
+                        max_age = unquote(value).and_then(|value| from_str(value[]));
+                        if max_age.is_none() {
+                            return None;
+                        }
+                    },
+                    ("includesubdomains", None) if !include_subdomains => include_subdomains = true,
+                    ("preload", None) if !preload => preload = true,
+                    ("max-age", _) | ("includesubdomains", _) | ("preload", _) => return None,
+                    // unknown directives are ignored
+                    _ => ()
+                }
+            }
+            max_age.map(|max_age| StrictTransportSecurity {
+                max_age: max_age,
+                include_subdomains: include_subdomains,
+                preload: preload
+            })
+        })
+    }
+}
+
+impl HeaderFormat for StrictTransportSecurity {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "max-age={}", self.max_age));
+        if self.include_subdomains {
+            try!(fmt.write(b"; includeSubDomains"));
+        }
+        if self.preload {
+            try!(fmt.write(b"; preload"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::StrictTransportSecurity;
+
+    fn parse(s: &str) -> Option<StrictTransportSecurity> {
+        Header::parse_header([s.as_bytes().to_vec()][])
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(parse("max-age=31536000"),
+                   Some(StrictTransportSecurity::excluding_subdomains(31536000)));
+        assert_eq!(parse("Max-Age=\"600\"; includeSubdomains; unknown=1"),
+                   Some(StrictTransportSecurity::including_subdomains(600)));
+        assert_eq!(parse("max-age=0; preload"), Some(StrictTransportSecurity {
+            max_age: 0,
+            include_subdomains: false,
+            preload: true
+        }));
+        assert_eq!(parse("includeSubDomains"), None);
+        assert_eq!(parse("max-age=1; max-age=2"), None);
+        assert_eq!(parse("max-age=soon"), None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let hsts = StrictTransportSecurity {
+            max_age: 31536000,
+            include_subdomains: true,
+            preload: true
+        };
+        assert_eq!(format!("{}", HeaderFormatter(&hsts))[],
+                   "max-age=31536000; includeSubDomains; preload");
+    }
+}
+
+bench_header!(bench, StrictTransportSecurity, { vec![b"max-age=31536000; includeSubDomains".to_vec()] });
@@ -0,0 +1,75 @@
+use header::{Header, HeaderFormat};
+use header::shared::{Encoding, QualityItem};
+use header::shared::Encoding::EncodingExt;
+use std::fmt;
+use super::util::{from_comma_list, fmt_comma_delimited};
+
+/// The `TE` header, telling which transfer codings the client accepts in
+/// the response, besides `chunked`, and whether it accepts trailers.
+///
+/// The `trailers` keyword is kept as `EncodingExt("trailers")`.
+///
+/// ```notrust
+/// TE: trailers, deflate;q=0.5
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7230#section-4.3
+#[deriving(Clone, PartialEq, Show)]
+pub struct Te(pub Vec<QualityItem<Encoding>>);
+
+deref!(Te -> Vec<QualityItem<Encoding>>);
+
+impl Te {
+    /// Whether the client accepts trailer fields after a chunked body.
+    pub fn trailers(&self) -> bool {
+        self.iter().any(|item| match item.item {
+            EncodingExt(ref s) => s[] == "trailers",
+            _ => false
+        })
+    }
+
+    /// The quality of a transfer coding, or `0` if it was not listed.
+    pub fn quality(&self, encoding: &Encoding) -> f32 {
+        self.iter().find(|item| item.item == *encoding).map_or(0.0, |item| item.quality)
+    }
+}
+
+impl Header for Te {
+    fn header_name(_: Option<Te>) -> &'static str {
+        "TE"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<Te> {
+        from_comma_list(raw).map(Te)
+    }
+}
+
+impl HeaderFormat for Te {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use header::shared::{QualityItem, qitem};
+    use header::shared::Encoding::{Deflate, Gzip, EncodingExt};
+    use super::Te;
+
+    #[test]
+    fn test_parse_header() {
+        let te: Te = Header::parse_header([b"trailers, deflate;q=0.5".to_vec()][]).unwrap();
+        assert_eq!(te, Te(vec![qitem(EncodingExt("trailers".to_string())),
+                               QualityItem::new(Deflate, 0.5)]));
+        assert!(te.trailers());
+        assert_eq!(te.quality(&Deflate), 0.5);
+        assert_eq!(te.quality(&Gzip), 0.0);
+        assert_eq!(format!("{}", HeaderFormatter(&te))[], "trailers, deflate; q=0.5");
+
+        let te: Te = Header::parse_header([b"gzip".to_vec()][]).unwrap();
+        assert!(!te.trailers());
+    }
+}
+
+bench_header!(bench, Te, { vec![b"trailers, deflate;q=0.5".to_vec()] });
@@ -0,0 +1,62 @@
+use header::{Header, HeaderFormat, CaseInsensitive};
+use std::fmt;
+use std::str::{from_str, from_utf8};
+use super::util::fmt_comma_delimited;
+
+/// The `Trailer` header, naming the header fields that will be sent as
+/// trailers after a chunked body.
+///
+/// Several header lines are joined into one list.
+///
+/// See https://tools.ietf.org/html/rfc7230#section-4.4
+#[deriving(Clone, PartialEq, Show)]
+pub struct Trailer(pub Vec<CaseInsensitive>);
+
+deref!(Trailer -> Vec<CaseInsensitive>);
+
+impl Header for Trailer {
+    fn header_name(_: Option<Trailer>) -> &'static str {
+        "Trailer"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<Trailer> {
+        let mut names = Vec::new();
+        for line in raw.iter() {
+            let s = match from_utf8(line[]) {
+                Ok(s) => s,
+                Err(_) => return None
+            };
+            for name in s.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()) {
+                names.push(from_str(name).unwrap());
+            }
+        }
+        if names.is_empty() {
+            None
+        } else {
+            Some(Trailer(names))
+        }
+    }
+}
+
+impl HeaderFormat for Trailer {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use super::Trailer;
+
+    #[test]
+    fn test_parse_header() {
+        let trailer: Option<Trailer> = Header::parse_header(
+            [b"Checksum, expires".to_vec(), b"X-Signature".to_vec()][]);
+        assert_eq!(trailer, Some(Trailer(vec!["checksum".parse().unwrap(),
+                                             "Expires".parse().unwrap(),
+                                             "x-signature".parse().unwrap()])));
+    }
+}
+
+bench_header!(bench, Trailer, { vec![b"Checksum, Expires".to_vec()] });
@@ -1,9 +1,8 @@
 use header::{Header, HeaderFormat};
 use std::fmt;
-use std::str::FromStr;
 use super::util::{from_comma_delimited, fmt_comma_delimited};
 
-use self::Encoding::{Chunked, Gzip, Deflate, Compress, EncodingExt};
+pub use header::shared::Encoding;
 
 /// The `Transfer-Encoding` header.
 ///
@@ -17,60 +16,13 @@ use self::Encoding::{Chunked, Gzip, Deflate, Compress, EncodingExt};
 /// According to the spec, if a `Content-Length` header is not included,
 /// this header should include `chunked` as the last encoding.
 ///
-/// The implementation uses a vector of `Encoding` values.
+/// The implementation uses a vector of `Encoding` values, which are shared
+/// with `Content-Encoding`, and re-exported here.
 #[deriving(Clone, PartialEq, Show)]
 pub struct TransferEncoding(pub Vec<Encoding>);
 
 deref!(TransferEncoding -> Vec<Encoding>);
 
-/// A value to be used with the `Transfer-Encoding` header.
-///
-/// Example:
-///
-/// ```
-/// # use hyper::header::TransferEncoding;
-/// # use hyper::header::transfer_encoding::Encoding::{Gzip, Chunked};
-/// # use hyper::header::Headers;
-/// # let mut headers = Headers::new();
-/// headers.set(TransferEncoding(vec![Gzip, Chunked]));
-#[deriving(Clone, PartialEq)]
-pub enum Encoding {
-    /// The `chunked` encoding.
-    Chunked,
-    /// The `gzip` encoding.
-    Gzip,
-    /// The `deflate` encoding.
-    Deflate,
-    /// The `compress` encoding.
-    Compress,
-    /// Some other encoding that is less common, can be any String.
-    EncodingExt(String)
-}
-
-impl fmt::Show for Encoding {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Chunked => "chunked",
-            Gzip => "gzip",
-            Deflate => "deflate",
-            Compress => "compress",
-            EncodingExt(ref s) => s.as_slice()
-        }.fmt(fmt)
-    }
-}
-
-impl FromStr for Encoding {
-    fn from_str(s: &str) -> Option<Encoding> {
-        match s {
-            "chunked" => Some(Chunked),
-            "deflate" => Some(Deflate),
-            "gzip" => Some(Gzip),
-            "compress" => Some(Compress),
-            _ => Some(EncodingExt(s.to_string()))
-        }
-    }
-}
-
 impl Header for TransferEncoding {
     fn header_name(_: Option<TransferEncoding>) -> &'static str {
         "Transfer-Encoding"
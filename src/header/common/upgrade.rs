@@ -1,40 +1,128 @@
 use header::{Header, HeaderFormat};
+use std::ascii::AsciiExt;
 use std::fmt::{mod, Show};
 use std::str::FromStr;
 use super::util::{from_comma_delimited, fmt_comma_delimited};
 
-use self::Protocol::{WebSocket, ProtocolExt};
+use self::ProtocolName::{Http, Tls, WebSocket, H2c, ProtocolExt};
 
 /// The `Upgrade` header.
+///
+/// A request lists the protocols the client would like to switch to, in
+/// order of preference, and a `101 Switching Protocols` response names the
+/// one chosen.
+///
+/// ```notrust
+/// Upgrade: HTTP/2.0, SHTTP/1.3, IRC/6.9, RTA/x11
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7230#section-6.7
 #[deriving(Clone, PartialEq, Show)]
 pub struct Upgrade(pub Vec<Protocol>);
 
 deref!(Upgrade -> Vec<Protocol>);
 
-/// Protocol values that can appear in the Upgrade header.
+impl Upgrade {
+    /// The first protocol with a name, in any version.
+    pub fn find(&self, name: &ProtocolName) -> Option<&Protocol> {
+        self.iter().find(|protocol| protocol.name == *name)
+    }
+}
+
+/// A protocol in the `Upgrade` header, with an optional version, like
+/// `HTTP/2.0`.
 #[deriving(Clone, PartialEq)]
-pub enum Protocol {
+pub struct Protocol {
+    /// The name of the protocol.
+    pub name: ProtocolName,
+    /// The version, if one was given.
+    pub version: Option<String>
+}
+
+impl Protocol {
+    /// A protocol with an optional version.
+    pub fn new(name: ProtocolName, version: Option<&str>) -> Protocol {
+        Protocol {
+            name: name,
+            version: version.map(|v| v.to_string())
+        }
+    }
+}
+
+/// The name of a protocol, compared ignoring case.
+#[deriving(Clone)]
+pub enum ProtocolName {
+    /// `HTTP`.
+    Http,
+    /// `TLS`, for RFC 2817 upgrades.
+    Tls,
     /// The websocket protocol.
     WebSocket,
+    /// `h2c`, HTTP/2 over cleartext TCP.
+    H2c,
     /// Some other less common protocol.
     ProtocolExt(String),
 }
 
+impl ProtocolName {
+    fn as_str(&self) -> &str {
+        match *self {
+            Http => "HTTP",
+            Tls => "TLS",
+            WebSocket => "websocket",
+            H2c => "h2c",
+            ProtocolExt(ref s) => s.as_slice()
+        }
+    }
+}
+
+impl PartialEq for ProtocolName {
+    fn eq(&self, other: &ProtocolName) -> bool {
+        self.as_str().eq_ignore_ascii_case(other.as_str())
+    }
+}
+
+impl FromStr for ProtocolName {
+    fn from_str(s: &str) -> Option<ProtocolName> {
+        let lower = s.to_ascii_lower();
+        match lower[] {
+            "" => None,
+            "http" => Some(Http),
+            "tls" => Some(Tls),
+            "websocket" => Some(WebSocket),
+            "h2c" => Some(H2c),
+            _ => Some(ProtocolExt(s.to_string()))
+        }
+    }
+}
+
+impl fmt::Show for ProtocolName {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
 impl FromStr for Protocol {
     fn from_str(s: &str) -> Option<Protocol> {
-        match s {
-            "websocket" => Some(WebSocket),
-            s => Some(ProtocolExt(s.to_string()))
+        let mut parts = s.splitn(1, '/');
+        let name = match parts.next().and_then(|name| name.parse()) {
+            Some(name) => name,
+            None => return None
+        };
+        match parts.next() {
+            Some("") => None,
+            version => Some(Protocol::new(name, version))
         }
     }
 }
 
 impl fmt::Show for Protocol {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            WebSocket => "websocket",
-            ProtocolExt(ref s) => s.as_slice()
-        }.fmt(fmt)
+        try!(self.name.fmt(fmt));
+        match self.version {
+            Some(ref version) => write!(fmt, "/{}", version),
+            None => Ok(())
+        }
     }
 }
 
@@ -55,5 +143,29 @@ impl HeaderFormat for Upgrade {
     }
 }
 
-bench_header!(bench, Upgrade, { vec![b"HTTP/2.0, RTA/x11, websocket".to_vec()] });
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::{Upgrade, Protocol};
+    use super::ProtocolName::{Http, WebSocket, H2c, ProtocolExt};
+
+    #[test]
+    fn test_parse_header() {
+        let upgrade: Upgrade = Header::parse_header([b"HTTP/2.0, RTA/x11, WebSocket".to_vec()][]).unwrap();
+        assert_eq!(upgrade, Upgrade(vec![
+            Protocol::new(Http, Some("2.0")),
+            Protocol::new(ProtocolExt("rta".to_string()), Some("x11")),
+            Protocol::new(WebSocket, None)
+        ]));
+        assert_eq!(upgrade.find(&WebSocket), Some(&Protocol::new(WebSocket, None)));
+        assert_eq!(upgrade.find(&H2c), None);
+    }
 
+    #[test]
+    fn test_fmt() {
+        let upgrade = Upgrade(vec![Protocol::new(H2c, None), Protocol::new(Http, Some("2.0"))]);
+        assert_eq!(format!("{}", HeaderFormatter(&upgrade))[], "h2c, HTTP/2.0");
+    }
+}
+
+bench_header!(bench, Upgrade, { vec![b"HTTP/2.0, RTA/x11, websocket".to_vec()] });
@@ -1,9 +1,12 @@
 //! Utility functions for Header implementations.
 
+use std::ascii::AsciiExt;
 use std::str::{FromStr, from_utf8};
 use std::fmt::{mod, Show};
 use time::{Tm, strptime};
 
+use percent_encoding::{percent_decode, percent_encode, EncodeSet};
+
 /// Reads a single raw string when parsing a header
 pub fn from_one_raw_str<T: FromStr>(raw: &[Vec<u8>]) -> Option<T> {
     if raw.len() != 1 {
@@ -17,13 +20,18 @@ pub fn from_one_raw_str<T: FromStr>(raw: &[Vec<u8>]) -> Option<T> {
 }
 
 /// Reads a comma-delimited raw header into a Vec.
-#[inline]
+///
+/// A list sent as several lines is read as if the lines were folded into
+/// one, separated by commas.
 pub fn from_comma_delimited<T: FromStr>(raw: &[Vec<u8>]) -> Option<Vec<T>> {
-    if raw.len() != 1 {
-        return None;
+    let mut items = Vec::new();
+    for line in raw.iter() {
+        match from_one_comma_delimited(line[]) {
+            Some(line_items) => items.extend(line_items.into_iter()),
+            None => return None
+        }
     }
-    // we JUST checked that raw.len() == 1, so raw[0] WILL exist.
-    from_one_comma_delimited(unsafe { raw.as_slice().unsafe_get(0).as_slice() })
+    Some(items)
 }
 
 /// Reads a comma-delimited raw string into a Vec.
@@ -39,6 +47,28 @@ pub fn from_one_comma_delimited<T: FromStr>(raw: &[u8]) -> Option<Vec<T>> {
     }
 }
 
+/// Reads a raw header with a list separated only by commas, for items that
+/// can contain spaces, such as parameters.
+///
+/// Empty items are skipped, but the list fails to parse if any other item
+/// does.
+pub fn from_comma_list<T: FromStr>(raw: &[Vec<u8>]) -> Option<Vec<T>> {
+    let mut items = Vec::new();
+    for line in raw.iter() {
+        let s = match from_utf8(line[]) {
+            Ok(s) => s,
+            Err(_) => return None
+        };
+        for item in s.split(',').map(|item| item.trim()).filter(|item| !item.is_empty()) {
+            match FromStr::from_str(item) {
+                Some(item) => items.push(item),
+                None => return None
+            }
+        }
+    }
+    Some(items)
+}
+
 /// Format an array into a comma-delimited string.
 pub fn fmt_comma_delimited<T: Show>(fmt: &mut fmt::Formatter, parts: &[T]) -> fmt::Result {
     let last = parts.len() - 1;
@@ -51,6 +81,87 @@ pub fn fmt_comma_delimited<T: Show>(fmt: &mut fmt::Formatter, parts: &[T]) -> fm
     Ok(())
 }
 
+/// Split a header value at `sep`, except inside quoted strings and
+/// `<...>` references.
+pub fn split_unquoted(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted, mut escaped, mut bracketed) = (0, false, false, false);
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quoted {
+            match c {
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => ()
+            }
+        } else if bracketed {
+            bracketed = c != '>';
+        } else if c == '"' {
+            quoted = true;
+        } else if c == '<' {
+            bracketed = true;
+        } else if c == sep {
+            parts.push(s[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(s[start..]);
+    parts
+}
+
+/// Read a parameter value, which is a token or a quoted string.
+///
+/// Returns `None` for an unterminated quoted string.
+pub fn unquote(s: &str) -> Option<String> {
+    let s = s.trim();
+    if !s.starts_with("\"") {
+        return Some(s.to_string());
+    }
+    if s.len() < 2 || !s.ends_with("\"") {
+        return None;
+    }
+    let mut value = String::new();
+    let mut escaped = false;
+    for c in s[1..s.len() - 1].chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+        } else {
+            value.push(c);
+            escaped = false;
+        }
+    }
+    Some(value)
+}
+
+/// Format a value as a quoted string.
+pub fn fmt_quoted(fmt: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(fmt, "\"{}\"", s.replace("\\", "\\\\").replace("\"", "\\\""))
+}
+
+/// Decode an RFC 5987 extended value, like `UTF-8''%e2%82%ac`.
+pub fn decode_ext_value(s: &str) -> Option<String> {
+    let mut parts = s.trim().splitn(2, '\'');
+    let (charset, encoded) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(charset), Some(_), Some(encoded)) => (charset, encoded),
+        _ => return None
+    };
+    let bytes = percent_decode(encoded.as_bytes());
+    if charset.eq_ignore_ascii_case("UTF-8") {
+        String::from_utf8(bytes).ok()
+    } else if charset.eq_ignore_ascii_case("ISO-8859-1") {
+        Some(bytes.iter().map(|&b| b as char).collect())
+    } else {
+        None
+    }
+}
+
+/// Write an RFC 5987 extended value, as UTF-8.
+pub fn fmt_ext_value(fmt: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    try!(fmt.write(b"UTF-8''"));
+    fmt.write(percent_encode(s.as_bytes(), EncodeSet::ExtValue).as_bytes())
+}
+
 /// Get a Tm from HTTP date formats.
 //    Prior to 1995, there were three different formats commonly used by
 //   servers to communicate timestamps.  For compatibility with old
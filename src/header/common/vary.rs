@@ -1,8 +1,10 @@
-use header::{Header, HeaderFormat, CaseInsensitive};
+use header::{Header, HeaderFormat, Headers, CaseInsensitive};
+use std::ascii::AsciiExt;
 use std::fmt::{mod};
+use std::str::from_str;
 use super::util::{from_comma_delimited, fmt_comma_delimited, from_one_raw_str};
 
-/// The `Allow` header.
+/// The `Vary` header.
 /// See also https://tools.ietf.org/html/rfc7231#section-7.1.4
 
 #[deriving(Clone, PartialEq, Show)]
@@ -13,6 +15,38 @@ pub enum Vary {
     Headers(Vec<CaseInsensitive>),
 }
 
+impl Vary {
+    /// Whether the response varies with a request header. `Any` varies with
+    /// everything.
+    pub fn contains(&self, name: &str) -> bool {
+        match *self {
+            Vary::Any => true,
+            Vary::Headers(ref fields) => {
+                fields.iter().any(|field| field.as_slice().eq_ignore_ascii_case(name))
+            }
+        }
+    }
+
+    /// Add a request header name, unless it is already there.
+    pub fn add(&mut self, name: &str) {
+        if self.contains(name) {
+            return;
+        }
+        if let Vary::Headers(ref mut fields) = *self {
+            fields.push(from_str(name).unwrap());
+        }
+    }
+
+    /// Add a request header name to the `Vary` header of `headers`, setting
+    /// one if there is none.
+    pub fn add_to(headers: &mut Headers, name: &str) {
+        if let Some(vary) = headers.get_mut::<Vary>() {
+            return vary.add(name);
+        }
+        headers.set(Vary::Headers(vec![from_str(name).unwrap()]));
+    }
+}
+
 impl Header for Vary {
     fn header_name(_: Option<Vary>) -> &'static str {
         "Vary"
@@ -42,7 +76,7 @@ impl HeaderFormat for Vary {
 #[cfg(test)]
 mod tests {
     use super::Vary;
-    use header::Header;
+    use header::{Header, Headers};
 
     #[test]
     fn test_vary() {
@@ -56,4 +90,24 @@ mod tests {
                                                  "cookIE".parse().unwrap(),
                                                  "AlLOw".parse().unwrap(),])));
     }
+
+    #[test]
+    fn test_add() {
+        let mut vary = Vary::Headers(vec!["Accept".parse().unwrap()]);
+        vary.add("accept");
+        vary.add("Origin");
+        assert_eq!(vary, Vary::Headers(vec!["Accept".parse().unwrap(), "Origin".parse().unwrap()]));
+        assert!(vary.contains("ORIGIN"));
+        assert!(!vary.contains("Cookie"));
+
+        let mut any = Vary::Any;
+        any.add("Origin");
+        assert_eq!(any, Vary::Any);
+
+        let mut headers = Headers::new();
+        Vary::add_to(&mut headers, "Accept-Encoding");
+        Vary::add_to(&mut headers, "Origin");
+        Vary::add_to(&mut headers, "origin");
+        assert_eq!(headers.to_string()[], "Vary: Accept-Encoding, Origin\r\n");
+    }
 }
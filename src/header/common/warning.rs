@@ -0,0 +1,193 @@
+use header::{Header, HeaderFormat};
+use std::fmt::{mod, Show};
+use std::str::{FromStr, from_str, from_utf8};
+use super::HttpDate;
+use super::util::{split_unquoted, unquote, fmt_quoted, fmt_comma_delimited};
+
+/// The `Warning` header, with more information about the status of a
+/// message, mostly used by caches.
+///
+/// ```notrust
+/// Warning: 110 cache.example:80 "Response is Stale"
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7234#section-5.5
+#[deriving(Clone, PartialEq, Show)]
+pub struct Warning(pub Vec<WarningValue>);
+
+deref!(Warning -> Vec<WarningValue>);
+
+/// One warning of a `Warning` header.
+#[deriving(Clone, PartialEq)]
+pub struct WarningValue {
+    /// The three digit warning code, such as `110`.
+    pub code: u16,
+    /// The host adding the warning, or `-` if it is unknown.
+    pub agent: String,
+    /// A description of the warning.
+    pub text: String,
+    /// When the warning was added.
+    pub date: Option<HttpDate>
+}
+
+impl WarningValue {
+    /// Create a warning without a date.
+    pub fn new(code: u16, agent: &str, text: &str) -> WarningValue {
+        WarningValue {
+            code: code,
+            agent: agent.to_string(),
+            text: text.to_string(),
+            date: None
+        }
+    }
+
+    /// `110 Response is Stale`, for a cache serving a response that isn't
+    /// fresh.
+    pub fn stale(agent: &str) -> WarningValue {
+        WarningValue::new(110, agent, "Response is Stale")
+    }
+
+    /// `111 Revalidation Failed`, for a cache serving a stale response
+    /// because it couldn't reach the server to validate it.
+    pub fn revalidation_failed(agent: &str) -> WarningValue {
+        WarningValue::new(111, agent, "Revalidation Failed")
+    }
+}
+
+impl FromStr for WarningValue {
+    fn from_str(s: &str) -> Option<WarningValue> {
+        let parts = split_unquoted(s.trim(), ' ').into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<&str>>();
+        if parts.len() != 3 && parts.len() != 4 {
+            return None;
+        }
+
+        let code = match from_str::<u16>(parts[0]) {
+            Some(code) if parts[0].len() == 3 && code >= 100 => code,
+            _ => return None
+        };
+        // the text and date must be quoted
+        if !parts[2].starts_with("\"") || parts.len() == 4 && !parts[3].starts_with("\"") {
+            return None;
+        }
+        let text = match unquote(parts[2]) {
+            Some(text) => text,
+            None => return None
+        };
+        let date = if parts.len() == 4 {
+            match unquote(parts[3]).and_then(|date| from_str::<HttpDate>(date[])) {
+                Some(date) => Some(date),
+                None => return None
+            }
+        } else {
+            None
+        };
+
+        Some(WarningValue {
+            code: code,
+            agent: parts[1].to_string(),
+            text: text,
+            date: date
+        })
+    }
+}
+
+impl Warning {
+    /// Whether there is a warning with `code`.
+    pub fn has_code(&self, code: u16) -> bool {
+        self.iter().any(|warning| warning.code == code)
+    }
+}
+
+impl Header for Warning {
+    fn header_name(_: Option<Warning>) -> &'static str {
+        "Warning"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<Warning> {
+        let mut warnings = Vec::new();
+        for line in raw.iter() {
+            let line = match from_utf8(line[]) {
+                Ok(line) => line,
+                Err(_) => return None
+            };
+            for value in split_unquoted(line, ',').into_iter().filter(|v| !v.trim().is_empty()) {
+                match from_str(value) {
+                    Some(warning) => warnings.push(warning),
+                    None => return None
+                }
+            }
+        }
+        if warnings.is_empty() {
+            None
+        } else {
+            Some(Warning(warnings))
+        }
+    }
+}
+
+impl HeaderFormat for Warning {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+/// Formats the warning as it is sent.
+impl Show for WarningValue {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{:03} {} ", self.code, self.agent));
+        try!(fmt_quoted(fmt, self.text[]));
+        if let Some(ref date) = self.date {
+            try!(write!(fmt, " \"{}\"", date));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::from_str;
+    use header::{Header, HeaderFormatter};
+    use super::{Warning, WarningValue};
+
+    #[test]
+    fn test_parse() {
+        let warning: Option<Warning> = Header::parse_header(
+            [b"110 cache.example:80 \"Response is Stale\", 299 - \"Misc, \\\"persistent\\\"\" \"Sun, 06 Nov 1994 08:49:37 GMT\"".to_vec()][]);
+        let warning = warning.unwrap();
+        assert_eq!(warning[0], WarningValue::new(110, "cache.example:80", "Response is Stale"));
+        assert_eq!(warning[1].code, 299);
+        assert_eq!(warning[1].agent[], "-");
+        assert_eq!(warning[1].text[], "Misc, \"persistent\"");
+        assert_eq!(warning[1].date, from_str("Sun, 06 Nov 1994 08:49:37 GMT"));
+        assert!(warning.has_code(299));
+        assert!(!warning.has_code(111));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let warning: Option<Warning> = Header::parse_header([b"110 - stale".to_vec()][]);
+        assert_eq!(warning, None);
+        let warning: Option<Warning> = Header::parse_header([b"1100 - \"stale\"".to_vec()][]);
+        assert_eq!(warning, None);
+        let warning: Option<Warning> = Header::parse_header([b"110 - \"stale\" \"today\"".to_vec()][]);
+        assert_eq!(warning, None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let mut revalidation = WarningValue::revalidation_failed("cache.example");
+        revalidation.date = from_str("Sun, 06 Nov 1994 08:49:37 GMT");
+        let warning = Warning(vec![WarningValue::stale("-"), revalidation]);
+        assert_eq!(format!("{}", HeaderFormatter(&warning))[],
+                   "110 - \"Response is Stale\", \
+                    111 cache.example \"Revalidation Failed\" \"Sun, 06 Nov 1994 08:49:37 GMT\"");
+
+        let parsed: Option<Warning> = Header::parse_header(
+            [format!("{}", HeaderFormatter(&warning)).into_bytes()][]);
+        assert_eq!(parsed, Some(warning));
+    }
+}
+
+bench_header!(bench, Warning, { vec![b"110 cache.example:80 \"Response is Stale\"".to_vec()] });
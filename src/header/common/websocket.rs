@@ -0,0 +1,396 @@
+use header::{Header, HeaderFormat};
+use std::fmt::{mod, Show};
+use std::str::{FromStr, from_str};
+use codec::{base64_encode, base64_decode, sha1};
+use super::util::{from_one_raw_str, from_comma_list, fmt_comma_delimited, split_unquoted, unquote};
+
+/// The GUID appended to the key to compute the accept value.
+static WEBSOCKET_GUID: &'static str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Whether `s` is base64 decoding to exactly `len` bytes.
+fn is_base64_of_len(s: &str, len: uint) -> bool {
+    match base64_decode(s) {
+        Ok(decoded) => decoded.len() == len,
+        Err(e) => {
+            debug!("websocket from_base64 error={}", e);
+            false
+        }
+    }
+}
+
+/// The `Sec-WebSocket-Key` header, a nonce sent by the client in an
+/// opening handshake.
+///
+/// The key is the base64 encoding of 16 bytes, and any other value is
+/// rejected.
+///
+/// ```notrust
+/// Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc6455#section-11.3.1
+#[deriving(Clone, PartialEq, Show)]
+pub struct SecWebSocketKey(String);
+
+impl SecWebSocketKey {
+    /// The key of a 16 byte `nonce`, which should be chosen randomly for
+    /// each connection.
+    pub fn from_nonce(nonce: [u8, ..16]) -> SecWebSocketKey {
+        SecWebSocketKey(base64_encode(nonce[]))
+    }
+
+    /// The key as it is sent.
+    pub fn as_str(&self) -> &str {
+        self.0[]
+    }
+}
+
+impl FromStr for SecWebSocketKey {
+    fn from_str(s: &str) -> Option<SecWebSocketKey> {
+        let s = s.trim();
+        if is_base64_of_len(s, 16) {
+            Some(SecWebSocketKey(s.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Header for SecWebSocketKey {
+    fn header_name(_: Option<SecWebSocketKey>) -> &'static str {
+        "Sec-WebSocket-Key"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<SecWebSocketKey> {
+        from_one_raw_str::<String>(raw).and_then(|s| from_str(s[]))
+    }
+}
+
+impl HeaderFormat for SecWebSocketKey {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+/// The `Sec-WebSocket-Accept` header, the server's proof that it read the
+/// client's `Sec-WebSocket-Key`.
+///
+/// The value is the base64 encoding of the SHA-1 hash of the key and the
+/// websocket GUID.
+///
+/// ```notrust
+/// Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc6455#section-11.3.3
+#[deriving(Clone, PartialEq, Show)]
+pub struct SecWebSocketAccept(String);
+
+impl SecWebSocketAccept {
+    /// The accept value answering `key`.
+    pub fn from_key(key: &SecWebSocketKey) -> SecWebSocketAccept {
+        let mut input = key.0.clone();
+        input.push_str(WEBSOCKET_GUID);
+        SecWebSocketAccept(base64_encode(sha1(input.as_bytes())[]))
+    }
+
+    /// Whether this is the accept value answering `key`, as a client should
+    /// check before using the connection.
+    pub fn matches(&self, key: &SecWebSocketKey) -> bool {
+        *self == SecWebSocketAccept::from_key(key)
+    }
+
+    /// The accept value as it is sent.
+    pub fn as_str(&self) -> &str {
+        self.0[]
+    }
+}
+
+impl FromStr for SecWebSocketAccept {
+    fn from_str(s: &str) -> Option<SecWebSocketAccept> {
+        let s = s.trim();
+        // the base64 of a SHA-1 hash
+        if is_base64_of_len(s, 20) {
+            Some(SecWebSocketAccept(s.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Header for SecWebSocketAccept {
+    fn header_name(_: Option<SecWebSocketAccept>) -> &'static str {
+        "Sec-WebSocket-Accept"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<SecWebSocketAccept> {
+        from_one_raw_str::<String>(raw).and_then(|s| from_str(s[]))
+    }
+}
+
+impl HeaderFormat for SecWebSocketAccept {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+/// The `Sec-WebSocket-Version` header.
+///
+/// A client sends the version it uses, and a server refusing it answers
+/// with the versions it supports.
+///
+/// See https://tools.ietf.org/html/rfc6455#section-11.3.5
+#[deriving(Clone, PartialEq, Show)]
+pub struct SecWebSocketVersion(pub Vec<u8>);
+
+deref!(SecWebSocketVersion -> Vec<u8>);
+
+impl SecWebSocketVersion {
+    /// Version 13, from RFC 6455.
+    pub fn thirteen() -> SecWebSocketVersion {
+        SecWebSocketVersion(vec![13])
+    }
+}
+
+impl Header for SecWebSocketVersion {
+    fn header_name(_: Option<SecWebSocketVersion>) -> &'static str {
+        "Sec-WebSocket-Version"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<SecWebSocketVersion> {
+        from_comma_list(raw).and_then(|versions: Vec<u8>| {
+            if versions.is_empty() {
+                None
+            } else {
+                Some(SecWebSocketVersion(versions))
+            }
+        })
+    }
+}
+
+impl HeaderFormat for SecWebSocketVersion {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+/// The `Sec-WebSocket-Protocol` header, listing the subprotocols a client
+/// would like to use, or naming the one a server chose.
+///
+/// See https://tools.ietf.org/html/rfc6455#section-11.3.4
+#[deriving(Clone, PartialEq, Show)]
+pub struct SecWebSocketProtocol(pub Vec<String>);
+
+deref!(SecWebSocketProtocol -> Vec<String>);
+
+impl SecWebSocketProtocol {
+    /// Whether the subprotocol `name` is listed. Subprotocol names are case
+    /// sensitive.
+    pub fn contains_protocol(&self, name: &str) -> bool {
+        self.iter().any(|protocol| protocol[] == name)
+    }
+}
+
+impl Header for SecWebSocketProtocol {
+    fn header_name(_: Option<SecWebSocketProtocol>) -> &'static str {
+        "Sec-WebSocket-Protocol"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<SecWebSocketProtocol> {
+        from_comma_list(raw).and_then(|protocols: Vec<String>| {
+            if protocols.is_empty() {
+                None
+            } else {
+                Some(SecWebSocketProtocol(protocols))
+            }
+        })
+    }
+}
+
+impl HeaderFormat for SecWebSocketProtocol {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+/// The `Sec-WebSocket-Extensions` header, listing protocol extensions and
+/// their parameters.
+///
+/// ```notrust
+/// Sec-WebSocket-Extensions: permessage-deflate; client_max_window_bits
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc6455#section-9.1
+#[deriving(Clone, PartialEq, Show)]
+pub struct SecWebSocketExtensions(pub Vec<WebSocketExtension>);
+
+deref!(SecWebSocketExtensions -> Vec<WebSocketExtension>);
+
+impl SecWebSocketExtensions {
+    /// The first extension called `name`.
+    pub fn find(&self, name: &str) -> Option<&WebSocketExtension> {
+        self.iter().find(|extension| extension.name[] == name)
+    }
+}
+
+/// An extension in the `Sec-WebSocket-Extensions` header.
+#[deriving(Clone, PartialEq)]
+pub struct WebSocketExtension {
+    /// The name of the extension.
+    pub name: String,
+    /// The parameters, each with an optional value.
+    pub params: Vec<(String, Option<String>)>
+}
+
+impl WebSocketExtension {
+    /// An extension without parameters.
+    pub fn new(name: &str) -> WebSocketExtension {
+        WebSocketExtension {
+            name: name.to_string(),
+            params: Vec::new()
+        }
+    }
+
+    /// The value of the parameter `name`, or `Some(None)` if the parameter
+    /// has no value.
+    pub fn param(&self, name: &str) -> Option<Option<&str>> {
+        self.params.iter().find(|&&(ref param, _)| param[] == name)
+            .map(|&(_, ref value)| value.as_ref().map(|v| v[]))
+    }
+}
+
+impl FromStr for WebSocketExtension {
+    fn from_str(s: &str) -> Option<WebSocketExtension> {
+        let mut parts = split_unquoted(s, ';').into_iter().map(|part| part.trim());
+        let name = match parts.next() {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => return None
+        };
+        let mut params = Vec::new();
+        for part in parts {
+            match part.find('=') {
+                Some(0) => return None,
+                Some(idx) => match unquote(part[idx + 1..].trim()) {
+                    Some(value) => params.push((part[..idx].trim().to_string(), Some(value))),
+                    None => return None
+                },
+                None if part.is_empty() => return None,
+                None => params.push((part.to_string(), None))
+            }
+        }
+        Some(WebSocketExtension {
+            name: name,
+            params: params
+        })
+    }
+}
+
+/// Formats the extension as it is sent.
+impl Show for WebSocketExtension {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(self.name.fmt(fmt));
+        for &(ref name, ref value) in self.params.iter() {
+            try!(write!(fmt, "; {}", name));
+            if let Some(ref value) = *value {
+                try!(write!(fmt, "={}", value));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Header for SecWebSocketExtensions {
+    fn header_name(_: Option<SecWebSocketExtensions>) -> &'static str {
+        "Sec-WebSocket-Extensions"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<SecWebSocketExtensions> {
+        from_comma_list(raw).and_then(|extensions: Vec<WebSocketExtension>| {
+            if extensions.is_empty() {
+                None
+            } else {
+                Some(SecWebSocketExtensions(extensions))
+            }
+        })
+    }
+}
+
+impl HeaderFormat for SecWebSocketExtensions {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::from_str;
+    use header::{Header, HeaderFormatter};
+    use super::{SecWebSocketKey, SecWebSocketAccept, SecWebSocketVersion};
+    use super::{SecWebSocketProtocol, SecWebSocketExtensions, WebSocketExtension};
+
+    #[test]
+    fn test_key() {
+        let key: Option<SecWebSocketKey> = Header::parse_header([b"dGhlIHNhbXBsZSBub25jZQ==".to_vec()][]);
+        let key = key.unwrap();
+        assert_eq!(key.as_str(), "dGhlIHNhbXBsZSBub25jZQ==");
+        assert_eq!(SecWebSocketKey::from_nonce(*b"the sample nonce"), key);
+
+        // decodes to 12 bytes
+        let key: Option<SecWebSocketKey> = Header::parse_header([b"dGhlIHNhbXBsZQ==".to_vec()][]);
+        assert_eq!(key, None);
+        let key: Option<SecWebSocketKey> = Header::parse_header([b"not base64!".to_vec()][]);
+        assert_eq!(key, None);
+    }
+
+    #[test]
+    fn test_accept() {
+        let key: SecWebSocketKey = from_str("dGhlIHNhbXBsZSBub25jZQ==").unwrap();
+        let accept = SecWebSocketAccept::from_key(&key);
+        assert_eq!(format!("{}", HeaderFormatter(&accept))[], "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+        let parsed: Option<SecWebSocketAccept> = Header::parse_header([b"s3pPLMBiTxaQ9kYGzzhZRbK+xOo=".to_vec()][]);
+        assert!(parsed.unwrap().matches(&key));
+        let other = SecWebSocketKey::from_nonce([0u8, ..16]);
+        assert!(!accept.matches(&other));
+
+        let parsed: Option<SecWebSocketAccept> = Header::parse_header([b"dGhlIHNhbXBsZSBub25jZQ==".to_vec()][]);
+        assert_eq!(parsed, None);
+    }
+
+    #[test]
+    fn test_version() {
+        let version: Option<SecWebSocketVersion> = Header::parse_header([b"13, 8".to_vec()][]);
+        assert_eq!(version, Some(SecWebSocketVersion(vec![13, 8])));
+        let version: Option<SecWebSocketVersion> = Header::parse_header([b"thirteen".to_vec()][]);
+        assert_eq!(version, None);
+        assert_eq!(format!("{}", HeaderFormatter(&SecWebSocketVersion::thirteen()))[], "13");
+    }
+
+    #[test]
+    fn test_protocol() {
+        let protocol: Option<SecWebSocketProtocol> = Header::parse_header([b"chat, superchat".to_vec()][]);
+        let protocol = protocol.unwrap();
+        assert!(protocol.contains_protocol("superchat"));
+        assert!(!protocol.contains_protocol("Chat"));
+        assert_eq!(format!("{}", HeaderFormatter(&protocol))[], "chat, superchat");
+    }
+
+    #[test]
+    fn test_extensions() {
+        let extensions: Option<SecWebSocketExtensions> = Header::parse_header(
+            [b"permessage-deflate; client_max_window_bits; server_max_window_bits=10, foo".to_vec()][]);
+        let extensions = extensions.unwrap();
+        let deflate = extensions.find("permessage-deflate").unwrap();
+        assert_eq!(deflate.param("client_max_window_bits"), Some(None));
+        assert_eq!(deflate.param("server_max_window_bits"), Some(Some("10")));
+        assert_eq!(deflate.param("other"), None);
+        assert_eq!(extensions[1], WebSocketExtension::new("foo"));
+        assert_eq!(format!("{}", HeaderFormatter(&extensions))[],
+                   "permessage-deflate; client_max_window_bits; server_max_window_bits=10, foo");
+
+        let extensions: Option<SecWebSocketExtensions> = Header::parse_header([b"; foo".to_vec()][]);
+        assert_eq!(extensions, None);
+    }
+}
+
+bench_header!(bench, SecWebSocketKey, { vec![b"dGhlIHNhbXBsZSBub25jZQ==".to_vec()] });
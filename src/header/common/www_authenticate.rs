@@ -0,0 +1,326 @@
+use header::{Header, HeaderFormat};
+use header::shared::Params;
+use std::ascii::AsciiExt;
+use std::fmt;
+use std::str::from_utf8;
+
+/// The `WWW-Authenticate` header, with the challenges of a
+/// `401 Unauthorized` response.
+///
+/// ```notrust
+/// WWW-Authenticate: Basic realm="admin", charset="UTF-8"
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7235#section-4.1
+#[deriving(Clone, PartialEq, Show)]
+pub struct WwwAuthenticate(pub Vec<Challenge>);
+
+deref!(WwwAuthenticate -> Vec<Challenge>);
+
+/// The `Proxy-Authenticate` header, with the challenges of a
+/// `407 Proxy Authentication Required` response.
+#[deriving(Clone, PartialEq, Show)]
+pub struct ProxyAuthenticate(pub Vec<Challenge>);
+
+deref!(ProxyAuthenticate -> Vec<Challenge>);
+
+/// An authentication challenge, naming a scheme and its parameters.
+#[deriving(Clone, PartialEq)]
+pub struct Challenge {
+    /// The name of the scheme, such as `Basic` or `Digest`.
+    pub scheme: String,
+    /// A single token instead of parameters, as some schemes use.
+    pub token: Option<String>,
+    /// The parameters, such as `realm` or `nonce`, in order.
+    pub params: Params
+}
+
+impl Challenge {
+    /// A challenge for a scheme, without parameters.
+    pub fn new(scheme: &str) -> Challenge {
+        Challenge {
+            scheme: scheme.to_string(),
+            token: None,
+            params: Params::new()
+        }
+    }
+
+    /// Add a parameter.
+    pub fn param(mut self, name: &str, value: &str) -> Challenge {
+        self.params.push(name, value);
+        self
+    }
+
+    /// The value of a parameter, whose name is case-insensitive.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params.get(name)
+    }
+
+    /// Whether this challenge is for a scheme, ignoring case.
+    pub fn is_scheme(&self, scheme: &str) -> bool {
+        self.scheme[].eq_ignore_ascii_case(scheme)
+    }
+}
+
+impl fmt::Show for Challenge {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(fmt.write(self.scheme.as_bytes()));
+        if let Some(ref token) = self.token {
+            try!(write!(fmt, " {}", token));
+        }
+        for (i, &(ref name, ref value)) in self.params.iter().enumerate() {
+            let value = value.replace("\\", "\\\\").replace("\"", "\\\"");
+            try!(write!(fmt, "{}{}=\"{}\"", if i == 0 { " " } else { ", " }, name, value));
+        }
+        Ok(())
+    }
+}
+
+impl Header for WwwAuthenticate {
+    fn header_name(_: Option<WwwAuthenticate>) -> &'static str {
+        "WWW-Authenticate"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<WwwAuthenticate> {
+        parse_challenges(raw).map(WwwAuthenticate)
+    }
+}
+
+impl HeaderFormat for WwwAuthenticate {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_challenges(fmt, self[])
+    }
+}
+
+impl Header for ProxyAuthenticate {
+    fn header_name(_: Option<ProxyAuthenticate>) -> &'static str {
+        "Proxy-Authenticate"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<ProxyAuthenticate> {
+        parse_challenges(raw).map(ProxyAuthenticate)
+    }
+}
+
+impl HeaderFormat for ProxyAuthenticate {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_challenges(fmt, self[])
+    }
+}
+
+fn fmt_challenges(fmt: &mut fmt::Formatter, challenges: &[Challenge]) -> fmt::Result {
+    for (i, challenge) in challenges.iter().enumerate() {
+        if i > 0 {
+            try!(fmt.write(b", "));
+        }
+        try!(challenge.fmt(fmt));
+    }
+    Ok(())
+}
+
+fn parse_challenges(raw: &[Vec<u8>]) -> Option<Vec<Challenge>> {
+    let mut challenges = Vec::new();
+    for line in raw.iter() {
+        let mut parser = Parser { s: line[], pos: 0 };
+        if from_utf8(line[]).is_err() || !parser.challenges(&mut challenges) {
+            return None;
+        }
+    }
+    if challenges.is_empty() {
+        None
+    } else {
+        Some(challenges)
+    }
+}
+
+/// A parser of the challenge list grammar, where a comma can separate
+/// either parameters or challenges.
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: uint
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).map(|&b| b)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(b' ') || self.peek() == Some(b'\t') {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b',') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end_of_item(&self) -> bool {
+        self.peek().map_or(true, |b| b == b',')
+    }
+
+    fn take_while(&mut self, f: |u8| -> bool) -> Option<String> {
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                Some(b) if f(b) => self.pos += 1,
+                _ => break
+            }
+        }
+        if self.pos > start {
+            // only ASCII was taken
+            from_utf8(self.s[start..self.pos]).ok().map(|s| s.to_string())
+        } else {
+            None
+        }
+    }
+
+    fn token(&mut self) -> Option<String> {
+        self.take_while(|b| (b as char).is_alphanumeric() && b < 0x80 ||
+                            b"!#$%&'*+-.^_`|~".contains(&b))
+    }
+
+    fn token68(&mut self) -> Option<String> {
+        let start = self.pos;
+        if self.take_while(|b| (b as char).is_alphanumeric() && b < 0x80 ||
+                               b"-._~+/".contains(&b)).is_none() {
+            return None;
+        }
+        self.take_while(|b| b == b'=');
+        from_utf8(self.s[start..self.pos]).ok().map(|s| s.to_string())
+    }
+
+    fn quoted(&mut self) -> Option<String> {
+        let mut value = Vec::new();
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return String::from_utf8(value).ok();
+                },
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(b) => value.push(b),
+                        None => return None
+                    }
+                },
+                Some(b) => value.push(b),
+                None => return None
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Parse the challenges in the line, returning whether it was valid.
+    fn challenges(&mut self, challenges: &mut Vec<Challenge>) -> bool {
+        loop {
+            self.skip_separators();
+            if self.peek().is_none() {
+                return true;
+            }
+            let mut challenge = match self.token() {
+                Some(scheme) => Challenge::new(scheme[]),
+                None => return false
+            };
+            self.skip_spaces();
+
+            let start = self.pos;
+            if let Some(token) = self.token68() {
+                self.skip_spaces();
+                if self.at_end_of_item() {
+                    challenge.token = Some(token);
+                    challenges.push(challenge);
+                    continue;
+                }
+                self.pos = start;
+            }
+
+            loop {
+                self.skip_spaces();
+                let start = self.pos;
+                let name = match self.token() {
+                    Some(name) => name,
+                    None => break
+                };
+                self.skip_spaces();
+                if self.peek() != Some(b'=') {
+                    // the scheme of the next challenge
+                    self.pos = start;
+                    break;
+                }
+                self.pos += 1;
+                self.skip_spaces();
+                let value = if self.peek() == Some(b'"') { self.quoted() } else { self.token() };
+                match value {
+                    Some(value) => challenge.params.push(name[], value[]),
+                    None => return false
+                }
+                self.skip_spaces();
+                if !self.at_end_of_item() {
+                    return false;
+                }
+                self.skip_separators();
+            }
+            challenges.push(challenge);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::{WwwAuthenticate, Challenge};
+
+    fn parse(s: &str) -> Option<WwwAuthenticate> {
+        Header::parse_header([s.as_bytes().to_vec()][])
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let auth = parse("Basic realm=\"admin, \\\"root\\\"\", charset=UTF-8").unwrap();
+        assert_eq!(auth, WwwAuthenticate(vec![
+            Challenge::new("Basic").param("realm", "admin, \"root\"").param("charset", "UTF-8")
+        ]));
+        assert_eq!(auth[0].get_param("Realm"), Some("admin, \"root\""));
+    }
+
+    #[test]
+    fn test_parse_multiple() {
+        let auth = parse("Negotiate abc==, Digest realm=\"x\", nonce=\"n\", \
+                          qop=\"auth,auth-int\", Bearer").unwrap();
+        let mut negotiate = Challenge::new("Negotiate");
+        negotiate.token = Some("abc==".to_string());
+        assert_eq!(auth, WwwAuthenticate(vec![
+            negotiate,
+            Challenge::new("Digest").param("realm", "x").param("nonce", "n")
+                .param("qop", "auth,auth-int"),
+            Challenge::new("Bearer")
+        ]));
+        assert!(auth[1].is_scheme("digest"));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("Basic realm=\"open"), None);
+        assert_eq!(parse("Basic realm=x y"), None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let auth = WwwAuthenticate(vec![
+            Challenge::new("Basic").param("realm", "say \"hi\"").param("charset", "UTF-8"),
+            Challenge::new("Bearer")
+        ]);
+        assert_eq!(format!("{}", HeaderFormatter(&auth))[],
+                   "Basic realm=\"say \\\"hi\\\"\", charset=\"UTF-8\", Bearer");
+    }
+}
+
+bench_header!(bench, WwwAuthenticate, {
+    vec![b"Digest realm=\"x\", nonce=\"n\", qop=\"auth\", Basic realm=\"x\"".to_vec()]
+});
@@ -0,0 +1,36 @@
+use header::{Header, HeaderFormat};
+use std::ascii::AsciiExt;
+use std::fmt;
+use super::util::from_one_raw_str;
+
+/// The `X-Content-Type-Options` header, with its only value `nosniff`,
+/// telling browsers to use the `Content-Type` of scripts and styles
+/// rather than guessing it.
+///
+/// See https://fetch.spec.whatwg.org/#x-content-type-options-header
+#[deriving(Copy, Clone, PartialEq, Show)]
+pub struct XContentTypeOptions;
+
+impl Header for XContentTypeOptions {
+    fn header_name(_: Option<XContentTypeOptions>) -> &'static str {
+        "X-Content-Type-Options"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<XContentTypeOptions> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            if s[].trim().eq_ignore_ascii_case("nosniff") {
+                Some(XContentTypeOptions)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl HeaderFormat for XContentTypeOptions {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write(b"nosniff")
+    }
+}
+
+bench_header!(bench, XContentTypeOptions, { vec![b"nosniff".to_vec()] });
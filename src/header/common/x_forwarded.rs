@@ -0,0 +1,113 @@
+use header::{Header, HeaderFormat};
+use std::fmt::{mod, Show};
+use std::str::from_utf8;
+use super::util::{from_one_raw_str, fmt_comma_delimited};
+
+/// The `X-Forwarded-For` header, listing the client and the proxies a
+/// request passed through, with the most recent last.
+///
+/// The entries are kept as they were sent, since they can be `unknown` or
+/// have ports. Several header lines are joined into one list.
+#[deriving(Clone, PartialEq, Show)]
+pub struct XForwardedFor(pub Vec<String>);
+
+deref!(XForwardedFor -> Vec<String>);
+
+impl Header for XForwardedFor {
+    fn header_name(_: Option<XForwardedFor>) -> &'static str {
+        "X-Forwarded-For"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<XForwardedFor> {
+        let mut nodes = Vec::new();
+        for line in raw.iter() {
+            match from_utf8(line[]) {
+                Ok(s) => nodes.extend(s.split(',').map(|node| node.trim())
+                                      .filter(|node| !node.is_empty())
+                                      .map(|node| node.to_string())),
+                Err(_) => return None
+            }
+        }
+        if nodes.is_empty() {
+            None
+        } else {
+            Some(XForwardedFor(nodes))
+        }
+    }
+}
+
+impl HeaderFormat for XForwardedFor {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt_comma_delimited(fmt, self[])
+    }
+}
+
+/// The `X-Forwarded-Proto` header, with the scheme the client used, such
+/// as `https`.
+#[deriving(Clone, PartialEq, Show)]
+pub struct XForwardedProto(pub String);
+
+deref!(XForwardedProto -> String);
+
+impl Header for XForwardedProto {
+    fn header_name(_: Option<XForwardedProto>) -> &'static str {
+        "X-Forwarded-Proto"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<XForwardedProto> {
+        from_one_raw_str(raw).map(|s: String| XForwardedProto(s[].trim().to_string()))
+    }
+}
+
+impl HeaderFormat for XForwardedProto {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+/// The `X-Forwarded-Host` header, with the `Host` the client asked for.
+#[deriving(Clone, PartialEq, Show)]
+pub struct XForwardedHost(pub String);
+
+deref!(XForwardedHost -> String);
+
+impl Header for XForwardedHost {
+    fn header_name(_: Option<XForwardedHost>) -> &'static str {
+        "X-Forwarded-Host"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<XForwardedHost> {
+        from_one_raw_str(raw).map(|s: String| XForwardedHost(s[].trim().to_string()))
+    }
+}
+
+impl HeaderFormat for XForwardedHost {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::{XForwardedFor, XForwardedProto};
+
+    #[test]
+    fn test_parse_header() {
+        let xff: Option<XForwardedFor> = Header::parse_header(
+            [b"203.0.113.7, 10.0.0.1".to_vec(), b"10.0.0.2".to_vec()][]);
+        assert_eq!(xff, Some(XForwardedFor(vec!["203.0.113.7".to_string(),
+                                                "10.0.0.1".to_string(),
+                                                "10.0.0.2".to_string()])));
+        let proto: Option<XForwardedProto> = Header::parse_header([b"https".to_vec()][]);
+        assert_eq!(proto, Some(XForwardedProto("https".to_string())));
+    }
+
+    #[test]
+    fn test_fmt() {
+        let xff = XForwardedFor(vec!["203.0.113.7".to_string(), "10.0.0.1".to_string()]);
+        assert_eq!(format!("{}", HeaderFormatter(&xff))[], "203.0.113.7, 10.0.0.1");
+    }
+}
+
+bench_header!(bench, XForwardedFor, { vec![b"203.0.113.7, 10.0.0.1".to_vec()] });
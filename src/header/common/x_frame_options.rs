@@ -0,0 +1,85 @@
+use header::{Header, HeaderFormat};
+use std::ascii::AsciiExt;
+use std::fmt;
+use super::util::from_one_raw_str;
+
+/// The `X-Frame-Options` header, telling browsers whether a page may be
+/// shown in a frame.
+///
+/// ```notrust
+/// X-Frame-Options: SAMEORIGIN
+/// ```
+///
+/// See https://tools.ietf.org/html/rfc7034#section-2
+#[deriving(Clone, PartialEq, Show)]
+pub enum XFrameOptions {
+    /// `DENY`, the page may not be framed.
+    Deny,
+    /// `SAMEORIGIN`, the page may only be framed by pages of its origin.
+    SameOrigin,
+    /// `ALLOW-FROM`, the page may only be framed by pages of this origin.
+    AllowFrom(String)
+}
+
+impl Header for XFrameOptions {
+    fn header_name(_: Option<XFrameOptions>) -> &'static str {
+        "X-Frame-Options"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Option<XFrameOptions> {
+        from_one_raw_str::<String>(raw).and_then(|s| {
+            let s = s[].trim();
+            if s.eq_ignore_ascii_case("deny") {
+                Some(XFrameOptions::Deny)
+            } else if s.eq_ignore_ascii_case("sameorigin") {
+                Some(XFrameOptions::SameOrigin)
+            } else if s.len() > 10 && s[..10].eq_ignore_ascii_case("allow-from") {
+                match s[10..].trim() {
+                    "" => None,
+                    origin => Some(XFrameOptions::AllowFrom(origin.to_string()))
+                }
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl HeaderFormat for XFrameOptions {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            XFrameOptions::Deny => fmt.write(b"DENY"),
+            XFrameOptions::SameOrigin => fmt.write(b"SAMEORIGIN"),
+            XFrameOptions::AllowFrom(ref origin) => write!(fmt, "ALLOW-FROM {}", origin)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+    use super::XFrameOptions;
+
+    #[test]
+    fn test_parse() {
+        let options: Option<XFrameOptions> = Header::parse_header([b"deny".to_vec()][]);
+        assert_eq!(options, Some(XFrameOptions::Deny));
+        let options: Option<XFrameOptions> = Header::parse_header([b"SAMEORIGIN".to_vec()][]);
+        assert_eq!(options, Some(XFrameOptions::SameOrigin));
+        let options: Option<XFrameOptions> = Header::parse_header([b"ALLOW-FROM https://example.domain".to_vec()][]);
+        assert_eq!(options, Some(XFrameOptions::AllowFrom("https://example.domain".to_string())));
+        let options: Option<XFrameOptions> = Header::parse_header([b"ALLOW-FROM".to_vec()][]);
+        assert_eq!(options, None);
+        let options: Option<XFrameOptions> = Header::parse_header([b"maybe".to_vec()][]);
+        assert_eq!(options, None);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let options = XFrameOptions::AllowFrom("https://example.domain".to_string());
+        assert_eq!(format!("{}", HeaderFormatter(&options))[], "ALLOW-FROM https://example.domain");
+        assert_eq!(format!("{}", HeaderFormatter(&XFrameOptions::SameOrigin))[], "SAMEORIGIN");
+    }
+}
+
+bench_header!(bench, XFrameOptions, { vec![b"SAMEORIGIN".to_vec()] });
@@ -7,25 +7,33 @@
 use std::any::Any;
 use std::ascii::{AsciiExt, AsciiCast};
 use std::borrow::Cow::{Borrowed, Owned};
+use std::collections::HashMap;
 use std::fmt::{mod, Show};
 use std::intrinsics::TypeId;
+use std::io::{IoResult, IoError, InvalidInput};
 use std::raw::TraitObject;
 use std::str::{SendStr, FromStr};
-use std::collections::HashMap;
-use std::collections::hash_map::{Entries, Entry};
-use std::{hash, mem};
+use std::slice::Items;
+use std::{cmp, hash, mem};
 
 use mucell::MuCell;
+use serialize::{Encodable, Encoder, Decodable, Decoder};
 use uany::{UncheckedAnyDowncast, UncheckedAnyMutDowncast};
 
-use http::{mod, LineEnding};
+use http::{mod, LineEnding, ParseMode, Parsed, LINE_ENDING};
+use names::intern_header_name;
 use {HttpResult};
+use HttpError::HttpHeaderError;
 
 pub use self::common::*;
+pub use self::shared::*;
 
 /// Common Headers
 pub mod common;
 
+/// Types shared by several headers.
+pub mod shared;
+
 /// A trait for any object that will represent a header field and value.
 ///
 /// This trait represents the construction and identification of headers,
@@ -57,6 +65,24 @@ pub trait HeaderFormat: HeaderClone + Any + Send + Sync {
     /// by the passed-in Formatter.
     fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result;
 
+    /// The number of lines the header is written as.
+    ///
+    /// A header with several values usually folds them into one line,
+    /// separated by commas, and keeps the default of `1`. A header whose
+    /// values can't be folded, like `Set-Cookie`, returns how many lines
+    /// `fmt_line` writes instead, and each is sent as a field of its own.
+    fn line_count(&self) -> uint {
+        1
+    }
+
+    /// Format one of the `line_count` lines of the header.
+    ///
+    /// The only line is the whole of `fmt_header` by default.
+    fn fmt_line(&self, index: uint, fmt: &mut fmt::Formatter) -> fmt::Result {
+        debug_assert_eq!(index, 0);
+        self.fmt_header(fmt)
+    }
+
 }
 
 #[doc(hidden)]
@@ -107,9 +133,26 @@ fn header_name<T: Header>() -> &'static str {
 }
 
 /// A map of header fields on requests and responses.
+///
+/// Fields are kept in the order they were first added. A field read from
+/// several lines keeps all of them, and each is written back where it was
+/// among the lines of the other fields. Setting a field replaces all of its
+/// lines, and its value is written where its first line was.
+///
+/// Lookups ignore the case of names, but a name is written with the case it
+/// was received or set with, since some peers are picky about it.
+///
+/// Raw values are only parsed when a typed header is first asked for, and
+/// the parsed value is kept for later calls. A value that fails to parse as
+/// some type isn't parsed as it again, unless it changes.
 #[deriving(Clone)]
 pub struct Headers {
-    data: HashMap<CaseInsensitive, MuCell<Item>>
+    // the fields, in the order they were first added
+    data: Vec<(CaseInsensitive, MuCell<Item>)>,
+    // the place of each field in `data`
+    index: HashMap<CaseInsensitive, uint>,
+    // the field of each line, in the order the lines are written
+    lines: Vec<uint>
 }
 
 impl Headers {
@@ -117,41 +160,163 @@ impl Headers {
     /// Creates a new, empty headers map.
     pub fn new() -> Headers {
         Headers {
-            data: HashMap::new()
+            data: Vec::new(),
+            index: HashMap::new(),
+            lines: Vec::new()
+        }
+    }
+
+    fn position(&self, name: &str) -> Option<uint> {
+        // FIXME(reem): Find a better way to do this lookup without find_equiv.
+        let name = CaseInsensitive(Borrowed(unsafe { mem::transmute::<&str, &str>(name) }));
+        self.index.get(&name).map(|&pos| pos)
+    }
+
+    fn find(&self, name: &str) -> Option<&MuCell<Item>> {
+        match self.position(name) {
+            Some(pos) => Some(&self.data[pos].1),
+            None => None
         }
     }
 
+    fn find_mut(&mut self, name: &str) -> Option<&mut MuCell<Item>> {
+        match self.position(name) {
+            Some(pos) => Some(&mut self.data[pos].1),
+            None => None
+        }
+    }
+
+    /// Replaces the field with the same name, keeping its place, or adds it
+    /// at the end.
+    fn insert(&mut self, name: CaseInsensitive, item: MuCell<Item>) {
+        match self.position(name.as_slice()) {
+            Some(pos) => {
+                self.data[pos] = (name, item);
+                self.keep_first_line(pos);
+            },
+            None => self.push(name, item)
+        }
+    }
+
+    /// Adds a new field, with one line at the end.
+    fn push(&mut self, name: CaseInsensitive, item: MuCell<Item>) {
+        let pos = self.data.len();
+        self.index.insert(name.clone(), pos);
+        self.lines.push(pos);
+        self.data.push((name, item));
+    }
+
+    /// Removes the field at `pos`, and its lines.
+    fn remove_at(&mut self, pos: uint) -> (CaseInsensitive, MuCell<Item>) {
+        let (name, item) = self.data.remove(pos).unwrap();
+        self.index.remove(&name);
+        for (_, place) in self.index.iter_mut() {
+            if *place > pos {
+                *place -= 1;
+            }
+        }
+        self.lines.retain(|&line| line != pos);
+        for line in self.lines.iter_mut() {
+            if *line > pos {
+                *line -= 1;
+            }
+        }
+        (name, item)
+    }
+
+    /// Drops all but the first line of the field at `pos`, when its value is
+    /// replaced.
+    fn keep_first_line(&mut self, pos: uint) {
+        let mut seen = false;
+        self.lines.retain(|&line| {
+            if line != pos {
+                true
+            } else if seen {
+                false
+            } else {
+                seen = true;
+                true
+            }
+        });
+    }
+
+    /// Gives each of the `count` lines of the field at `pos` a place of its
+    /// own, after the last place it has.
+    fn spread_lines(&mut self, pos: uint, count: uint) {
+        let places = self.lines.iter().filter(|&&line| line == pos).count();
+        if places >= count {
+            return;
+        }
+        let last = self.lines.len() - 1 - self.lines.iter().rev().position(|&line| line == pos).unwrap();
+        for _ in range(places, count) {
+            self.lines.insert(last + 1, pos);
+        }
+    }
+
+    /// Calls `f` with each line to write, in order, as the field and the
+    /// index of the line in it.
+    ///
+    /// Each place of a field gets one of its lines, and the last place the
+    /// ones left, as a typed value may have more lines than it has places.
+    fn each_line<E>(&self, f: |&CaseInsensitive, &Item, uint| -> Result<(), E>) -> Result<(), E> {
+        let mut left = Vec::from_elem(self.data.len(), 0u);
+        for &pos in self.lines.iter() {
+            left[pos] += 1;
+        }
+        let mut next = Vec::from_elem(self.data.len(), 0u);
+        for &pos in self.lines.iter() {
+            let (ref name, ref item) = self.data[pos];
+            let item = item.borrow();
+            left[pos] -= 1;
+            let count = item.line_count();
+            let end = if left[pos] == 0 { count } else { cmp::min(next[pos] + 1, count) };
+            for index in range(next[pos], end) {
+                try!(f(name, &*item, index));
+            }
+            next[pos] = cmp::max(next[pos], end);
+        }
+        Ok(())
+    }
+
     #[doc(hidden)]
     pub fn from_raw<R: Reader>(rdr: &mut R) -> HttpResult<Headers> {
+        Headers::from_raw_with(rdr, ParseMode::Strict)
+    }
+
+    #[doc(hidden)]
+    pub fn from_raw_with<R: Reader>(rdr: &mut R, mode: ParseMode) -> HttpResult<Headers> {
+        let buf = try!(http::read_header_section(rdr));
+        match try!(http::parse_headers(buf[], mode)) {
+            Parsed::Complete(raw, _) => Ok(Headers::from_raw_headers(raw[])),
+            // the buffer was read up to the empty line
+            Parsed::Incomplete => unreachable!()
+        }
+    }
+
+    /// Copy header fields borrowed from a buffer, as parsed by
+    /// `http::parse_headers`, into a new `Headers`.
+    pub fn from_raw_headers(raw: &[http::RawHeader]) -> Headers {
         let mut headers = Headers::new();
-        loop {
-            match try!(http::read_header(rdr)) {
-                Some((name, value)) => {
-                    debug!("raw header: {}={}", name, value[].to_ascii());
-                    let name = CaseInsensitive(Owned(name));
-                    let mut item = match headers.data.entry(name) {
-                        Entry::Vacant(entry) => entry.set(MuCell::new(Item::raw(vec![]))),
-                        Entry::Occupied(entry) => entry.into_mut()
-                    };
-
-                    match &mut item.borrow_mut().raw {
-                        &Some(ref mut raw) => raw.push(value),
-                        // Unreachable
-                        _ => {}
-                    };
-                },
-                None => break,
-            }
+        for field in raw.iter() {
+            let name = intern_header_name(field.name.as_bytes());
+            headers.push_raw(CaseInsensitive(name), field.value.to_vec());
         }
-        Ok(headers)
+        headers
     }
 
     /// Set a header field to the corresponding value.
     ///
-    /// The field is determined by the type of the value being set.
+    /// The field is determined by the type of the value being set. If the
+    /// field is already there, it keeps its name as it was received or set.
     pub fn set<H: Header + HeaderFormat>(&mut self, value: H) {
-        self.data.insert(CaseInsensitive(Borrowed(header_name::<H>())),
-                         MuCell::new(Item::typed(box value as Box<HeaderFormat + Send + Sync>)));
+        let item = MuCell::new(Item::typed(box value as Box<HeaderFormat + Send + Sync>));
+        match self.position(header_name::<H>()) {
+            Some(pos) => {
+                self.data[pos].1 = item;
+                self.keep_first_line(pos);
+            },
+            None => self.push(CaseInsensitive(Borrowed(header_name::<H>())), item)
+        }
     }
 
     /// Access the raw value of a header.
@@ -166,28 +331,16 @@ impl Headers {
     /// let raw_content_type = headers.get_raw("content-type");
     /// ```
     pub fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
-        self.data
-            // FIXME(reem): Find a better way to do this lookup without find_equiv.
-            .get(&CaseInsensitive(Borrowed(unsafe { mem::transmute::<&str, &str>(name) })))
-            .and_then(|item| {
-                if let Some(ref raw) = item.borrow().raw {
-                    return unsafe { mem::transmute(Some(raw[])) };
-                }
-
-                let worked = item.try_mutate(|item| {
-                    let raw = vec![item.typed.as_ref().unwrap().to_string().into_bytes()];
-                    item.raw = Some(raw);
-                });
-                debug_assert!(worked, "item.try_mutate should return true");
-
-                let item = item.borrow();
-                let raw = item.raw.as_ref().unwrap();
-                unsafe { mem::transmute(Some(raw[])) }
-            })
+        self.find(name).map(raw_of)
     }
 
     /// Set the raw value of a header, bypassing any typed headers.
     ///
+    /// The header is written with `name` exactly as given.
+    ///
+    /// Neither `name` nor `value` are checked, so a value read from
+    /// somewhere untrusted should be set with `try_set_raw` instead.
+    ///
     /// Example:
     ///
     /// ```
@@ -196,7 +349,80 @@ impl Headers {
     /// headers.set_raw("content-length", vec!["5".as_bytes().to_vec()]);
     /// ```
     pub fn set_raw<K: IntoCow<'static, String, str>>(&mut self, name: K, value: Vec<Vec<u8>>) {
-        self.data.insert(CaseInsensitive(name.into_cow()), MuCell::new(Item::raw(value)));
+        self.insert(CaseInsensitive(name.into_cow()), MuCell::new(Item::raw(value)));
+    }
+
+    /// Set the raw value of a header like `set_raw`, if it is valid.
+    ///
+    /// Returns `HttpHeaderError`, and leaves the headers alone, if `name` is
+    /// not a token or a line of `value` has control characters such as CR or
+    /// LF, which would let the value add headers of its own.
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// # let mut headers = Headers::new();
+    /// assert!(headers.try_set_raw("location", vec![b"/".to_vec()]).is_ok());
+    /// assert!(headers.try_set_raw("location", vec![b"/\r\nSet-Cookie: a=b".to_vec()]).is_err());
+    /// ```
+    pub fn try_set_raw<K: IntoCow<'static, String, str>>(&mut self, name: K, value: Vec<Vec<u8>>)
+            -> HttpResult<()> {
+        let name = name.into_cow();
+        if !http::is_valid_header_name(name.as_slice()) ||
+                !value.iter().all(|line| http::is_valid_header_value(line[])) {
+            return Err(HttpHeaderError);
+        }
+        self.insert(CaseInsensitive(name), MuCell::new(Item::raw(value)));
+        Ok(())
+    }
+
+    /// Add a raw line to a header, after the lines it already has.
+    ///
+    /// Unlike `set_raw`, this keeps the existing value, so a field can be
+    /// given several lines:
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// # let mut headers = Headers::new();
+    /// headers.append_raw("x-forwarded-for", b"10.0.0.1".to_vec());
+    /// headers.append_raw("x-forwarded-for", b"10.0.0.2".to_vec());
+    /// assert_eq!(headers.get_raw("x-forwarded-for").unwrap().len(), 2);
+    /// ```
+    pub fn append_raw<K: IntoCow<'static, String, str>>(&mut self, name: K, value: Vec<u8>) {
+        self.push_raw(CaseInsensitive(name.into_cow()), value);
+    }
+
+    /// Add a raw line to a header like `append_raw`, if it is valid.
+    ///
+    /// The name and value are checked like in `try_set_raw`.
+    pub fn try_append_raw<K: IntoCow<'static, String, str>>(&mut self, name: K, value: Vec<u8>)
+            -> HttpResult<()> {
+        let name = name.into_cow();
+        if !http::is_valid_header_name(name.as_slice()) || !http::is_valid_header_value(value[]) {
+            return Err(HttpHeaderError);
+        }
+        self.push_raw(CaseInsensitive(name), value);
+        Ok(())
+    }
+
+    fn push_raw(&mut self, name: CaseInsensitive, value: Vec<u8>) {
+        let pos = match self.position(name.as_slice()) {
+            Some(pos) => pos,
+            None => return self.push(name, MuCell::new(Item::raw(vec![value])))
+        };
+        let count = {
+            let item = self.data[pos].1.borrow_mut();
+            if item.raw.is_none() {
+                let raw = typed_lines(item.typed.as_ref().unwrap()).unwrap_or(Vec::new());
+                item.raw = Some(raw);
+            }
+            item.typed = None;
+            item.invalid = None;
+            item.raw.as_ref().unwrap().len()
+        };
+        // the lines already there stay in place, and the new one goes last
+        self.spread_lines(pos, count);
+        self.data[pos].1.borrow_mut().raw.as_mut().unwrap().push(value);
+        self.lines.push(pos);
     }
 
     /// Get a reference to the header field's value, if it exists.
@@ -211,16 +437,19 @@ impl Headers {
     /// Get a mutable reference to the header field's value, if it exists.
     pub fn get_mut<H: Header + HeaderFormat>(&mut self) -> Option<&mut H> {
         self.get_or_parse_mut::<H>().map(|item| {
-            unsafe { downcast_mut(item.borrow_mut()) }
+            let item = item.borrow_mut();
+            // the typed value may change, so the raw value would be stale
+            item.raw = None;
+            unsafe { downcast_mut(item) }
         })
     }
 
     fn get_or_parse<H: Header + HeaderFormat>(&self) -> Option<&MuCell<Item>> {
-        self.data.get(&CaseInsensitive(Borrowed(header_name::<H>()))).and_then(get_or_parse::<H>)
+        self.find(header_name::<H>()).and_then(get_or_parse::<H>)
     }
 
     fn get_or_parse_mut<H: Header + HeaderFormat>(&mut self) -> Option<&mut MuCell<Item>> {
-        self.data.get_mut(&CaseInsensitive(Borrowed(header_name::<H>()))).and_then(get_or_parse_mut::<H>)
+        self.find_mut(header_name::<H>()).and_then(get_or_parse_mut::<H>)
     }
 
     /// Returns a boolean of whether a certain header is in the map.
@@ -234,13 +463,46 @@ impl Headers {
     /// let has_type = headers.has::<ContentType>();
     /// ```
     pub fn has<H: Header + HeaderFormat>(&self) -> bool {
-        self.data.contains_key(&CaseInsensitive(Borrowed(header_name::<H>())))
+        self.position(header_name::<H>()).is_some()
     }
 
     /// Removes a header from the map, if one existed.
-    /// Returns true if a header has been removed.
-    pub fn remove<H: Header + HeaderFormat>(&mut self) -> bool {
-        self.data.remove(&CaseInsensitive(Borrowed(Header::header_name(None::<H>)))).is_some()
+    ///
+    /// Returns the removed value, or `None` if there was no such header or
+    /// its value could not be parsed as `H`. The header is removed either way.
+    pub fn remove<H: Header + HeaderFormat>(&mut self) -> Option<H> {
+        let (_, item) = match self.position(header_name::<H>()) {
+            Some(pos) => self.remove_at(pos),
+            None => return None
+        };
+        get_or_parse::<H>(&item).map(|item| {
+            unsafe { downcast::<H>(&*item.borrow()) }.clone()
+        })
+    }
+
+    /// Removes a header by name, returning its raw value if one existed.
+    ///
+    /// A typed value is formatted into its lines.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// # let mut headers = Headers::new();
+    /// headers.set_raw("keep-alive", vec![b"timeout=5".to_vec()]);
+    /// assert_eq!(headers.remove_raw("Keep-Alive"), Some(vec![b"timeout=5".to_vec()]));
+    /// assert!(headers.get_raw("keep-alive").is_none());
+    /// ```
+    pub fn remove_raw(&mut self, name: &str) -> Option<Vec<Vec<u8>>> {
+        let (_, mut item) = match self.position(name) {
+            Some(pos) => self.remove_at(pos),
+            None => return None
+        };
+        let item = item.borrow_mut();
+        match item.raw.take() {
+            Some(raw) => Some(raw),
+            None => Some(typed_lines(item.typed.as_ref().unwrap()).unwrap_or(Vec::new()))
+        }
     }
 
     /// Returns an iterator over the header fields.
@@ -250,6 +512,56 @@ impl Headers {
         }
     }
 
+    /// Returns an iterator over the names and raw values of all header fields.
+    ///
+    /// Typed headers are formatted to get their raw value, so this includes
+    /// every header in the map:
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// # let headers = Headers::new();
+    /// for (name, lines) in headers.iter_raw() {
+    ///     for line in lines.iter() {
+    ///         println!("{}: {}", name, String::from_utf8_lossy(line[]));
+    ///     }
+    /// }
+    /// ```
+    pub fn iter_raw<'a>(&'a self) -> HeadersRaw<'a> {
+        HeadersRaw {
+            inner: self.data.iter()
+        }
+    }
+
+    /// Write the header fields, each line ending in CRLF, to a `Writer`.
+    ///
+    /// This is what requests and responses send. Raw values are written as
+    /// they are, and typed values are formatted straight into the `Writer`.
+    /// A field whose name is not a token, or whose value has control
+    /// characters, such as CR or LF, fails with `InvalidInput` instead, so it
+    /// can't add lines of its own to the message.
+    pub fn write_to<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+        self.each_line(|name, item, index| {
+            if !http::is_valid_header_name(name.as_slice()) {
+                return Err(invalid_input("invalid header name"));
+            }
+            try!(w.write_str(name.as_slice()));
+            try!(w.write(b": "));
+            match item.raw {
+                Some(ref raw) => {
+                    if !http::is_valid_header_value(raw[index][]) {
+                        return Err(invalid_input("invalid header value"));
+                    }
+                    try!(w.write(raw[index][]));
+                },
+                None => {
+                    let typed = item.typed.as_ref().unwrap();
+                    try!(write!(&mut ValueWriter(w), "{}", Line(typed, index)));
+                }
+            }
+            w.write(LINE_ENDING)
+        })
+    }
+
     /// Returns the number of headers in the map.
     pub fn len(&self) -> uint {
         self.data.len()
@@ -257,28 +569,80 @@ impl Headers {
 
     /// Remove all headers from the map.
     pub fn clear(&mut self) {
-        self.data.clear()
+        self.data.clear();
+        self.index.clear();
+        self.lines.clear();
     }
 }
 
 impl fmt::Show for Headers {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        for header in self.iter() {
-            try!(write!(fmt, "{}{}", header, LineEnding));
+        self.each_line(|name, item, index| {
+            match item.raw {
+                Some(ref raw) => {
+                    if !http::is_valid_header_value(raw[index][]) {
+                        return Err(fmt::Error);
+                    }
+                    try!(write!(fmt, "{}: ", name));
+                    try!(fmt.write(raw[index][]));
+                },
+                None => {
+                    // typed values are only checked now, as they are written
+                    let value = Line(item.typed.as_ref().unwrap(), index).to_string();
+                    if !http::is_valid_header_value(value.as_bytes()) {
+                        return Err(fmt::Error);
+                    }
+                    try!(write!(fmt, "{}: {}", name, value));
+                }
+            }
+            write!(fmt, "{}", LineEnding)
+        })
+    }
+}
+
+/// A `Writer` passing on only bytes that are valid in a header value.
+struct ValueWriter<'a, W: 'a>(&'a mut W);
+
+impl<'a, W: Writer> Writer for ValueWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        if !http::is_valid_header_value(buf) {
+            return Err(invalid_input("invalid header value"));
         }
-        Ok(())
+        self.0.write(buf)
+    }
+}
+
+fn invalid_input(desc: &'static str) -> IoError {
+    IoError {
+        kind: InvalidInput,
+        desc: desc,
+        detail: None
     }
 }
 
 /// An `Iterator` over the fields in a `Headers` map.
 pub struct HeadersItems<'a> {
-    inner: Entries<'a, CaseInsensitive, MuCell<Item>>
+    inner: Items<'a, (CaseInsensitive, MuCell<Item>)>
 }
 
 impl<'a> Iterator<HeaderView<'a>> for HeadersItems<'a> {
     fn next(&mut self) -> Option<HeaderView<'a>> {
         match self.inner.next() {
-            Some((k, v)) => Some(HeaderView(k, v)),
+            Some(&(ref k, ref v)) => Some(HeaderView(k, v)),
+            None => None
+        }
+    }
+}
+
+/// An `Iterator` over the names and raw values of the fields in a `Headers` map.
+pub struct HeadersRaw<'a> {
+    inner: Items<'a, (CaseInsensitive, MuCell<Item>)>
+}
+
+impl<'a> Iterator<(&'a str, &'a [Vec<u8>])> for HeadersRaw<'a> {
+    fn next(&mut self) -> Option<(&'a str, &'a [Vec<u8>])> {
+        match self.inner.next() {
+            Some(&(ref k, ref v)) => Some((k.as_slice(), raw_of(v))),
             None => None
         }
     }
@@ -294,7 +658,8 @@ impl<'a> HeaderView<'a> {
         CaseInsensitive(header_name::<H>().into_cow()) == *self.0
     }
 
-    /// Get the Header name as a slice.
+    /// Get the Header name as a slice, with the case it was received or set
+    /// with.
     #[inline]
     pub fn name(&self) -> &'a str {
         self.0.as_slice()
@@ -310,7 +675,18 @@ impl<'a> HeaderView<'a> {
         })
     }
 
+    /// Get the raw lines of the header value.
+    ///
+    /// A typed value is formatted into its lines.
+    #[inline]
+    pub fn raw(&self) -> &'a [Vec<u8>] {
+        raw_of(self.1)
+    }
+
     /// Get just the header value as a String.
+    ///
+    /// A header sent as several lines, like `Set-Cookie`, has each of them
+    /// on a line of its own, as they can't be joined with commas.
     #[inline]
     pub fn value_string(&self) -> String {
         (*self.1.borrow()).to_string()
@@ -319,14 +695,22 @@ impl<'a> HeaderView<'a> {
 
 impl<'a> fmt::Show for HeaderView<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}: {}", self.0, *self.1.borrow())
+        let item = self.1.borrow();
+        for index in range(0, item.line_count()) {
+            if index != 0 {
+                try!(write!(f, "{}", LineEnding));
+            }
+            try!(write!(f, "{}: ", self.0));
+            try!(item.fmt_line(index, f));
+        }
+        Ok(())
     }
 }
 
 impl<'a> Extend<HeaderView<'a>> for Headers {
     fn extend<I: Iterator<HeaderView<'a>>>(&mut self, mut iter: I) {
         for header in iter {
-            self.data.insert((*header.0).clone(), (*header.1).clone());
+            self.insert((*header.0).clone(), (*header.1).clone());
         }
     }
 }
@@ -339,10 +723,54 @@ impl<'a> FromIterator<HeaderView<'a>> for Headers {
     }
 }
 
+/// `Headers` are encoded as a map of names to the lines of their raw values.
+///
+/// Each byte of a value is a character of the string, so values that are not
+/// UTF-8 come back as they were.
+impl<E, S: Encoder<E>> Encodable<S, E> for Headers {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
+        s.emit_map(self.len(), |s| {
+            for (i, (name, raw)) in self.iter_raw().enumerate() {
+                try!(s.emit_map_elt_key(i, |s| s.emit_str(name)));
+                let lines: Vec<String> = raw.iter().map(|line| {
+                    line.iter().map(|&b| b as char).collect()
+                }).collect();
+                try!(s.emit_map_elt_val(i, |s| lines.encode(s)));
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<E, D: Decoder<E>> Decodable<D, E> for Headers {
+    fn decode(d: &mut D) -> Result<Headers, E> {
+        d.read_map(|d, len| {
+            let mut headers = Headers::new();
+            for i in range(0, len) {
+                let name: String = try!(d.read_map_elt_key(i, |d| Decodable::decode(d)));
+                let lines: Vec<String> = try!(d.read_map_elt_val(i, |d| Decodable::decode(d)));
+                let mut raw = Vec::with_capacity(lines.len());
+                for line in lines.iter() {
+                    if line.chars().any(|c| c as u32 > 0xFF) {
+                        return Err(d.error("header values are bytes"));
+                    }
+                    raw.push(line.chars().map(|c| c as u8).collect());
+                }
+                if headers.try_set_raw(name, raw).is_err() {
+                    return Err(d.error("invalid header"));
+                }
+            }
+            Ok(headers)
+        })
+    }
+}
+
 #[deriving(Clone)]
 struct Item {
     raw: Option<Vec<Vec<u8>>>,
-    typed: Option<Box<HeaderFormat + Send + Sync>>
+    typed: Option<Box<HeaderFormat + Send + Sync>>,
+    // the type the raw value last failed to parse as, so it isn't tried again
+    invalid: Option<TypeId>
 }
 
 impl Item {
@@ -350,6 +778,7 @@ impl Item {
         Item {
             raw: Some(data),
             typed: None,
+            invalid: None
         }
     }
 
@@ -357,11 +786,69 @@ impl Item {
         Item {
             raw: None,
             typed: Some(ty),
+            invalid: None
+        }
+    }
+
+    fn line_count(&self) -> uint {
+        match self.raw {
+            Some(ref raw) => raw.len(),
+            None => self.typed.as_ref().unwrap().line_count()
+        }
+    }
+
+    fn fmt_line(&self, index: uint, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.raw {
+            Some(ref raw) => fmt.write(raw[index][]),
+            None => self.typed.as_ref().unwrap().fmt_line(index, fmt)
         }
     }
 
 }
 
+/// One of the lines of a typed header, formatted with `fmt_line`.
+struct Line<'a>(&'a Box<HeaderFormat + Send + Sync>, uint);
+
+impl<'a> Show for Line<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        (**self.0).fmt_line(self.1, fmt)
+    }
+}
+
+/// The lines of a typed value, or `None` if any of them is not a valid
+/// header value, so it must not be kept as raw lines that are written as
+/// they are.
+fn typed_lines(typed: &Box<HeaderFormat + Send + Sync>) -> Option<Vec<Vec<u8>>> {
+    let lines: Vec<Vec<u8>> = range(0, typed.line_count()).map(|index| {
+        Line(typed, index).to_string().into_bytes()
+    }).collect();
+    if lines.iter().all(|line| http::is_valid_header_value(line[])) {
+        Some(lines)
+    } else {
+        None
+    }
+}
+
+fn raw_of(item: &MuCell<Item>) -> &[Vec<u8>] {
+    if let Some(ref raw) = item.borrow().raw {
+        return unsafe { mem::transmute(raw[]) };
+    }
+
+    let raw = match typed_lines(item.borrow().typed.as_ref().unwrap()) {
+        Some(raw) => raw,
+        // a value that can't be sent has no raw lines
+        None => return &[]
+    };
+    let worked = item.try_mutate(|item| {
+        item.raw = Some(raw);
+    });
+    debug_assert!(worked, "item.try_mutate should return true");
+
+    let item = item.borrow();
+    let raw = item.raw.as_ref().unwrap();
+    unsafe { mem::transmute(raw[]) }
+}
+
 fn get_or_parse<H: Header + HeaderFormat>(item: &MuCell<Item>) -> Option<&MuCell<Item>> {
     match item.borrow().typed {
         Some(ref typed) if typed.is::<H>() => return Some(item),
@@ -371,6 +858,9 @@ fn get_or_parse<H: Header + HeaderFormat>(item: &MuCell<Item>) -> Option<&MuCell
         }
         _ => ()
     }
+    if item.borrow().invalid == Some(TypeId::of::<H>()) {
+        return None;
+    }
 
     let worked = item.try_mutate(parse::<H>);
     debug_assert!(worked, "item.try_mutate should return true");
@@ -396,6 +886,9 @@ fn get_or_parse_mut<H: Header + HeaderFormat>(item: &mut MuCell<Item>) -> Option
         Some(false) => return None,
         None => ()
     }
+    if item.borrow().invalid == Some(TypeId::of::<H>()) {
+        return None;
+    }
 
     parse::<H>(item.borrow_mut());
     if item.borrow().typed.is_some() {
@@ -413,6 +906,9 @@ fn parse<H: Header + HeaderFormat>(item: &mut Item) {
         },
         None => unreachable!()
     };
+    if item.typed.is_none() {
+        item.invalid = Some(TypeId::of::<H>());
+    }
 }
 
 unsafe fn downcast<H: Header + HeaderFormat>(item: &Item) -> &H {
@@ -425,18 +921,13 @@ unsafe fn downcast_mut<H: Header + HeaderFormat>(item: &mut Item) -> &mut H {
 
 impl fmt::Show for Item {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match self.typed {
-            Some(ref h) => h.fmt_header(fmt),
-            None => match self.raw {
-                Some(ref raw) => {
-                    for part in raw.iter() {
-                        try!(fmt.write(part.as_slice()));
-                    }
-                    Ok(())
-                },
-                None => unreachable!()
+        for index in range(0, self.line_count()) {
+            if index != 0 {
+                try!(write!(fmt, "{}", LineEnding));
             }
+            try!(self.fmt_line(index, fmt));
         }
+        Ok(())
     }
 }
 
@@ -508,16 +999,22 @@ impl<'a, H: HeaderFormat> Show for HeaderFormatter<'a, H> {
 
 #[cfg(test)]
 mod tests {
-    use std::io::MemReader;
+    use std::io::{MemReader, MemWriter, InvalidInput};
     use std::fmt;
     use std::borrow::Cow::Borrowed;
     use std::hash::sip::hash;
+    use std::sync::atomic::{AtomicUint, INIT_ATOMIC_UINT, SeqCst};
     use mime::Mime;
     use mime::TopLevel::Text;
     use mime::SubLevel::Plain;
     use super::CaseInsensitive;
     use super::{Headers, Header, HeaderFormat};
-    use super::common::{ContentLength, ContentType, Accept, Host};
+    use super::common::{ContentLength, ContentType, Accept, Host, Location};
+    use super::shared::{QualityItem, qitem};
+    use serialize::json;
+
+    use HttpError::HttpHeaderError;
+    use http::ParseMode::Lenient;
 
     use test::Bencher;
 
@@ -540,6 +1037,21 @@ mod tests {
         assert_eq!(headers.get(), Some(&ContentLength(10)));
     }
 
+    #[test]
+    fn test_from_raw_lenient() {
+        let headers = Headers::from_raw_with(&mut mem("X-Folded: a\n\tb,\r\n c\nContent-Length: 10\n\n"),
+                                             Lenient).unwrap();
+        assert_eq!(headers.get_raw("X-Folded"), Some([b"a b, c".to_vec()][]));
+        assert_eq!(headers.get(), Some(&ContentLength(10)));
+
+        assert_eq!(Headers::from_raw_with(&mut mem(" a\r\n\r\n"), Lenient).err(), Some(HttpHeaderError));
+        assert_eq!(Headers::from_raw(&mut mem("X-Folded: a\r\n b\r\n\r\n")).err(), Some(HttpHeaderError));
+        assert_eq!(Headers::from_raw_with(&mut mem("Content-Length: 1\n 0\n\n"), Lenient).err(),
+                   Some(HttpHeaderError));
+        assert_eq!(Headers::from_raw_with(&mut mem("transfer-encoding: gzip,\n chunked\n\n"), Lenient).err(),
+                   Some(HttpHeaderError));
+    }
+
     #[test]
     fn test_content_type() {
         let content_type = Header::parse_header(["text/plain".as_bytes().to_vec()].as_slice());
@@ -548,8 +1060,9 @@ mod tests {
 
     #[test]
     fn test_accept() {
-        let text_plain = Mime(Text, Plain, vec![]);
-        let application_vendor = "application/vnd.github.v3.full+json; q=0.5".parse().unwrap();
+        let text_plain = qitem(Mime(Text, Plain, vec![]));
+        let application_vendor = QualityItem::new(
+            "application/vnd.github.v3.full+json".parse().unwrap(), 0.5);
 
         let accept = Header::parse_header([b"text/plain".to_vec()].as_slice());
         assert_eq!(accept, Some(Accept(vec![text_plain.clone()])));
@@ -587,6 +1100,45 @@ mod tests {
         }
     }
 
+    static COUNTED_PARSES: AtomicUint = INIT_ATOMIC_UINT;
+
+    #[deriving(Clone)]
+    struct Counted;
+
+    impl Header for Counted {
+        fn header_name(_: Option<Counted>) -> &'static str {
+            "X-Counted"
+        }
+        fn parse_header(raw: &[Vec<u8>]) -> Option<Counted> {
+            COUNTED_PARSES.fetch_add(1, SeqCst);
+            if raw == [b"ok".to_vec()][] { Some(Counted) } else { None }
+        }
+    }
+
+    impl HeaderFormat for Counted {
+        fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            fmt.write(b"ok")
+        }
+    }
+
+    #[test]
+    fn test_parse_once() {
+        let headers = Headers::from_raw(&mut mem("X-Counted: ok\r\n\r\n")).unwrap();
+        assert_eq!(COUNTED_PARSES.load(SeqCst), 0);
+        assert!(headers.get::<Counted>().is_some());
+        assert!(headers.get::<Counted>().is_some());
+        assert_eq!(COUNTED_PARSES.load(SeqCst), 1);
+
+        let mut headers = Headers::from_raw(&mut mem("X-Counted: bad\r\n\r\n")).unwrap();
+        assert!(headers.get::<Counted>().is_none());
+        assert!(headers.get_mut::<Counted>().is_none());
+        assert_eq!(COUNTED_PARSES.load(SeqCst), 2);
+
+        headers.set_raw("X-Counted", vec![b"ok".to_vec()]);
+        assert!(headers.get::<Counted>().is_some());
+        assert_eq!(COUNTED_PARSES.load(SeqCst), 3);
+    }
+
     #[test]
     fn test_different_structs_for_same_header() {
         let headers = Headers::from_raw(&mut mem("Content-Length: 10\r\n\r\n")).unwrap();
@@ -639,6 +1191,115 @@ mod tests {
         assert_eq!(headers.get(), Some(&ContentLength(20)));
     }
 
+    #[test]
+    fn test_headers_show_keeps_order() {
+        let mut headers = Headers::from_raw(&mut mem("X-B: 1\r\nX-A: 2\r\nX-B: 3\r\n\r\n")).unwrap();
+        headers.set(ContentLength(5));
+        assert_eq!(headers.to_string()[], "X-B: 1\r\nX-A: 2\r\nX-B: 3\r\nContent-Length: 5\r\n");
+        headers.set_raw("x-a", vec![b"4".to_vec(), b"5".to_vec()]);
+        assert_eq!(headers.to_string()[], "X-B: 1\r\nx-a: 4\r\nx-a: 5\r\nX-B: 3\r\nContent-Length: 5\r\n");
+        headers.set_raw("X-B", vec![b"6".to_vec()]);
+        assert_eq!(headers.to_string()[], "X-B: 6\r\nx-a: 4\r\nx-a: 5\r\nContent-Length: 5\r\n");
+        headers.remove_raw("x-a");
+        headers.append_raw("X-A", b"7".to_vec());
+        headers.append_raw("X-B", b"8".to_vec());
+        assert_eq!(headers.to_string()[], "X-B: 6\r\nContent-Length: 5\r\nX-A: 7\r\nX-B: 8\r\n");
+    }
+
+    #[test]
+    fn test_view_show_lines() {
+        let headers = Headers::from_raw(&mut mem("Set-Cookie: a=1, b\r\nSet-Cookie: c=2\r\n\r\n")).unwrap();
+        let view = headers.iter().next().unwrap();
+        assert_eq!(view.to_string()[], "Set-Cookie: a=1, b\r\nSet-Cookie: c=2");
+        assert_eq!(view.value_string()[], "a=1, b\r\nc=2");
+    }
+
+    #[test]
+    fn test_name_case() {
+        let mut headers = Headers::from_raw(&mut mem("content-LENGTH: 10\r\nX-Api-KEY: a\r\n\r\n")).unwrap();
+        headers.set(ContentLength(20));
+        assert_eq!(headers.iter().map(|h| h.name()).collect::<Vec<&str>>(),
+                   vec!["content-LENGTH", "X-Api-KEY"]);
+        assert_eq!(headers.to_string()[], "content-LENGTH: 20\r\nX-Api-KEY: a\r\n");
+
+        headers.set_raw("X-API-KEY", vec![b"b".to_vec()]);
+        assert_eq!(headers.to_string()[], "content-LENGTH: 20\r\nX-API-KEY: b\r\n");
+    }
+
+    #[test]
+    fn test_append_raw() {
+        let mut headers = Headers::new();
+        headers.set(ContentLength(10));
+        headers.append_raw("Content-Length", b"10".to_vec());
+        assert_eq!(headers.get_raw("content-length").unwrap(), [b"10".to_vec(), b"10".to_vec()][]);
+        assert_eq!(headers.get::<ContentLength>(), None);
+        assert_eq!(headers.to_string()[], "Content-Length: 10\r\nContent-Length: 10\r\n");
+    }
+
+    #[test]
+    fn test_set_raw_invalid() {
+        let mut headers = Headers::new();
+        assert_eq!(headers.try_set_raw("Location", vec![b"/\r\nSet-Cookie: a=b".to_vec()]),
+                   Err(HttpHeaderError));
+        assert_eq!(headers.try_set_raw("Bad Name", vec![b"a".to_vec()]), Err(HttpHeaderError));
+        assert_eq!(headers.try_set_raw("", vec![b"a".to_vec()]), Err(HttpHeaderError));
+        assert_eq!(headers.try_append_raw("X-Foo", b"a\nb".to_vec()), Err(HttpHeaderError));
+        assert_eq!(headers.try_append_raw("X-Foo", b"a\x00".to_vec()), Err(HttpHeaderError));
+        assert_eq!(headers.len(), 0);
+
+        assert!(headers.try_set_raw("X-Foo", vec![b"a\tb \xff".to_vec()]).is_ok());
+    }
+
+    #[test]
+    fn test_show_invalid_typed() {
+        let mut headers = Headers::new();
+        headers.set(Location("/\r\nSet-Cookie: a=b".to_string()));
+        assert!(write!(&mut MemWriter::new(), "{}", headers).is_err());
+        assert!(headers.write_to(&mut MemWriter::new()).is_err());
+    }
+
+    #[test]
+    fn test_write_to_invalid() {
+        // reading the raw value of a typed one doesn't let it through
+        let mut headers = Headers::new();
+        headers.set(Location("/\r\nSet-Cookie: a=b".to_string()));
+        assert!(headers.get_raw("Location").unwrap().is_empty());
+        let err = headers.write_to(&mut MemWriter::new()).unwrap_err();
+        assert_eq!(err.kind, InvalidInput);
+
+        let mut headers = Headers::new();
+        headers.set_raw("Location", vec![b"/\r\nSet-Cookie: a=b".to_vec()]);
+        assert_eq!(headers.write_to(&mut MemWriter::new()).unwrap_err().kind, InvalidInput);
+        assert!(write!(&mut MemWriter::new(), "{}", headers).is_err());
+
+        let mut headers = Headers::new();
+        headers.set_raw("Bad: Name", vec![b"a".to_vec()]);
+        assert_eq!(headers.write_to(&mut MemWriter::new()).unwrap_err().kind, InvalidInput);
+    }
+
+    #[test]
+    fn test_write_to() {
+        let mut headers = Headers::from_raw(&mut mem("X-Foo: a\r\nX-Foo: b\r\n\r\n")).unwrap();
+        headers.set(ContentLength(15));
+        let mut w = MemWriter::new();
+        headers.write_to(&mut w).unwrap();
+        assert_eq!(w.get_ref(), b"X-Foo: a\r\nX-Foo: b\r\nContent-Length: 15\r\n"[]);
+        assert_eq!(w.get_ref(), headers.to_string().as_bytes());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut headers = Headers::from_raw(&mut mem("Content-Length: 10\r\nX-Foo: a\r\nX-Foo: b\r\n\r\n")).unwrap();
+        headers.set(Host { hostname: "foo.bar".to_string(), port: None });
+
+        assert_eq!(headers.remove::<ContentLength>(), Some(ContentLength(10)));
+        assert_eq!(headers.remove::<ContentLength>(), None);
+        assert_eq!(headers.remove_raw("x-foo"), Some(vec![b"a".to_vec(), b"b".to_vec()]));
+        assert_eq!(headers.remove_raw("x-foo"), None);
+        assert_eq!(headers.remove_raw("Host"), Some(vec![b"foo.bar".to_vec()]));
+        assert_eq!(headers.len(), 0);
+    }
+
     #[test]
     fn test_len() {
         let mut headers = Headers::new();
@@ -673,6 +1334,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_iter_raw() {
+        let mut headers = Headers::from_raw(&mut mem("X-Foo: a\r\nX-Foo: b\r\n\r\n")).unwrap();
+        headers.set(ContentLength(11));
+
+        let mut raw = headers.iter_raw().map(|(name, lines)| {
+            (name.to_string(), lines.to_vec())
+        }).collect::<Vec<(String, Vec<Vec<u8>>)>>();
+        raw.sort();
+        assert_eq!(raw, vec![
+            ("Content-Length".to_string(), vec![b"11".to_vec()]),
+            ("X-Foo".to_string(), vec![b"a".to_vec(), b"b".to_vec()]),
+        ]);
+    }
+
+    #[test]
+    fn test_get_mut_updates_raw() {
+        let mut headers = Headers::from_raw(&mut mem("Content-Length: 10\r\n\r\n")).unwrap();
+        *headers.get_mut::<ContentLength>().unwrap() = ContentLength(20);
+        assert_eq!(headers.get_raw("Content-Length").unwrap(), [b"20".to_vec()][]);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut headers = Headers::new();
+        headers.set(ContentLength(10));
+        headers.set_raw("X-Bytes", vec![b"caf\xe9".to_vec(), b"two".to_vec()]);
+        let encoded = json::encode(&headers);
+        assert!(encoded[].contains("\"Content-Length\":[\"10\"]"));
+
+        let decoded: Headers = json::decode(encoded[]).unwrap();
+        assert_eq!(decoded.get::<ContentLength>(), Some(&ContentLength(10)));
+        assert_eq!(decoded.get_raw("X-Bytes").unwrap(), [b"caf\xe9".to_vec(), b"two".to_vec()][]);
+
+        assert!(json::decode::<Headers>("{\"Bad Name\":[\"x\"]}").is_err());
+    }
+
     #[bench]
     fn bench_header_get(b: &mut Bencher) {
         let mut headers = Headers::new();
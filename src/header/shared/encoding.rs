@@ -0,0 +1,58 @@
+use std::fmt;
+use std::str::FromStr;
+
+use self::Encoding::{Chunked, Gzip, Deflate, Compress, Identity, EncodingExt};
+
+/// A content or transfer coding, used by the `Transfer-Encoding`,
+/// `Content-Encoding` and `Accept-Encoding` headers.
+///
+/// Example:
+///
+/// ```
+/// # use hyper::header::TransferEncoding;
+/// # use hyper::header::shared::Encoding::{Gzip, Chunked};
+/// # use hyper::header::Headers;
+/// # let mut headers = Headers::new();
+/// headers.set(TransferEncoding(vec![Gzip, Chunked]));
+/// ```
+#[deriving(Clone, PartialEq)]
+pub enum Encoding {
+    /// The `chunked` encoding.
+    Chunked,
+    /// The `gzip` encoding.
+    Gzip,
+    /// The `deflate` encoding.
+    Deflate,
+    /// The `compress` encoding.
+    Compress,
+    /// The `identity` encoding, which leaves the body unchanged.
+    Identity,
+    /// Some other encoding that is less common, can be any String.
+    EncodingExt(String)
+}
+
+impl fmt::Show for Encoding {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Chunked => "chunked",
+            Gzip => "gzip",
+            Deflate => "deflate",
+            Compress => "compress",
+            Identity => "identity",
+            EncodingExt(ref s) => s.as_slice()
+        }.fmt(fmt)
+    }
+}
+
+impl FromStr for Encoding {
+    fn from_str(s: &str) -> Option<Encoding> {
+        match s {
+            "chunked" => Some(Chunked),
+            "deflate" => Some(Deflate),
+            "gzip" => Some(Gzip),
+            "compress" => Some(Compress),
+            "identity" => Some(Identity),
+            _ => Some(EncodingExt(s.to_string()))
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! Types shared by the values of several headers.
+
+pub use self::encoding::Encoding;
+pub use self::params::Params;
+pub use self::quality_item::{QualityItem, q, qitem};
+
+/// Exposes the Encoding type.
+pub mod encoding;
+
+/// Exposes the Params type.
+pub mod params;
+
+/// Exposes the QualityItem type.
+pub mod quality_item;
@@ -0,0 +1,62 @@
+use std::ascii::AsciiExt;
+
+/// The `name=value` parameters of a header value, in the order they were
+/// parsed or added.
+///
+/// Names are compared ignoring case. The parameters of `WWW-Authenticate`,
+/// `Content-Disposition`, `Link` and `Forwarded` are kept in one of these.
+///
+/// ```
+/// # use hyper::header::shared::Params;
+/// let mut params = Params::new();
+/// params.push("realm", "admin");
+/// assert_eq!(params.get("Realm"), Some("admin"));
+/// assert_eq!(params.get("nonce"), None);
+/// ```
+#[deriving(Clone, PartialEq, Show)]
+pub struct Params(pub Vec<(String, String)>);
+
+impl Params {
+    /// No parameters.
+    pub fn new() -> Params {
+        Params(Vec::new())
+    }
+
+    /// Add a parameter, after any already there.
+    pub fn push(&mut self, name: &str, value: &str) {
+        self.0.push((name.to_string(), value.to_string()));
+    }
+
+    /// The value of the first parameter named `name`, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.iter().find(|&&(ref n, _)| n[].eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| value[])
+    }
+}
+
+impl Deref<Vec<(String, String)>> for Params {
+    fn deref<'a>(&'a self) -> &'a Vec<(String, String)> {
+        &self.0
+    }
+}
+
+impl DerefMut<Vec<(String, String)>> for Params {
+    fn deref_mut<'a>(&'a mut self) -> &'a mut Vec<(String, String)> {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Params;
+
+    #[test]
+    fn test_get_ignores_case() {
+        let mut params = Params::new();
+        params.push("filename", "a.txt");
+        params.push("FileName", "b.txt");
+        assert_eq!(params.get("FILENAME"), Some("a.txt"));
+        assert_eq!(params.get("name"), None);
+        assert_eq!(params.len(), 2);
+    }
+}
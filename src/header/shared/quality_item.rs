@@ -0,0 +1,134 @@
+use std::fmt::{mod, Show};
+use std::str::{FromStr, from_str};
+
+/// An item in a list with a quality value, or weight, like `gzip; q=0.8`.
+///
+/// The quality is between `0` and `1`, and defaults to `1`. A quality of
+/// `0` means the item is not acceptable. It is written with at most three
+/// decimals, as the grammar allows.
+///
+/// `Accept`, `AcceptEncoding`, `AcceptLanguage` and `Te` are lists of these.
+///
+/// See https://tools.ietf.org/html/rfc7231#section-5.3.1
+#[deriving(Clone, PartialEq)]
+pub struct QualityItem<T> {
+    /// The item.
+    pub item: T,
+    /// The quality of the item. A quality outside `0` to `1` is written as
+    /// the nearest of them.
+    pub quality: f32
+}
+
+impl<T> QualityItem<T> {
+    /// Create an item with a quality, which is clamped to be between `0`
+    /// and `1`.
+    pub fn new(item: T, quality: f32) -> QualityItem<T> {
+        QualityItem {
+            item: item,
+            quality: clamp(quality)
+        }
+    }
+}
+
+/// Bring a quality into `0` to `1`, where `NaN` is `0`.
+fn clamp(quality: f32) -> f32 {
+    if quality > 1.0 {
+        1.0
+    } else if quality >= 0.0 {
+        quality
+    } else {
+        0.0
+    }
+}
+
+/// Create an item with a quality, as `q(Gzip, 0.5)` for `gzip; q=0.5`.
+pub fn q<T>(item: T, quality: f32) -> QualityItem<T> {
+    QualityItem::new(item, quality)
+}
+
+/// Create an item with the default quality of `1`.
+pub fn qitem<T>(item: T) -> QualityItem<T> {
+    QualityItem::new(item, 1.0)
+}
+
+impl<T: FromStr> FromStr for QualityItem<T> {
+    fn from_str(s: &str) -> Option<QualityItem<T>> {
+        let mut item = s;
+        let mut quality = 1.0;
+        // the parameters before the weight belong to the item, and the
+        // extension parameters after it are ignored
+        for (i, _) in s.char_indices().filter(|&(_, c)| c == ';') {
+            let param = s[i + 1..].split(';').next().unwrap_or("").trim();
+            if param.starts_with("q=") || param.starts_with("Q=") {
+                quality = match from_str::<f32>(param[2..].trim()) {
+                    Some(q) if q >= 0.0 && q <= 1.0 => q,
+                    _ => return None
+                };
+                item = s[..i];
+                break;
+            }
+        }
+        from_str(item.trim()).map(|item| QualityItem::new(item, quality))
+    }
+}
+
+impl<T: Show> Show for QualityItem<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(self.item.fmt(fmt));
+        let quality = clamp(self.quality);
+        let thousandths = match (quality * 1000.0 + 0.5) as uint {
+            // any weight above zero is still acceptable
+            0 if quality > 0.0 => 1,
+            thousandths => thousandths
+        };
+        match thousandths {
+            1000 => Ok(()),
+            0 => write!(fmt, "; q=0"),
+            _ => {
+                let decimals = format!("{:03}", thousandths);
+                write!(fmt, "; q=0.{}", decimals[].trim_right_chars('0'))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::from_str;
+    use super::{QualityItem, q, qitem};
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(from_str::<QualityItem<String>>("gzip"), Some(qitem("gzip".to_string())));
+        assert_eq!(from_str::<QualityItem<String>>("gzip; q=0.5"),
+                   Some(QualityItem::new("gzip".to_string(), 0.5)));
+        assert_eq!(from_str::<QualityItem<String>>("gzip;q=0"),
+                   Some(QualityItem::new("gzip".to_string(), 0.0)));
+        assert_eq!(from_str::<QualityItem<String>>("text/html;level=1;q=0.5"),
+                   Some(QualityItem::new("text/html;level=1".to_string(), 0.5)));
+        assert_eq!(from_str::<QualityItem<String>>("text/html;q=0.5;ext=1"),
+                   Some(QualityItem::new("text/html".to_string(), 0.5)));
+        assert_eq!(from_str::<QualityItem<String>>("gzip; q=2"), None);
+        assert_eq!(from_str::<QualityItem<String>>("gzip; q=x"), None);
+    }
+
+    #[test]
+    fn test_show() {
+        assert_eq!(qitem("gzip").to_string()[], "gzip");
+        assert_eq!(q("gzip", 0.5).to_string()[], "gzip; q=0.5");
+        assert_eq!(QualityItem::new("gzip", 0.05).to_string()[], "gzip; q=0.05");
+        assert_eq!(QualityItem::new("gzip", 1.0 / 3.0).to_string()[], "gzip; q=0.333");
+        assert_eq!(QualityItem::new("gzip", 0.0).to_string()[], "gzip; q=0");
+        assert_eq!(QualityItem::new("gzip", 0.9999).to_string()[], "gzip");
+        assert_eq!(QualityItem::new("gzip", 0.0004).to_string()[], "gzip; q=0.001");
+        assert_eq!(QualityItem::new("gzip", 1.5).to_string()[], "gzip");
+        assert_eq!(q("gzip", 2.0).quality, 1.0);
+        assert_eq!(QualityItem::new("gzip", -0.5).to_string()[], "gzip; q=0");
+        // the field can still be set out of range
+        let mut item = qitem("gzip");
+        item.quality = 1.5;
+        assert_eq!(item.to_string()[], "gzip");
+        item.quality = -1.0;
+        assert_eq!(item.to_string()[], "gzip; q=0");
+    }
+}
@@ -1,14 +1,51 @@
 //! Pieces pertaining to the HTTP message protocol.
+//!
+//! These work on any `Reader` or buffer, without the client or server, so
+//! tools such as proxies, test servers and sniffers can use them to parse
+//! and write HTTP/1.x messages:
+//!
+//! - `read_request_line` and `read_status_line` read the first line of a
+//!   message from a `Reader`, and `Headers::from_raw` the header section.
+//! - `parse_request_head`, `parse_response_head` and `parse_headers` parse
+//!   from a buffer that may not hold the whole head yet, without blocking.
+//! - `HttpReader` reads a body framed by its length, by chunks, or by the end
+//!   of the stream, and `HttpWriter` writes one. `parse_chunk_size` parses a
+//!   chunk size line from a buffer.
+//!
+//! ```
+//! use std::default::Default;
+//! use std::io::MemReader;
+//! use hyper::http::{parse_request_head, HttpReader, ParseMode, Parsed};
+//!
+//! let buf = b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+//! let ((method, _, _), headers, len) = match parse_request_head(buf, ParseMode::Strict).unwrap() {
+//!     Parsed::Complete((line, headers), len) => (line, headers, len),
+//!     Parsed::Incomplete => panic!("the head is in the buffer")
+//! };
+//! assert_eq!(method, hyper::Post);
+//! assert_eq!(headers.get_raw("Transfer-Encoding"), Some([b"chunked".to_vec()][]));
+//!
+//! let rdr = MemReader::new(buf[len..].to_vec());
+//! let mut body = HttpReader::ChunkedReader(rdr, None, Default::default());
+//! assert_eq!(body.read_to_string().unwrap()[], "hello");
+//! ```
+use std::ascii::AsciiExt;
+use std::borrow::Cow;
 use std::borrow::Cow::{Borrowed, Owned};
 use std::cmp::min;
+use std::default::Default;
 use std::fmt;
-use std::io::{mod, Reader, IoResult, BufWriter};
-use std::num::from_u16;
+use std::io::{mod, Reader, IoError, IoResult, BufReader, BufWriter, InvalidInput};
+use std::num::{from_u16, Int};
 use std::str::{mod, SendStr, FromStr};
 
 use url::Url;
 use url::ParseError as UrlError;
 
+use header::Headers;
+use names::intern_header_name;
+use header::common::{Connection, ContentLength};
+use header::common::connection::{Close, KeepAlive};
 use method;
 use status::StatusCode;
 use uri;
@@ -30,7 +67,10 @@ pub enum HttpReader<R> {
     /// A Reader used when a Content-Length header is passed with a positive integer.
     SizedReader(R, uint),
     /// A Reader used when Transfer-Encoding is `chunked`.
-    ChunkedReader(R, Option<uint>),
+    ///
+    /// The `Option` is the remaining size of the current chunk, and is `None`
+    /// to start with.
+    ChunkedReader(R, Option<uint>, ChunkLimits),
     /// A Reader used for responses that don't indicate a length or chunked.
     ///
     /// Note: This should only used for `Response`s. It is illegal for a
@@ -58,11 +98,24 @@ impl<R: Reader> HttpReader<R> {
     pub fn unwrap(self) -> R {
         match self {
             SizedReader(r, _) => r,
-            ChunkedReader(r, _) => r,
+            ChunkedReader(r, _, _) => r,
             EofReader(r) => r,
             EmptyReader(r) => r,
         }
     }
+
+    /// Access the inner Reader mutably.
+    ///
+    /// Warning: You should not read from this directly, as you can corrupt
+    /// the state.
+    pub fn get_mut<'a>(&'a mut self) -> &'a mut R {
+        match *self {
+            SizedReader(ref mut r, _) => r,
+            ChunkedReader(ref mut r, _, _) => r,
+            EofReader(ref mut r) => r,
+            EmptyReader(ref mut r) => r,
+        }
+    }
 }
 
 impl<R: Reader> Reader for HttpReader<R> {
@@ -82,11 +135,23 @@ impl<R: Reader> Reader for HttpReader<R> {
                     Ok(num)
                 }
             },
-            ChunkedReader(ref mut body, ref mut opt_remaining) => {
+            ChunkedReader(ref mut body, ref mut opt_remaining, ref mut limits) => {
                 let mut rem = match *opt_remaining {
                     Some(ref rem) => *rem,
                     // None means we don't know the size of the next chunk
-                    None => try!(read_chunk_size(body))
+                    None => {
+                        let size = try!(read_chunk_size(body, limits.max_chunk_size));
+                        if size > limits.max_body_size {
+                            return Err(IoError {
+                                kind: InvalidInput,
+                                desc: "Chunked body is larger than allowed",
+                                detail: Some(format!("a chunk of {} bytes, with {} bytes left",
+                                                     size, limits.max_body_size))
+                            });
+                        }
+                        limits.max_body_size -= size;
+                        size
+                    }
                 };
                 debug!("Chunked read, remaining={}", rem);
 
@@ -130,24 +195,46 @@ fn eat<R: Reader>(rdr: &mut R, bytes: &[u8]) -> IoResult<()> {
     Ok(())
 }
 
+/// How large a chunk may be by default, 64MB.
+pub const DEFAULT_MAX_CHUNK_SIZE: uint = 64 * 1024 * 1024;
+
+/// Limits on the chunks of a body read by a `ChunkedReader`, so a peer can't
+/// make a reader expect absurd amounts of data, such as a chunk of 16EB.
+///
+/// Going over a limit is an `InvalidInput` error.
+#[deriving(Copy, Clone, PartialEq, Show)]
+pub struct ChunkLimits {
+    /// The largest size a chunk may have. The default is
+    /// `DEFAULT_MAX_CHUNK_SIZE`.
+    pub max_chunk_size: uint,
+    /// How much the chunks may still add up to. A `ChunkedReader` counts this
+    /// down as it reads chunk sizes. The default has no limit.
+    pub max_body_size: uint
+}
+
+impl Default for ChunkLimits {
+    fn default() -> ChunkLimits {
+        ChunkLimits {
+            max_chunk_size: DEFAULT_MAX_CHUNK_SIZE,
+            max_body_size: Int::max_value()
+        }
+    }
+}
+
 /// Chunked chunks start with 1*HEXDIGIT, indicating the size of the chunk.
-fn read_chunk_size<R: Reader>(rdr: &mut R) -> IoResult<uint> {
+fn read_chunk_size<R: Reader>(rdr: &mut R, max: uint) -> IoResult<uint> {
     let mut size = 0u;
     let radix = 16;
     let mut in_ext = false;
+    let mut digits = 0u;
     loop {
-        match try!(rdr.read_byte()) {
-            b@b'0'...b'9' if !in_ext => {
-                size *= radix;
-                size += (b - b'0') as uint;
-            },
-            b@b'a'...b'f' if !in_ext => {
-                size *= radix;
-                size += (b + 10 - b'a') as uint;
-            },
-            b@b'A'...b'F' if !in_ext => {
-                size *= radix;
-                size += (b + 10 - b'A') as uint;
+        let digit = match try!(rdr.read_byte()) {
+            b@b'0'...b'9' if !in_ext => (b - b'0') as uint,
+            b@b'a'...b'f' if !in_ext => (b + 10 - b'a') as uint,
+            b@b'A'...b'F' if !in_ext => (b + 10 - b'A') as uint,
+            // a size line without a size is not the last chunk
+            CR | b';' | b' ' | b'\t' if digits == 0 => {
+                return Err(io::standard_error(io::InvalidInput))
             },
             CR => {
                 match try!(rdr.read_byte()) {
@@ -155,16 +242,47 @@ fn read_chunk_size<R: Reader>(rdr: &mut R) -> IoResult<uint> {
                     _ => return Err(io::standard_error(io::InvalidInput))
                 }
             },
-            ext => {
+            // chunk extensions have no meaning here, and are skipped
+            b';' | b' ' | b'\t' => {
                 in_ext = true;
-                todo!("chunk extension byte={}", ext);
-            }
-        }
+                continue;
+            },
+            // but they end with the line, which must not end in a bare LF
+            LF => return Err(io::standard_error(io::InvalidInput)),
+            _ if in_ext => continue,
+            _ => return Err(io::standard_error(io::InvalidInput))
+        };
+        digits += 1;
+        size = match size.checked_mul(radix).and_then(|size| size.checked_add(digit)) {
+            Some(size) if size <= max => size,
+            _ => return Err(IoError {
+                kind: InvalidInput,
+                desc: "Chunk is larger than allowed",
+                detail: Some(format!("the largest allowed is {} bytes", max))
+            })
+        };
     }
     debug!("chunk size={}", size);
     Ok(size)
 }
 
+/// Parse the line starting a chunk, such as `1f\r\n`, from the start of
+/// `buf`, returning the size of the chunk.
+///
+/// Like for `ChunkedReader`, chunk extensions are skipped, and a size larger
+/// than `max` is an error. After a chunk of the size, the chunk data ends
+/// with CRLF. A size of `0` is the last chunk, which is followed by the
+/// trailer section.
+pub fn parse_chunk_size(buf: &[u8], max: uint) -> HttpResult<Parsed<uint>> {
+    match buf.iter().position(|&b| b == LF) {
+        Some(end) => {
+            let size = try!(read_chunk_size(&mut BufReader::new(buf[..end + 1]), max));
+            Ok(Parsed::Complete(size, end + 1))
+        },
+        None => Ok(Parsed::Incomplete)
+    }
+}
+
 /// Writers to handle different Transfer-Encodings.
 pub enum HttpWriter<W: Writer> {
     /// A no-op Writer, used initially before Transfer-Encoding is determined.
@@ -284,6 +402,23 @@ pub const LF: u8 = b'\n';
 pub const STAR: u8 = b'*';
 pub const LINE_ENDING: &'static [u8] = &[CR, LF];
 
+/// How strictly the head of a message is parsed.
+#[deriving(Copy, Clone, PartialEq, Show)]
+pub enum ParseMode {
+    /// Only accept messages following the grammar. This is the default.
+    Strict,
+    /// Also accept what peers commonly get wrong: lines ending with a bare
+    /// `LF`, header values continued on lines starting with whitespace
+    /// (`obs-fold`), and status lines without a reason phrase.
+    Lenient
+}
+
+impl Default for ParseMode {
+    fn default() -> ParseMode {
+        ParseMode::Strict
+    }
+}
+
 /// A `Show`able struct to easily write line endings to a formatter.
 pub struct LineEnding;
 
@@ -301,6 +436,19 @@ impl AsSlice<u8> for LineEnding {
     }
 }
 
+/// Determines if a header name is valid, which it is if it is a `token`.
+pub fn is_valid_header_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(is_token)
+}
+
+/// Determines if a header value can be written as it is.
+///
+/// Control characters other than horizontal tab are rejected, including the
+/// CR and LF that would end the header line early.
+pub fn is_valid_header_value(value: &[u8]) -> bool {
+    value.iter().all(|&b| b == b'\t' || (b >= b' ' && b != 0x7f))
+}
+
 /// Determines if byte is a token char.
 ///
 /// > ```notrust
@@ -378,16 +526,17 @@ pub fn read_method<R: Reader>(stream: &mut R) -> HttpResult<method::Method> {
 
     debug!("method buf = {}", buf[].to_ascii());
 
-    let maybe_method = match buf[0..7] {
-        b"GET    " => Some(method::Method::Get),
-        b"PUT    " => Some(method::Method::Put),
-        b"POST   " => Some(method::Method::Post),
-        b"HEAD   " => Some(method::Method::Head),
-        b"PATCH  " => Some(method::Method::Patch),
-        b"TRACE  " => Some(method::Method::Trace),
-        b"DELETE " => Some(method::Method::Delete),
-        b"CONNECT" => Some(method::Method::Connect),
-        b"OPTIONS" => Some(method::Method::Options),
+    // the space after the name keeps extensions like `GETX` from matching
+    let maybe_method = match buf[0..8] {
+        b"GET     " => Some(method::Method::Get),
+        b"PUT     " => Some(method::Method::Put),
+        b"POST    " => Some(method::Method::Post),
+        b"HEAD    " => Some(method::Method::Head),
+        b"PATCH   " => Some(method::Method::Patch),
+        b"TRACE   " => Some(method::Method::Trace),
+        b"DELETE  " => Some(method::Method::Delete),
+        b"CONNECT " => Some(method::Method::Connect),
+        b"OPTIONS " => Some(method::Method::Options),
         _ => None,
     };
 
@@ -432,17 +581,19 @@ pub fn read_uri<R: Reader>(stream: &mut R) -> HttpResult<uri::RequestUri> {
 
     if s.as_slice().starts_with("/") {
         Ok(AbsolutePath(s))
-    } else if s.as_slice().contains("/") {
+    } else if s.as_slice().contains("://") {
         Ok(AbsoluteUri(try!(Url::parse(s.as_slice()))))
     } else {
+        // the authority form is only a host and port, as for CONNECT
+        if s.as_slice().contains_char('/') || s.as_slice().contains_char('?') ||
+                s.as_slice().contains_char('#') || s.as_slice().contains_char('@') {
+            return Err(HttpUriError(UrlError::InvalidCharacter));
+        }
         let mut temp = "http://".to_string();
         temp.push_str(s.as_slice());
         try!(Url::parse(temp.as_slice()));
-        todo!("compare vs u.authority()");
         Ok(Authority(s))
     }
-
-
 }
 
 
@@ -482,9 +633,9 @@ const MAX_HEADER_FIELD_LENGTH: uint = 1000;
 
 /// The raw bytes when parsing a header line.
 ///
-/// A String and Vec<u8>, divided by COLON (`:`). The String is guaranteed
+/// A SendStr and Vec<u8>, divided by COLON (`:`). The SendStr is guaranteed
 /// to be all `token`s. See `is_token` source for all valid characters.
-pub type RawHeaderLine = (String, Vec<u8>);
+pub type RawHeaderLine = (SendStr, Vec<u8>);
 
 /// Read a RawHeaderLine from a Reader.
 ///
@@ -507,36 +658,80 @@ pub type RawHeaderLine = (String, Vec<u8>);
 /// >                ; see Section 3.2.4
 /// > ```
 pub fn read_header<R: Reader>(stream: &mut R) -> HttpResult<Option<RawHeaderLine>> {
-    let mut name = String::new();
-    let mut value = vec![];
+    match try!(read_header_line(stream, ParseMode::Strict)) {
+        HeaderLine::Field(name, value) => Ok(Some((name, value))),
+        HeaderLine::End => Ok(None),
+        // only lenient parsing reads continuation lines
+        HeaderLine::Folded(_) => unreachable!()
+    }
+}
+
+/// A line of the header section of a message.
+#[deriving(PartialEq, Show)]
+pub enum HeaderLine {
+    /// A header field.
+    Field(SendStr, Vec<u8>),
+    /// A line starting with whitespace, continuing the value of the previous
+    /// field (`obs-fold`). Only read when parsing leniently.
+    Folded(Vec<u8>),
+    /// The empty line ending the header section.
+    End
+}
+
+/// Read a line of the header section, as `read_header` does, parsing it as
+/// strictly as `mode` says.
+pub fn read_header_line<R: Reader>(stream: &mut R, mode: ParseMode) -> HttpResult<HeaderLine> {
+    let lenient = mode == ParseMode::Lenient;
+    let mut name = [0u8, ..MAX_HEADER_NAME_LENGTH];
+    let mut len = 0u;
+    let mut folded = false;
 
     loop {
         match try!(stream.read_byte()) {
-            CR if name.len() == 0 => {
+            CR if len == 0 => {
                 match try!(stream.read_byte()) {
-                    LF => return Ok(None),
+                    LF => return Ok(HeaderLine::End),
                     _ => return Err(HttpHeaderError)
                 }
             },
+            LF if len == 0 && lenient => return Ok(HeaderLine::End),
+            b' ' | b'\t' if len == 0 && lenient => {
+                folded = true;
+                break;
+            },
             b':' => break,
             b if is_token(b) => {
-                if name.len() > MAX_HEADER_NAME_LENGTH { return Err(HttpHeaderError); }
-                name.push(b as char)
+                if len == MAX_HEADER_NAME_LENGTH { return Err(HttpHeaderError); }
+                name[len] = b;
+                len += 1;
             },
             _nontoken => return Err(HttpHeaderError)
         };
     }
 
+    let value = try!(read_header_value(stream, lenient));
+    if folded {
+        debug!("folded header value = {}", value[].to_ascii());
+        return Ok(HeaderLine::Folded(value));
+    }
+
+    let name = intern_header_name(name[..len]);
     debug!("header name = {}", name);
+    debug!("header value = {}", value[].to_ascii());
+    Ok(HeaderLine::Field(name, value))
+}
 
+/// Read a header value, without leading whitespace, and its line ending.
+fn read_header_value<R: Reader>(stream: &mut R, lenient: bool) -> HttpResult<Vec<u8>> {
+    let mut value = vec![];
     let mut ows = true; //optional whitespace
 
-    todo!("handle obs-folding (gross!)");
     loop {
         match try!(stream.read_byte()) {
             CR => break,
+            LF if lenient => return Ok(value),
             LF => return Err(HttpHeaderError),
-            b' ' if ows => {},
+            b' ' | b'\t' if ows => {},
             b => {
                 ows = false;
                 if value.len() > MAX_HEADER_FIELD_LENGTH { return Err(HttpHeaderError); }
@@ -545,13 +740,10 @@ pub fn read_header<R: Reader>(stream: &mut R) -> HttpResult<Option<RawHeaderLine
         };
     }
 
-    debug!("header value = {}", value[].to_ascii());
-
     match try!(stream.read_byte()) {
-        LF => Ok(Some((name, value))),
+        LF => Ok(value),
         _ => Err(HttpHeaderError)
     }
-
 }
 
 /// `request-line   = method SP request-target SP HTTP-version CRLF`
@@ -559,19 +751,29 @@ pub type RequestLine = (method::Method, uri::RequestUri, HttpVersion);
 
 /// Read the `RequestLine`, such as `GET / HTTP/1.1`.
 pub fn read_request_line<R: Reader>(stream: &mut R) -> HttpResult<RequestLine> {
+    read_request_line_with(stream, ParseMode::Strict)
+}
+
+/// Read the `RequestLine`, parsing it as strictly as `mode` says.
+pub fn read_request_line_with<R: Reader>(stream: &mut R, mode: ParseMode) -> HttpResult<RequestLine> {
     debug!("read request line");
     let method = try!(read_method(stream));
     debug!("method = {}", method);
     let uri = try!(read_uri(stream));
     debug!("uri = {}", uri);
+    // the asterisk form is only for server-wide OPTIONS requests
+    if uri == Star && method != method::Method::Options {
+        return Err(HttpUriError(UrlError::InvalidCharacter));
+    }
     let version = try!(read_http_version(stream));
     debug!("version = {}", version);
 
-    if try!(stream.read_byte()) != CR {
-        return Err(HttpVersionError);
-    }
-    if try!(stream.read_byte()) != LF {
-        return Err(HttpVersionError);
+    match try!(stream.read_byte()) {
+        CR => if try!(stream.read_byte()) != LF {
+            return Err(HttpVersionError);
+        },
+        LF if mode == ParseMode::Lenient => (),
+        _ => return Err(HttpVersionError)
     }
 
     Ok((method, uri, version))
@@ -605,17 +807,28 @@ impl Clone for RawStatus {
 /// > reason-phrase  = *( HTAB / SP / VCHAR / obs-text )
 /// >```
 pub fn read_status_line<R: Reader>(stream: &mut R) -> HttpResult<StatusLine> {
+    read_status_line_with(stream, ParseMode::Strict)
+}
+
+/// Read the StatusLine, parsing it as strictly as `mode` says.
+pub fn read_status_line_with<R: Reader>(stream: &mut R, mode: ParseMode) -> HttpResult<StatusLine> {
     let version = try!(read_http_version(stream));
     if try!(stream.read_byte()) != SP {
         return Err(HttpVersionError);
     }
-    let code = try!(read_status(stream));
+    let code = try!(read_status_with(stream, mode));
 
     Ok((version, code))
 }
 
 /// Read the StatusCode from a stream.
 pub fn read_status<R: Reader>(stream: &mut R) -> HttpResult<RawStatus> {
+    read_status_with(stream, ParseMode::Strict)
+}
+
+/// Read the StatusCode from a stream, parsing it as strictly as `mode` says.
+pub fn read_status_with<R: Reader>(stream: &mut R, mode: ParseMode) -> HttpResult<RawStatus> {
+    let lenient = mode == ParseMode::Lenient;
     let code = [
         try!(stream.read_byte()),
         try!(stream.read_byte()),
@@ -627,14 +840,20 @@ pub fn read_status<R: Reader>(stream: &mut R) -> HttpResult<RawStatus> {
         None => return Err(HttpStatusError)
     };
 
-    match try!(stream.read_byte()) {
-        b' ' => (),
-        _ => return Err(HttpStatusError)
-    }
-
     let mut buf = [b' ', ..32];
 
-    {
+    // a missing reason phrase leaves the buffer blank
+    let has_reason = match try!(stream.read_byte()) {
+        b' ' => true,
+        CR if lenient => match try!(stream.read_byte()) {
+            LF => false,
+            _ => return Err(HttpStatusError)
+        },
+        LF if lenient => false,
+        _ => return Err(HttpStatusError)
+    };
+
+    if has_reason {
         let mut bufwrt = BufWriter::new(&mut buf);
         'read: loop {
             match try!(stream.read_byte()) {
@@ -642,6 +861,7 @@ pub fn read_status<R: Reader>(stream: &mut R) -> HttpResult<RawStatus> {
                     LF => break,
                     _ => return Err(HttpStatusError)
                 },
+                LF if lenient => break,
                 b => match bufwrt.write_u8(b) {
                     Ok(_) => (),
                     Err(_) => {
@@ -651,6 +871,7 @@ pub fn read_status<R: Reader>(stream: &mut R) -> HttpResult<RawStatus> {
                                     LF => break 'read,
                                     _ => return Err(HttpStatusError)
                                 },
+                                LF if lenient => break 'read,
                                 _ => { /* ignore */ }
                             }
                         }
@@ -683,6 +904,247 @@ pub fn read_status<R: Reader>(stream: &mut R) -> HttpResult<RawStatus> {
     Ok(RawStatus(code, reason))
 }
 
+/// Whether the connection persists after a message, following the rules of
+/// RFC 7230, section 6.3.
+///
+/// `version` and `headers` are those of the message. `delimited` is whether
+/// the end of its body is known without the connection closing, which isn't
+/// the case for a response body with neither a `Content-Length` nor chunks,
+/// or a tunnel.
+///
+/// Both the server and the client use this, for requests and responses, so
+/// a proxy can too. An HTTP/1.1 connection persists unless a message has
+/// `Connection: close`. An HTTP/1.0 connection only persists if the message
+/// has `Connection: keep-alive`.
+pub fn should_keep_alive(version: HttpVersion, headers: &Headers, delimited: bool) -> bool {
+    if !delimited {
+        return false;
+    }
+    match (version, headers.get::<Connection>()) {
+        (Http09, _) => false,
+        (Http10, conn) => conn.map_or(false, |conn| conn.contains(&KeepAlive)),
+        (_, conn) => !conn.map_or(false, |conn| conn.contains(&Close))
+    }
+}
+
+/// Check that the headers of a message frame its body in only one way.
+///
+/// A message with both `Transfer-Encoding` and `Content-Length`, or with
+/// differing `Content-Length`s, is framed differently by different
+/// implementations, which lets a request be smuggled past a proxy inside
+/// another. Such messages are rejected with `HttpHeaderError`, as are invalid
+/// `Content-Length`s. Repeats of the same `Content-Length` are combined.
+pub fn check_framing(headers: &mut Headers) -> HttpResult<()> {
+    let len = match headers.get_raw("Content-Length") {
+        None => return Ok(()),
+        Some(_) if headers.get_raw("Transfer-Encoding").is_some() => return Err(HttpHeaderError),
+        Some(raw) => {
+            let mut len = None;
+            for line in raw.iter() {
+                for value in line[].split(|&b| b == b',') {
+                    let value: Option<uint> = match str::from_utf8(value) {
+                        Ok(value) => FromStr::from_str(value.trim()),
+                        Err(_) => None
+                    };
+                    match (len, value) {
+                        (_, None) => return Err(HttpHeaderError),
+                        (Some(len), Some(value)) if len != value => return Err(HttpHeaderError),
+                        (_, value) => len = value
+                    }
+                }
+            }
+            len
+        }
+    };
+    if let Some(len) = len {
+        headers.set(ContentLength(len));
+    }
+    Ok(())
+}
+
+/// The result of parsing a head from a buffer that may not hold all of it.
+#[deriving(PartialEq, Show)]
+pub enum Parsed<T> {
+    /// The parsed head, and how many bytes of the buffer it took. The rest
+    /// of the buffer is the start of the body, or of the next message.
+    Complete(T, uint),
+    /// The buffer ends before the head does. Parse again once more bytes
+    /// have been read into it.
+    Incomplete
+}
+
+impl<T> Parsed<T> {
+    /// Whether the whole head was parsed.
+    pub fn is_complete(&self) -> bool {
+        match *self {
+            Parsed::Complete(..) => true,
+            Parsed::Incomplete => false
+        }
+    }
+}
+
+/// The longest line the header section is read with, which holds the
+/// longest name and value with some whitespace around them.
+const MAX_HEADER_LINE_LENGTH: uint = MAX_HEADER_NAME_LENGTH + MAX_HEADER_FIELD_LENGTH + 64;
+
+/// Read the header section of a message into a buffer, up to and including
+/// the empty line ending it, for `parse_headers`.
+///
+/// Nothing is checked but the length of the lines; `parse_headers` does
+/// the rest.
+pub fn read_header_section<R: Reader>(stream: &mut R) -> HttpResult<Vec<u8>> {
+    let mut buf = Vec::new();
+    let mut start = 0;
+    loop {
+        let b = try!(stream.read_byte());
+        buf.push(b);
+        if b == LF {
+            let len = buf.len() - 1 - start;
+            if len == 0 || (len == 1 && buf[start] == CR) {
+                return Ok(buf);
+            }
+            start = buf.len();
+        } else if buf.len() - start > MAX_HEADER_LINE_LENGTH {
+            return Err(HttpHeaderError);
+        }
+    }
+}
+
+/// A header field borrowed from the buffer it was parsed from.
+#[deriving(PartialEq, Show)]
+pub struct RawHeader<'a> {
+    /// The name, as it was received.
+    pub name: &'a str,
+    /// The value, without leading whitespace. This is only copied from the
+    /// buffer if the field was folded, which only lenient parsing allows.
+    pub value: Cow<'a, Vec<u8>, [u8]>
+}
+
+/// Parse the header section at the start of `buf`, up to and including the
+/// empty line ending it, borrowing the names and values from `buf`.
+///
+/// Nothing is allocated for each field, so a proxy can look at the fields it
+/// cares about and forward the rest as they are. `Headers::from_raw_headers`
+/// copies them into `Headers` to keep them, which is how the client and
+/// server keep the fields of the section `read_header_section` read. Like
+/// `parse_request_head`, this returns `Incomplete` until `buf` holds the
+/// whole section.
+pub fn parse_headers<'a>(buf: &'a [u8], mode: ParseMode) -> HttpResult<Parsed<Vec<RawHeader<'a>>>> {
+    let lenient = mode == ParseMode::Lenient;
+    let mut headers: Vec<RawHeader<'a>> = Vec::new();
+    let mut pos = 0;
+    loop {
+        let end = match buf[pos..].iter().position(|&b| b == LF) {
+            Some(i) => pos + i,
+            None => return Ok(Parsed::Incomplete)
+        };
+        let line = if end > pos && buf[end - 1] == CR {
+            buf[pos..end - 1]
+        } else if lenient {
+            buf[pos..end]
+        } else {
+            return Err(HttpHeaderError);
+        };
+        pos = end + 1;
+
+        if line.is_empty() {
+            return Ok(Parsed::Complete(headers, pos));
+        }
+        if line.contains(&CR) {
+            return Err(HttpHeaderError);
+        }
+
+        if line[0] == SP || line[0] == b'\t' {
+            // obs-fold continues the value of the previous field, with a space
+            let last = match headers.last_mut() {
+                Some(last) => last,
+                None => return Err(HttpHeaderError)
+            };
+            if !lenient || !may_fold(last.name) {
+                return Err(HttpHeaderError);
+            }
+            let mut value = last.value.to_vec();
+            value.push(SP);
+            value.push_all(trim_ows(line));
+            last.value = Owned(value);
+            continue;
+        }
+
+        let colon = match line.iter().position(|&b| b == b':') {
+            Some(colon) if colon > 0 && colon <= MAX_HEADER_NAME_LENGTH => colon,
+            _ => return Err(HttpHeaderError)
+        };
+        if !line[..colon].iter().all(|&b| is_token(b)) {
+            return Err(HttpHeaderError);
+        }
+        let value = trim_ows(line[colon + 1..]);
+        if value.len() > MAX_HEADER_FIELD_LENGTH {
+            return Err(HttpHeaderError);
+        }
+        headers.push(RawHeader {
+            // tokens are ASCII, so this can't fail
+            name: str::from_utf8(line[..colon]).unwrap(),
+            value: Borrowed(value)
+        });
+    }
+}
+
+/// Leave out the optional whitespace before a header value.
+fn trim_ows(value: &[u8]) -> &[u8] {
+    match value.iter().position(|&b| b != SP && b != b'\t') {
+        Some(start) => value[start..],
+        None => value[value.len()..]
+    }
+}
+
+/// Whether a header can be continued on another line with obs-fold, when
+/// parsing leniently.
+///
+/// Implementations that don't unfold would frame a message differently if
+/// `Content-Length` or `Transfer-Encoding` were folded, or pick a different
+/// `Host`, so those can't be.
+pub fn may_fold(name: &str) -> bool {
+    !["Content-Length", "Transfer-Encoding", "Host"].iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+/// Parse a request head from the start of `buf`, without reading anything.
+///
+/// This never blocks, so bytes can come from any source: a non-blocking
+/// socket, or a file of captured requests. If `buf` doesn't hold the whole
+/// head, `Incomplete` is returned, and parsing can be resumed by calling this
+/// again with the same buffer once more bytes were appended to it.
+pub fn parse_request_head(buf: &[u8], mode: ParseMode) -> HttpResult<Parsed<(RequestLine, Headers)>> {
+    let start = match buf.iter().position(|&b| b == LF) {
+        Some(end) => end + 1,
+        None => return Ok(Parsed::Incomplete)
+    };
+    let line = try!(read_request_line_with(&mut BufReader::new(buf[..start]), mode));
+    match try!(parse_headers(buf[start..], mode)) {
+        Parsed::Complete(raw, len) => {
+            Ok(Parsed::Complete((line, Headers::from_raw_headers(raw[])), start + len))
+        },
+        Parsed::Incomplete => Ok(Parsed::Incomplete)
+    }
+}
+
+/// Parse a response head from the start of `buf`, without reading anything.
+///
+/// Like `parse_request_head`, this returns `Incomplete` until `buf` holds the
+/// whole head.
+pub fn parse_response_head(buf: &[u8], mode: ParseMode) -> HttpResult<Parsed<(StatusLine, Headers)>> {
+    let start = match buf.iter().position(|&b| b == LF) {
+        Some(end) => end + 1,
+        None => return Ok(Parsed::Incomplete)
+    };
+    let line = try!(read_status_line_with(&mut BufReader::new(buf[..start]), mode));
+    match try!(parse_headers(buf[start..], mode)) {
+        Parsed::Complete(raw, len) => {
+            Ok(Parsed::Complete((line, Headers::from_raw_headers(raw[])), start + len))
+        },
+        Parsed::Incomplete => Ok(Parsed::Incomplete)
+    }
+}
+
 #[inline]
 fn expect(r: IoResult<u8>, expected: u8) -> HttpResult<()> {
     match r {
@@ -695,6 +1157,7 @@ fn expect(r: IoResult<u8>, expected: u8) -> HttpResult<()> {
 #[cfg(test)]
 mod tests {
     use std::io::{mod, MemReader, MemWriter};
+    use std::num::Int;
     use std::borrow::Cow::{Borrowed, Owned};
     use test::Bencher;
     use uri::RequestUri;
@@ -702,12 +1165,23 @@ mod tests {
     use method;
     use version::HttpVersion;
     use version::HttpVersion::{Http10, Http11, Http20};
-    use HttpError::{HttpVersionError, HttpMethodError};
+    use HttpError::{HttpHeaderError, HttpStatusError, HttpVersionError, HttpMethodError,
+                    HttpUriError};
     use HttpResult;
     use url::Url;
+    use url::ParseError as UrlError;
 
     use super::{read_method, read_uri, read_http_version, read_header,
                 RawHeaderLine, read_status, RawStatus};
+    use super::{read_header_line, read_request_line, read_request_line_with, read_status_with,
+                HeaderLine};
+    use super::{check_framing, parse_request_head, parse_response_head, should_keep_alive, Parsed};
+    use super::{parse_chunk_size, parse_headers, read_header_section, RawHeader, ChunkLimits,
+                DEFAULT_MAX_CHUNK_SIZE};
+    use super::HttpReader::ChunkedReader;
+    use header::Headers;
+    use header::common::ContentLength;
+    use super::ParseMode::{Strict, Lenient};
 
     fn mem(s: &str) -> MemReader {
         MemReader::new(s.as_bytes().to_vec())
@@ -728,6 +1202,8 @@ mod tests {
         read("TRACE /", Ok(method::Method::Trace));
         read("PATCH /", Ok(method::Method::Patch));
         read("FOO /", Ok(method::Method::Extension("FOO".to_string())));
+        read("OPTIONSX /", Ok(method::Method::Extension("OPTIONSX".to_string())));
+        read("PROPFIND /", Ok(method::Method::Extension("PROPFIND".to_string())));
         read("akemi!~#HOMURA /", Ok(method::Method::Extension("akemi!~#HOMURA".to_string())));
         read(" ", Err(HttpMethodError));
     }
@@ -741,7 +1217,13 @@ mod tests {
         read("* ", Ok(Star));
         read("http://hyper.rs/ ", Ok(AbsoluteUri(Url::parse("http://hyper.rs/").unwrap())));
         read("hyper.rs ", Ok(Authority("hyper.rs".to_string())));
+        read("hyper.rs:443 ", Ok(Authority("hyper.rs:443".to_string())));
+        read("[::1]:443 ", Ok(Authority("[::1]:443".to_string())));
         read("/ ", Ok(AbsolutePath("/".to_string())));
+        read("http://hyper.rs:8080/a?b=c ",
+             Ok(AbsoluteUri(Url::parse("http://hyper.rs:8080/a?b=c").unwrap())));
+        read("hyper.rs/path ", Err(HttpUriError(UrlError::InvalidCharacter)));
+        read("user@hyper.rs:443 ", Err(HttpUriError(UrlError::InvalidCharacter)));
     }
 
     #[test]
@@ -789,8 +1271,205 @@ mod tests {
             assert_eq!(read_header(&mut mem(s)), result);
         }
 
-        read("Host: rust-lang.org\r\n", Ok(Some(("Host".to_string(),
+        read("Host: rust-lang.org\r\n", Ok(Some((Borrowed("Host"),
                                                 "rust-lang.org".as_bytes().to_vec()))));
+        read("X-Custom: a\r\n", Ok(Some((Owned("X-Custom".to_string()), b"a".to_vec()))));
+        read("host: a\r\n", Ok(Some((Owned("host".to_string()), b"a".to_vec()))));
+
+        match read_header(&mut mem("Content-Length: 0\r\n")) {
+            Ok(Some((Borrowed(name), _))) => assert_eq!(name, "Content-Length"),
+            other => panic!("expected a borrowed name, got {}", other)
+        }
+    }
+
+    #[test]
+    fn test_read_header_line_modes() {
+        let mut rdr = mem("Host: a\n  folded\n\n");
+        assert_eq!(read_header_line(&mut rdr, Lenient),
+                   Ok(HeaderLine::Field(Borrowed("Host"), b"a".to_vec())));
+        assert_eq!(read_header_line(&mut rdr, Lenient), Ok(HeaderLine::Folded(b"folded".to_vec())));
+        assert_eq!(read_header_line(&mut rdr, Lenient), Ok(HeaderLine::End));
+
+        assert_eq!(read_header_line(&mut mem("Host: a\n"), Strict), Err(HttpHeaderError));
+        assert_eq!(read_header_line(&mut mem(" folded\r\n"), Strict), Err(HttpHeaderError));
+        assert_eq!(read_header_line(&mut mem("\n"), Strict), Err(HttpHeaderError));
+    }
+
+    #[test]
+    fn test_read_request_line_asterisk() {
+        assert_eq!(read_request_line(&mut mem("OPTIONS * HTTP/1.1\r\n")),
+                   Ok((method::Method::Options, Star, Http11)));
+        assert_eq!(read_request_line(&mut mem("GET * HTTP/1.1\r\n")),
+                   Err(HttpUriError(UrlError::InvalidCharacter)));
+    }
+
+    #[test]
+    fn test_should_keep_alive() {
+        fn keep_alive(version: HttpVersion, connection: Option<&str>, delimited: bool) -> bool {
+            let mut headers = Headers::new();
+            if let Some(connection) = connection {
+                headers.set_raw("Connection", vec![connection.as_bytes().to_vec()]);
+            }
+            should_keep_alive(version, &headers, delimited)
+        }
+
+        assert!(keep_alive(Http11, None, true));
+        assert!(keep_alive(Http11, Some("keep-alive"), true));
+        assert!(!keep_alive(Http11, Some("Close"), true));
+        assert!(!keep_alive(Http11, None, false));
+        assert!(!keep_alive(Http10, None, true));
+        assert!(!keep_alive(Http10, Some("x-foo"), true));
+        assert!(keep_alive(Http10, Some("Keep-Alive"), true));
+        assert!(!keep_alive(Http10, Some("keep-alive"), false));
+    }
+
+    #[test]
+    fn test_check_framing() {
+        fn check(lines: &[(&str, &str)]) -> HttpResult<Option<ContentLength>> {
+            let mut headers = Headers::new();
+            for &(name, value) in lines.iter() {
+                headers.append_raw(name.to_string(), value.as_bytes().to_vec());
+            }
+            try!(check_framing(&mut headers));
+            Ok(headers.get::<ContentLength>().map(|len| *len))
+        }
+
+        assert_eq!(check(&[]), Ok(None));
+        assert_eq!(check(&[("Transfer-Encoding", "chunked")]), Ok(None));
+        assert_eq!(check(&[("Content-Length", "5")]), Ok(Some(ContentLength(5))));
+        assert_eq!(check(&[("Content-Length", "5"), ("content-length", "5")]), Ok(Some(ContentLength(5))));
+        assert_eq!(check(&[("Content-Length", "5, 5")]), Ok(Some(ContentLength(5))));
+        assert_eq!(check(&[("Content-Length", "5"), ("Content-Length", "6")]), Err(HttpHeaderError));
+        assert_eq!(check(&[("Content-Length", "5, ")]), Err(HttpHeaderError));
+        assert_eq!(check(&[("Content-Length", "-1")]), Err(HttpHeaderError));
+        assert_eq!(check(&[("Content-Length", "5"), ("Transfer-Encoding", "chunked")]),
+                   Err(HttpHeaderError));
+    }
+
+    #[test]
+    fn test_parse_chunk_size() {
+        let max = DEFAULT_MAX_CHUNK_SIZE;
+        assert_eq!(parse_chunk_size(b"1f\r\ndata", max).unwrap(), Parsed::Complete(31, 4));
+        assert_eq!(parse_chunk_size(b"A;name=value\r\n", max).unwrap(), Parsed::Complete(10, 14));
+        assert_eq!(parse_chunk_size(b"0\r\n", max).unwrap(), Parsed::Complete(0, 3));
+        assert_eq!(parse_chunk_size(b"1f\r", max).unwrap(), Parsed::Incomplete);
+        assert!(parse_chunk_size(b"1g\r\n", max).is_err());
+        assert!(parse_chunk_size(b"1\n", max).is_err());
+        assert!(parse_chunk_size(b"1;ext\n", max).is_err());
+        assert!(parse_chunk_size(b"\r\n", max).is_err());
+        assert!(parse_chunk_size(b";x\r\n", max).is_err());
+        assert!(parse_chunk_size(b"20\r\n", 31).is_err());
+        // 16EB, and more than fits in a uint
+        assert!(parse_chunk_size(b"10000000000000000\r\n", max).is_err());
+        assert!(parse_chunk_size(b"fffffffffffffffffffff\r\n", Int::max_value()).is_err());
+    }
+
+    #[test]
+    fn test_chunk_limits() {
+        let limits = ChunkLimits { max_chunk_size: 5, max_body_size: 8 };
+
+        let mut body = ChunkedReader(mem("5\r\nhello\r\n0\r\n\r\n"), None, limits);
+        assert_eq!(body.read_to_string().unwrap()[], "hello");
+
+        let mut body = ChunkedReader(mem("6\r\nhello!\r\n0\r\n\r\n"), None, limits);
+        assert_eq!(body.read_to_string().unwrap_err().kind, io::InvalidInput);
+
+        let mut body = ChunkedReader(mem("5\r\nhello\r\n5\r\nworld\r\n0\r\n\r\n"), None, limits);
+        assert_eq!(body.read_to_string().unwrap_err().kind, io::InvalidInput);
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        let buf = b"Host:  example.domain\r\nX-Empty:\r\n\r\nbody";
+        match parse_headers(buf, Strict).unwrap() {
+            Parsed::Complete(headers, len) => {
+                assert_eq!(headers, vec![
+                    RawHeader { name: "Host", value: Borrowed(b"example.domain"[]) },
+                    RawHeader { name: "X-Empty", value: Borrowed(b""[]) }
+                ]);
+                assert_eq!(buf[len..], b"body"[]);
+            },
+            Parsed::Incomplete => panic!("headers should be complete")
+        }
+        assert!(!parse_headers(b"Host: example.domain\r\n", Strict).unwrap().is_complete());
+
+        match parse_headers(b"X-Folded: a\n\tb\n\n", Lenient).unwrap() {
+            Parsed::Complete(headers, _) => {
+                assert_eq!(headers, vec![RawHeader { name: "X-Folded", value: Owned(b"a b".to_vec()) }]);
+            },
+            Parsed::Incomplete => panic!("headers should be complete")
+        }
+        assert!(parse_headers(b"X-Folded: a\r\n b\r\n\r\n", Strict).is_err());
+        assert!(parse_headers(b"Host: a\n b\n\n", Lenient).is_err());
+        assert!(parse_headers(b"Bad Name: a\r\n\r\n", Strict).is_err());
+        assert!(parse_headers(b": a\r\n\r\n", Strict).is_err());
+        assert!(parse_headers(b"X-CR: a\rb\r\n\r\n", Strict).is_err());
+    }
+
+    #[test]
+    fn test_read_header_section() {
+        let mut rdr = mem("Host: a\r\nX-Foo: b\r\n\r\nbody");
+        assert_eq!(read_header_section(&mut rdr).unwrap(), b"Host: a\r\nX-Foo: b\r\n\r\n".to_vec());
+        assert_eq!(rdr.read_to_end().unwrap(), b"body".to_vec());
+        assert_eq!(read_header_section(&mut mem("Host: a\n\n")).unwrap(), b"Host: a\n\n".to_vec());
+
+        let long = format!("X-Long: {}\r\n\r\n", String::from_char(2000, 'a'));
+        assert_eq!(read_header_section(&mut mem(long[])), Err(HttpHeaderError));
+        assert!(read_header_section(&mut mem("Host: a\r\n")).is_err());
+    }
+
+    #[test]
+    fn test_parse_request_head() {
+        let buf = b"GET /path HTTP/1.1\r\nHost: example.domain\r\n\r\nbody";
+        for end in range(0, buf.len() - 4) {
+            assert!(!parse_request_head(buf[..end], Strict).unwrap().is_complete());
+        }
+        match parse_request_head(buf, Strict).unwrap() {
+            Parsed::Complete(((method, uri, version), headers), len) => {
+                assert_eq!(method, method::Method::Get);
+                assert_eq!(uri, AbsolutePath("/path".to_string()));
+                assert_eq!(version, Http11);
+                assert_eq!(headers.get_raw("Host"), Some([b"example.domain".to_vec()][]));
+                assert_eq!(buf[len..], b"body"[]);
+            },
+            Parsed::Incomplete => panic!("head should be complete")
+        }
+
+        assert!(parse_request_head(b"GET /path HTTP/1.1\n\n", Strict).is_err());
+        match parse_request_head(b"GET /path HTTP/1.1\n\n", Lenient).unwrap() {
+            Parsed::Complete(_, len) => assert_eq!(len, 20),
+            Parsed::Incomplete => panic!("head should be complete")
+        }
+        assert!(parse_request_head(b"GET\r\n\r\n", Strict).is_err());
+    }
+
+    #[test]
+    fn test_parse_response_head() {
+        let buf = b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+        assert!(!parse_response_head(buf[..30], Strict).unwrap().is_complete());
+        match parse_response_head(buf, Strict).unwrap() {
+            Parsed::Complete(((version, status), headers), len) => {
+                assert_eq!(version, Http11);
+                assert_eq!(status, RawStatus(404, Borrowed("Not Found")));
+                assert_eq!(headers.get_raw("Content-Length"), Some([b"0".to_vec()][]));
+                assert_eq!(len, buf.len());
+            },
+            Parsed::Incomplete => panic!("head should be complete")
+        }
+    }
+
+    #[test]
+    fn test_read_lines_lenient() {
+        assert_eq!(read_request_line_with(&mut mem("GET / HTTP/1.1\n"), Lenient),
+                   Ok((method::Method::Get, AbsolutePath("/".to_string()), Http11)));
+        assert_eq!(read_request_line_with(&mut mem("GET / HTTP/1.1\n"), Strict),
+                   Err(HttpVersionError));
+
+        assert_eq!(read_status_with(&mut mem("200\r\n"), Lenient),
+                   Ok(RawStatus(200, Owned("".to_string()))));
+        assert_eq!(read_status_with(&mut mem("404 Not Found\n"), Lenient),
+                   Ok(RawStatus(404, Borrowed("Not Found"))));
+        assert_eq!(read_status_with(&mut mem("200\r\n"), Strict), Err(HttpStatusError));
     }
 
     #[test]
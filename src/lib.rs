@@ -186,13 +186,20 @@ macro_rules! inspect(
 #[macro_escape]
 mod mock;
 
+mod names;
+
 pub mod client;
+pub mod codec;
+pub mod form;
 pub mod method;
+pub mod mime_guess;
 pub mod header;
 pub mod http;
 pub mod net;
+pub mod percent_encoding;
 pub mod server;
 pub mod status;
+pub mod testing;
 pub mod uri;
 pub mod version;
 
@@ -1,7 +1,12 @@
 //! The HTTP request method
 use std::fmt;
+use std::hash;
 use std::str::FromStr;
 
+use serialize::{Encodable, Encoder, Decodable, Decoder};
+
+use http::is_token;
+
 use self::Method::{Options, Get, Post, Put, Delete, Head, Trace, Connect, Patch,
                    Extension};
 
@@ -13,7 +18,11 @@ use self::Method::{Options, Get, Post, Put, Delete, Head, Trace, Connect, Patch,
 ///
 /// It may make sense to grow this to include all variants currently
 /// registered with IANA, if they are at all common to use.
-#[deriving(Clone, PartialEq, Eq, Hash)]
+///
+/// Methods are case-sensitive, so `get` is an extension method, not `GET`.
+/// An `Extension` with the name of a standard method is equal to it, and
+/// hashes the same, so `Extension("GET".to_string()) == Get`.
+#[deriving(Clone)]
 pub enum Method {
     /// OPTIONS
     Options,
@@ -37,15 +46,46 @@ pub enum Method {
     Extension(String)
 }
 
+/// Registered extension methods that are safe, from WebDAV and its extensions.
+static SAFE_EXTENSIONS: [&'static str, ..3] = ["PROPFIND", "REPORT", "SEARCH"];
+
+/// Registered extension methods that are idempotent, but not safe.
+static IDEMPOTENT_EXTENSIONS: [&'static str, ..9] = ["ACL", "BIND", "COPY", "MKCALENDAR", "MKCOL",
+                                                     "MOVE", "PROPPATCH", "REBIND", "UNBIND"];
+
 impl Method {
     /// Whether a method is considered "safe", meaning the request is
     /// essentially read-only.
     ///
     /// See [the spec](https://tools.ietf.org/html/rfc7231#section-4.2.1)
     /// for more words.
+    #[deprecated = "use is_safe"]
     pub fn safe(&self) -> bool {
+        self.is_safe()
+    }
+
+    /// Whether a method is considered "idempotent", meaning the request has
+    /// the same result is executed multiple times.
+    ///
+    /// See [the spec](https://tools.ietf.org/html/rfc7231#section-4.2.2) for
+    /// more words.
+    #[deprecated = "use is_idempotent"]
+    pub fn idempotent(&self) -> bool {
+        self.is_idempotent()
+    }
+
+    /// Whether a method is considered "safe", meaning the request is
+    /// essentially read-only.
+    ///
+    /// This includes the safe registered extension methods, such as WebDAV's
+    /// `PROPFIND`. Other extension methods are not safe.
+    ///
+    /// See [the spec](https://tools.ietf.org/html/rfc7231#section-4.2.1)
+    /// for more words.
+    pub fn is_safe(&self) -> bool {
         match *self {
             Get | Head | Options | Trace => true,
+            Extension(ref s) => SAFE_EXTENSIONS.iter().any(|m| *m == s[]),
             _ => false
         }
     }
@@ -53,23 +93,59 @@ impl Method {
     /// Whether a method is considered "idempotent", meaning the request has
     /// the same result is executed multiple times.
     ///
+    /// Safe methods are idempotent, and so are registered extension methods
+    /// like WebDAV's `MOVE`. Other extension methods, like `PURGE`, are not.
+    ///
     /// See [the spec](https://tools.ietf.org/html/rfc7231#section-4.2.2) for
     /// more words.
-    pub fn idempotent(&self) -> bool {
-        if self.safe() {
+    pub fn is_idempotent(&self) -> bool {
+        if self.is_safe() {
             true
         } else {
             match *self {
                 Put | Delete => true,
+                Extension(ref s) => IDEMPOTENT_EXTENSIONS.iter().any(|m| *m == s[]),
                 _ => false
             }
         }
     }
+
+    /// The name of the method, as sent in a request.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Options => "OPTIONS",
+            Get => "GET",
+            Post => "POST",
+            Put => "PUT",
+            Delete => "DELETE",
+            Head => "HEAD",
+            Trace => "TRACE",
+            Connect => "CONNECT",
+            Patch => "PATCH",
+            Extension(ref s) => s[]
+        }
+    }
 }
 
+impl PartialEq for Method {
+    fn eq(&self, other: &Method) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Method {}
+
+impl<H: hash::Writer> hash::Hash<H> for Method {
+    fn hash(&self, hasher: &mut H) {
+        self.as_str().hash(hasher)
+    }
+}
+
+/// Parses a method name, which must be a token. Names are case-sensitive, and
+/// the standard methods are parsed as their own variants.
 impl FromStr for Method {
     fn from_str(s: &str) -> Option<Method> {
-        if s == "" {
+        if s == "" || !s.bytes().all(|b| is_token(b)) {
             None
         } else {
             Some(match s {
@@ -88,20 +164,26 @@ impl FromStr for Method {
     }
 }
 
+/// A `Method` is encoded as its name.
+impl<E, S: Encoder<E>> Encodable<S, E> for Method {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
+        s.emit_str(self.as_str())
+    }
+}
+
+impl<E, D: Decoder<E>> Decodable<D, E> for Method {
+    fn decode(d: &mut D) -> Result<Method, E> {
+        let name = try!(d.read_str());
+        match FromStr::from_str(name[]) {
+            Some(method) => Ok(method),
+            None => Err(d.error(format!("invalid method: {}", name)[]))
+        }
+    }
+}
+
 impl fmt::Show for Method {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Options => "OPTIONS",
-            Get => "GET",
-            Post => "POST",
-            Put => "PUT",
-            Delete => "DELETE",
-            Head => "HEAD",
-            Trace => "TRACE",
-            Connect => "CONNECT",
-            Patch => "PATCH",
-            Extension(ref s) => s.as_slice()
-        }.fmt(fmt)
+        self.as_str().fmt(fmt)
     }
 }
 
@@ -109,8 +191,9 @@ impl fmt::Show for Method {
 mod tests {
     use std::collections::HashMap;
     use std::str::FromStr;
+    use serialize::json;
     use super::Method;
-    use super::Method::{Get, Post, Put, Extension};
+    use super::Method::{Get, Post, Put, Delete, Extension};
 
     #[test]
     fn test_safe() {
@@ -125,11 +208,40 @@ mod tests {
         assert_eq!(false, Post.idempotent());
     }
 
+    #[test]
+    fn test_is_safe() {
+        assert!(Get.is_safe());
+        assert!(Extension("PROPFIND".to_string()).is_safe());
+        assert!(!Extension("propfind".to_string()).is_safe());
+        assert!(!Extension("PURGE".to_string()).is_safe());
+    }
+
+    #[test]
+    fn test_is_idempotent() {
+        assert!(Delete.is_idempotent());
+        assert!(Extension("PROPFIND".to_string()).is_idempotent());
+        assert!(Extension("MOVE".to_string()).is_idempotent());
+        assert!(!Extension("LOCK".to_string()).is_idempotent());
+        assert!(!Extension("PURGE".to_string()).is_idempotent());
+    }
+
     #[test]
     fn test_from_str() {
         assert_eq!(Some(Get), FromStr::from_str("GET"));
         assert_eq!(Some(Extension("MOVE".to_string())),
                    FromStr::from_str("MOVE"));
+        assert_eq!(Some(Extension("get".to_string())), "get".parse());
+        assert_eq!(None::<Method>, "".parse());
+        assert_eq!(None::<Method>, "PROP FIND".parse());
+    }
+
+    #[test]
+    fn test_eq_standard() {
+        assert_eq!(Extension("GET".to_string()), Get);
+        assert!(Extension("get".to_string()) != Get);
+        let mut counter: HashMap<Method,uint> = HashMap::new();
+        counter.insert(Extension("POST".to_string()), 1);
+        assert_eq!(Some(&1), counter.get(&Post));
     }
 
     #[test]
@@ -139,6 +251,14 @@ mod tests {
                    format!("{}", Extension("MOVE".to_string())));
     }
 
+    #[test]
+    fn test_serialize() {
+        assert_eq!(json::encode(&Get)[], "\"GET\"");
+        assert_eq!(json::decode::<Method>("\"PROPFIND\"").unwrap(),
+                   Extension("PROPFIND".to_string()));
+        assert!(json::decode::<Method>("\"NOT A METHOD\"").is_err());
+    }
+
     #[test]
     fn test_hashable() {
         let mut counter: HashMap<Method,uint> = HashMap::new();
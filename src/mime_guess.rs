@@ -0,0 +1,128 @@
+//! Guessing media types from file extensions.
+//!
+//! For setting a `Content-Type` from the name of a file, such as one served
+//! from disk, or the `filename` of an uploaded part.
+//!
+//! ```
+//! use hyper::mime_guess::{guess, MimeGuesser};
+//!
+//! assert_eq!(guess(&Path::new("static/style.css")), Some("text/css".parse().unwrap()));
+//!
+//! let mut guesser = MimeGuesser::new();
+//! guesser.register("rs", "text/x-rust".parse().unwrap());
+//! assert_eq!(guesser.guess(&Path::new("lib.rs")), Some("text/x-rust".parse().unwrap()));
+//! assert_eq!(guesser.guess(&Path::new("index.html")), Some("text/html".parse().unwrap()));
+//! ```
+use std::ascii::AsciiExt;
+
+use mime::Mime;
+
+/// The extensions known without registering any, and their types.
+static KNOWN: &'static [(&'static str, &'static str)] = &[
+    ("bin", "application/octet-stream"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/x-icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "application/javascript"),
+    ("json", "application/json"),
+    ("md", "text/markdown"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("ogg", "audio/ogg"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("ttf", "application/font-sfnt"),
+    ("txt", "text/plain"),
+    ("wav", "audio/wav"),
+    ("webm", "video/webm"),
+    ("webp", "image/webp"),
+    ("woff", "application/font-woff"),
+    ("xml", "application/xml"),
+    ("zip", "application/zip"),
+];
+
+/// The media type of a known file extension, ignoring case.
+pub fn from_extension(ext: &str) -> Option<Mime> {
+    KNOWN.iter().find(|&&(known, _)| known.eq_ignore_ascii_case(ext))
+        .and_then(|&(_, mime)| mime.parse())
+}
+
+/// The media type of the file at `path`, from its extension.
+pub fn guess(path: &Path) -> Option<Mime> {
+    path.extension_str().and_then(from_extension)
+}
+
+/// Guesses media types from the known extensions, and from the ones
+/// registered with it.
+#[deriving(Clone, Default)]
+pub struct MimeGuesser {
+    custom: Vec<(String, Mime)>
+}
+
+impl MimeGuesser {
+    /// Create a guesser that only knows the extensions `from_extension` does.
+    pub fn new() -> MimeGuesser {
+        MimeGuesser { custom: Vec::new() }
+    }
+
+    /// Guess `mime` for files with the extension `ext`.
+    ///
+    /// This takes the place of a known type for `ext`, and of one registered
+    /// before.
+    pub fn register(&mut self, ext: &str, mime: Mime) {
+        self.custom.push((ext.to_string(), mime));
+    }
+
+    /// The media type of the extension `ext`, ignoring case.
+    pub fn from_extension(&self, ext: &str) -> Option<Mime> {
+        match self.custom.iter().rev().find(|&&(ref custom, _)| custom[].eq_ignore_ascii_case(ext)) {
+            Some(&(_, ref mime)) => Some(mime.clone()),
+            None => from_extension(ext)
+        }
+    }
+
+    /// The media type of the file at `path`, from its extension.
+    pub fn guess(&self, path: &Path) -> Option<Mime> {
+        path.extension_str().and_then(|ext| self.from_extension(ext))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mime::Mime;
+    use super::{from_extension, guess, MimeGuesser};
+
+    fn mime(s: &str) -> Option<Mime> {
+        s.parse()
+    }
+
+    #[test]
+    fn test_guess() {
+        assert_eq!(guess(&Path::new("index.html")), mime("text/html"));
+        assert_eq!(guess(&Path::new("/srv/photos/CAT.JPG")), mime("image/jpeg"));
+        assert_eq!(guess(&Path::new("archive.tar.gz")), mime("application/gzip"));
+        assert_eq!(guess(&Path::new("README")), None);
+        assert_eq!(from_extension("unknown"), None);
+    }
+
+    #[test]
+    fn test_register() {
+        let mut guesser = MimeGuesser::new();
+        guesser.register("rs", mime("text/x-rust").unwrap());
+        guesser.register("json", mime("application/vnd.api+json").unwrap());
+        assert_eq!(guesser.guess(&Path::new("src/lib.RS")), mime("text/x-rust"));
+        assert_eq!(guesser.from_extension("json"), mime("application/vnd.api+json"));
+        assert_eq!(guesser.from_extension("png"), mime("image/png"));
+
+        guesser.register("rs", mime("text/plain").unwrap());
+        assert_eq!(guesser.from_extension("rs"), mime("text/plain"));
+    }
+}
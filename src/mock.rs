@@ -3,6 +3,22 @@ use std::io::{IoResult, MemReader, MemWriter};
 use std::io::net::ip::SocketAddr;
 
 use net::{NetworkStream, NetworkConnector};
+use server::{Handler, Request, Response};
+
+/// Parse a `SocketAddr`, such as `"127.0.0.1:80"`.
+macro_rules! sock(
+    ($s:expr) => (::std::str::from_str::<::std::io::net::ip::SocketAddr>($s).unwrap())
+);
+
+/// Have `handler` handle a request read from `head`, and return everything
+/// it wrote as the response.
+pub fn handle<H: Handler>(handler: &H, head: &str) -> String {
+    let mut stream = MockStream::with_input(head.as_bytes());
+    let req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+    let mut w = MemWriter::new();
+    handler.handle(req, Response::new(&mut w));
+    String::from_utf8(w.unwrap()).unwrap()
+}
 
 pub struct MockStream {
     pub read: MemReader,
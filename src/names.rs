@@ -0,0 +1,75 @@
+//! Names of standard header fields, as they are usually written.
+//!
+//! Parsing borrows these instead of allocating a name for each field.
+use std::borrow::Cow::{Borrowed, Owned};
+use std::str::SendStr;
+
+/// Get the name for the bytes of a header name, which must be `token`s.
+///
+/// The names of headers seen in most messages are borrowed if they have the
+/// usual case, and any other name is allocated.
+pub fn intern_header_name(name: &[u8]) -> SendStr {
+    match standard_header_name(name) {
+        Some(standard) => Borrowed(standard),
+        // tokens are ASCII, so this can't fail
+        None => Owned(String::from_utf8(name.to_vec()).unwrap())
+    }
+}
+
+/// Find a standard header name by its length, and then its bytes.
+fn standard_header_name(name: &[u8]) -> Option<&'static str> {
+    macro_rules! standard(
+        ($($standard:expr),+) => ({
+            $(if name == $standard.as_bytes() { return Some($standard); })+
+            None
+        })
+    );
+
+    match name.len() {
+        2 => standard!("TE"),
+        3 => standard!("Age", "Via"),
+        4 => standard!("Date", "ETag", "Host", "Link", "Vary"),
+        5 => standard!("Allow", "Range"),
+        6 => standard!("Accept", "Cookie", "Expect", "Origin", "Pragma", "Server"),
+        7 => standard!("Expires", "Referer", "Trailer", "Upgrade"),
+        8 => standard!("If-Match", "If-Range", "Location"),
+        9 => standard!("Forwarded"),
+        10 => standard!("Connection", "Keep-Alive", "Set-Cookie", "User-Agent"),
+        11 => standard!("Retry-After"),
+        12 => standard!("Content-Type"),
+        13 => standard!("Accept-Ranges", "Authorization", "Cache-Control", "Content-Range",
+                        "If-None-Match", "Last-Modified"),
+        14 => standard!("Accept-Charset", "Content-Length"),
+        15 => standard!("Accept-Encoding", "Accept-Language", "X-Forwarded-For"),
+        16 => standard!("Content-Encoding", "Content-Language", "Content-Location",
+                        "WWW-Authenticate", "X-Forwarded-Host", "X-Requested-With"),
+        17 => standard!("If-Modified-Since", "Transfer-Encoding", "X-Forwarded-Proto"),
+        18 => standard!("Proxy-Authenticate"),
+        19 => standard!("Content-Disposition", "If-Unmodified-Since", "Proxy-Authorization"),
+        25 => standard!("Strict-Transport-Security"),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow::{Borrowed, Owned};
+    use super::intern_header_name;
+
+    #[test]
+    fn test_intern_header_name() {
+        for name in ["TE", "ETag", "X-Forwarded-Proto", "Strict-Transport-Security"].iter() {
+            match intern_header_name(name.as_bytes()) {
+                Borrowed(interned) => assert_eq!(interned, *name),
+                Owned(_) => panic!("expected {} to be borrowed", name)
+            }
+        }
+        // only the usual case is borrowed
+        for name in ["Etag", "host", "X-Foo"].iter() {
+            match intern_header_name(name.as_bytes()) {
+                Owned(interned) => assert_eq!(interned[], *name),
+                Borrowed(_) => panic!("expected {} to be allocated", name)
+            }
+        }
+    }
+}
@@ -4,7 +4,7 @@ use std::boxed::BoxAny;
 use std::fmt;
 use std::intrinsics::TypeId;
 use std::io::{IoResult, IoError, ConnectionAborted, InvalidInput, OtherIoError,
-              Stream, Listener, Acceptor};
+              Stream, Listener, Acceptor, MemWriter};
 use std::io::net::ip::{SocketAddr, ToSocketAddr, Port};
 use std::io::net::tcp::{TcpStream, TcpListener, TcpAcceptor};
 use std::mem::{mod, transmute, transmute_copy};
@@ -41,6 +41,20 @@ pub trait NetworkListener<S: NetworkStream, A: NetworkAcceptor<S>>: Listener<S,
     /// Bind to a socket with SSL. Otherwise behaves the same as bind().
     fn bind_with_ssl<To: ToSocketAddr>(addr: To, cert: Path, key: Path) -> IoResult<Self>;
 
+    /// Bind to a socket with SSL, asking clients for a certificate signed by
+    /// one of the certificate authorities in the `ca` file.
+    ///
+    /// Listeners that cannot verify clients return an error by default.
+    fn bind_with_client_auth<To: ToSocketAddr>(addr: To, cert: Path, key: Path, ca: Path)
+                                              -> IoResult<Self> {
+        let _ = (addr, cert, key, ca);
+        Err(IoError {
+            kind: InvalidInput,
+            desc: "Listener cannot verify client certificates",
+            detail: None
+        })
+    }
+
     /// Get the address this Listener ended up listening on.
     fn socket_name(&mut self) -> IoResult<SocketAddr>;
 }
@@ -55,6 +69,27 @@ pub trait NetworkAcceptor<S: NetworkStream>: Acceptor<S> + Clone + Send {
 pub trait NetworkStream: Stream + Any + StreamClone + Send {
     /// Get the remote address of the underlying connection.
     fn peer_name(&mut self) -> IoResult<SocketAddr>;
+
+    /// Close the reading half of the connection, so blocked reads return.
+    ///
+    /// This does nothing by default.
+    fn close_read(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+
+    /// The PEM encoded certificate the peer presented, if any.
+    ///
+    /// This is `None` by default.
+    fn peer_certificate(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Make writes that block for longer than `timeout_ms`, counted from
+    /// this call, fail with a `TimedOut` error. `None` removes the timeout.
+    ///
+    /// This does nothing by default.
+    #[allow(unused_variables)]
+    fn set_write_timeout(&mut self, timeout_ms: Option<u64>) {}
 }
 
 #[doc(hidden)]
@@ -179,18 +214,18 @@ impl NetworkListener<HttpStream, HttpAcceptor> for HttpListener {
 
     #[inline]
     fn bind_with_ssl<To: ToSocketAddr>(addr: To, cert: Path, key: Path) -> IoResult<HttpListener> {
-        // TODO: Make these more configurable
-        let mut ssl_context = try!(SslContext::new(Sslv23).map_err(lift_ssl_error));
-        if let Some(err) = ssl_context.set_cipher_list("DEFAULT") {
-            return Err(lift_ssl_error(err));
-        }
-        if let Some(err) = ssl_context.set_certificate_file(&cert, X509FileType::PEM) {
-            return Err(lift_ssl_error(err));
-        }
-        if let Some(err) = ssl_context.set_private_key_file(&key, X509FileType::PEM) {
+        let mut ssl_context = try!(server_ssl_context(cert, key));
+        ssl_context.set_verify(SslVerifyNone, None);
+        Ok(HttpsL(try!(TcpListener::bind(addr)), ssl_context))
+    }
+
+    fn bind_with_client_auth<To: ToSocketAddr>(addr: To, cert: Path, key: Path, ca: Path)
+                                              -> IoResult<HttpListener> {
+        let mut ssl_context = try!(server_ssl_context(cert, key));
+        if let Some(err) = ssl_context.set_CA_file(&ca) {
             return Err(lift_ssl_error(err));
         }
-        ssl_context.set_verify(SslVerifyNone, None);
+        ssl_context.set_verify(SslVerifyPeer, None);
         Ok(HttpsL(try!(TcpListener::bind(addr)), ssl_context))
     }
 
@@ -203,6 +238,21 @@ impl NetworkListener<HttpStream, HttpAcceptor> for HttpListener {
     }
 }
 
+fn server_ssl_context(cert: Path, key: Path) -> IoResult<SslContext> {
+    // TODO: Make these more configurable
+    let mut ssl_context = try!(SslContext::new(Sslv23).map_err(lift_ssl_error));
+    if let Some(err) = ssl_context.set_cipher_list("DEFAULT") {
+        return Err(lift_ssl_error(err));
+    }
+    if let Some(err) = ssl_context.set_certificate_file(&cert, X509FileType::PEM) {
+        return Err(lift_ssl_error(err));
+    }
+    if let Some(err) = ssl_context.set_private_key_file(&key, X509FileType::PEM) {
+        return Err(lift_ssl_error(err));
+    }
+    Ok(ssl_context)
+}
+
 /// A `NetworkAcceptor` for `HttpStream`s.
 #[deriving(Clone)]
 pub enum HttpAcceptor {
@@ -280,6 +330,36 @@ impl NetworkStream for HttpStream {
             Https(ref mut inner) => inner.get_mut().peer_name()
         }
     }
+
+    fn close_read(&mut self) -> IoResult<()> {
+        match *self {
+            Http(ref mut inner) => inner.close_read(),
+            Https(ref mut inner) => inner.get_mut().close_read()
+        }
+    }
+
+    fn set_write_timeout(&mut self, timeout_ms: Option<u64>) {
+        match *self {
+            Http(ref mut inner) => inner.set_write_timeout(timeout_ms),
+            Https(ref mut inner) => inner.get_mut().set_write_timeout(timeout_ms)
+        }
+    }
+
+    fn peer_certificate(&self) -> Option<Vec<u8>> {
+        match *self {
+            Http(..) => None,
+            Https(ref inner) => inner.get_peer_certificate().and_then(|cert| {
+                let mut pem = MemWriter::new();
+                match cert.write_pem(&mut pem) {
+                    Ok(..) => Some(pem.unwrap()),
+                    Err(e) => {
+                        debug!("peer certificate error: {}", e);
+                        None
+                    }
+                }
+            })
+        }
+    }
 }
 
 /// A connector that will produce HttpStreams.
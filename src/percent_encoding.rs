@@ -0,0 +1,181 @@
+//! Percent-encoding and decoding.
+//!
+//! Which bytes are encoded depends on where the result goes, such as the
+//! path or the query of a URI, so each place has an `EncodeSet`. Decoding is
+//! the same everywhere, except for form data, where `+` is a space.
+//!
+//! ```
+//! use hyper::percent_encoding::{percent_encode, percent_decode, EncodeSet};
+//!
+//! assert_eq!(percent_encode(b"a b/c", EncodeSet::Path)[], "a%20b/c");
+//! assert_eq!(percent_encode(b"a&b=c", EncodeSet::Query)[], "a%26b%3Dc");
+//! assert_eq!(percent_decode(b"a%20b"), b"a b".to_vec());
+//! ```
+use self::EncodeSet::{Path, Query, Userinfo, Form, ExtValue};
+
+/// The bytes left as they are when encoding for a part of a message. Every
+/// other byte is encoded as `%XX`.
+///
+/// Letters, digits, and `-._~` are never encoded, except `~` in `Form`.
+#[deriving(Copy, Clone, PartialEq, Show)]
+pub enum EncodeSet {
+    /// The path of a URI, which keeps `/` and the other characters a path may
+    /// have, such as `:@!$&'()*+,;=`.
+    Path,
+    /// A key or value in the query of a URI. The `&`, `=`, `+` and `#` that
+    /// would end it are encoded.
+    Query,
+    /// The user or password of the userinfo of a URI, so `:` and `@` are
+    /// encoded.
+    Userinfo,
+    /// A key or value of `application/x-www-form-urlencoded` data, keeping
+    /// only letters, digits and `*-._`. A space is written as `+`.
+    Form,
+    /// The value of an RFC 5987 extended parameter, like the `filename*` of
+    /// `Content-Disposition`, keeping the characters of `attr-char`.
+    ExtValue,
+}
+
+impl EncodeSet {
+    /// Whether `b` is left as it is.
+    pub fn keeps(&self, b: u8) -> bool {
+        match b {
+            b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'-' | b'.' | b'_' => return true,
+            _ => ()
+        }
+        match *self {
+            Path => b"~!$&'()*+,;=:@/".contains(&b),
+            Query => b"~!$'()*,;:@/?".contains(&b),
+            Userinfo => b"~!$&'()*+,;=".contains(&b),
+            Form => b == b'*',
+            ExtValue => b"~!#$&+^`|".contains(&b),
+        }
+    }
+}
+
+/// Percent-encode `input` for where `set` says it goes.
+pub fn percent_encode(input: &[u8], set: EncodeSet) -> String {
+    let mut output = String::with_capacity(input.len());
+    for &b in input.iter() {
+        if set.keeps(b) {
+            output.push(b as char);
+        } else if b == b' ' && set == Form {
+            output.push('+');
+        } else {
+            output.push_str(format!("%{:02X}", b)[]);
+        }
+    }
+    output
+}
+
+/// Decode the percent-escapes of `input`.
+///
+/// A `%` not followed by two hex digits is kept as it is.
+pub fn percent_decode(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        let b = input[i];
+        if b == b'%' && i + 2 < input.len() {
+            match (from_hex(input[i + 1]), from_hex(input[i + 2])) {
+                (Some(high), Some(low)) => {
+                    output.push(high * 16 + low);
+                    i += 3;
+                    continue;
+                },
+                _ => ()
+            }
+        }
+        output.push(b);
+        i += 1;
+    }
+    output
+}
+
+/// Decode the percent-escapes of `input` as UTF-8, replacing what is not
+/// UTF-8 with `U+FFFD`.
+pub fn percent_decode_lossy(input: &[u8]) -> String {
+    String::from_utf8_lossy(percent_decode(input)[]).into_owned()
+}
+
+/// Decode `application/x-www-form-urlencoded` data, such as a query, into
+/// key and value pairs.
+///
+/// Repeated keys are all kept, in order. A key without `=` has an empty
+/// value, and empty pairs, as in `a=1&&b=2`, are skipped.
+pub fn form_decode(input: &[u8]) -> Vec<(String, String)> {
+    input.split(|&b| b == b'&').filter(|pair| !pair.is_empty()).map(|pair| {
+        let mut parts = pair.splitn(1, |&b| b == b'=');
+        let key = parts.next().unwrap_or(b"");
+        let value = parts.next().unwrap_or(b"");
+        (form_decode_part(key), form_decode_part(value))
+    }).collect()
+}
+
+fn form_decode_part(input: &[u8]) -> String {
+    let plus_as_space: Vec<u8> = input.iter().map(|&b| if b == b'+' { b' ' } else { b }).collect();
+    percent_decode_lossy(plus_as_space[])
+}
+
+/// Encode key and value pairs as `application/x-www-form-urlencoded` data.
+pub fn form_encode(pairs: &[(String, String)]) -> String {
+    let mut output = String::new();
+    for &(ref key, ref value) in pairs.iter() {
+        if !output.is_empty() {
+            output.push('&');
+        }
+        output.push_str(percent_encode(key.as_bytes(), Form)[]);
+        output.push('=');
+        output.push_str(percent_encode(value.as_bytes(), Form)[]);
+    }
+    output
+}
+
+fn from_hex(b: u8) -> Option<u8> {
+    match b {
+        b'0'...b'9' => Some(b - b'0'),
+        b'a'...b'f' => Some(b - b'a' + 10),
+        b'A'...b'F' => Some(b - b'A' + 10),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{percent_encode, percent_decode, percent_decode_lossy, form_encode, form_decode};
+    use super::EncodeSet::{Path, Query, Userinfo, Form, ExtValue};
+
+    #[test]
+    fn test_percent_encode() {
+        let input = b"a b/c?d&e=f+g:h@i~\xe2\x82\xac%";
+        assert_eq!(percent_encode(input, Path)[], "a%20b/c%3Fd&e=f+g:h@i~%E2%82%AC%25");
+        assert_eq!(percent_encode(input, Query)[], "a%20b/c?d%26e%3Df%2Bg:h@i~%E2%82%AC%25");
+        assert_eq!(percent_encode(input, Userinfo)[], "a%20b%2Fc%3Fd&e=f+g%3Ah%40i~%E2%82%AC%25");
+        assert_eq!(percent_encode(input, Form)[],
+                   "a+b%2Fc%3Fd%26e%3Df%2Bg%3Ah%40i%7E%E2%82%AC%25");
+        assert_eq!(percent_encode(input, ExtValue)[],
+                   "a%20b%2Fc%3Fd&e%3Df+g%3Ah%40i~%E2%82%AC%25");
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode(b"a%20b%2fc%E2%82%AC"), b"a b/c\xe2\x82\xac".to_vec());
+        assert_eq!(percent_decode(b"100%"), b"100%".to_vec());
+        assert_eq!(percent_decode(b"%zz%4"), b"%zz%4".to_vec());
+        assert_eq!(percent_decode(b"a+b"), b"a+b".to_vec());
+        assert_eq!(percent_decode_lossy(b"%ff")[], "\uFFFD");
+    }
+
+    #[test]
+    fn test_form() {
+        let pairs = form_decode(b"q=two+words&tag=a%26b&tag=%E2%9C%93&&empty");
+        assert_eq!(pairs, vec![
+            ("q".to_string(), "two words".to_string()),
+            ("tag".to_string(), "a&b".to_string()),
+            ("tag".to_string(), "\u2713".to_string()),
+            ("empty".to_string(), "".to_string())
+        ]);
+        assert_eq!(form_encode(pairs[])[], "q=two+words&tag=a%26b&tag=%E2%9C%93&empty=");
+        assert_eq!(form_decode(form_encode(pairs[]).as_bytes()), pairs);
+    }
+}
@@ -0,0 +1,356 @@
+//! A server-side response cache.
+//!
+//! The `Cache` middleware stores responses to `GET` requests that are
+//! cacheable according to their `Cache-Control` or `Expires` headers, and
+//! serves them without calling the wrapped `Handler` while they are fresh.
+//! Conditional requests are answered with `304 Not Modified` when the
+//! validators of the response match.
+//!
+//! ```no_run
+//! # use hyper::Server;
+//! # use hyper::Ipv4Addr;
+//! # use hyper::server::{Request, Response};
+//! use hyper::header::common::CacheControl;
+//! use hyper::header::common::cache_control::CacheDirective::{Public, MaxAge};
+//! use hyper::server::cache::Cache;
+//!
+//! fn report(_: Request, mut res: Response) {
+//!     res.headers_mut().set(CacheControl(vec![Public, MaxAge(300)]));
+//!     let mut res = res.start().unwrap();
+//!     res.write(b"an expensive report").unwrap();
+//!     res.end().unwrap();
+//! }
+//!
+//! Server::http(Ipv4Addr(127, 0, 0, 1), 3000).listen(Cache::new(report, 1024)).unwrap();
+//! ```
+use std::collections::LruCache;
+use std::str::from_utf8;
+use std::sync::Mutex;
+
+use time::get_time;
+
+use header::Headers;
+use header::common::{Age, CacheControl, Etag, Expires, IfModifiedSince, IfNoneMatch, LastModified,
+                     Pragma, SetCookie, Vary};
+use header::common::cache_control::CacheDirective::{NoCache, NoStore, Private, MaxAge, SMaxAge};
+use method::Method::Get;
+use net::Fresh;
+use server::{Handler, Request, Response};
+use server::capture::{capture, send, send_not_modified};
+use status::StatusCode;
+use status::StatusCode::InternalServerError;
+use uri::RequestUri::{AbsolutePath, AbsoluteUri};
+
+/// A response kept by a `CacheStore`.
+#[deriving(Clone)]
+pub struct CachedResponse {
+    /// The status of the response.
+    pub status: StatusCode,
+    /// The headers of the response, without `Content-Length` or
+    /// `Transfer-Encoding`.
+    pub headers: Headers,
+    /// The whole body of the response.
+    pub body: Vec<u8>,
+    /// When the response was stored, in seconds since the epoch.
+    pub stored: i64,
+    /// How many seconds after being stored the response is fresh.
+    pub max_age: i64
+}
+
+impl CachedResponse {
+    /// Whether this response is still fresh at `now`, in seconds since the epoch.
+    pub fn is_fresh(&self, now: i64) -> bool {
+        now < self.stored + self.max_age
+    }
+}
+
+/// Storage for a `Cache`.
+pub trait CacheStore: Sync + Send {
+    /// Get the response stored for `key`.
+    fn get(&self, key: &str) -> Option<CachedResponse>;
+    /// Store a response for `key`.
+    fn put(&self, key: String, res: CachedResponse);
+    /// Remove the response stored for `key`.
+    fn remove(&self, key: &str);
+}
+
+/// A `CacheStore` in memory, evicting the least recently used responses.
+pub struct MemoryStore {
+    cache: Mutex<LruCache<String, CachedResponse>>
+}
+
+impl MemoryStore {
+    /// Create a store keeping at most `capacity` responses.
+    pub fn new(capacity: uint) -> MemoryStore {
+        MemoryStore {
+            cache: Mutex::new(LruCache::new(capacity))
+        }
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        self.cache.lock().get(&key.to_string()).map(|res| res.clone())
+    }
+
+    fn put(&self, key: String, res: CachedResponse) {
+        self.cache.lock().insert(key, res);
+    }
+
+    fn remove(&self, key: &str) {
+        self.cache.lock().remove(&key.to_string());
+    }
+}
+
+/// A middleware `Handler` caching responses to `GET` requests.
+///
+/// Only `200 OK` responses with a `max-age`, `s-maxage` or `Expires` are
+/// stored. Responses that are `private`, `no-cache` or `no-store`, that set
+/// a cookie, or that have a `Vary` header are never stored, and neither are
+/// responses to requests with an `Authorization` header.
+pub struct Cache<H, S = MemoryStore> {
+    inner: H,
+    store: S
+}
+
+impl<H: Handler> Cache<H, MemoryStore> {
+    /// Wrap a `Handler`, keeping at most `capacity` responses in memory.
+    pub fn new(inner: H, capacity: uint) -> Cache<H, MemoryStore> {
+        Cache::with_store(inner, MemoryStore::new(capacity))
+    }
+}
+
+impl<H: Handler, S: CacheStore> Cache<H, S> {
+    /// Wrap a `Handler`, keeping responses in `store`.
+    pub fn with_store(inner: H, store: S) -> Cache<H, S> {
+        Cache {
+            inner: inner,
+            store: store
+        }
+    }
+}
+
+impl<H: Handler, S: CacheStore> Handler for Cache<H, S> {
+    fn handle(&self, req: Request, res: Response<Fresh>) {
+        if req.method != Get || req.headers.get_raw("Authorization").is_some() {
+            return self.inner.handle(req, res);
+        }
+
+        let key = cache_key(&req);
+        let (no_cache, no_store) = request_directives(&req.headers);
+        let now = get_time().sec;
+
+        if !no_cache {
+            match self.store.get(key[]) {
+                Some(ref cached) if cached.is_fresh(now) => {
+                    debug!("cache hit {}", key);
+                    let mut headers = cached.headers.clone();
+                    headers.set(Age((now - cached.stored) as u64));
+                    return respond(&req.headers, cached.status, &headers, cached.body[], res);
+                },
+                Some(..) => self.store.remove(key[]),
+                None => ()
+            }
+        }
+
+        let req_headers = req.headers.clone();
+        let captured = match capture(&self.inner, req) {
+            Ok(captured) => captured,
+            Err(e) => {
+                error!("cache handler response error: {}", e);
+                let mut res = res;
+                *res.status_mut() = InternalServerError;
+                if let Err(e) = res.start().and_then(|res| res.end()) {
+                    error!("cache response error: {}", e);
+                }
+                return;
+            }
+        };
+
+        if !no_store && captured.status == StatusCode::Ok {
+            if let Some(max_age) = freshness(&captured.headers, now) {
+                debug!("cache store {} for {}s", key, max_age);
+                self.store.put(key, CachedResponse {
+                    status: captured.status,
+                    headers: captured.headers.clone(),
+                    body: captured.body.clone(),
+                    stored: now,
+                    max_age: max_age
+                });
+            }
+        }
+        respond(&req_headers, captured.status, &captured.headers, captured.body[], res);
+    }
+}
+
+/// Whether a conditional request with `req` headers can be answered with
+/// `304 Not Modified`, instead of a response with `res` headers.
+///
+/// `If-None-Match` is checked against the `Etag` using the weak comparison,
+/// and takes precedence over `If-Modified-Since`, which is checked against
+/// `Last-Modified`.
+pub fn is_not_modified(req: &Headers, res: &Headers) -> bool {
+    if let Some(if_none_match) = req.get::<IfNoneMatch>() {
+        return if_none_match.matches(res.get::<Etag>());
+    }
+
+    match (req.get::<IfModifiedSince>(), res.get::<LastModified>()) {
+        (Some(since), Some(modified)) => modified.date() <= since.date(),
+        _ => false
+    }
+}
+
+fn respond(req: &Headers, status: StatusCode, headers: &Headers, body: &[u8],
+           res: Response<Fresh>) {
+    let result = if status == StatusCode::Ok && is_not_modified(req, headers) {
+        send_not_modified(headers, res)
+    } else {
+        send(status, headers, body, res)
+    };
+    if let Err(e) = result {
+        error!("cache response error: {}", e);
+    }
+}
+
+fn cache_key(req: &Request) -> String {
+    let host = req.headers.get_raw("Host").and_then(|raw| {
+        raw.iter().next().and_then(|host| from_utf8(host[]).ok())
+    }).unwrap_or("");
+    match req.uri {
+        AbsolutePath(ref path) => format!("{}{}", host, path),
+        AbsoluteUri(ref url) => url.to_string(),
+        ref uri => format!("{}", uri)
+    }
+}
+
+/// Returns whether the request has `no-cache` and `no-store`.
+///
+/// Without `Cache-Control`, the HTTP/1.0 `Pragma: no-cache` is used instead.
+fn request_directives(headers: &Headers) -> (bool, bool) {
+    match headers.get::<CacheControl>() {
+        Some(cc) => (cc.iter().any(|d| *d == NoCache || *d == NoStore),
+                     cc.iter().any(|d| *d == NoStore)),
+        None => (headers.get::<Pragma>().map_or(false, |pragma| pragma.is_no_cache()), false)
+    }
+}
+
+/// How many seconds a response with `headers` is fresh for, if it can be stored.
+fn freshness(headers: &Headers, now: i64) -> Option<i64> {
+    if headers.has::<SetCookie>() || headers.has::<Vary>() {
+        return None;
+    }
+    if let Some(cc) = headers.get::<CacheControl>() {
+        if cc.iter().any(|d| *d == NoCache || *d == NoStore || *d == Private) {
+            return None;
+        }
+        let shared = cc.iter().filter_map(|d| match *d {
+            SMaxAge(secs) => Some(secs),
+            _ => None
+        }).next();
+        let max_age = shared.or_else(|| cc.iter().filter_map(|d| match *d {
+            MaxAge(secs) => Some(secs),
+            _ => None
+        }).next());
+        if let Some(secs) = max_age {
+            return if secs > 0 { Some(secs as i64) } else { None };
+        }
+    }
+    headers.get::<Expires>().and_then(|expires| {
+        if expires.is_expired_at(now) { None } else { Some(expires.seconds_from(now)) }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUint, INIT_ATOMIC_UINT, SeqCst};
+
+    use header::Headers;
+    use header::common::{CacheControl, Etag};
+    use header::common::cache_control::CacheDirective::{MaxAge, Private};
+    use mock::handle;
+    use net::Fresh;
+    use server::{Request, Response};
+    use super::{Cache, is_not_modified};
+
+    static PUBLIC_CALLS: AtomicUint = INIT_ATOMIC_UINT;
+
+    fn public(_: Request, mut res: Response<Fresh>) {
+        PUBLIC_CALLS.fetch_add(1, SeqCst);
+        res.headers_mut().set(CacheControl(vec![MaxAge(60)]));
+        res.headers_mut().set(Etag { weak: false, tag: "v1".to_string() });
+        let mut res = res.start().unwrap();
+        res.write(b"cached body").unwrap();
+        res.end().unwrap();
+    }
+
+    static PRIVATE_CALLS: AtomicUint = INIT_ATOMIC_UINT;
+
+    fn private(_: Request, mut res: Response<Fresh>) {
+        PRIVATE_CALLS.fetch_add(1, SeqCst);
+        res.headers_mut().set(CacheControl(vec![Private, MaxAge(60)]));
+        res.start().unwrap().end().unwrap();
+    }
+
+    #[test]
+    fn test_cache_hit() {
+        let cache = Cache::new(public, 8);
+        let first = handle(&cache, "GET /a HTTP/1.1\r\nHost: example.domain\r\n\r\n");
+        let second = handle(&cache, "GET /a HTTP/1.1\r\nHost: example.domain\r\n\r\n");
+        assert_eq!(PUBLIC_CALLS.load(SeqCst), 1);
+        assert!(first[].ends_with("\r\n\r\ncached body"));
+        assert!(second[].ends_with("\r\n\r\ncached body"));
+        assert!(second[].contains("Age: "));
+
+        let conditional = handle(&cache, "GET /a HTTP/1.1\r\nHost: example.domain\r\n\
+                                       If-None-Match: \"v0\", W/\"v1\"\r\n\r\n");
+        assert!(conditional[].starts_with("HTTP/1.1 304 Not Modified\r\n"));
+        assert!(conditional[].ends_with("\r\n\r\n"));
+        assert_eq!(PUBLIC_CALLS.load(SeqCst), 1);
+    }
+
+    static PRAGMA_CALLS: AtomicUint = INIT_ATOMIC_UINT;
+
+    fn pragma(_: Request, mut res: Response<Fresh>) {
+        PRAGMA_CALLS.fetch_add(1, SeqCst);
+        res.headers_mut().set(CacheControl(vec![MaxAge(60)]));
+        res.start().unwrap().end().unwrap();
+    }
+
+    #[test]
+    fn test_pragma_no_cache() {
+        let cache = Cache::new(pragma, 8);
+        handle(&cache, "GET /a HTTP/1.0\r\n\r\n");
+        handle(&cache, "GET /a HTTP/1.0\r\nPragma: no-cache\r\n\r\n");
+        assert_eq!(PRAGMA_CALLS.load(SeqCst), 2);
+        handle(&cache, "GET /a HTTP/1.0\r\nPragma: no-cache\r\nCache-Control: max-age=60\r\n\r\n");
+        assert_eq!(PRAGMA_CALLS.load(SeqCst), 2);
+    }
+
+    #[test]
+    fn test_not_stored() {
+        let cache = Cache::new(private, 8);
+        handle(&cache, "GET /a HTTP/1.1\r\n\r\n");
+        handle(&cache, "GET /a HTTP/1.1\r\n\r\n");
+        assert_eq!(PRIVATE_CALLS.load(SeqCst), 2);
+    }
+
+    #[test]
+    fn test_is_not_modified() {
+        let mut res = Headers::new();
+        res.set(Etag { weak: true, tag: "abc".to_string() });
+        res.set_raw("Last-Modified", vec![b"Sun, 06 Nov 1994 08:49:37 GMT".to_vec()]);
+
+        let mut req = Headers::new();
+        req.set_raw("If-None-Match", vec![b"\"abc\"".to_vec()]);
+        assert!(is_not_modified(&req, &res));
+        req.set_raw("If-None-Match", vec![b"\"xyz\"".to_vec()]);
+        req.set_raw("If-Modified-Since", vec![b"Sun, 06 Nov 1994 08:49:37 GMT".to_vec()]);
+        assert!(!is_not_modified(&req, &res));
+
+        let mut req = Headers::new();
+        req.set_raw("If-Modified-Since", vec![b"Sun, 06 Nov 1994 08:49:37 GMT".to_vec()]);
+        assert!(is_not_modified(&req, &res));
+        req.set_raw("If-Modified-Since", vec![b"Sat, 05 Nov 1994 08:49:37 GMT".to_vec()]);
+        assert!(!is_not_modified(&req, &res));
+    }
+}
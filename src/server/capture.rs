@@ -0,0 +1,95 @@
+//! Buffering a whole response from a `Handler`, for middleware that needs to
+//! look at or store the body before anything is sent.
+use std::ascii::AsciiExt;
+use std::default::Default;
+use std::io::{IoResult, MemReader, MemWriter};
+use std::num::FromPrimitive;
+
+use header::Headers;
+use header::common::{ContentLength, TransferEncoding};
+use http::read_status_line;
+use http::HttpReader::{SizedReader, ChunkedReader, EofReader};
+use net::Fresh;
+use server::{Handler, Request, Response};
+use status::StatusCode;
+use status::StatusCode::NotModified;
+use HttpResult;
+use HttpError::HttpStatusError;
+
+/// A response written by a `Handler`, without its body framing.
+pub struct Captured {
+    pub status: StatusCode,
+    pub headers: Headers,
+    pub body: Vec<u8>
+}
+
+/// Run `handler` with a `Response` writing to memory, and read it back.
+pub fn capture<H: Handler>(handler: &H, req: Request) -> HttpResult<Captured> {
+    let mut buf = MemWriter::new();
+    handler.handle(req, Response::new(&mut buf));
+
+    let mut rdr = MemReader::new(buf.unwrap());
+    let (_, mut raw_status) = try!(read_status_line(&mut rdr));
+    let mut headers = try!(Headers::from_raw(&mut rdr));
+    // interim responses are not kept
+    while raw_status.0 == 100 || (raw_status.0 > 101 && raw_status.0 < 200) {
+        raw_status = try!(read_status_line(&mut rdr)).1;
+        headers = try!(Headers::from_raw(&mut rdr));
+    }
+    let status = match FromPrimitive::from_u16(raw_status.0) {
+        Some(status) => status,
+        None => return Err(HttpStatusError)
+    };
+
+    let body = match headers.get::<ContentLength>() {
+        Some(&ContentLength(len)) => try!(SizedReader(rdr, len).read_to_end()),
+        None if headers.has::<TransferEncoding>() => try!(ChunkedReader(rdr, None, Default::default()).read_to_end()),
+        None => try!(EofReader(rdr).read_to_end())
+    };
+    headers.remove::<ContentLength>();
+    headers.remove::<TransferEncoding>();
+
+    Ok(Captured {
+        status: status,
+        headers: headers,
+        body: body
+    })
+}
+
+/// Write a response, with a `Content-Length` for the body.
+pub fn send(status: StatusCode, headers: &Headers, body: &[u8],
+            mut res: Response<Fresh>) -> IoResult<()> {
+    *res.status_mut() = status;
+    res.headers_mut().extend(headers.iter());
+    match status as u16 {
+        100...199 | 204 | 304 => (),
+        _ => res.headers_mut().set(ContentLength(body.len()))
+    }
+    let mut res = try!(res.start());
+    if !body.is_empty() {
+        try!(res.write(body));
+    }
+    res.end()
+}
+
+/// Headers a `304 Not Modified` response carries over from the full response.
+///
+/// See https://tools.ietf.org/html/rfc7232#section-4.1
+static NOT_MODIFIED_HEADERS: [&'static str, ..7] = [
+    "Cache-Control",
+    "Content-Location",
+    "Date",
+    "Etag",
+    "Expires",
+    "Last-Modified",
+    "Vary",
+];
+
+/// Answer with `304 Not Modified` instead of the response with `headers`.
+pub fn send_not_modified(headers: &Headers, mut res: Response<Fresh>) -> IoResult<()> {
+    *res.status_mut() = NotModified;
+    res.headers_mut().extend(headers.iter().filter(|header| {
+        NOT_MODIFIED_HEADERS.iter().any(|name| name.eq_ignore_ascii_case(header.name()))
+    }));
+    res.start().and_then(|res| res.end())
+}
@@ -0,0 +1,164 @@
+//! Client certificates of HTTPS connections.
+//!
+//! When a `Server` is set up to `verify_clients`, the certificate a client
+//! presented during the TLS handshake is available from
+//! `Request::peer_certificate`, for certificate-based authorization:
+//!
+//! ```no_run
+//! # use hyper::server::{Request, Response};
+//! # use hyper::status::StatusCode::Forbidden;
+//! use hyper::server::certificate::AltName::Dns;
+//!
+//! fn internal(req: Request, mut res: Response) {
+//!     let allowed = req.peer_certificate().map_or(false, |cert| {
+//!         cert.subject_alt_names.contains(&Dns("billing.internal".to_string()))
+//!     });
+//!     if !allowed {
+//!         *res.status_mut() = Forbidden;
+//!     }
+//!     res.start().unwrap().end().unwrap();
+//! }
+//! ```
+use std::io::MemReader;
+use std::io::net::ip::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use openssl::nid::Nid;
+use openssl::x509::{X509, X509Name, GeneralName};
+
+/// A certificate presented by the client, and verified by the server.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Certificate {
+    /// The subject distinguished name, such as `CN=client, O=Example`.
+    pub subject: String,
+    /// The names in the subject alternative name extension.
+    pub subject_alt_names: Vec<AltName>,
+    /// The PEM encoding of the whole certificate.
+    pub pem: Vec<u8>
+}
+
+/// A subject alternative name of a `Certificate`.
+#[deriving(Clone, PartialEq, Show)]
+pub enum AltName {
+    /// A `dNSName`, such as `example.domain`.
+    Dns(String),
+    /// An `rfc822Name`, which is an email address.
+    Email(String),
+    /// A `uniformResourceIdentifier`.
+    Uri(String),
+    /// An `iPAddress`.
+    Ip(IpAddr)
+}
+
+/// The attributes of the subject name, in the order they are shown.
+static SUBJECT_ATTRIBUTES: [(Nid, &'static str), ..6] = [
+    (Nid::CommonName, "CN"),
+    (Nid::OrganizationalUnitName, "OU"),
+    (Nid::OrganizationName, "O"),
+    (Nid::LocalityName, "L"),
+    (Nid::StateOrProvinceName, "ST"),
+    (Nid::CountryName, "C")
+];
+
+impl Certificate {
+    /// Read the subject and subject alternative names of a PEM certificate.
+    ///
+    /// Returns `None` if OpenSSL can't parse the certificate.
+    pub fn from_pem(pem: Vec<u8>) -> Option<Certificate> {
+        let (subject, alt_names) = match X509::from_pem(&mut MemReader::new(pem.clone())) {
+            Ok(x509) => {
+                let alt_names = match x509.subject_alt_names() {
+                    Some(names) => names.iter().filter_map(alt_name).collect(),
+                    None => Vec::new()
+                };
+                (format_name(&x509.subject_name()), alt_names)
+            },
+            Err(e) => {
+                debug!("certificate error: {}", e);
+                return None;
+            }
+        };
+        Some(Certificate {
+            subject: subject,
+            subject_alt_names: alt_names,
+            pem: pem
+        })
+    }
+}
+
+fn format_name(name: &X509Name) -> String {
+    let mut out = String::new();
+    for &(nid, short) in SUBJECT_ATTRIBUTES.iter() {
+        if let Some(value) = name.text_by_nid(nid) {
+            if !out.is_empty() {
+                out.push_str(", ");
+            }
+            out.push_str(short);
+            out.push('=');
+            out.push_str(value[]);
+        }
+    }
+    out
+}
+
+fn alt_name(name: &GeneralName) -> Option<AltName> {
+    if let Some(dns) = name.dnsname() {
+        return Some(AltName::Dns(dns.to_string()));
+    }
+    if let Some(email) = name.email() {
+        return Some(AltName::Email(email.to_string()));
+    }
+    if let Some(uri) = name.uri() {
+        return Some(AltName::Uri(uri.to_string()));
+    }
+    match name.ipaddress() {
+        Some(ip) if ip.len() == 4 => Some(AltName::Ip(Ipv4Addr(ip[0], ip[1], ip[2], ip[3]))),
+        Some(ip) if ip.len() == 16 => {
+            let mut parts = [0u16, ..8];
+            for i in range(0u, 8) {
+                parts[i] = (ip[2 * i] as u16 << 8) | ip[2 * i + 1] as u16;
+            }
+            Some(AltName::Ip(Ipv6Addr(parts[0], parts[1], parts[2], parts[3],
+                                      parts[4], parts[5], parts[6], parts[7])))
+        },
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::net::ip::Ipv4Addr;
+    use super::Certificate;
+    use super::AltName::{Dns, Ip};
+
+    // a self-signed certificate for `/O=Example/CN=client`, with the
+    // alternative names `client.example` and `10.0.0.1`
+    const PEM: &'static str = "-----BEGIN CERTIFICATE-----\n\
+                       MIIBvjCCAWSgAwIBAgIUZgRmsJ+jMFA/E0cFeZf9d6bNybQwCgYIKoZIzj0EAwIw\n\
+                       IzEQMA4GA1UECgwHRXhhbXBsZTEPMA0GA1UEAwwGY2xpZW50MCAXDTI2MTAxNDA3\n\
+                       MjIwOVoYDzIxMjYwOTIwMDcyMjA5WjAjMRAwDgYDVQQKDAdFeGFtcGxlMQ8wDQYD\n\
+                       VQQDDAZjbGllbnQwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAATxPncOUNuiexcN\n\
+                       MKWFY2DnzgM6KZozb5wI7yTzI+Posg9ZO10noQRWvnnyDspgBU7STAL4LX/OSu+I\n\
+                       H8EkegEUo3QwcjAdBgNVHQ4EFgQUifBkhxaBjrCSZ6KUMsB1yI23lEUwHwYDVR0j\n\
+                       BBgwFoAUifBkhxaBjrCSZ6KUMsB1yI23lEUwDwYDVR0TAQH/BAUwAwEB/zAfBgNV\n\
+                       HREEGDAWgg5jbGllbnQuZXhhbXBsZYcECgAAATAKBggqhkjOPQQDAgNIADBFAiBx\n\
+                       oJg2zywuUO5attYZjXHtfpM41UDfwhVBZTUYbgKQcgIhAL8XGJkj5mk6ktxjE1Tk\n\
+                       Y3Cq4vxj7XVE2v6NTe8R3NQK\n\
+                       -----END CERTIFICATE-----\n";
+
+    #[test]
+    fn test_from_pem() {
+        let pem = PEM.as_bytes().to_vec();
+        let cert = Certificate::from_pem(pem.clone()).unwrap();
+        assert_eq!(cert.subject[], "CN=client, O=Example");
+        assert_eq!(cert.subject_alt_names,
+                   vec![Dns("client.example".to_string()), Ip(Ipv4Addr(10, 0, 0, 1))]);
+        assert_eq!(cert.pem, pem);
+    }
+
+    #[test]
+    fn test_from_pem_malformed() {
+        let pem = PEM.as_bytes();
+        assert_eq!(Certificate::from_pem(pem[..pem.len() / 2].to_vec()), None);
+        assert_eq!(Certificate::from_pem(vec![]), None);
+    }
+}
@@ -0,0 +1,304 @@
+//! Cross-Origin Resource Sharing.
+//!
+//! The `Cors` middleware lets browsers make cross-origin requests to a
+//! `Handler` from an allowlist of origins, as described by the
+//! [Fetch standard](https://fetch.spec.whatwg.org/#http-cors-protocol).
+//!
+//! ```no_run
+//! # use hyper::Server;
+//! # use hyper::Ipv4Addr;
+//! # use hyper::server::{Request, Response};
+//! # fn api(_: Request, res: Response) { res.start().unwrap().end().unwrap(); }
+//! use hyper::method::Method::{Get, Post};
+//! use hyper::server::cors::Cors;
+//!
+//! let cors = Cors::new(api)
+//!     .allow_origin("https://example.domain")
+//!     .allow_methods(vec![Get, Post])
+//!     .allow_headers(vec!["Content-Type".to_string()])
+//!     .max_age(3600);
+//! Server::http(Ipv4Addr(127, 0, 0, 1), 3000).listen(cors).unwrap();
+//! ```
+use std::ascii::AsciiExt;
+use std::str::from_utf8;
+
+use header::Headers;
+use header::common::Vary;
+use header::common::{AccessControlAllowOrigin, AccessControlAllowCredentials};
+use header::common::{AccessControlAllowMethods, AccessControlAllowHeaders};
+use header::common::{AccessControlExposeHeaders, AccessControlMaxAge};
+use header::common::{AccessControlRequestMethod, AccessControlRequestHeaders};
+use method::Method;
+use method::Method::{Get, Head, Post, Options};
+use net::Fresh;
+use server::{Handler, Request, Response};
+use server::response::StartHook;
+use status::StatusCode::{NoContent, Forbidden};
+
+/// A middleware `Handler` answering CORS preflight requests, and adding the
+/// `Access-Control-*` headers to responses for allowed origins.
+///
+/// Requests without an `Origin` header are passed to the wrapped `Handler`
+/// unchanged. Preflight requests are answered by the middleware itself, with
+/// `204 No Content` if allowed and `403 Forbidden` otherwise. With an
+/// allowlist of origins, every response gets `Vary: Origin`.
+pub struct Cors<H> {
+    inner: H,
+    origins: Option<Vec<String>>,
+    methods: Vec<Method>,
+    headers: Vec<String>,
+    exposed: Vec<String>,
+    credentials: bool,
+    max_age: Option<u32>
+}
+
+impl<H: Handler> Cors<H> {
+    /// Wrap a `Handler`.
+    ///
+    /// No origin is allowed until `allow_origin` or `allow_any_origin` is
+    /// used. The allowed methods default to `GET`, `HEAD` and `POST`.
+    pub fn new(inner: H) -> Cors<H> {
+        Cors {
+            inner: inner,
+            origins: Some(Vec::new()),
+            methods: vec![Get, Head, Post],
+            headers: Vec::new(),
+            exposed: Vec::new(),
+            credentials: false,
+            max_age: None
+        }
+    }
+
+    /// Allow requests from `origin`, such as `https://example.domain`.
+    pub fn allow_origin(mut self, origin: &str) -> Cors<H> {
+        if let Some(ref mut origins) = self.origins {
+            origins.push(origin.to_string());
+        }
+        self
+    }
+
+    /// Allow requests from any origin.
+    pub fn allow_any_origin(mut self) -> Cors<H> {
+        self.origins = None;
+        self
+    }
+
+    /// Set the methods allowed in cross-origin requests.
+    pub fn allow_methods(mut self, methods: Vec<Method>) -> Cors<H> {
+        self.methods = methods;
+        self
+    }
+
+    /// Set the request headers allowed in cross-origin requests.
+    pub fn allow_headers(mut self, headers: Vec<String>) -> Cors<H> {
+        self.headers = headers;
+        self
+    }
+
+    /// Set the response headers that scripts may read.
+    pub fn expose_headers(mut self, headers: Vec<String>) -> Cors<H> {
+        self.exposed = headers;
+        self
+    }
+
+    /// Allow cross-origin requests to include credentials, such as cookies.
+    pub fn allow_credentials(mut self) -> Cors<H> {
+        self.credentials = true;
+        self
+    }
+
+    /// Let clients cache the result of a preflight request for `secs` seconds.
+    pub fn max_age(mut self, secs: u32) -> Cors<H> {
+        self.max_age = Some(secs);
+        self
+    }
+
+    fn is_allowed_origin(&self, origin: &str) -> bool {
+        match self.origins {
+            Some(ref origins) => origins.iter().any(|o| o[].eq_ignore_ascii_case(origin)),
+            None => true
+        }
+    }
+
+    fn are_allowed_headers(&self, headers: &[String]) -> bool {
+        headers.iter().all(|requested| {
+            self.headers.iter().any(|h| h[].eq_ignore_ascii_case(requested[]))
+        })
+    }
+
+    fn set_origin_headers(&self, origin: &str, headers: &mut Headers) {
+        // a wildcard cannot be used for requests with credentials
+        if self.origins.is_none() && !self.credentials {
+            headers.set(AccessControlAllowOrigin::Any);
+        } else {
+            headers.set(AccessControlAllowOrigin::Value(origin.to_string()));
+        }
+        if self.credentials {
+            headers.set(AccessControlAllowCredentials);
+        }
+    }
+
+    fn preflight(&self, origin: &str, req: &Request, mut res: Response<Fresh>) {
+        let method = req.headers.get::<AccessControlRequestMethod>().map(|m| m.0.clone());
+        let allowed_headers = match req.headers.get::<AccessControlRequestHeaders>() {
+            Some(&AccessControlRequestHeaders(ref headers)) => self.are_allowed_headers(headers[]),
+            None => true
+        };
+        let allowed_method = method.as_ref().map_or(false, |m| self.methods.contains(m));
+
+        if self.is_allowed_origin(origin) && allowed_method && allowed_headers {
+            *res.status_mut() = NoContent;
+            self.set_origin_headers(origin, res.headers_mut());
+            res.headers_mut().set(AccessControlAllowMethods(self.methods.clone()));
+            if !self.headers.is_empty() {
+                res.headers_mut().set(AccessControlAllowHeaders(self.headers.clone()));
+            }
+            if let Some(secs) = self.max_age {
+                res.headers_mut().set(AccessControlMaxAge(secs));
+            }
+        } else {
+            debug!("CORS preflight rejected, origin={} method={}", origin, method);
+            *res.status_mut() = Forbidden;
+        }
+
+        if let Err(e) = res.start().and_then(|res| res.end()) {
+            error!("CORS preflight response error: {}", e);
+        }
+    }
+}
+
+impl<H: Handler> Handler for Cors<H> {
+    fn handle(&self, req: Request, mut res: Response<Fresh>) {
+        // every response depends on the origin, allowed or not, when only
+        // some are allowed, so a cache must not share them between origins
+        if self.origins.is_some() {
+            res.add_start_hook(box VaryOrigin);
+        }
+
+        let origin = raw_str(&req.headers, "Origin").map(|origin| origin.to_string());
+        let origin = match origin {
+            Some(origin) => origin,
+            None => return self.inner.handle(req, res)
+        };
+
+        if req.method == Options && req.headers.has::<AccessControlRequestMethod>() {
+            return self.preflight(origin[], &req, res);
+        }
+
+        if self.is_allowed_origin(origin[]) {
+            self.set_origin_headers(origin[], res.headers_mut());
+            if !self.exposed.is_empty() {
+                res.headers_mut().set(AccessControlExposeHeaders(self.exposed.clone()));
+            }
+        }
+        self.inner.handle(req, res);
+    }
+}
+
+/// Adds `Origin` to the `Vary` header, after the wrapped `Handler` is done
+/// with the headers.
+struct VaryOrigin;
+
+impl StartHook for VaryOrigin {
+    fn on_start(&mut self, res: &mut Response<Fresh>) {
+        Vary::add_to(res.headers_mut(), "Origin");
+    }
+}
+
+fn raw_str<'a>(headers: &'a Headers, name: &str) -> Option<&'a str> {
+    headers.get_raw(name).and_then(|raw| {
+        if raw.len() == 1 {
+            from_utf8(raw[0][]).ok().map(|s| s.trim())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use header::common::Vary;
+    use method::Method::{Get, Put};
+    use mock::handle;
+    use net::Fresh;
+    use server::{Request, Response};
+    use super::Cors;
+
+    fn ok(_: Request, res: Response<Fresh>) {
+        res.start().unwrap().end().unwrap();
+    }
+
+    fn cors() -> Cors<fn(Request, Response<Fresh>)> {
+        Cors::new(ok as fn(Request, Response<Fresh>))
+            .allow_origin("http://allowed.domain")
+            .allow_methods(vec![Get, Put])
+            .allow_headers(vec!["X-Foo".to_string()])
+            .max_age(60)
+    }
+
+    #[test]
+    fn test_preflight() {
+        let res = handle(&cors(), "OPTIONS /a HTTP/1.1\r\n\
+                                Origin: http://allowed.domain\r\n\
+                                Access-Control-Request-Method: PUT\r\n\
+                                Access-Control-Request-Headers: x-foo\r\n\r\n");
+        assert!(res[].starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(res[].contains("Access-Control-Allow-Origin: http://allowed.domain\r\n"));
+        assert!(res[].contains("Access-Control-Allow-Methods: GET, PUT\r\n"));
+        assert!(res[].contains("Access-Control-Allow-Headers: X-Foo\r\n"));
+        assert!(res[].contains("Access-Control-Max-Age: 60\r\n"));
+    }
+
+    #[test]
+    fn test_preflight_rejected() {
+        let res = handle(&cors(), "OPTIONS /a HTTP/1.1\r\n\
+                                Origin: http://allowed.domain\r\n\
+                                Access-Control-Request-Method: DELETE\r\n\r\n");
+        assert!(res[].starts_with("HTTP/1.1 403 Forbidden\r\n"));
+        assert!(!res[].contains("Access-Control-Allow-Origin"));
+    }
+
+    #[test]
+    fn test_actual_request() {
+        let res = handle(&cors(), "GET /a HTTP/1.1\r\nOrigin: http://allowed.domain\r\n\r\n");
+        assert!(res[].contains("Access-Control-Allow-Origin: http://allowed.domain\r\n"));
+        assert!(res[].contains("Vary: Origin\r\n"));
+
+        let res = handle(&cors(), "GET /a HTTP/1.1\r\nOrigin: http://evil.domain\r\n\r\n");
+        assert!(res[].starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(!res[].contains("Access-Control-Allow-Origin"));
+        assert!(res[].contains("Vary: Origin\r\n"));
+    }
+
+    fn drops_vary(_: Request, mut res: Response<Fresh>) {
+        res.headers_mut().remove::<Vary>();
+        res.start().unwrap().end().unwrap();
+    }
+
+    #[test]
+    fn test_vary_on_every_response() {
+        let res = handle(&cors(), "GET /a HTTP/1.1\r\n\r\n");
+        assert!(res[].contains("Vary: Origin\r\n"));
+
+        let res = handle(&cors(), "OPTIONS /a HTTP/1.1\r\n\
+                                Origin: http://evil.domain\r\n\
+                                Access-Control-Request-Method: PUT\r\n\r\n");
+        assert!(res[].starts_with("HTTP/1.1 403 Forbidden\r\n"));
+        assert!(res[].contains("Vary: Origin\r\n"));
+
+        let cors = Cors::new(drops_vary).allow_origin("http://allowed.domain");
+        let res = handle(&cors, "GET /a HTTP/1.1\r\nOrigin: http://allowed.domain\r\n\r\n");
+        assert!(res[].contains("Vary: Origin\r\n"));
+
+        let res = handle(&Cors::new(ok).allow_any_origin(), "GET /a HTTP/1.1\r\n\r\n");
+        assert!(!res[].contains("Vary"));
+    }
+
+    #[test]
+    fn test_any_origin() {
+        let cors = Cors::new(ok).allow_any_origin();
+        let res = handle(&cors, "GET /a HTTP/1.1\r\nOrigin: http://any.domain\r\n\r\n");
+        assert!(res[].contains("Access-Control-Allow-Origin: *\r\n"));
+    }
+}
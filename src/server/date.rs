@@ -0,0 +1,47 @@
+//! A per-second cache of the `Date` header value.
+//!
+//! Formatting a `Tm` for every response shows up in profiles, and the value
+//! only changes once a second anyway.
+use std::cell::RefCell;
+
+use time::{get_time, now_utc};
+
+use header::HeaderFormatter;
+use header::common::Date;
+
+struct CachedDate {
+    sec: i64,
+    value: Vec<u8>
+}
+
+thread_local!(static CACHE: RefCell<CachedDate> = RefCell::new(CachedDate {
+    sec: 0,
+    value: vec![]
+}));
+
+/// Returns the raw value for a `Date` header of the current time.
+///
+/// The formatted string is recomputed at most once per second, per thread.
+pub fn now() -> Vec<u8> {
+    let sec = get_time().sec;
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.sec != sec {
+            cache.sec = sec;
+            cache.value = format!("{}", HeaderFormatter(&Date(now_utc()))).into_bytes();
+        }
+        cache.value.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use header::common::Date;
+
+    #[test]
+    fn test_now_parses() {
+        let date: Option<Date> = Header::parse_header(&[super::now()]);
+        assert!(date.is_some());
+    }
+}
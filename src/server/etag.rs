@@ -0,0 +1,158 @@
+//! Generating entity tags, and answering conditional requests with them.
+//!
+//! An `Etag` can be computed from the metadata of a file, which is cheap but
+//! only weakly identifies the content, or from a hash of the content itself.
+//! The `Etags` middleware does the latter for every response that does not
+//! have an `Etag` already, and answers matching conditional requests with
+//! `304 Not Modified`.
+//!
+//! ```no_run
+//! # use hyper::Server;
+//! # use hyper::Ipv4Addr;
+//! # use hyper::server::{Request, Response};
+//! # fn page(_: Request, res: Response) { res.start().unwrap().end().unwrap(); }
+//! use hyper::server::etag::Etags;
+//!
+//! Server::http(Ipv4Addr(127, 0, 0, 1), 3000).listen(Etags::new(page)).unwrap();
+//! ```
+use std::io::FileStat;
+
+use codec::sha1_hex;
+use header::common::Etag;
+use method::Method::{Get, Head};
+use net::Fresh;
+use server::{Handler, Request, Response};
+use server::cache::is_not_modified;
+use server::capture::{capture, send, send_not_modified};
+use status::StatusCode;
+use status::StatusCode::InternalServerError;
+
+/// A strong `Etag` from a hash of the content.
+pub fn from_bytes(body: &[u8]) -> Etag {
+    Etag {
+        weak: false,
+        tag: sha1_hex(body)
+    }
+}
+
+/// A weak `Etag` from the size and modification time of a file.
+///
+/// The content of the file is not read, so this is cheap, but a file
+/// changed twice in the same millisecond keeps its tag.
+pub fn from_file_stat(stat: &FileStat) -> Etag {
+    Etag {
+        weak: true,
+        tag: format!("{:x}-{:x}", stat.size, stat.modified)
+    }
+}
+
+/// A middleware `Handler` adding an `Etag` to responses, and answering
+/// conditional requests that match it.
+///
+/// Responses to `GET` and `HEAD` requests are buffered, and a `200 OK`
+/// without an `Etag` gets one from a hash of the body. If the request has an
+/// `If-None-Match` or `If-Modified-Since` that matches the response, only
+/// `304 Not Modified` is sent.
+pub struct Etags<H> {
+    inner: H
+}
+
+impl<H: Handler> Etags<H> {
+    /// Wrap a `Handler`.
+    pub fn new(inner: H) -> Etags<H> {
+        Etags {
+            inner: inner
+        }
+    }
+}
+
+impl<H: Handler> Handler for Etags<H> {
+    fn handle(&self, req: Request, mut res: Response<Fresh>) {
+        if req.method != Get && req.method != Head {
+            return self.inner.handle(req, res);
+        }
+
+        let req_headers = req.headers.clone();
+        let mut captured = match capture(&self.inner, req) {
+            Ok(captured) => captured,
+            Err(e) => {
+                error!("etag handler response error: {}", e);
+                *res.status_mut() = InternalServerError;
+                if let Err(e) = res.start().and_then(|res| res.end()) {
+                    error!("etag response error: {}", e);
+                }
+                return;
+            }
+        };
+
+        if captured.status != StatusCode::Ok {
+            if let Err(e) = send(captured.status, &captured.headers, captured.body[], res) {
+                error!("etag response error: {}", e);
+            }
+            return;
+        }
+
+        if !captured.headers.has::<Etag>() {
+            captured.headers.set(from_bytes(captured.body[]));
+        }
+
+        let result = if is_not_modified(&req_headers, &captured.headers) {
+            send_not_modified(&captured.headers, res)
+        } else {
+            send(captured.status, &captured.headers, captured.body[], res)
+        };
+        if let Err(e) = result {
+            error!("etag response error: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{File, TempDir};
+    use std::io::fs::stat;
+
+    use mock::handle;
+    use net::Fresh;
+    use server::{Request, Response};
+    use super::{Etags, from_bytes, from_file_stat};
+
+    fn hello(_: Request, res: Response<Fresh>) {
+        let mut res = res.start().unwrap();
+        res.write(b"hello").unwrap();
+        res.end().unwrap();
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let etag = from_bytes(b"hello");
+        assert!(!etag.weak);
+        assert_eq!(etag.tag[], "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d");
+        assert!(from_bytes(b"hello!") != etag);
+    }
+
+    #[test]
+    fn test_from_file_stat() {
+        let dir = TempDir::new("hyper-etag").unwrap();
+        let path = dir.path().join("file");
+        File::create(&path).write(b"hello").unwrap();
+        let stat = stat(&path).unwrap();
+
+        let etag = from_file_stat(&stat);
+        assert!(etag.weak);
+        assert!(etag.tag[].starts_with("5-"));
+    }
+
+    #[test]
+    fn test_etags() {
+        let etags = Etags::new(hello);
+        let res = handle(&etags, "GET / HTTP/1.1\r\n\r\n");
+        assert!(res[].contains("Etag: \"aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d\"\r\n"));
+        assert!(res[].ends_with("\r\n\r\nhello"));
+
+        let res = handle(&etags, "GET / HTTP/1.1\r\n\
+                               If-None-Match: \"aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d\"\r\n\r\n");
+        assert!(res[].starts_with("HTTP/1.1 304 Not Modified\r\n"));
+        assert!(res[].ends_with("\r\n\r\n"));
+    }
+}
@@ -0,0 +1,127 @@
+//! Typed storage for data attached to a request.
+//!
+//! Middleware often needs to hand information to the handlers that run after
+//! it, such as an authenticated user or a request id. Instead of agreeing on
+//! string keys, values are stored and retrieved by their type.
+use std::any::{Any, AnyRefExt, AnyMutRefExt};
+use std::boxed::BoxAny;
+use std::collections::HashMap;
+use std::fmt;
+use std::intrinsics::TypeId;
+
+/// A map of values keyed by their type.
+///
+/// Example:
+///
+/// ```
+/// # use hyper::server::Extensions;
+/// #[deriving(PartialEq, Show)]
+/// struct RequestId(uint);
+///
+/// let mut ext = Extensions::new();
+/// ext.insert(RequestId(7));
+/// assert_eq!(ext.get::<RequestId>(), Some(&RequestId(7)));
+/// ```
+pub struct Extensions {
+    map: HashMap<TypeId, Box<Any + 'static>>
+}
+
+impl Extensions {
+    /// Creates a new, empty extensions map.
+    pub fn new() -> Extensions {
+        Extensions {
+            map: HashMap::new()
+        }
+    }
+
+    /// Insert a value, returning the previous value of the same type, if any.
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.map.insert(TypeId::of::<T>(), box value as Box<Any>).map(|old| {
+            *old.downcast::<T>().ok().expect("extension stored under wrong TypeId")
+        })
+    }
+
+    /// Get a reference to the value of type `T`, if one was inserted.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.map.get(&TypeId::of::<T>()).and_then(|val| {
+            let val: &Any = &**val;
+            val.downcast_ref::<T>()
+        })
+    }
+
+    /// Get a mutable reference to the value of type `T`, if one was inserted.
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.map.get_mut(&TypeId::of::<T>()).and_then(|val| {
+            let val: &mut Any = &mut **val;
+            val.downcast_mut::<T>()
+        })
+    }
+
+    /// Returns whether a value of type `T` is in the map.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Removes the value of type `T` from the map, returning it.
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.map.remove(&TypeId::of::<T>()).and_then(|val| {
+            val.downcast::<T>().ok().map(|val| *val)
+        })
+    }
+
+    /// Returns the number of values in the map.
+    pub fn len(&self) -> uint {
+        self.map.len()
+    }
+
+    /// Remove all values from the map.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+impl fmt::Show for Extensions {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Extensions({} values)", self.map.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Extensions;
+
+    #[deriving(PartialEq, Show)]
+    struct Foo(uint);
+
+    #[deriving(PartialEq, Show)]
+    struct Bar(&'static str);
+
+    #[test]
+    fn test_insert_get() {
+        let mut ext = Extensions::new();
+        assert_eq!(ext.insert(Foo(1)), None);
+        ext.insert(Bar("bar"));
+        assert_eq!(ext.get::<Foo>(), Some(&Foo(1)));
+        assert_eq!(ext.get::<Bar>(), Some(&Bar("bar")));
+        assert_eq!(ext.len(), 2);
+    }
+
+    #[test]
+    fn test_insert_replaces() {
+        let mut ext = Extensions::new();
+        ext.insert(Foo(1));
+        assert_eq!(ext.insert(Foo(2)), Some(Foo(1)));
+        assert_eq!(ext.get::<Foo>(), Some(&Foo(2)));
+        assert_eq!(ext.len(), 1);
+    }
+
+    #[test]
+    fn test_get_mut_and_remove() {
+        let mut ext = Extensions::new();
+        ext.insert(Foo(1));
+        ext.get_mut::<Foo>().unwrap().0 = 5;
+        assert_eq!(ext.remove::<Foo>(), Some(Foo(5)));
+        assert!(!ext.contains::<Foo>());
+        assert_eq!(ext.remove::<Bar>(), None);
+    }
+}
@@ -0,0 +1,111 @@
+//! Answering requests with methods a `Handler` doesn't support.
+//!
+//! The `Methods` middleware passes on requests with the methods a `Handler`
+//! supports. It answers `OPTIONS` requests with an `Allow` header listing
+//! them, and any other request with `405 Method Not Allowed`.
+//!
+//! ```no_run
+//! # use hyper::Server;
+//! # use hyper::Ipv4Addr;
+//! # use hyper::server::{Request, Response};
+//! # fn items(_: Request, res: Response) { res.start().unwrap().end().unwrap(); }
+//! use hyper::method::Method::{Get, Post};
+//! use hyper::server::methods::Methods;
+//!
+//! let items = Methods::new(items, vec![Get, Post]);
+//! Server::http(Ipv4Addr(127, 0, 0, 1), 3000).listen(items).unwrap();
+//! ```
+use header::common::{Allow, ContentLength};
+use method::Method;
+use method::Method::{Get, Head, Options};
+use net::Fresh;
+use server::{Handler, Request, Response};
+use status::StatusCode::MethodNotAllowed;
+
+/// A middleware `Handler` only passing on requests with some methods.
+pub struct Methods<H> {
+    inner: H,
+    allow: Allow,
+    options: bool
+}
+
+impl<H: Handler> Methods<H> {
+    /// Wrap a `Handler` supporting `methods`.
+    ///
+    /// `HEAD` is supported if `GET` is, as the server answers it the same
+    /// way without the body. `OPTIONS` is always answered, by the middleware
+    /// itself unless it is in `methods`.
+    pub fn new(inner: H, methods: Vec<Method>) -> Methods<H> {
+        let mut methods = methods;
+        let options = methods.contains(&Options);
+        if methods.contains(&Get) && !methods.contains(&Head) {
+            methods.push(Head);
+        }
+        if !options {
+            methods.push(Options);
+        }
+        Methods {
+            inner: inner,
+            allow: Allow(methods),
+            options: options
+        }
+    }
+
+    /// The `Allow` header sent with the responses of the middleware.
+    pub fn allow(&self) -> &Allow {
+        &self.allow
+    }
+}
+
+impl<H: Handler> Handler for Methods<H> {
+    fn handle(&self, req: Request, mut res: Response<Fresh>) {
+        if req.method == Options && !self.options {
+            debug!("answering OPTIONS with {}", self.allow);
+        } else if self.allow.contains(&req.method) {
+            return self.inner.handle(req, res);
+        } else {
+            debug!("method not allowed: {}", req.method);
+            *res.status_mut() = MethodNotAllowed;
+        }
+        res.headers_mut().set(self.allow.clone());
+        res.headers_mut().set(ContentLength(0));
+        if let Err(e) = res.start().and_then(|res| res.end()) {
+            error!("methods response error: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use method::Method::{Get, Post, Head, Options};
+    use mock::handle;
+    use net::Fresh;
+    use server::{Request, Response};
+    use super::Methods;
+
+    fn hello(_: Request, res: Response<Fresh>) {
+        let mut res = res.start().unwrap();
+        res.write(b"hello").unwrap();
+        res.end().unwrap();
+    }
+
+    #[test]
+    fn test_methods() {
+        let methods = Methods::new(hello, vec![Get, Post]);
+        assert_eq!(methods.allow().0, vec![Get, Post, Head, Options]);
+
+        let res = handle(&methods, "GET / HTTP/1.1\r\n\r\n");
+        assert!(res[].starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(res[].ends_with("hello"));
+
+        let res = handle(&methods, "DELETE / HTTP/1.1\r\n\r\n");
+        assert!(res[].starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(res[].contains("Allow: GET, POST, HEAD, OPTIONS\r\n"));
+
+        let res = handle(&methods, "OPTIONS / HTTP/1.1\r\n\r\n");
+        assert!(res[].starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(res[].contains("Allow: GET, POST, HEAD, OPTIONS\r\n"));
+        assert!(!res[].contains("hello"));
+    }
+}
@@ -0,0 +1,291 @@
+//! Counters of the requests and connections a `Server` handles.
+//!
+//! A `Server` only counts when it is given `Metrics`, which can then be read
+//! with `snapshot`, or served as text by the `Report` middleware:
+//!
+//! ```no_run
+//! # use hyper::Server;
+//! # use hyper::Ipv4Addr;
+//! # use hyper::server::{Request, Response};
+//! # fn app(_: Request, res: Response) { res.start().unwrap().end().unwrap(); }
+//! use hyper::server::metrics::{Metrics, Report};
+//!
+//! let metrics = Metrics::new();
+//! let server = Server::http(Ipv4Addr(127, 0, 0, 1), 3000).metrics(metrics.clone());
+//! server.listen(Report::new(app, metrics, "/metrics")).unwrap();
+//! ```
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUint, SeqCst};
+
+use header::common::ContentType;
+use method::Method::Get;
+use mime::Mime;
+use mime::TopLevel::Text;
+use mime::SubLevel::Plain;
+use net::Fresh;
+use server::{Handler, Request, Response};
+
+/// The upper bounds, in seconds, of the request duration histogram buckets.
+pub static DURATION_BUCKETS: [f64, ..10] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0
+];
+
+/// The counters of a `Server`, shared by its connections.
+///
+/// Each counter is updated on its own, without a lock, so a `Snapshot`
+/// taken while requests are handled may be a request apart between them.
+/// Cloning `Metrics` gives another handle to the same counters.
+#[deriving(Clone)]
+pub struct Metrics {
+    counters: Arc<Counters>
+}
+
+struct Counters {
+    informational: AtomicUint,
+    success: AtomicUint,
+    redirection: AtomicUint,
+    client_error: AtomicUint,
+    server_error: AtomicUint,
+    active_connections: AtomicUint,
+    connections: AtomicUint,
+    bytes_read: AtomicUint,
+    bytes_written: AtomicUint,
+    durations: Vec<AtomicUint>,
+    duration_micros: AtomicUint
+}
+
+/// The values of `Metrics` at some point.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Snapshot {
+    /// Responses with a `1xx` status.
+    pub informational: u64,
+    /// Responses with a `2xx` status.
+    pub success: u64,
+    /// Responses with a `3xx` status.
+    pub redirection: u64,
+    /// Responses with a `4xx` status.
+    pub client_error: u64,
+    /// Responses with a `5xx` status.
+    pub server_error: u64,
+    /// Connections open now.
+    pub active_connections: u64,
+    /// Connections accepted in total.
+    pub connections: u64,
+    /// Bytes read from clients.
+    pub bytes_read: u64,
+    /// Bytes written to clients.
+    pub bytes_written: u64,
+    /// Requests handled, in each of `DURATION_BUCKETS`, and then slower ones.
+    ///
+    /// The counts are not cumulative: a request is only in the first bucket
+    /// it fits in.
+    pub durations: Vec<u64>,
+    /// The total time spent handling requests, in seconds, counted to the
+    /// microsecond.
+    pub duration_sum: f64
+}
+
+impl Snapshot {
+    /// The number of requests handled.
+    pub fn requests(&self) -> u64 {
+        self.durations.iter().fold(0, |sum, &count| sum + count)
+    }
+}
+
+impl Metrics {
+    /// Create counters starting at zero.
+    pub fn new() -> Metrics {
+        Metrics {
+            counters: Arc::new(Counters {
+                informational: AtomicUint::new(0),
+                success: AtomicUint::new(0),
+                redirection: AtomicUint::new(0),
+                client_error: AtomicUint::new(0),
+                server_error: AtomicUint::new(0),
+                active_connections: AtomicUint::new(0),
+                connections: AtomicUint::new(0),
+                bytes_read: AtomicUint::new(0),
+                bytes_written: AtomicUint::new(0),
+                durations: range(0, DURATION_BUCKETS.len() + 1).map(|_| AtomicUint::new(0)).collect(),
+                duration_micros: AtomicUint::new(0)
+            })
+        }
+    }
+
+    /// The current values of the counters.
+    pub fn snapshot(&self) -> Snapshot {
+        let counters = &*self.counters;
+        Snapshot {
+            informational: counters.informational.load(SeqCst) as u64,
+            success: counters.success.load(SeqCst) as u64,
+            redirection: counters.redirection.load(SeqCst) as u64,
+            client_error: counters.client_error.load(SeqCst) as u64,
+            server_error: counters.server_error.load(SeqCst) as u64,
+            active_connections: counters.active_connections.load(SeqCst) as u64,
+            connections: counters.connections.load(SeqCst) as u64,
+            bytes_read: counters.bytes_read.load(SeqCst) as u64,
+            bytes_written: counters.bytes_written.load(SeqCst) as u64,
+            durations: counters.durations.iter().map(|count| count.load(SeqCst) as u64).collect(),
+            duration_sum: counters.duration_micros.load(SeqCst) as f64 / 1_000_000.0
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn connection_opened(&self) {
+        self.counters.connections.fetch_add(1, SeqCst);
+        self.counters.active_connections.fetch_add(1, SeqCst);
+    }
+
+    #[doc(hidden)]
+    pub fn connection_closed(&self) {
+        self.counters.active_connections.fetch_sub(1, SeqCst);
+    }
+
+    /// Count a request answered with `status`, where `0` means no response
+    /// was started.
+    #[doc(hidden)]
+    pub fn record_request(&self, status: uint, secs: f64, read: u64, written: u64) {
+        let counters = &*self.counters;
+        let bucket = DURATION_BUCKETS.iter().position(|&bound| secs <= bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        let class = match status {
+            100...199 => Some(&counters.informational),
+            200...299 => Some(&counters.success),
+            300...399 => Some(&counters.redirection),
+            400...499 => Some(&counters.client_error),
+            500...599 => Some(&counters.server_error),
+            _ => None
+        };
+        if let Some(class) = class {
+            class.fetch_add(1, SeqCst);
+        }
+        counters.durations[bucket].fetch_add(1, SeqCst);
+        counters.duration_micros.fetch_add((secs * 1_000_000.0) as uint, SeqCst);
+        counters.bytes_read.fetch_add(read as uint, SeqCst);
+        counters.bytes_written.fetch_add(written as uint, SeqCst);
+    }
+}
+
+/// Renders the counters in the Prometheus text format.
+impl fmt::Show for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let snapshot = self.snapshot();
+        let classes = [
+            ("1xx", snapshot.informational),
+            ("2xx", snapshot.success),
+            ("3xx", snapshot.redirection),
+            ("4xx", snapshot.client_error),
+            ("5xx", snapshot.server_error),
+        ];
+        for &(class, count) in classes.iter() {
+            try!(writeln!(f, "hyper_responses_total{{class=\"{}\"}} {}", class, count));
+        }
+        try!(writeln!(f, "hyper_connections_active {}", snapshot.active_connections));
+        try!(writeln!(f, "hyper_connections_total {}", snapshot.connections));
+        try!(writeln!(f, "hyper_bytes_read_total {}", snapshot.bytes_read));
+        try!(writeln!(f, "hyper_bytes_written_total {}", snapshot.bytes_written));
+
+        let mut cumulative = 0;
+        for (bound, &count) in DURATION_BUCKETS.iter().zip(snapshot.durations.iter()) {
+            cumulative += count;
+            try!(writeln!(f, "hyper_request_duration_seconds_bucket{{le=\"{}\"}} {}",
+                          bound, cumulative));
+        }
+        try!(writeln!(f, "hyper_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+                      snapshot.requests()));
+        try!(writeln!(f, "hyper_request_duration_seconds_sum {}", snapshot.duration_sum));
+        writeln!(f, "hyper_request_duration_seconds_count {}", snapshot.requests())
+    }
+}
+
+/// A middleware `Handler` answering `GET` requests for a path with the
+/// `Metrics` as text, and passing other requests on.
+pub struct Report<H> {
+    inner: H,
+    metrics: Metrics,
+    path: String
+}
+
+impl<H: Handler> Report<H> {
+    /// Wrap a `Handler`, serving `metrics` at `path`.
+    pub fn new(inner: H, metrics: Metrics, path: &str) -> Report<H> {
+        Report {
+            inner: inner,
+            metrics: metrics,
+            path: path.to_string()
+        }
+    }
+}
+
+impl<H: Handler> Handler for Report<H> {
+    fn handle(&self, req: Request, mut res: Response<Fresh>) {
+        if req.method != Get || req.path().as_ref() != Some(&self.path) {
+            return self.inner.handle(req, res);
+        }
+
+        res.headers_mut().set(ContentType(Mime(Text, Plain, vec![])));
+        let body = self.metrics.to_string();
+        let result = res.start().and_then(|mut res| {
+            try!(res.write(body.as_bytes()));
+            res.end()
+        });
+        if let Err(e) = result {
+            error!("metrics response error: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use mock::handle;
+    use net::Fresh;
+    use server::{Request, Response};
+    use super::{Metrics, Report};
+
+    fn ok(_: Request, res: Response<Fresh>) {
+        res.start().unwrap().end().unwrap();
+    }
+
+    #[test]
+    fn test_record() {
+        let metrics = Metrics::new();
+        metrics.connection_opened();
+        metrics.record_request(200, 0.002, 100, 300);
+        metrics.record_request(404, 0.0005, 50, 80);
+        metrics.record_request(0, 10.0, 10, 0);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.success, 1);
+        assert_eq!(snapshot.client_error, 1);
+        assert_eq!(snapshot.active_connections, 1);
+        assert_eq!(snapshot.bytes_read, 160);
+        assert_eq!(snapshot.bytes_written, 380);
+        assert_eq!(snapshot.requests(), 3);
+        assert_eq!(snapshot.durations[0], 1);
+        assert_eq!(snapshot.durations[1], 1);
+        assert_eq!(snapshot.durations[10], 1);
+
+        metrics.connection_closed();
+        assert_eq!(metrics.snapshot().active_connections, 0);
+        assert_eq!(metrics.snapshot().connections, 1);
+    }
+
+    #[test]
+    fn test_report() {
+        let metrics = Metrics::new();
+        metrics.record_request(200, 0.002, 100, 300);
+        let report = Report::new(ok, metrics, "/metrics");
+
+        let res = handle(&report, "GET /metrics HTTP/1.1\r\n\r\n");
+        assert!(res[].contains("Content-Type: text/plain\r\n"));
+        assert!(res[].contains("hyper_responses_total{class=\"2xx\"} 1\n"));
+        assert!(res[].contains("hyper_request_duration_seconds_bucket{le=\"0.001\"} 0\n"));
+        assert!(res[].contains("hyper_request_duration_seconds_bucket{le=\"0.005\"} 1\n"));
+        assert!(res[].contains("hyper_request_duration_seconds_count 1\n"));
+
+        let res = handle(&report, "GET / HTTP/1.1\r\n\r\n");
+        assert!(!res[].contains("hyper_"));
+    }
+}
@@ -1,72 +1,245 @@
 //! HTTP Server
-use std::io::{Listener, EndOfFile, BufferedReader, BufferedWriter};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::default::Default;
+use std::io::{Listener, EndOfFile, TimedOut, BufferedReader, BufferedWriter, IoResult,
+              standard_error};
 use std::io::net::ip::{IpAddr, Port, SocketAddr};
+use std::mem;
 use std::os;
-use std::sync::{Arc, TaskPool};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard, Condvar, TaskPool};
+use std::sync::atomic::{AtomicBool, AtomicUint, SeqCst};
 use std::thread::{Builder, JoinGuard};
+use std::time::Duration;
 
+use time::precise_time_s;
 
-pub use self::request::Request;
+
+pub use self::extensions::Extensions;
+pub use self::request::{Request, Upgraded};
 pub use self::response::Response;
 
 pub use net::{Fresh, Streaming};
 
 use HttpError::HttpIoError;
-use {HttpResult};
+use {HttpError, HttpResult};
+use header;
 use header::common::Connection;
 use header::common::connection::{KeepAlive, Close};
+use http::{should_keep_alive, ChunkLimits, ParseMode};
+use self::certificate::Certificate;
+use self::metrics::Metrics;
+use self::response::StartHook;
+use method::Method::Connect;
+use status::StatusCode::SwitchingProtocols;
 use net::{NetworkListener, NetworkAcceptor, NetworkStream,
           HttpAcceptor, HttpListener, HttpStream};
-use version::HttpVersion::{Http10, Http11};
+use version::HttpVersion::Http10;
+
+macro_rules! try_option(
+    ($e:expr) => {{
+        match $e {
+            Some(v) => v,
+            None => return None
+        }
+    }}
+);
 
+pub mod auth;
+pub mod cache;
+pub mod certificate;
+pub mod cors;
+pub mod etag;
+pub mod extensions;
+pub mod metrics;
+pub mod methods;
+pub mod multipart;
+pub mod negotiate;
+pub mod proxy;
+pub mod ratelimit;
 pub mod request;
 pub mod response;
+pub mod security;
+pub mod session;
+
+mod capture;
+mod date;
 
 /// A server can listen on a TCP socket.
 ///
 /// Once listening, it will create a `Request`/`Response` pair for each
 /// incoming connection, and hand them to the provided handler.
+///
+/// A server can listen on several addresses at once, such as an HTTP and an
+/// HTTPS port, with every connection dispatched to the same handler:
+///
+/// ```no_run
+/// # use hyper::server::{Server, Request, Response};
+/// # use hyper::{Ipv4Addr, Ipv6Addr};
+/// # fn hello(_: Request, res: Response) { res.start().unwrap().end().unwrap(); }
+/// let server = Server::http(Ipv4Addr(0, 0, 0, 0), 80)
+///     .and_http(Ipv6Addr(0, 0, 0, 0, 0, 0, 0, 0), 80)
+///     .and_https(Ipv4Addr(0, 0, 0, 0), 443, Path::new("cert.pem"), Path::new("key.pem"));
+/// server.listen(hello).unwrap();
+/// ```
 pub struct Server<L = HttpListener> {
+    binds: Vec<Bind>,
+    client_ca: Option<Path>,
+    metrics: Option<Metrics>,
+    server_header: Option<String>,
+    write_timeout: Option<Duration>,
+    parse_mode: ParseMode,
+    chunk_limits: ChunkLimits,
+    error_handler: Option<Arc<Box<ErrorHandler + Send + Sync>>>,
+}
+
+/// An address a `Server` will bind to, and optionally the SSL files for it.
+struct Bind {
     ip: IpAddr,
     port: Port,
     cert: Option<Path>,
     key: Option<Path>
 }
 
-macro_rules! try_option(
-    ($e:expr) => {{
-        match $e {
-            Some(v) => v,
-            None => return None
-        }
-    }}
-);
-
 impl Server<HttpListener> {
     /// Creates a new server that will handle `HttpStream`s.
     pub fn http(ip: IpAddr, port: Port) -> Server {
         Server {
-            ip: ip,
-            port: port,
-            cert: None,
-            key: None
+            binds: vec![Bind {
+                ip: ip,
+                port: port,
+                cert: None,
+                key: None
+            }],
+            client_ca: None,
+            metrics: None,
+            server_header: None,
+            write_timeout: None,
+            parse_mode: ParseMode::Strict,
+            chunk_limits: Default::default(),
+            error_handler: None
         }
     }
 
     /// Creates a new server that will handle HTTPS streams.
     pub fn https(ip: IpAddr, port: Port, cert: Path, key:Path) -> Server {
         Server {
+            binds: vec![Bind {
+                ip: ip,
+                port: port,
+                cert: Some(cert),
+                key: Some(key)
+            }],
+            client_ca: None,
+            metrics: None,
+            server_header: None,
+            write_timeout: None,
+            parse_mode: ParseMode::Strict,
+            chunk_limits: Default::default(),
+            error_handler: None
+        }
+    }
+}
+
+impl<L: NetworkListener<S, A>, S: NetworkStream, A: NetworkAcceptor<S>> Server<L> {
+    /// Additionally listen for HTTP connections on another address.
+    pub fn and_http(mut self, ip: IpAddr, port: Port) -> Server<L> {
+        self.binds.push(Bind {
+            ip: ip,
+            port: port,
+            cert: None,
+            key: None
+        });
+        self
+    }
+
+    /// Additionally listen for HTTPS connections on another address.
+    pub fn and_https(mut self, ip: IpAddr, port: Port, cert: Path, key: Path) -> Server<L> {
+        self.binds.push(Bind {
             ip: ip,
             port: port,
             cert: Some(cert),
             key: Some(key)
-        }
+        });
+        self
+    }
+
+    /// Ask HTTPS clients for a certificate, signed by one of the certificate
+    /// authorities in the `ca` file.
+    ///
+    /// A verified certificate is available from `Request::peer_certificate`.
+    /// Clients without a certificate can still connect, so handlers must
+    /// check for one.
+    pub fn verify_clients(mut self, ca: Path) -> Server<L> {
+        self.client_ca = Some(ca);
+        self
+    }
+
+    /// Send a `Server` header with `value` in every response that does not
+    /// have one already.
+    ///
+    /// No `Server` header is sent by default, since it tells clients which
+    /// software, and often which version of it, they are talking to.
+    pub fn server_header(mut self, value: &str) -> Server<L> {
+        self.server_header = Some(value.to_string());
+        self
+    }
+
+    /// Close connections where writing a response makes no progress for
+    /// `timeout`, because the client stopped reading it.
+    ///
+    /// Without a timeout, such a client keeps a worker thread blocked on the
+    /// write for as long as the connection stays open.
+    ///
+    /// A timeout is reported to the handler given to `on_error`.
+    pub fn write_timeout(mut self, timeout: Duration) -> Server<L> {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Report the errors that end a connection to `handler`, instead of
+    /// only logging them.
+    ///
+    /// These are requests that can't be parsed, and responses that time out
+    /// being written, as set with `write_timeout`.
+    pub fn on_error<E: ErrorHandler>(mut self, handler: E) -> Server<L> {
+        self.error_handler = Some(Arc::new(box handler as Box<ErrorHandler + Send + Sync>));
+        self
+    }
+
+    /// Set how strictly requests are parsed.
+    ///
+    /// `ParseMode::Lenient` accepts what clients commonly get wrong, such as
+    /// lines ending with a bare `LF`. Requests are parsed strictly by default.
+    pub fn parse_mode(mut self, mode: ParseMode) -> Server<L> {
+        self.parse_mode = mode;
+        self
+    }
+
+    /// Set how large the chunks of a chunked request body may be, and how
+    /// much they may add up to.
+    ///
+    /// By default, a chunk may be up to `http::DEFAULT_MAX_CHUNK_SIZE`, and
+    /// there is no limit on the whole body.
+    pub fn chunk_limits(mut self, limits: ChunkLimits) -> Server<L> {
+        self.chunk_limits = limits;
+        self
+    }
+
+    /// Count the requests and connections of this server in `metrics`.
+    ///
+    /// Nothing is counted unless this is set.
+    pub fn metrics(mut self, metrics: Metrics) -> Server<L> {
+        self.metrics = Some(metrics);
+        self
     }
-}
 
-impl<L: NetworkListener<S, A>, S: NetworkStream, A: NetworkAcceptor<S>> Server<L> {
     /// Binds to a socket, and starts handling connections using a task pool.
     ///
+    /// Each bound address gets its own acceptor thread and a task pool of
+    /// `threads` size.
+    ///
     /// This method has unbound type parameters, so can be used when you want to use
     /// something other than the provided HttpStream, HttpAcceptor, and HttpListener.
     pub fn listen_network<H, S, A, L>(self, handler: H, threads: uint) -> HttpResult<Listening<A>>
@@ -74,83 +247,52 @@ impl<L: NetworkListener<S, A>, S: NetworkStream, A: NetworkAcceptor<S>> Server<L
           S: NetworkStream + Clone,
           A: NetworkAcceptor<S>,
           L: NetworkListener<S, A>, {
-        debug!("binding to {}:{}", self.ip, self.port);
-        let mut listener: L = if self.cert.is_some() && self.key.is_some() {
-            try!(NetworkListener::<S, A>::bind_with_ssl((self.ip, self.port),
-                    self.cert.unwrap(), self.key.unwrap()))
-        } else {
-            try!(NetworkListener::<S, A>::bind((self.ip, self.port)))
-        };
+        let handler = Arc::new(handler);
+        let drain = Arc::new(Drain::new());
+        let mut acceptors = Vec::with_capacity(self.binds.len());
+        let mut guards = Vec::with_capacity(self.binds.len());
+        let mut sockets = Vec::with_capacity(self.binds.len());
 
-        let socket = try!(listener.socket_name());
-
-        let acceptor = try!(listener.listen());
-
-        let mut captured = acceptor.clone();
-        let guard = Builder::new().name("hyper acceptor".to_string()).spawn(move || {
-            let handler = Arc::new(handler);
-            debug!("threads = {}", threads);
-            let pool = TaskPool::new(threads);
-            for conn in captured.incoming() {
-                match conn {
-                    Ok(mut stream) => {
-                        debug!("Incoming stream");
-                        let handler = handler.clone();
-                        pool.execute(move || {
-                            let addr = match stream.peer_name() {
-                                Ok(addr) => addr,
-                                Err(e) => {
-                                    error!("Peer Name error: {}", e);
-                                    return;
-                                }
-                            };
-                            let mut rdr = BufferedReader::new(stream.clone());
-                            let mut wrt = BufferedWriter::new(stream);
-
-                            let mut keep_alive = true;
-                            while keep_alive {
-                                let mut res = Response::new(&mut wrt);
-                                let req = match Request::new(&mut rdr, addr) {
-                                    Ok(req) => req,
-                                    Err(e@HttpIoError(_)) => {
-                                        debug!("ioerror in keepalive loop = {}", e);
-                                        return;
-                                    }
-                                    Err(e) => {
-                                        //TODO: send a 400 response
-                                        error!("request error = {}", e);
-                                        return;
-                                    }
-                                };
-
-                                keep_alive = match (req.version, req.headers.get::<Connection>()) {
-                                    (Http10, Some(conn)) if !conn.contains(&KeepAlive) => false,
-                                    (Http11, Some(conn)) if conn.contains(&Close)  => false,
-                                    _ => true
-                                };
-                                res.version = req.version;
-                                handler.handle(req, res);
-                                debug!("keep_alive = {}", keep_alive);
-                            }
-
-                        });
-                    },
-                    Err(ref e) if e.kind == EndOfFile => {
-                        debug!("server closed");
-                        break;
-                    },
-                    Err(e) => {
-                        error!("Connection failed: {}", e);
-                        continue;
+        let client_ca = self.client_ca;
+        let options = Options {
+            metrics: self.metrics,
+            server_header: self.server_header,
+            write_timeout: self.write_timeout,
+            parse_mode: self.parse_mode,
+            chunk_limits: self.chunk_limits,
+            error_handler: self.error_handler
+        };
+        for bind in self.binds.into_iter() {
+            match bind_acceptor::<S, A, L>(bind, client_ca.clone()) {
+                Ok((acceptor, socket)) => {
+                    let captured = acceptor.clone();
+                    let handler = handler.clone();
+                    let drain = drain.clone();
+                    let options = options.clone();
+                    let name = format!("hyper acceptor {}", socket);
+                    guards.push(Builder::new().name(name).spawn(move || {
+                        accept_loop(captured, handler, drain, options, threads)
+                    }));
+                    acceptors.push(acceptor);
+                    sockets.push(socket);
+                },
+                Err(e) => {
+                    // the acceptor threads already started must stop, or
+                    // dropping their guards would block forever
+                    for acceptor in acceptors.iter_mut() {
+                        let _ = acceptor.close();
                     }
+                    return Err(e);
                 }
             }
-        });
+        }
 
         Ok(Listening {
-            acceptor: acceptor,
-            guard: Some(guard),
-            socket: socket,
+            acceptors: acceptors,
+            guards: guards,
+            drain: drain,
+            socket: sockets[0],
+            sockets: sockets,
         })
     }
 
@@ -166,27 +308,414 @@ impl<L: NetworkListener<S, A>, S: NetworkStream, A: NetworkAcceptor<S>> Server<L
 
 }
 
+fn bind_acceptor<S, A, L>(bind: Bind, client_ca: Option<Path>) -> HttpResult<(A, SocketAddr)>
+where S: NetworkStream,
+      A: NetworkAcceptor<S>,
+      L: NetworkListener<S, A> {
+    debug!("binding to {}:{}", bind.ip, bind.port);
+    let mut listener: L = match (bind.cert, bind.key, client_ca) {
+        (Some(cert), Some(key), Some(ca)) => {
+            try!(NetworkListener::<S, A>::bind_with_client_auth((bind.ip, bind.port),
+                                                                   cert, key, ca))
+        },
+        (Some(cert), Some(key), None) => {
+            try!(NetworkListener::<S, A>::bind_with_ssl((bind.ip, bind.port), cert, key))
+        },
+        _ => try!(NetworkListener::<S, A>::bind((bind.ip, bind.port)))
+    };
+
+    let socket = try!(listener.socket_name());
+    let acceptor = try!(listener.listen());
+    Ok((acceptor, socket))
+}
+
+fn accept_loop<S, A, H>(mut acceptor: A, handler: Arc<H>, drain: Arc<Drain>,
+                        options: Options, threads: uint)
+where S: NetworkStream + Clone,
+      A: NetworkAcceptor<S>,
+      H: Handler {
+    debug!("threads = {}", threads);
+    let pool = TaskPool::new(threads);
+    for conn in acceptor.incoming() {
+        match conn {
+            Ok(stream) => {
+                debug!("Incoming stream");
+                let handler = handler.clone();
+                let drain = drain.clone();
+                let options = options.clone();
+                let active = Active::new(drain.clone());
+                pool.execute(move || {
+                    let _active = active;
+                    if let Some(ref metrics) = options.metrics {
+                        metrics.connection_opened();
+                    }
+                    handle_connection(stream, &*handler, &drain, &options);
+                    if let Some(ref metrics) = options.metrics {
+                        metrics.connection_closed();
+                    }
+                });
+            },
+            Err(ref e) if e.kind == EndOfFile => {
+                debug!("server closed");
+                break;
+            },
+            Err(e) => {
+                error!("Connection failed: {}", e);
+                continue;
+            }
+        }
+    }
+
+    // let the connections finish their current requests
+    let connections = drain.lock();
+    while connections.active > 0 {
+        drain.finished.wait(&connections);
+    }
+}
+
+/// The connections of a `Listening`, so they can be drained when it closes.
+struct Drain {
+    connections: Mutex<Connections>,
+    // signalled when the last active connection is done
+    finished: Condvar
+}
+
+impl Drain {
+    fn new() -> Drain {
+        Drain {
+            connections: Mutex::new(Connections {
+                closing: false,
+                active: 0,
+                next_id: 0,
+                idle: HashMap::new()
+            }),
+            finished: Condvar::new()
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<Connections> {
+        self.connections.lock()
+    }
+}
+
+struct Connections {
+    closing: bool,
+    active: uint,
+    next_id: uint,
+    // clones of the connections waiting for their next request
+    idle: HashMap<uint, Box<NetworkStream + Send>>
+}
+
+impl Connections {
+
+    fn close(&mut self) {
+        self.closing = true;
+        for (_, stream) in self.idle.iter_mut() {
+            let _ = stream.close_read();
+        }
+        self.idle.clear();
+    }
+}
+
+/// Counts a connection as active until it is dropped, which happens even if
+/// the handler panics.
+struct Active(Arc<Drain>);
+
+impl Active {
+    fn new(drain: Arc<Drain>) -> Active {
+        drain.lock().active += 1;
+        Active(drain)
+    }
+}
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        let mut connections = self.0.lock();
+        connections.active -= 1;
+        if connections.active == 0 {
+            self.0.finished.notify_all();
+        }
+    }
+}
+
+/// Sends `Connection: close` if the server started closing while the
+/// response was being handled.
+struct CloseOnDrain(Arc<Drain>);
+
+impl StartHook for CloseOnDrain {
+    fn on_start(&mut self, res: &mut Response<Fresh>) {
+        if self.0.lock().closing {
+            res.headers_mut().set(Connection(vec![Close]));
+        }
+    }
+}
+
+/// What the connections of a `Server` are handled with.
+#[deriving(Clone)]
+struct Options {
+    metrics: Option<Metrics>,
+    server_header: Option<String>,
+    write_timeout: Option<Duration>,
+    parse_mode: ParseMode,
+    chunk_limits: ChunkLimits,
+    error_handler: Option<Arc<Box<ErrorHandler + Send + Sync>>>
+}
+
+impl Options {
+    /// Give an error ending the connection to `addr` to the error handler,
+    /// or log it if there is none.
+    fn report(&self, addr: SocketAddr, err: HttpError) {
+        match self.error_handler {
+            Some(ref handler) => handler.on_error(addr, err),
+            None => error!("connection to {} failed: {}", addr, err)
+        }
+    }
+}
+
+/// Sets the `Server` header, if the handler did not.
+struct SetServer(String);
+
+impl StartHook for SetServer {
+    fn on_start(&mut self, res: &mut Response<Fresh>) {
+        if !res.headers().has::<header::common::Server>() {
+            let value = mem::replace(&mut self.0, String::new());
+            res.headers_mut().set(header::common::Server(value));
+        }
+    }
+}
+
+/// Remembers the status a response was started with, for the `Metrics`, and
+/// so a connection that switched protocols is not read for another request.
+struct WatchStatus(Arc<AtomicUint>);
+
+impl StartHook for WatchStatus {
+    fn on_start(&mut self, res: &mut Response<Fresh>) {
+        self.0.store(res.status() as uint, SeqCst);
+    }
+}
+
+/// A stream counting the bytes read from or written to it.
+struct Counted<S> {
+    inner: S,
+    count: u64
+}
+
+impl<S> Counted<S> {
+    fn new(inner: S) -> Counted<S> {
+        Counted {
+            inner: inner,
+            count: 0
+        }
+    }
+
+    /// The bytes counted since the last call.
+    fn take_count(&mut self) -> u64 {
+        mem::replace(&mut self.count, 0)
+    }
+}
+
+impl<S: Reader> Reader for Counted<S> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        let n = try!(self.inner.read(buf));
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+impl<S: Writer> Writer for Counted<S> {
+    fn write(&mut self, msg: &[u8]) -> IoResult<()> {
+        try!(self.inner.write(msg));
+        self.count += msg.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.inner.flush()
+    }
+}
+
+/// A stream failing any write that blocks for longer than a timeout.
+///
+/// Once a write timed out, every later write fails right away, so the
+/// connection can be dropped without blocking on a flush again.
+struct Deadline<S> {
+    inner: S,
+    timeout_ms: Option<u64>,
+    timed_out: bool
+}
+
+impl<S: NetworkStream> Writer for Deadline<S> {
+    fn write(&mut self, msg: &[u8]) -> IoResult<()> {
+        if self.timed_out {
+            return Err(standard_error(TimedOut));
+        }
+        if self.timeout_ms.is_some() {
+            // the timeout counts from when it is set
+            self.inner.set_write_timeout(self.timeout_ms);
+        }
+        match self.inner.write(msg) {
+            Err(e) => {
+                self.timed_out = e.kind == TimedOut;
+                Err(e)
+            },
+            ok => ok
+        }
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        if self.timed_out {
+            return Err(standard_error(TimedOut));
+        }
+        self.inner.flush()
+    }
+}
+
+fn handle_connection<S, H>(mut stream: S, handler: &H, drain: &Arc<Drain>,
+                           options: &Options)
+where S: NetworkStream + Clone,
+      H: Handler {
+    let addr = match stream.peer_name() {
+        Ok(addr) => addr,
+        Err(e) => {
+            error!("Peer Name error: {}", e);
+            return;
+        }
+    };
+    let raw = stream.clone();
+    let certificate = raw.peer_certificate().and_then(Certificate::from_pem);
+    let mut rdr = BufferedReader::new(Counted::new(stream.clone()));
+    let mut wrt = BufferedWriter::new(Counted::new(Deadline {
+        inner: stream,
+        timeout_ms: options.write_timeout.map(|timeout| timeout.num_milliseconds() as u64),
+        timed_out: false
+    }));
+    let connection = Rc::new(RefCell::new(Extensions::new()));
+    let id = {
+        let mut drain = drain.lock();
+        drain.next_id += 1;
+        drain.next_id
+    };
+
+    let mut keep_alive = true;
+    while keep_alive {
+        {
+            let mut drain = drain.lock();
+            if drain.closing {
+                debug!("server closing, dropping idle connection");
+                return;
+            }
+            drain.idle.insert(id, box raw.clone() as Box<NetworkStream + Send>);
+        }
+        let status = Arc::new(AtomicUint::new(0));
+        let closes = Arc::new(AtomicBool::new(false));
+        let started;
+        {
+            let req = Request::with_parse_mode(&mut rdr, addr, options.parse_mode);
+            drain.lock().idle.remove(&id);
+
+            let mut res = Response::new(&mut wrt);
+            let mut req = match req {
+                Ok(mut req) => {
+                    req.set_chunk_limits(options.chunk_limits);
+                    req
+                },
+                Err(e@HttpIoError(_)) => {
+                    debug!("ioerror in keepalive loop = {}", e);
+                    return;
+                }
+                Err(e) => {
+                    //TODO: send a 400 response
+                    options.report(addr, e);
+                    return;
+                }
+            };
+            started = precise_time_s();
+
+            // HTTP/1.0 connections only persist if the client asks for it
+            keep_alive = should_keep_alive(req.version, &req.headers, req.method != Connect);
+            if req.method == Connect {
+                // a tunnel owns the rest of the connection
+                req.set_stream(box raw.clone() as Box<NetworkStream + Send>);
+                keep_alive = false;
+            } else if req.is_upgrade() {
+                // and so does another protocol, if the handler switches to it
+                req.set_stream(box raw.clone() as Box<NetworkStream + Send>);
+            }
+            req.set_peer_certificate(certificate.clone());
+            req.set_connection_extensions(connection.clone());
+            res.version = req.version;
+            if keep_alive && req.version == Http10 {
+                res.headers_mut().set(Connection(vec![KeepAlive]));
+            }
+            res.set_close_flag(closes.clone());
+            res.add_start_hook(box CloseOnDrain(drain.clone()));
+            res.add_start_hook(box WatchStatus(status.clone()));
+            if let Some(ref value) = options.server_header {
+                res.add_start_hook(box SetServer(value.clone()));
+            }
+            handler.handle(req, res);
+        }
+
+        let status = status.load(SeqCst);
+        if status == SwitchingProtocols as uint || closes.load(SeqCst) {
+            keep_alive = false;
+        }
+        let (read, written) = (rdr.get_mut().take_count(), wrt.get_mut().take_count());
+        if let Some(ref metrics) = options.metrics {
+            metrics.record_request(status, precise_time_s() - started, read, written);
+        }
+        if wrt.get_ref().inner.timed_out {
+            debug!("writing the response to {} timed out, closing the connection", addr);
+            options.report(addr, HttpIoError(standard_error(TimedOut)));
+            return;
+        }
+        debug!("keep_alive = {}", keep_alive);
+    }
+}
+
 /// A listening server, which can later be closed.
 pub struct Listening<A = HttpAcceptor> {
-    acceptor: A,
-    guard: Option<JoinGuard<()>>,
-    /// The socket addresses that the server is bound to.
+    acceptors: Vec<A>,
+    guards: Vec<JoinGuard<()>>,
+    drain: Arc<Drain>,
+    /// The socket address that the server was first bound to.
     pub socket: SocketAddr,
+    /// All of the socket addresses that the server is bound to.
+    pub sockets: Vec<SocketAddr>,
 }
 
 impl<A: NetworkAcceptor<S>, S: NetworkStream> Listening<A> {
     /// Causes the current thread to wait for this listening to complete.
+    ///
+    /// After `close`, this waits for the connections to finish the requests
+    /// they are handling.
     pub fn await(&mut self) {
-        if let Some(guard) = self.guard.take() {
+        for guard in self.guards.drain() {
             let _ = guard.join();
         }
     }
 
-    /// Stop the server from listening to its socket address.
+    /// Stop the server from listening to its socket addresses.
+    ///
+    /// Connections waiting for a request are closed. Connections handling a
+    /// request finish it, send `Connection: close` if the response was not
+    /// started yet, and are closed afterwards.
+    ///
+    /// All the acceptors are closed even if closing one fails, and the first
+    /// error is returned.
     pub fn close(&mut self) -> HttpResult<()> {
         debug!("closing server");
-        try!(self.acceptor.close());
-        Ok(())
+        self.drain.lock().close();
+        // close every acceptor, even if one of them fails
+        let mut result = Ok(());
+        for acceptor in self.acceptors.iter_mut() {
+            if let Err(e) = acceptor.close() {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result.map_err(HttpIoError)
     }
 }
 
@@ -204,3 +733,112 @@ impl<F> Handler for F where F: Fn(Request, Response<Fresh>), F: Sync + Send {
     }
 }
 
+/// A handler for the errors that end the connections of a server, given
+/// with `Server::on_error`.
+pub trait ErrorHandler: Sync + Send {
+    /// Receives the address of the client, and the error.
+    fn on_error(&self, SocketAddr, HttpError);
+}
+
+impl<F> ErrorHandler for F where F: Fn(SocketAddr, HttpError), F: Sync + Send {
+    fn on_error(&self, addr: SocketAddr, err: HttpError) {
+        (*self)(addr, err)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::TimedOut;
+    use std::io::net::ip::{Ipv4Addr, SocketAddr};
+    use std::io::net::tcp::TcpStream;
+    use std::io::timer::sleep;
+    use std::sync::atomic::{AtomicUint, INIT_ATOMIC_UINT, SeqCst};
+    use std::time::Duration;
+
+    use HttpError;
+    use HttpError::HttpIoError;
+    use client::Client;
+    use net::Fresh;
+    use testing::serve;
+    use super::{Request, Response, Server};
+    use super::metrics::Metrics;
+
+    fn ok(_: Request, res: Response<Fresh>) {
+        res.start().unwrap().end().unwrap();
+    }
+
+    fn panics(_: Request, _: Response<Fresh>) {
+        panic!("handler panicked");
+    }
+
+    #[test]
+    fn test_close_after_handler_panics() {
+        let server = serve(panics).unwrap();
+        assert!(Client::new().get(server.url("/")[]).send().is_err());
+        // closes the server, and would wait forever if the panicked
+        // connection were still counted as active
+        drop(server);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let metrics = Metrics::new();
+        let mut listening = Server::http(Ipv4Addr(127, 0, 0, 1), 0).metrics(metrics.clone())
+            .listen_threads(ok, 1).unwrap();
+        let url = format!("http://{}/", listening.socket);
+        Client::new().get(url[]).send().unwrap().read_to_end().unwrap();
+        listening.close().unwrap();
+        listening.await();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.success, 1);
+        assert_eq!(snapshot.requests(), 1);
+        assert_eq!(snapshot.connections, 1);
+        assert_eq!(snapshot.active_connections, 0);
+    }
+
+    static TIMEOUTS: AtomicUint = INIT_ATOMIC_UINT;
+
+    fn count_timeouts(_: SocketAddr, err: HttpError) {
+        if let HttpIoError(ref e) = err {
+            if e.kind == TimedOut {
+                TIMEOUTS.fetch_add(1, SeqCst);
+            }
+        }
+    }
+
+    fn large(_: Request, res: Response<Fresh>) {
+        let mut res = res.start().unwrap();
+        let chunk = [b'x', ..65536];
+        for _ in range(0u, 1024) {
+            if res.write(chunk[]).is_err() {
+                return;
+            }
+        }
+        let _ = res.end();
+    }
+
+    #[test]
+    fn test_write_timeout_reported() {
+        let mut listening = Server::http(Ipv4Addr(127, 0, 0, 1), 0)
+            .write_timeout(Duration::milliseconds(100))
+            .on_error(count_timeouts)
+            .listen_threads(large, 1).unwrap();
+        let mut client = TcpStream::connect(listening.socket).unwrap();
+        client.write(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+        // the response is never read, so writing it stalls
+        for _ in range(0u, 500) {
+            if TIMEOUTS.load(SeqCst) > 0 {
+                break;
+            }
+            sleep(Duration::milliseconds(10));
+        }
+        assert_eq!(TIMEOUTS.load(SeqCst), 1);
+
+        drop(client);
+        listening.close().unwrap();
+        listening.await();
+    }
+}
@@ -0,0 +1,342 @@
+//! Streaming `multipart/form-data` parsing.
+//!
+//! Parts are read one at a time, and each part body is a `Reader`, so large
+//! uploads can be copied to disk without ever being held in memory.
+//!
+//! ```no_run
+//! # use hyper::server::{Request, Response};
+//! use std::io::File;
+//! use std::io::util::copy;
+//! use hyper::server::multipart::Multipart;
+//!
+//! fn upload(req: Request, res: Response) {
+//!     let mut multipart = Multipart::from_request(req).unwrap();
+//!     let mut n = 0u;
+//!     while let Some(mut part) = multipart.read_part().unwrap() {
+//!         let mut file = File::create(&Path::new(format!("upload-{}", n)));
+//!         copy(&mut part, &mut file).unwrap();
+//!         n += 1;
+//!     }
+//!     res.start().unwrap().end().unwrap();
+//! }
+//! ```
+use std::cmp::min;
+use std::io::{mod, IoResult, IoError, EndOfFile, InvalidInput};
+use std::slice::bytes::copy_memory;
+
+use header::Headers;
+use header::common::{ContentDisposition, ContentType};
+use mime::Mime;
+use mime_guess;
+use http::{CR, LF};
+use server::Request;
+use HttpResult;
+use HttpError::{HttpHeaderError, HttpIoError};
+
+use self::State::{Preamble, Body, Boundary, Done};
+
+/// How much is read from the source at a time.
+const READ_SIZE: uint = 4096;
+
+#[deriving(PartialEq, Show)]
+enum State {
+    Preamble,
+    Body,
+    Boundary,
+    Done
+}
+
+/// A streaming reader of the parts of a multipart body.
+pub struct Multipart<R> {
+    source: R,
+    buf: Vec<u8>,
+    pos: uint,
+    eof: bool,
+    delimiter: Vec<u8>,
+    state: State,
+}
+
+impl<R: Reader> Multipart<R> {
+    /// Create a reader of a multipart body, delimited by `boundary`.
+    pub fn new(source: R, boundary: &str) -> Multipart<R> {
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.push_all(boundary.as_bytes());
+        Multipart {
+            source: source,
+            // the first delimiter is not preceded by a CRLF, but pretending it
+            // was lets every delimiter be found the same way
+            buf: b"\r\n".to_vec(),
+            pos: 0,
+            eof: false,
+            delimiter: delimiter,
+            state: Preamble,
+        }
+    }
+
+    /// Read the headers of the next part, and get a `Reader` over its body.
+    ///
+    /// Any unread body of the previous part is skipped. Returns `None` once
+    /// the closing boundary has been read.
+    pub fn read_part<'a>(&'a mut self) -> HttpResult<Option<Part<'a, R>>> {
+        let mut skip = [0u8, ..READ_SIZE];
+        while self.state == Preamble || self.state == Body {
+            match self.read_body(&mut skip) {
+                Ok(..) => (),
+                Err(ref e) if e.kind == EndOfFile => (),
+                Err(e) => return Err(HttpIoError(e))
+            }
+        }
+
+        if self.state == Done {
+            return Ok(None);
+        }
+
+        let headers = {
+            let mut head = Head(self);
+            let mut b = try!(head.read_byte());
+            if b == b'-' {
+                if try!(head.read_byte()) != b'-' {
+                    return Err(HttpIoError(invalid("Invalid multipart boundary")));
+                }
+                None
+            } else {
+                // transport padding
+                while b == b' ' || b == b'\t' {
+                    b = try!(head.read_byte());
+                }
+                if b != CR || try!(head.read_byte()) != LF {
+                    return Err(HttpIoError(invalid("Invalid multipart boundary")));
+                }
+                Some(try!(Headers::from_raw(&mut head)))
+            }
+        };
+
+        match headers {
+            Some(headers) => {
+                self.state = Body;
+                Ok(Some(Part {
+                    headers: headers,
+                    multipart: self
+                }))
+            },
+            None => {
+                debug!("multipart closing boundary");
+                self.state = Done;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Unwraps this Multipart and returns the underlying Reader.
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    fn fill(&mut self, wanted: uint) -> IoResult<()> {
+        if self.buf.len() - self.pos >= wanted || self.eof {
+            return Ok(());
+        }
+        if self.pos > 0 {
+            self.buf = self.buf[self.pos..].to_vec();
+            self.pos = 0;
+        }
+        let mut chunk = [0u8, ..READ_SIZE];
+        while self.buf.len() < wanted && !self.eof {
+            match self.source.read(&mut chunk) {
+                Ok(n) => self.buf.push_all(chunk[..n]),
+                Err(ref e) if e.kind == EndOfFile => self.eof = true,
+                Err(e) => return Err(e)
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn buffered(&self) -> &[u8] {
+        self.buf[self.pos..]
+    }
+
+    fn take(&mut self, out: &mut [u8], n: uint) -> uint {
+        let n = min(n, out.len());
+        copy_memory(out, self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        n
+    }
+
+    fn read_body(&mut self, out: &mut [u8]) -> IoResult<uint> {
+        match self.state {
+            Preamble | Body => (),
+            _ => return Err(io::standard_error(EndOfFile))
+        }
+
+        let dlen = self.delimiter.len();
+        try!(self.fill(dlen));
+        let available = match find(self.buffered(), self.delimiter[]) {
+            Some(0) => {
+                self.pos += dlen;
+                self.state = Boundary;
+                return Err(io::standard_error(EndOfFile));
+            },
+            Some(idx) => idx,
+            None if self.eof => {
+                return Err(invalid("Multipart body ended without a closing boundary"));
+            },
+            // the end of the buffer could be the start of a delimiter
+            None => self.buffered().len() - (dlen - 1)
+        };
+        Ok(self.take(out, available))
+    }
+}
+
+/// A single part of a multipart body.
+///
+/// The part is a `Reader` over its own body.
+pub struct Part<'a, R: 'a> {
+    /// The headers of this part.
+    pub headers: Headers,
+    multipart: &'a mut Multipart<R>,
+}
+
+impl<'a, R: Reader> Part<'a, R> {
+    /// The media type of this part, from its `Content-Type`, or guessed from
+    /// the `filename` of its `Content-Disposition` without one.
+    pub fn content_type(&self) -> Option<Mime> {
+        match self.headers.get::<ContentType>() {
+            Some(&ContentType(ref mime)) => Some(mime.clone()),
+            None => self.headers.get::<ContentDisposition>()
+                .and_then(|disposition| disposition.filename())
+                .and_then(|filename| mime_guess::guess(&Path::new(filename)))
+        }
+    }
+}
+
+impl<'a, R: Reader> Reader for Part<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        self.multipart.read_body(buf)
+    }
+}
+
+impl<'a> Multipart<Request<'a>> {
+    /// Create a Multipart reader over the body of a `multipart/*` request.
+    ///
+    /// The boundary is taken from the `Content-Type` header. Returns an
+    /// `HttpHeaderError` if it is not a multipart type, or has no boundary.
+    pub fn from_request(req: Request<'a>) -> HttpResult<Multipart<Request<'a>>> {
+        let boundary = match req.headers.get::<ContentType>() {
+            Some(content_type) if (content_type.0).0.to_string()[] == "multipart" => {
+                content_type.boundary()
+            },
+            _ => None
+        };
+        match boundary {
+            Some(boundary) => Ok(Multipart::new(req, boundary[])),
+            None => Err(HttpHeaderError)
+        }
+    }
+}
+
+struct Head<'a, R: 'a>(&'a mut Multipart<R>);
+
+impl<'a, R: Reader> Reader for Head<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        try!(self.0.fill(1));
+        let available = self.0.buffered().len();
+        if available == 0 {
+            return Err(io::standard_error(EndOfFile));
+        }
+        Ok(self.0.take(buf, available))
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<uint> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn invalid(desc: &'static str) -> IoError {
+    IoError {
+        kind: InvalidInput,
+        desc: desc,
+        detail: None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::MemReader;
+    use super::Multipart;
+
+    fn mem(s: &str) -> MemReader {
+        MemReader::new(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_read_parts() {
+        let body = "preamble\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"a\"\r\n\
+            \r\n\
+            first value\r\n\
+            --XyZ  \r\n\
+            Content-Disposition: form-data; name=\"b\"; filename=\"b.txt\"\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            line one\r\n--not the boundary\r\nline two\r\n\
+            --XyZ--\r\n\
+            epilogue";
+        let mut multipart = Multipart::new(mem(body), "XyZ");
+
+        {
+            let mut part = multipart.read_part().unwrap().unwrap();
+            assert_eq!(part.headers.get_raw("content-disposition").unwrap(),
+                       [b"form-data; name=\"a\"".to_vec()][]);
+            assert_eq!(part.read_to_string().unwrap()[], "first value");
+        }
+        {
+            let mut part = multipart.read_part().unwrap().unwrap();
+            assert_eq!(part.headers.get_raw("content-type").unwrap(), [b"text/plain".to_vec()][]);
+            assert_eq!(part.read_to_string().unwrap()[],
+                       "line one\r\n--not the boundary\r\nline two");
+        }
+        assert!(multipart.read_part().unwrap().is_none());
+        assert!(multipart.read_part().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_part_content_type() {
+        let body = "--b\r\n\
+            Content-Disposition: form-data; name=\"photo\"; filename=\"cat.PNG\"\r\n\
+            \r\n\
+            png\r\n\
+            --b\r\n\
+            Content-Disposition: form-data; name=\"notes\"; filename=\"notes.html\"\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            text\r\n\
+            --b\r\n\
+            Content-Disposition: form-data; name=\"field\"\r\n\
+            \r\n\
+            value\r\n\
+            --b--";
+        let mut multipart = Multipart::new(mem(body), "b");
+        assert_eq!(multipart.read_part().unwrap().unwrap().content_type(), "image/png".parse());
+        assert_eq!(multipart.read_part().unwrap().unwrap().content_type(), "text/plain".parse());
+        assert_eq!(multipart.read_part().unwrap().unwrap().content_type(), None);
+    }
+
+    #[test]
+    fn test_skip_unread_part() {
+        let body = "--b\r\n\r\nskipped\r\n--b\r\n\r\nread\r\n--b--";
+        let mut multipart = Multipart::new(mem(body), "b");
+        assert!(multipart.read_part().unwrap().is_some());
+        let mut part = multipart.read_part().unwrap().unwrap();
+        assert_eq!(part.read_to_string().unwrap()[], "read");
+    }
+
+    #[test]
+    fn test_missing_close() {
+        let body = "--b\r\n\r\nnever ends";
+        let mut multipart = Multipart::new(mem(body), "b");
+        let mut part = multipart.read_part().unwrap().unwrap();
+        assert!(part.read_to_string().is_err());
+    }
+}
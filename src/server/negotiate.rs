@@ -0,0 +1,202 @@
+//! Server-driven content negotiation.
+//!
+//! `negotiate` weighs the representations a handler has of a resource
+//! against the `Accept`, `Accept-Language` and `Accept-Encoding` headers of a
+//! request, and says which to send, and what `Vary` header the response
+//! needs for caches to tell the representations apart.
+//!
+//! ```
+//! # use hyper::header::Headers;
+//! use hyper::server::negotiate::{negotiate, Representation};
+//!
+//! let available = [
+//!     Representation::new("text/html".parse().unwrap()).language("en"),
+//!     Representation::new("text/html".parse().unwrap()).language("de"),
+//! ];
+//! let mut headers = Headers::new();
+//! headers.set_raw("Accept-Language", vec![b"de, en;q=0.5".to_vec()]);
+//!
+//! let negotiation = negotiate(&headers, &available);
+//! assert_eq!(negotiation.best, Some(&available[1]));
+//! assert!(negotiation.vary.unwrap().contains("Accept-Language"));
+//! ```
+use mime::Mime;
+
+use header::Headers;
+use header::common::{Accept, AcceptEncoding, AcceptLanguage, Vary};
+use header::shared::Encoding;
+use header::shared::Encoding::Identity;
+
+/// One way a server can send a resource.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Representation {
+    /// The media type, sent as `Content-Type`.
+    pub media_type: Mime,
+    /// The language tag, sent as `Content-Language`, if the content has one.
+    pub language: Option<String>,
+    /// The content coding, sent as `Content-Encoding`. `identity` is none.
+    pub encoding: Encoding,
+}
+
+impl Representation {
+    /// A representation of a media type, without a language or coding.
+    pub fn new(media_type: Mime) -> Representation {
+        Representation {
+            media_type: media_type,
+            language: None,
+            encoding: Identity
+        }
+    }
+
+    /// Set the language tag of this representation.
+    pub fn language(mut self, tag: &str) -> Representation {
+        self.language = Some(tag.to_string());
+        self
+    }
+
+    /// Set the content coding of this representation.
+    pub fn encoding(mut self, encoding: Encoding) -> Representation {
+        self.encoding = encoding;
+        self
+    }
+}
+
+/// The outcome of `negotiate`.
+#[deriving(Show)]
+pub struct Negotiation<'a> {
+    /// The representation the client prefers, or `None` if it accepts none
+    /// of them, which is usually answered with `406 Not Acceptable`.
+    pub best: Option<&'a Representation>,
+    /// The `Vary` header the response should have, naming the headers that
+    /// the representations differ in. `None` if there is only one kind.
+    pub vary: Option<Vary>,
+}
+
+impl<'a> Negotiation<'a> {
+    /// Add the names in `vary` to the `Vary` header of `headers`.
+    pub fn set_vary(&self, headers: &mut Headers) {
+        if let Some(Vary::Headers(ref names)) = self.vary {
+            for name in names.iter() {
+                Vary::add_to(headers, name.as_slice());
+            }
+        }
+    }
+}
+
+/// Pick which of the `available` representations the request prefers.
+///
+/// The quality of a representation is that of its media type, language,
+/// and coding multiplied, where a header the request doesn't have accepts
+/// everything. A representation without a language is acceptable in any.
+/// When several are preferred equally, the first of them is picked, so
+/// `available` should be in the order the server prefers.
+pub fn negotiate<'a>(headers: &Headers, available: &'a [Representation]) -> Negotiation<'a> {
+    let accept = headers.get::<Accept>();
+    let accept_language = headers.get::<AcceptLanguage>();
+    let accept_encoding = headers.get::<AcceptEncoding>();
+
+    let mut best = None;
+    for representation in available.iter() {
+        let quality = accept.map_or(1.0, |accept| accept.quality(&representation.media_type)) *
+            match (accept_language, &representation.language) {
+                (Some(accept_language), &Some(ref tag)) => accept_language.quality(tag[]),
+                _ => 1.0
+            } *
+            accept_encoding.map_or(1.0, |accept_encoding| {
+                accept_encoding.quality(&representation.encoding)
+            });
+        if quality > 0.0 && best.map_or(true, |(_, most)| quality > most) {
+            best = Some((representation, quality));
+        }
+    }
+
+    let mut vary = Vary::Headers(Vec::new());
+    if differ(available, |a, b| a.media_type == b.media_type) {
+        vary.add("Accept");
+    }
+    if differ(available, |a, b| a.language == b.language) {
+        vary.add("Accept-Language");
+    }
+    if differ(available, |a, b| a.encoding == b.encoding) {
+        vary.add("Accept-Encoding");
+    }
+
+    Negotiation {
+        best: best.map(|(representation, _)| representation),
+        vary: match vary {
+            Vary::Headers(ref names) if names.is_empty() => None,
+            vary => Some(vary)
+        }
+    }
+}
+
+/// Whether some of the representations are not the `same` as the first.
+fn differ(available: &[Representation], same: |&Representation, &Representation| -> bool) -> bool {
+    match available.first() {
+        Some(first) => available.iter().any(|representation| !same(representation, first)),
+        None => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Headers;
+    use header::common::Vary;
+    use header::shared::Encoding::Gzip;
+    use super::{negotiate, Representation};
+
+    fn headers(raw: &[(&str, &str)]) -> Headers {
+        let mut headers = Headers::new();
+        for &(name, value) in raw.iter() {
+            headers.set_raw(name.to_string(), vec![value.as_bytes().to_vec()]);
+        }
+        headers
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let available = [
+            Representation::new("application/json".parse().unwrap()),
+            Representation::new("text/html".parse().unwrap()).language("en"),
+            Representation::new("text/html".parse().unwrap()).language("fr").encoding(Gzip),
+        ];
+
+        let negotiation = negotiate(&headers(&[]), &available);
+        assert_eq!(negotiation.best, Some(&available[0]));
+        let vary = negotiation.vary.unwrap();
+        assert!(vary.contains("Accept") && vary.contains("Accept-Language") &&
+                vary.contains("Accept-Encoding"));
+
+        let negotiation = negotiate(&headers(&[("Accept", "text/html"),
+                                              ("Accept-Language", "fr, en;q=0.5"),
+                                              ("Accept-Encoding", "gzip")]), &available);
+        assert_eq!(negotiation.best, Some(&available[2]));
+
+        // the client can't decode gzip, so gets the English page instead
+        let negotiation = negotiate(&headers(&[("Accept", "text/html"),
+                                              ("Accept-Language", "fr, en;q=0.5"),
+                                              ("Accept-Encoding", "gzip;q=0")]), &available);
+        assert_eq!(negotiation.best, Some(&available[1]));
+
+        let negotiation = negotiate(&headers(&[("Accept", "image/png")]), &available);
+        assert_eq!(negotiation.best, None);
+        assert!(negotiation.vary.is_some());
+    }
+
+    #[test]
+    fn test_vary() {
+        let available = [Representation::new("text/plain".parse().unwrap())];
+        assert!(negotiate(&headers(&[]), &available).vary.is_none());
+
+        let available = [
+            Representation::new("text/plain".parse().unwrap()),
+            Representation::new("text/plain".parse().unwrap()).encoding(Gzip),
+        ];
+        let negotiation = negotiate(&headers(&[]), &available);
+        let mut res_headers = headers(&[("Vary", "Cookie")]);
+        negotiation.set_vary(&mut res_headers);
+        let vary = res_headers.get::<Vary>().unwrap();
+        assert!(vary.contains("Cookie") && vary.contains("Accept-Encoding"));
+        assert!(!vary.contains("Accept"));
+    }
+}
@@ -0,0 +1,244 @@
+//! A reverse proxy `Handler`.
+//!
+//! Incoming requests are forwarded to an upstream origin using the hyper
+//! `client`, and the upstream response is streamed back to the client.
+use std::ascii::AsciiExt;
+use std::io::net::ip::IpAddr;
+use std::io::util::copy;
+
+use url::Url;
+
+use client;
+use header::Headers;
+use header::common::{Connection, Forwarded, Host, XForwardedFor};
+use header::common::forwarded::node_ip;
+use net::Fresh;
+use server::{Handler, Request, Response};
+use status::StatusCode;
+use status::StatusCode::{BadGateway, BadRequest};
+use uri::RequestUri::{AbsolutePath, AbsoluteUri};
+use HttpResult;
+
+/// Headers that only apply to a single connection, and must not be forwarded.
+///
+/// See https://tools.ietf.org/html/rfc7230#section-6.1
+static HOP_BY_HOP: [&'static str, ..8] = [
+    "Connection",
+    "Keep-Alive",
+    "Proxy-Authenticate",
+    "Proxy-Authorization",
+    "TE",
+    "Trailer",
+    "Transfer-Encoding",
+    "Upgrade",
+];
+
+/// A `Handler` that forwards every request to an upstream server.
+///
+/// The `Host` header is rewritten to the upstream host, the client address is
+/// appended to `X-Forwarded-For`, and hop-by-hop headers are stripped in both
+/// directions. Request and response bodies are streamed, not buffered.
+///
+/// ```no_run
+/// # use hyper::Server;
+/// # use hyper::Ipv4Addr;
+/// # use hyper::Url;
+/// use hyper::server::proxy::ReverseProxy;
+///
+/// let proxy = ReverseProxy::new(Url::parse("http://127.0.0.1:3000/app").unwrap());
+/// Server::http(Ipv4Addr(0, 0, 0, 0), 8080).listen(proxy).unwrap();
+/// ```
+pub struct ReverseProxy {
+    prefix: String
+}
+
+impl ReverseProxy {
+    /// Create a proxy forwarding to the `upstream` origin.
+    ///
+    /// The path of `upstream`, if any, is prepended to the path of every
+    /// forwarded request.
+    pub fn new(upstream: Url) -> ReverseProxy {
+        let mut prefix = format!("{}://{}", upstream.scheme,
+                                 upstream.serialize_host().unwrap_or(String::new()));
+        if let Some(port) = upstream.port() {
+            prefix.push_str(format!(":{}", port)[]);
+        }
+        if let Some(path) = upstream.serialize_path() {
+            prefix.push_str(path[].trim_right_chars('/'));
+        }
+        ReverseProxy {
+            prefix: prefix
+        }
+    }
+
+    fn upstream_url(&self, req: &Request) -> Option<Url> {
+        let path = match req.uri {
+            AbsolutePath(ref path) => path.clone(),
+            AbsoluteUri(ref url) => {
+                let mut path = url.serialize_path().unwrap_or("/".to_string());
+                if let Some(ref query) = url.query {
+                    path.push('?');
+                    path.push_str(query[]);
+                }
+                path
+            },
+            _ => return None
+        };
+        Url::parse(format!("{}{}", self.prefix, path)[]).ok()
+    }
+}
+
+impl Handler for ReverseProxy {
+    fn handle(&self, mut req: Request, mut res: Response<Fresh>) {
+        let url = match self.upstream_url(&req) {
+            Some(url) => url,
+            None => return respond(res, BadRequest)
+        };
+
+        let mut upstream = match forward(url, &mut req) {
+            Ok(upstream) => upstream,
+            Err(e) => {
+                error!("reverse proxy upstream error: {}", e);
+                return respond(res, BadGateway);
+            }
+        };
+
+        *res.status_mut() = upstream.status;
+        res.headers_mut().extend(upstream.headers.iter().filter(|header| {
+            !is_hop_by_hop(header.name(), &upstream.headers)
+        }));
+
+        let mut res = match res.start() {
+            Ok(res) => res,
+            Err(e) => {
+                error!("reverse proxy response error: {}", e);
+                return;
+            }
+        };
+        match copy(&mut upstream, &mut res) {
+            Ok(..) => (),
+            Err(e) => {
+                error!("reverse proxy body error: {}", e);
+                return;
+            }
+        }
+        if let Err(e) = res.end() {
+            error!("reverse proxy response error: {}", e);
+        }
+    }
+}
+
+fn forward(url: Url, req: &mut Request) -> HttpResult<client::Response> {
+    debug!("reverse proxy {} {}", req.method, url);
+    let mut outgoing = try!(client::Request::new(req.method.clone(), url));
+
+    // the client already set the Host header for the upstream
+    outgoing.headers_mut().extend(req.headers.iter().filter(|header| {
+        !header.is::<Host>() && !is_hop_by_hop(header.name(), &req.headers)
+    }));
+
+    let mut forwarded_for = match req.headers.get::<XForwardedFor>() {
+        Some(&XForwardedFor(ref nodes)) => nodes.clone(),
+        None => Vec::new()
+    };
+    forwarded_for.push(req.remote_addr.ip.to_string());
+    outgoing.headers_mut().set(XForwardedFor(forwarded_for));
+
+    let mut outgoing = try!(outgoing.start());
+    try!(copy(req, &mut outgoing));
+    outgoing.send()
+}
+
+fn respond(mut res: Response<Fresh>, status: StatusCode) {
+    *res.status_mut() = status;
+    if let Err(e) = res.start().and_then(|res| res.end()) {
+        error!("reverse proxy response error: {}", e);
+    }
+}
+
+/// Whether a header should be dropped when forwarding a message.
+///
+/// Besides the standard hop-by-hop headers, any header named by the
+/// `Connection` header is also hop-by-hop.
+pub fn is_hop_by_hop(name: &str, headers: &Headers) -> bool {
+    if HOP_BY_HOP.iter().any(|hop| hop.eq_ignore_ascii_case(name)) {
+        return true;
+    }
+    headers.get::<Connection>().map_or(false, |conn| conn.has(name))
+}
+
+/// The address of the client that made a request, when the peer at
+/// `remote` may be one of the `trusted` proxies.
+///
+/// If the peer is not trusted, it is the client. Otherwise the nodes in
+/// `Forwarded`, or else in `X-Forwarded-For`, are walked back from the most
+/// recent, skipping trusted proxies, since anything before the first
+/// untrusted node could have been forged by the client. An unknown or
+/// obfuscated node stops the walk at the last address known.
+pub fn client_ip(remote: IpAddr, headers: &Headers, trusted: &[IpAddr]) -> IpAddr {
+    if !trusted.contains(&remote) {
+        return remote;
+    }
+    let nodes: Vec<String> = match headers.get::<Forwarded>() {
+        Some(forwarded) => forwarded.iter().filter_map(|element| {
+            element.forwarded_for().map(|node| node.to_string())
+        }).collect(),
+        None => match headers.get::<XForwardedFor>() {
+            Some(&XForwardedFor(ref nodes)) => nodes.clone(),
+            None => Vec::new()
+        }
+    };
+
+    let mut client = remote;
+    for node in nodes.iter().rev() {
+        match node_ip(node[]) {
+            Some(ip) => {
+                client = ip;
+                if !trusted.contains(&ip) {
+                    break;
+                }
+            },
+            None => break
+        }
+    }
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::net::ip::Ipv4Addr;
+    use header::Headers;
+    use header::common::Connection;
+    use header::common::connection::{Close, ConnectionHeader};
+    use super::{is_hop_by_hop, client_ip};
+
+    #[test]
+    fn test_is_hop_by_hop() {
+        let mut headers = Headers::new();
+        headers.set(Connection(vec![Close, ConnectionHeader("x-secret".to_string())]));
+
+        assert!(is_hop_by_hop("connection", &headers));
+        assert!(is_hop_by_hop("Transfer-Encoding", &headers));
+        assert!(is_hop_by_hop("X-Secret", &headers));
+        assert!(!is_hop_by_hop("Content-Length", &headers));
+        assert!(!is_hop_by_hop("Content-Length", &Headers::new()));
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let proxy = Ipv4Addr(10, 0, 0, 1);
+        let trusted = [proxy, Ipv4Addr(10, 0, 0, 2)];
+        let mut headers = Headers::new();
+        headers.set_raw("X-Forwarded-For", vec![b"198.51.100.1, 203.0.113.7, 10.0.0.2".to_vec()]);
+
+        assert_eq!(client_ip(proxy, &headers, &trusted), Ipv4Addr(203, 0, 113, 7));
+        // an untrusted peer could have made up the header
+        assert_eq!(client_ip(Ipv4Addr(192, 0, 2, 1), &headers, &trusted), Ipv4Addr(192, 0, 2, 1));
+        assert_eq!(client_ip(proxy, &Headers::new(), &trusted), proxy);
+
+        headers.set_raw("Forwarded", vec![b"for=198.51.100.9, for=unknown;by=10.0.0.2".to_vec()]);
+        assert_eq!(client_ip(proxy, &headers, &trusted), proxy);
+        headers.set_raw("Forwarded", vec![b"for=\"[2001:db8::1]:80\", for=10.0.0.2".to_vec()]);
+        assert_eq!(format!("{}", client_ip(proxy, &headers, &trusted))[], "2001:db8::1");
+    }
+}
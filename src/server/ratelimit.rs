@@ -0,0 +1,191 @@
+//! Per-client rate limiting.
+//!
+//! The `RateLimit` middleware gives every client a token bucket. Each request
+//! takes a token, and tokens are refilled at a steady rate, so a client can
+//! make short bursts of requests but not exceed the rate over time.
+//!
+//! ```no_run
+//! # use hyper::Server;
+//! # use hyper::Ipv4Addr;
+//! # use hyper::server::{Request, Response};
+//! # fn api(_: Request, res: Response) { res.start().unwrap().end().unwrap(); }
+//! use hyper::server::ratelimit::RateLimit;
+//!
+//! // 100 requests a minute for each client IP
+//! Server::http(Ipv4Addr(127, 0, 0, 1), 3000).listen(RateLimit::new(api, 100, 60)).unwrap();
+//! ```
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use time::precise_time_s;
+
+use header::common::RetryAfter;
+use net::Fresh;
+use server::{Handler, Request, Response};
+use status::StatusCode::TooManyRequests;
+
+/// How many buckets are kept before full ones are dropped.
+const MAX_BUCKETS: uint = 10_000;
+
+/// Chooses which bucket a request takes a token from.
+pub trait KeyExtractor: Sync + Send {
+    /// The key of the client making `req`, or `None` to not limit it.
+    fn key(&self, req: &Request) -> Option<String>;
+}
+
+impl<F> KeyExtractor for F where F: Fn(&Request) -> Option<String>, F: Sync + Send {
+    fn key(&self, req: &Request) -> Option<String> {
+        (*self)(req)
+    }
+}
+
+/// A `KeyExtractor` limiting each peer IP address.
+///
+/// Behind a proxy, every request comes from the proxy address, so a key
+/// extractor reading a forwarded address header should be used instead.
+#[deriving(Copy)]
+pub struct PeerIp;
+
+impl KeyExtractor for PeerIp {
+    fn key(&self, req: &Request) -> Option<String> {
+        Some(req.remote_addr.ip.to_string())
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: f64
+}
+
+/// A middleware `Handler` limiting how often each client can make requests.
+///
+/// A request from a client without tokens left is answered with
+/// `429 Too Many Requests`, and a `Retry-After` header with the number of
+/// seconds until a token is available.
+pub struct RateLimit<H, K = PeerIp> {
+    inner: H,
+    extractor: K,
+    capacity: f64,
+    rate: f64,
+    buckets: Mutex<HashMap<String, Bucket>>
+}
+
+impl<H: Handler> RateLimit<H, PeerIp> {
+    /// Wrap a `Handler`, allowing each peer IP `requests` requests every
+    /// `secs` seconds.
+    pub fn new(inner: H, requests: uint, secs: u64) -> RateLimit<H, PeerIp> {
+        RateLimit::with_key(inner, PeerIp, requests, secs)
+    }
+}
+
+impl<H: Handler, K: KeyExtractor> RateLimit<H, K> {
+    /// Wrap a `Handler`, allowing `requests` requests every `secs` seconds
+    /// for each key found by `extractor`.
+    pub fn with_key(inner: H, extractor: K, requests: uint, secs: u64) -> RateLimit<H, K> {
+        assert!(requests > 0 && secs > 0, "rate limit must allow some requests");
+        RateLimit {
+            inner: inner,
+            extractor: extractor,
+            capacity: requests as f64,
+            rate: requests as f64 / secs as f64,
+            buckets: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Take a token for `key`, or return how many seconds until there is one.
+    fn take(&self, key: String, now: f64) -> Result<(), u64> {
+        let mut buckets = self.buckets.lock();
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&key) {
+            self.prune(&mut *buckets, now);
+        }
+
+        let capacity = self.capacity;
+        let bucket = match buckets.get_mut(&key) {
+            Some(bucket) => {
+                bucket.tokens = (bucket.tokens + (now - bucket.updated) * self.rate).min(capacity);
+                bucket.updated = now;
+                bucket.tokens -= 1.0;
+                if bucket.tokens >= 0.0 {
+                    return Ok(());
+                }
+                bucket.tokens += 1.0;
+                return Err(((1.0 - bucket.tokens) / self.rate).ceil() as u64);
+            },
+            None => Bucket {
+                tokens: capacity - 1.0,
+                updated: now
+            }
+        };
+        buckets.insert(key, bucket);
+        Ok(())
+    }
+
+    /// Drop every bucket that would be full by now, since a new one is the same.
+    fn prune(&self, buckets: &mut HashMap<String, Bucket>, now: f64) {
+        let full: Vec<String> = buckets.iter().filter(|&(_, bucket)| {
+            bucket.tokens + (now - bucket.updated) * self.rate >= self.capacity
+        }).map(|(key, _)| key.clone()).collect();
+        debug!("rate limit pruning {} of {} buckets", full.len(), buckets.len());
+        for key in full.iter() {
+            buckets.remove(key);
+        }
+    }
+}
+
+impl<H: Handler, K: KeyExtractor> Handler for RateLimit<H, K> {
+    fn handle(&self, req: Request, mut res: Response<Fresh>) {
+        let limited = match self.extractor.key(&req) {
+            Some(key) => self.take(key, precise_time_s()).err(),
+            None => None
+        };
+
+        match limited {
+            None => self.inner.handle(req, res),
+            Some(secs) => {
+                debug!("rate limited {}, retry after {}s", req.remote_addr, secs);
+                *res.status_mut() = TooManyRequests;
+                res.headers_mut().set(RetryAfter::Delay(Duration::seconds(secs as i64)));
+                if let Err(e) = res.start().and_then(|res| res.end()) {
+                    error!("rate limit response error: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use mock::handle;
+    use net::Fresh;
+    use server::{Request, Response};
+    use super::RateLimit;
+
+    fn ok(_: Request, res: Response<Fresh>) {
+        res.start().unwrap().end().unwrap();
+    }
+
+    #[test]
+    fn test_take() {
+        let limit = RateLimit::new(ok, 2, 10);
+        assert_eq!(limit.take("a".to_string(), 0.0), Ok(()));
+        assert_eq!(limit.take("a".to_string(), 0.0), Ok(()));
+        assert_eq!(limit.take("a".to_string(), 1.0), Err(4));
+        assert_eq!(limit.take("b".to_string(), 1.0), Ok(()));
+        assert_eq!(limit.take("a".to_string(), 5.0), Ok(()));
+        assert_eq!(limit.take("a".to_string(), 5.0), Err(5));
+    }
+
+    #[test]
+    fn test_too_many_requests() {
+        let limit = RateLimit::new(ok, 1, 60);
+        let mut results = Vec::new();
+        for _ in range(0u, 2) {
+            results.push(handle(&limit, "GET / HTTP/1.1\r\n\r\n"));
+        }
+        assert!(results[0][].starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(results[1][].starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+        assert!(results[1][].contains("Retry-After: 60\r\n"));
+    }
+}
@@ -2,18 +2,36 @@
 //!
 //! These are requests that a `hyper::Server` receives, and include its method,
 //! target URI, headers, and message body.
-use std::io::IoResult;
+use std::cell::{RefCell, RefMut};
+use std::default::Default;
+use std::io::{IoResult, IoError, EndOfFile, InvalidInput, standard_error};
 use std::io::net::ip::SocketAddr;
+use std::io::util::LimitReader;
+use std::rc::Rc;
+
+use cookie::Cookie;
+use serialize::Decodable;
 
 use {HttpResult};
+use HttpError::{HttpHeaderError, HttpIoError};
 use version::{HttpVersion};
-use method::Method::{mod, Get, Head};
+use method::Method::{mod, Get, Head, Connect};
+use form::{mod, FormDecoder, FormError, FormResult};
 use header::Headers;
-use header::common::{ContentLength, TransferEncoding};
-use http::{read_request_line};
+use header::common::{Connection, ContentLength, ContentType, Cookies, Host, Trailer,
+                     TransferEncoding, Upgrade};
+use header::common::connection::ConnectionHeader;
+use header::common::transfer_encoding::Encoding::Chunked;
+use http::{check_framing, read_request_line_with, ChunkLimits, ParseMode};
 use http::HttpReader;
-use http::HttpReader::{SizedReader, ChunkedReader, EmptyReader};
+use http::HttpReader::{SizedReader, ChunkedReader, EofReader, EmptyReader};
+use net::{Fresh, NetworkStream};
+use percent_encoding::{form_decode, percent_decode_lossy};
 use uri::RequestUri;
+use server::Extensions;
+use server::certificate::Certificate;
+use server::response::Response;
+use status::StatusCode::SwitchingProtocols;
 
 /// A request bundles several parts of an incoming `NetworkStream`, given to a `Handler`.
 pub struct Request<'a> {
@@ -27,7 +45,13 @@ pub struct Request<'a> {
     pub uri: RequestUri,
     /// The version of HTTP for this request.
     pub version: HttpVersion,
-    body: HttpReader<&'a mut (Reader + 'a)>
+    /// Data attached to this request by middleware, keyed by type.
+    pub extensions: Extensions,
+    body: HttpReader<&'a mut (Reader + 'a)>,
+    stream: Option<Box<NetworkStream + Send>>,
+    certificate: Option<Certificate>,
+    trailers: Option<Headers>,
+    connection: Rc<RefCell<Extensions>>
 }
 
 
@@ -35,23 +59,37 @@ impl<'a> Request<'a> {
 
     /// Create a new Request, reading the StartLine and Headers so they are
     /// immediately useful.
-    pub fn new(mut stream: &'a mut (Reader + 'a), addr: SocketAddr) -> HttpResult<Request<'a>> {
-        let (method, uri, version) = try!(read_request_line(&mut stream));
+    pub fn new(stream: &'a mut (Reader + 'a), addr: SocketAddr) -> HttpResult<Request<'a>> {
+        Request::with_parse_mode(stream, addr, ParseMode::Strict)
+    }
+
+    /// Create a new Request, parsing its head as strictly as `mode` says.
+    pub fn with_parse_mode(mut stream: &'a mut (Reader + 'a), addr: SocketAddr,
+                           mode: ParseMode) -> HttpResult<Request<'a>> {
+        let (method, uri, version) = try!(read_request_line_with(&mut stream, mode));
         debug!("Request Line: {} {} {}", method, uri, version);
-        let headers = try!(Headers::from_raw(&mut stream));
+        let mut headers = try!(Headers::from_raw_with(&mut stream, mode));
         debug!("Headers: [\n{}]", headers);
-
+        try!(check_framing(&mut headers));
 
         let body = if method == Get || method == Head {
             EmptyReader(stream)
+        } else if method == Connect {
+            // everything after the head belongs to the tunnel
+            EofReader(stream)
         } else if headers.has::<ContentLength>() {
             match headers.get::<ContentLength>() {
                 Some(&ContentLength(len)) => SizedReader(stream, len),
                 None => unreachable!()
             }
         } else if headers.has::<TransferEncoding>() {
-            todo!("check for Transfer-Encoding: chunked");
-            ChunkedReader(stream, None)
+            // without chunked last, the length of the body can't be known
+            match headers.get::<TransferEncoding>() {
+                Some(&TransferEncoding(ref codings)) if codings.last() == Some(&Chunked) => {
+                    ChunkedReader(stream, None, Default::default())
+                },
+                _ => return Err(HttpHeaderError)
+            }
         } else {
             EmptyReader(stream)
         };
@@ -62,25 +100,315 @@ impl<'a> Request<'a> {
             uri: uri,
             headers: headers,
             version: version,
-            body: body
+            extensions: Extensions::new(),
+            body: body,
+            stream: None,
+            certificate: None,
+            trailers: None,
+            connection: Rc::new(RefCell::new(Extensions::new()))
+        })
+    }
+
+    /// The host the request is for.
+    ///
+    /// This is the host of an absolute or authority form target, which
+    /// overrides the `Host` header, and otherwise the `Host` header.
+    pub fn host(&self) -> Option<Host> {
+        self.uri.host().or_else(|| self.headers.get::<Host>().map(|host| host.clone()))
+    }
+
+    /// The path of the request target, still percent-encoded.
+    ///
+    /// This is `None` for `Authority` and `Star` targets. It is read from
+    /// `uri` each time, so it follows changes to it.
+    pub fn path(&self) -> Option<String> {
+        self.uri.path()
+    }
+
+    /// The query of the request target, without the `?`.
+    pub fn query(&self) -> Option<&str> {
+        self.uri.query()
+    }
+
+    /// The segments of the path, percent-decoded.
+    ///
+    /// The path `/a%20b/c` has the segments `a b` and `c`, and the path `/`
+    /// has a single empty segment.
+    pub fn path_segments(&self) -> Vec<String> {
+        match self.path() {
+            Some(path) => {
+                let path = if path[].starts_with("/") { path[1..] } else { path[] };
+                path.split('/').map(|segment| {
+                    percent_decode_lossy(segment.as_bytes())
+                }).collect()
+            },
+            None => Vec::new()
+        }
+    }
+
+    /// The query parameters, decoded, in the order they were sent.
+    ///
+    /// See `RequestUri::query_pairs`.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        self.uri.query_pairs()
+    }
+
+    /// The first value of the query parameter `name`.
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        self.query_pairs().into_iter().find(|&(ref key, _)| key[] == name).map(|(_, value)| value)
+    }
+
+    /// Decode the query of the request as a struct, by the names of its
+    /// fields. See `hyper::form`.
+    pub fn decode_query<T: Decodable<FormDecoder, FormError>>(&self) -> FormResult<T> {
+        form::decode_pairs(self.query_pairs())
+    }
+
+    /// Get a cookie sent with this request, by name.
+    pub fn cookie(&self, name: &str) -> Option<&Cookie> {
+        self.headers.get::<Cookies>().and_then(|cookies| {
+            cookies.iter().find(|cookie| cookie.name[] == name)
+        })
+    }
+
+    /// Take the underlying connection, to tunnel it after answering a `CONNECT`.
+    ///
+    /// This is only available for `CONNECT` requests, and requests that ask to
+    /// `upgrade`, which are better answered with `upgrade`. The returned stream is
+    /// used to write to the client, while bytes the client sends after the
+    /// request head are read from the `Request` itself, so nothing the server
+    /// had already buffered is lost. The server closes the connection once the
+    /// handler returns.
+    pub fn take_stream(&mut self) -> Option<Box<NetworkStream + Send>> {
+        self.stream.take()
+    }
+
+    /// The names of the trailer headers the client declared in the `Trailer`
+    /// header, which it may send after a chunked body.
+    pub fn trailer_names(&self) -> Vec<String> {
+        match self.headers.get::<Trailer>() {
+            Some(trailer) if self.version.supports_trailers() => {
+                trailer.iter().map(|name| name.as_slice().to_string()).collect()
+            },
+            _ => Vec::new()
+        }
+    }
+
+    /// The trailer headers the client sent after a chunked body.
+    ///
+    /// These are read along with the end of the body, so this is `None` until
+    /// the body has been read to the end, and for bodies that are not chunked.
+    /// Trailers are kept apart from `headers`, since they were not seen by
+    /// whatever already looked at those.
+    pub fn trailers(&self) -> Option<&Headers> {
+        self.trailers.as_ref()
+    }
+
+    /// Set how large the chunks of a chunked body may be, and how much they
+    /// may add up to, before reading it.
+    ///
+    /// A chunk over the limits is an `InvalidInput` error when reading.
+    pub fn set_chunk_limits(&mut self, limits: ChunkLimits) {
+        if let ChunkedReader(_, _, ref mut current) = self.body {
+            *current = limits;
+        }
+    }
+
+    /// Whether this request asks to switch to a protocol in its `Upgrade` header.
+    ///
+    /// Only versions that support `Upgrade`, such as HTTP/1.1, can ask to.
+    pub fn is_upgrade(&self) -> bool {
+        self.version.supports_upgrade() && self.headers.has::<Upgrade>() &&
+            self.headers.get::<Connection>().map_or(false, |conn| conn.has("upgrade"))
+    }
+
+    /// Answer with `101 Switching Protocols`, and take over the connection.
+    ///
+    /// The headers of `res`, such as the `Upgrade` header naming the chosen
+    /// protocol, are sent with it. The returned `Upgraded` reads what the
+    /// client sends next, starting with anything the server had buffered after
+    /// the request head, and writes to the client directly. The server closes
+    /// the connection once the handler returns.
+    ///
+    /// This is an error if the request `is_upgrade` is false.
+    pub fn upgrade<'b>(self, mut res: Response<'b, Fresh>) -> IoResult<Upgraded<'a>> {
+        let stream = match self.stream {
+            Some(stream) => stream,
+            None => return Err(IoError {
+                kind: InvalidInput,
+                desc: "Request did not ask to upgrade",
+                detail: None
+            })
+        };
+        *res.status_mut() = SwitchingProtocols;
+        res.headers_mut().set(Connection(vec![ConnectionHeader("Upgrade".to_string())]));
+        try!(res.start_tunnel());
+        Ok(Upgraded {
+            reader: self.body.unwrap(),
+            stream: stream
         })
     }
+
+    /// Read an `application/x-www-form-urlencoded` body into decoded pairs.
+    ///
+    /// Returns an `HttpHeaderError` if the `Content-Type` is not a urlencoded
+    /// form, and an `HttpIoError` if the body is longer than `limit` bytes.
+    /// Repeated keys are kept, in the order they were sent.
+    pub fn read_form(&mut self, limit: uint) -> HttpResult<Vec<(String, String)>> {
+        let is_form = match self.headers.get::<ContentType>() {
+            Some(&ContentType(ref mime)) => {
+                format!("{}/{}", mime.0, mime.1)[] == "application/x-www-form-urlencoded"
+            },
+            None => false
+        };
+        if !is_form {
+            return Err(HttpHeaderError);
+        }
+
+        let too_large = IoError {
+            kind: InvalidInput,
+            desc: "Form body is larger than the limit",
+            detail: None
+        };
+        match self.headers.get::<ContentLength>() {
+            Some(&ContentLength(len)) if len > limit => return Err(HttpIoError(too_large)),
+            _ => ()
+        }
+
+        let body = try!(LimitReader::new(self.by_ref(), limit + 1).read_to_end());
+        if body.len() > limit {
+            return Err(HttpIoError(too_large));
+        }
+        Ok(form_decode(body[]))
+    }
+
+    /// Read an `application/x-www-form-urlencoded` body as a struct, by the
+    /// names of its fields.
+    ///
+    /// Fails as `read_form` does, or with an `HttpIoError` if the form does
+    /// not decode as `T`.
+    pub fn decode_form<T: Decodable<FormDecoder, FormError>>(&mut self, limit: uint) -> HttpResult<T> {
+        let pairs = try!(self.read_form(limit));
+        form::decode_pairs(pairs).map_err(|err| HttpIoError(IoError {
+            kind: InvalidInput,
+            desc: "Invalid form body",
+            detail: Some(err.to_string())
+        }))
+    }
+
+    #[doc(hidden)]
+    pub fn set_stream(&mut self, stream: Box<NetworkStream + Send>) {
+        self.stream = Some(stream);
+    }
+
+    /// Data attached to the connection this request came on, keyed by type.
+    ///
+    /// Unlike `extensions`, these are kept for the next requests on the same
+    /// connection, which suits state such as a client that authenticated the
+    /// connection once, or a protocol negotiated for it. The map can only be
+    /// borrowed once at a time.
+    pub fn connection_extensions(&self) -> RefMut<Extensions> {
+        self.connection.borrow_mut()
+    }
+
+    #[doc(hidden)]
+    pub fn set_connection_extensions(&mut self, connection: Rc<RefCell<Extensions>>) {
+        self.connection = connection;
+    }
+
+    /// The verified certificate of the client, if it sent one.
+    ///
+    /// This is only available over HTTPS, when the `Server` was set up to
+    /// `verify_clients`.
+    pub fn peer_certificate(&self) -> Option<&Certificate> {
+        self.certificate.as_ref()
+    }
+
+    #[doc(hidden)]
+    pub fn set_peer_certificate(&mut self, certificate: Option<Certificate>) {
+        self.certificate = certificate;
+    }
 }
 
 impl<'a> Reader for Request<'a> {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
-        self.body.read(buf)
+        match self.body.read(buf) {
+            Err(ref e) if e.kind == EndOfFile && self.trailers.is_none() => {
+                if let ChunkedReader(ref mut body, Some(0), _) = self.body {
+                    // the trailer section follows the last chunk
+                    self.trailers = Some(match Headers::from_raw(body) {
+                        Ok(trailers) => trailers,
+                        Err(HttpIoError(e)) => return Err(e),
+                        Err(_) => return Err(IoError {
+                            kind: InvalidInput,
+                            desc: "Invalid trailer headers",
+                            detail: None
+                        })
+                    });
+                }
+            },
+            result => return result
+        }
+        Err(standard_error(EndOfFile))
+    }
+}
+
+/// A connection switched to another protocol with `Request::upgrade`.
+pub struct Upgraded<'a> {
+    reader: &'a mut (Reader + 'a),
+    stream: Box<NetworkStream + Send>
+}
+
+impl<'a> Upgraded<'a> {
+    /// Take the connection, losing whatever the server had buffered.
+    ///
+    /// This is for protocols where the client waits for the `101` before it
+    /// sends anything, so nothing can have been buffered.
+    pub fn into_stream(self) -> Box<NetworkStream + Send> {
+        self.stream
+    }
+}
+
+impl<'a> Reader for Upgraded<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        self.reader.read(buf)
+    }
+}
+
+impl<'a> Writer for Upgraded<'a> {
+    fn write(&mut self, msg: &[u8]) -> IoResult<()> {
+        self.stream.write(msg)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.stream.flush()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::RefCell;
+    use std::io::{InvalidInput, MemWriter};
+    use std::rc::Rc;
+    use std::str::from_utf8;
+
+    use form::FormError::MissingField;
+    use header::common::Host;
+    use http::ChunkLimits;
+    use http::ParseMode::Lenient;
     use mock::MockStream;
+    use net::NetworkStream;
+    use server::{Extensions, Response};
+    use uri::RequestUri::{AbsolutePath, Authority};
+    use HttpError;
+    use HttpError::{HttpHeaderError, HttpIoError};
     use super::Request;
 
-    macro_rules! sock(
-        ($s:expr) => (::std::str::from_str::<::std::io::net::ip::SocketAddr>($s).unwrap())
-    );
+    #[deriving(Decodable, PartialEq, Show)]
+    struct Tags {
+        name: String,
+        tag: Vec<String>,
+    }
 
     #[test]
     fn test_get_empty_body() {
@@ -95,6 +423,38 @@ mod tests {
         assert_eq!(req.read_to_string(), Ok("".to_string()));
     }
 
+    #[test]
+    fn test_parse_mode() {
+        let head = b"GET / HTTP/1.1\nHost: example.domain\n\n";
+
+        let mut stream = MockStream::with_input(head);
+        assert!(Request::new(&mut stream, sock!("127.0.0.1:80")).is_err());
+
+        let mut stream = MockStream::with_input(head);
+        let req = Request::with_parse_mode(&mut stream, sock!("127.0.0.1:80"), Lenient).unwrap();
+        assert_eq!(req.headers.get_raw("Host"), Some([b"example.domain".to_vec()][]));
+    }
+
+    #[test]
+    fn test_host() {
+        let mut stream = MockStream::with_input(b"\
+            GET http://other.domain:8080/path HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            \r\n\
+        ");
+        let req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.host(), Some(Host::new("other.domain", Some(8080))));
+        assert_eq!(req.path(), Some("/path".to_string()));
+
+        let mut stream = MockStream::with_input(b"\
+            GET /path HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            \r\n\
+        ");
+        let req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.host(), Some(Host::new("example.domain", None)));
+    }
+
     #[test]
     fn test_head_empty_body() {
         let mut stream = MockStream::with_input(b"\
@@ -120,4 +480,277 @@ mod tests {
         let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
         assert_eq!(req.read_to_string(), Ok("".to_string()));
     }
+
+    #[test]
+    fn test_connect_body_is_tunnel() {
+        let mut stream = MockStream::with_input(b"\
+            CONNECT example.domain:443 HTTP/1.1\r\n\
+            Host: example.domain:443\r\n\
+            \r\n\
+            tunneled bytes\
+        ");
+
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.uri, Authority("example.domain:443".to_string()));
+        assert!(req.take_stream().is_none());
+        assert_eq!(req.read_to_string(), Ok("tunneled bytes".to_string()));
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let mut stream = MockStream::with_input(b"\
+            GET /chat HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Connection: Upgrade\r\n\
+            Upgrade: echo\r\n\
+            \r\n\
+            early bytes\
+        ");
+
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert!(req.is_upgrade());
+        req.set_stream(box MockStream::new() as Box<NetworkStream + Send>);
+        let mut w = MemWriter::new();
+        let mut upgraded = req.upgrade(Response::new(&mut w)).unwrap();
+        assert_eq!(upgraded.read_to_string(), Ok("early bytes".to_string()));
+        upgraded.write(b"echo").unwrap();
+
+        let head = from_utf8(w.get_ref()).unwrap();
+        assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(head.contains("Connection: Upgrade\r\n"));
+        assert!(head.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_upgrade_without_request() {
+        let mut stream = MockStream::with_input(b"GET / HTTP/1.1\r\n\r\n");
+        let req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert!(!req.is_upgrade());
+        let mut w = MemWriter::new();
+        assert!(req.upgrade(Response::new(&mut w)).is_err());
+        assert!(w.get_ref().is_empty());
+
+        let mut stream = MockStream::with_input(b"\
+            GET /chat HTTP/1.0\r\n\
+            Connection: Upgrade\r\n\
+            Upgrade: echo\r\n\
+            \r\n\
+        ");
+        let req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert!(!req.is_upgrade());
+    }
+
+    #[test]
+    fn test_target_accessors() {
+        let mut stream = MockStream::with_input(b"\
+            GET /files/a%20b/c?x=1&name=two+words&x=3 HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            \r\n\
+        ");
+
+        let req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.path(), Some("/files/a%20b/c".to_string()));
+        assert_eq!(req.query(), Some("x=1&name=two+words&x=3"));
+        assert_eq!(req.path_segments(), vec!["files".to_string(), "a b".to_string(),
+                                             "c".to_string()]);
+        assert_eq!(req.query_pairs().len(), 3);
+        assert_eq!(req.query_param("x"), Some("1".to_string()));
+        assert_eq!(req.query_param("name"), Some("two words".to_string()));
+        assert_eq!(req.query_param("y"), None);
+
+        // a middleware rewriting the target is seen by later handlers
+        let mut req = req;
+        req.uri = AbsolutePath("/rewritten?x=2".to_string());
+        assert_eq!(req.path(), Some("/rewritten".to_string()));
+        assert_eq!(req.query_param("x"), Some("2".to_string()));
+
+        let mut stream = MockStream::with_input(b"\
+            CONNECT example.domain:443 HTTP/1.1\r\n\
+            \r\n\
+        ");
+        let req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.path(), None);
+        assert!(req.path_segments().is_empty());
+        assert!(req.query_pairs().is_empty());
+    }
+
+    #[test]
+    fn test_chunked_trailers() {
+        let mut stream = MockStream::with_input(b"\
+            POST / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Transfer-Encoding: chunked\r\n\
+            Trailer: Checksum, Expires\r\n\
+            \r\n\
+            5\r\n\
+            hello\r\n\
+            0\r\n\
+            Checksum: abc\r\n\
+            \r\n\
+        ");
+
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.trailer_names(), vec!["Checksum".to_string(), "Expires".to_string()]);
+        assert!(req.trailers().is_none());
+        assert_eq!(req.read_to_string(), Ok("hello".to_string()));
+        let trailers = req.trailers().unwrap();
+        assert_eq!(trailers.get_raw("Checksum").unwrap(), [b"abc".to_vec()][]);
+        assert_eq!(trailers.len(), 1);
+    }
+
+    #[test]
+    fn test_chunk_limits() {
+        let mut stream = MockStream::with_input(b"\
+            POST / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Transfer-Encoding: chunked\r\n\
+            \r\n\
+            5\r\n\
+            hello\r\n\
+            5\r\n\
+            world\r\n\
+            0\r\n\
+            \r\n\
+        ");
+
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        req.set_chunk_limits(ChunkLimits { max_chunk_size: 5, max_body_size: 8 });
+        let mut buf = [0u8, ..5];
+        assert_eq!(req.read(&mut buf), Ok(5));
+        assert_eq!(req.read(&mut buf).unwrap_err().kind, InvalidInput);
+    }
+
+    #[test]
+    fn test_conflicting_framing() {
+        fn read(head: &[u8]) -> Option<HttpError> {
+            let mut stream = MockStream::with_input(head);
+            let err = Request::new(&mut stream, sock!("127.0.0.1:80")).err();
+            err
+        }
+
+        assert_eq!(read(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\
+                          Transfer-Encoding: chunked\r\n\r\n"), Some(HttpHeaderError));
+        assert_eq!(read(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\
+                          Content-Length: 6\r\n\r\n"), Some(HttpHeaderError));
+        assert_eq!(read(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\n"),
+                   Some(HttpHeaderError));
+
+        let mut stream = MockStream::with_input(b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\
+                                                  Content-Length: 2\r\n\r\nok");
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.read_to_string(), Ok("ok".to_string()));
+    }
+
+    #[test]
+    fn test_connection_extensions() {
+        #[deriving(PartialEq, Show)]
+        struct User(&'static str);
+
+        let connection = Rc::new(RefCell::new(Extensions::new()));
+        let mut stream = MockStream::with_input(b"\
+            GET /login HTTP/1.1\r\n\
+            \r\n\
+            GET /account HTTP/1.1\r\n\
+            \r\n\
+        ");
+
+        {
+            let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+            req.set_connection_extensions(connection.clone());
+            req.connection_extensions().insert(User("sean"));
+        }
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert!(req.connection_extensions().get::<User>().is_none());
+        req.set_connection_extensions(connection.clone());
+        assert_eq!(req.connection_extensions().get::<User>(), Some(&User("sean")));
+    }
+
+    #[test]
+    fn test_read_form() {
+        let mut stream = MockStream::with_input(b"\
+            POST / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Content-Type: application/x-www-form-urlencoded\r\n\
+            Content-Length: 29\r\n\
+            \r\n\
+            name=hyper+rs&tag=a%26b&tag=c\
+        ");
+
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.read_form(1024).unwrap(), vec![
+            ("name".to_string(), "hyper rs".to_string()),
+            ("tag".to_string(), "a&b".to_string()),
+            ("tag".to_string(), "c".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_read_form_errors() {
+        let mut stream = MockStream::with_input(b"\
+            POST / HTTP/1.1\r\n\
+            Content-Type: text/plain\r\n\
+            Content-Length: 3\r\n\
+            \r\n\
+            a=b\
+        ");
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.read_form(1024), Err(HttpHeaderError));
+
+        let mut stream = MockStream::with_input(b"\
+            POST / HTTP/1.1\r\n\
+            Content-Type: application/x-www-form-urlencoded\r\n\
+            Content-Length: 7\r\n\
+            \r\n\
+            a=b&c=d\
+        ");
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        match req.read_form(4) {
+            Err(HttpIoError(..)) => (),
+            other => panic!("expected an HttpIoError, got {}", other)
+        }
+    }
+
+    #[test]
+    fn test_decode_form() {
+        let mut stream = MockStream::with_input(b"\
+            POST /?name=query HTTP/1.1\r\n\
+            Content-Type: application/x-www-form-urlencoded\r\n\
+            Content-Length: 29\r\n\
+            \r\n\
+            name=hyper+rs&tag=a%26b&tag=c\
+        ");
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.decode_query::<Tags>(), Ok(Tags { name: "query".to_string(), tag: vec![] }));
+        assert_eq!(req.decode_form::<Tags>(1024).unwrap(), Tags {
+            name: "hyper rs".to_string(),
+            tag: vec!["a&b".to_string(), "c".to_string()]
+        });
+
+        let mut stream = MockStream::with_input(b"\
+            POST / HTTP/1.1\r\n\
+            Content-Type: application/x-www-form-urlencoded\r\n\
+            Content-Length: 3\r\n\
+            \r\n\
+            a=b\
+        ");
+        let mut req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.decode_query::<Tags>(), Err(MissingField("name".to_string())));
+        match req.decode_form::<Tags>(1024) {
+            Err(HttpIoError(ref err)) => assert_eq!(err.kind, InvalidInput),
+            other => panic!("expected an HttpIoError, got {}", other)
+        }
+    }
+
+    #[test]
+    fn test_cookie() {
+        let mut stream = MockStream::with_input(b"\
+            GET / HTTP/1.1\r\n\
+            Cookie: foo=bar; session=abc\r\n\
+            \r\n\
+        ");
+
+        let req = Request::new(&mut stream, sock!("127.0.0.1:80")).unwrap();
+        assert_eq!(req.cookie("session").map(|c| c.value[]), Some("abc"));
+        assert!(req.cookie("missing").is_none());
+    }
 }
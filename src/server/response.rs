@@ -2,28 +2,64 @@
 //!
 //! These are responses sent by a `hyper::Server` to clients, after
 //! receiving a request.
-use std::io::IoResult;
+use std::io::{IoResult, IoError, InvalidInput};
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, SeqCst};
 
-use time::now_utc;
+use cookie::Cookie;
 
 use header;
 use header::common;
-use http::{CR, LF, LINE_ENDING, HttpWriter};
-use http::HttpWriter::{ThroughWriter, ChunkedWriter, SizedWriter};
+use header::common::connection::Close;
+use http::{should_keep_alive, CR, LF, LINE_ENDING, HttpWriter};
+use http::HttpWriter::{ThroughWriter, ChunkedWriter, SizedWriter, EmptyWriter};
 use status;
 use net::{Fresh, Streaming};
+use server::date;
 use version;
 
 /// The outgoing half for a Tcp connection, created by a `Server` and given to a `Handler`.
 pub struct Response<'a, W = Fresh> {
     /// The HTTP version of this response.
     pub version: version::HttpVersion,
-    // Stream the Response is writing to, not accessible through UnwrittenResponse
-    body: HttpWriter<&'a mut (Writer + 'a)>,
+    // Stream the Response is writing to, not accessible through UnwrittenResponse.
+    // It is only taken out while `write_buffered` changes its framing.
+    body: Option<HttpWriter<&'a mut (Writer + 'a)>>,
     // The status code for the request.
     status: status::StatusCode,
+    // A reason phrase to send instead of the canonical one of the status.
+    reason: Option<String>,
     // The outgoing headers on this response.
-    headers: header::Headers
+    headers: header::Headers,
+    // Hooks to run on the head right before it is written.
+    hooks: Vec<Box<StartHook + Send>>,
+    // The body written so far, while the head is held back to learn its length.
+    buffer: Option<Vec<u8>>,
+    // How much of the body can be buffered before it is sent chunked.
+    buffer_limit: uint,
+    // Small writes not sent yet, to be sent as one chunk.
+    chunk: Vec<u8>,
+    // How large the chunk can get before it is sent.
+    chunk_size: uint,
+    // Set when the head says the connection closes after this response.
+    close_flag: Option<Arc<AtomicBool>>
+}
+
+// The body is only missing while it is moved into a new framing.
+const REFRAMING: &'static str = "the body of the response is being reframed";
+
+/// How many bytes of small writes are collected by default into one chunk.
+pub const DEFAULT_CHUNK_SIZE: uint = 4096;
+
+/// A hook run on a `Response` right before its head is written.
+///
+/// Middleware can use this to adjust the status or headers after the wrapped
+/// `Handler` is done with them, such as adding a `Set-Cookie` header for a
+/// session that was changed while handling the request.
+pub trait StartHook {
+    /// Called from `start` or `start_tunnel`, before anything is written.
+    fn on_start(&mut self, res: &mut Response<Fresh>);
 }
 
 impl<'a, W> Response<'a, W> {
@@ -31,9 +67,102 @@ impl<'a, W> Response<'a, W> {
     #[inline]
     pub fn status(&self) -> status::StatusCode { self.status }
 
+    /// The reason phrase sent with the status, which is the canonical one
+    /// of the status unless another was set.
+    pub fn reason(&self) -> &str {
+        match self.reason {
+            Some(ref reason) => reason[],
+            None => self.status.canonical_reason().unwrap_or("")
+        }
+    }
+
     /// The headers of this response.
     pub fn headers(&self) -> &header::Headers { &self.headers }
 
+    /// Deconstruct this Response into its constituent parts.
+    ///
+    /// Any `StartHook`s that have not run yet, and any buffered body that has
+    /// not been written yet, are dropped.
+    pub fn deconstruct(self) -> (version::HttpVersion, HttpWriter<&'a mut (Writer + 'a)>,
+                                 status::StatusCode, header::Headers) {
+        (self.version, self.body.unwrap(), self.status, self.headers)
+    }
+
+    /// Move this response into one of another phantom type, writing to
+    /// `body`, and holding back `buffer` if there is one.
+    ///
+    /// Hooks that have not run are dropped, and the chunk starts empty.
+    fn reframe<T>(self, body: Option<HttpWriter<&'a mut (Writer + 'a)>>,
+                  buffer: Option<Vec<u8>>) -> Response<'a, T> {
+        Response {
+            version: self.version,
+            body: body,
+            status: self.status,
+            reason: self.reason,
+            headers: self.headers,
+            hooks: Vec::new(),
+            buffer: buffer,
+            buffer_limit: self.buffer_limit,
+            chunk: Vec::new(),
+            chunk_size: self.chunk_size,
+            close_flag: self.close_flag
+        }
+    }
+
+    /// The writer of the body.
+    fn body(&mut self) -> &mut HttpWriter<&'a mut (Writer + 'a)> {
+        self.body.as_mut().expect(REFRAMING)
+    }
+
+    /// Take the stream out of the body writer, to frame it anew.
+    fn take_stream(&mut self) -> &'a mut (Writer + 'a) {
+        self.body.take().expect(REFRAMING).unwrap()
+    }
+
+    fn write_head(&mut self) -> IoResult<()> {
+        let status_line = format!("{} {} {}", self.version, self.status as u16, self.reason());
+        debug!("writing head: {}", status_line);
+
+        if !self.headers.has::<common::Date>() {
+            self.headers.set_raw("Date", vec![date::now()]);
+        }
+
+        if let Some(ref flag) = self.close_flag {
+            // HTTP/1.0 connections only persist if the response says so
+            flag.store(!should_keep_alive(self.version, &self.headers, true), SeqCst);
+        }
+
+        debug!("headers [\n{}]", self.headers);
+        let body = self.body.as_mut().expect(REFRAMING);
+        try!(write!(body, "{}{}{}", status_line, CR as char, LF as char));
+        try!(self.headers.write_to(body));
+        body.write(LINE_ENDING)
+    }
+
+    /// Frame a body of unknown length: chunked, or for versions without
+    /// chunks, such as HTTP/1.0, by closing the connection after it.
+    fn set_chunked(&mut self) {
+        if !self.version.supports_chunked() {
+            self.headers.set(common::Connection(vec![Close]));
+            return;
+        }
+        let encodings = match self.headers.get_mut::<common::TransferEncoding>() {
+            Some(&common::TransferEncoding(ref mut encodings)) => {
+                //TODO: check if chunked is already in encodings. use HashSet?
+                encodings.push(common::transfer_encoding::Encoding::Chunked);
+                false
+            },
+            None => true
+        };
+
+        if encodings {
+            self.headers.set::<common::TransferEncoding>(
+                common::TransferEncoding(vec![common::transfer_encoding::Encoding::Chunked]))
+        }
+    }
+}
+
+impl<'a> Response<'a, Fresh> {
     /// Construct a Response from its constituent parts.
     pub fn construct(version: version::HttpVersion,
                      body: HttpWriter<&'a mut (Writer + 'a)>,
@@ -41,114 +170,604 @@ impl<'a, W> Response<'a, W> {
                      headers: header::Headers) -> Response<'a, Fresh> {
         Response {
             status: status,
+            reason: None,
             version: version,
-            body: body,
-            headers: headers
+            body: Some(body),
+            headers: headers,
+            hooks: Vec::new(),
+            buffer: None,
+            buffer_limit: 0,
+            chunk: Vec::new(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            close_flag: None
         }
     }
 
-    /// Deconstruct this Response into its constituent parts.
-    pub fn deconstruct(self) -> (version::HttpVersion, HttpWriter<&'a mut (Writer + 'a)>,
-                                 status::StatusCode, header::Headers) {
-        (self.version, self.body, self.status, self.headers)
-    }
-}
-
-impl<'a> Response<'a, Fresh> {
     /// Creates a new Response that can be used to write to a network stream.
     pub fn new(stream: &'a mut (Writer + 'a)) -> Response<'a, Fresh> {
-        Response {
-            status: status::StatusCode::Ok,
-            version: version::HttpVersion::Http11,
-            headers: header::Headers::new(),
-            body: ThroughWriter(stream)
-        }
+        Response::construct(version::HttpVersion::Http11, ThroughWriter(stream),
+                            status::StatusCode::Ok, header::Headers::new())
     }
 
     /// Consume this Response<Fresh>, writing the Headers and Status and creating a Response<Streaming>
+    ///
+    /// Responses with a status that cannot have a body, such as `204 No Content`
+    /// and `304 Not Modified`, are not chunked, and writing a body is an error.
+    ///
+    /// If neither `Content-Length` nor `Transfer-Encoding` are set, the body
+    /// is sent chunked. With a buffer limit set by `set_buffer_limit`, up to
+    /// that much of the body is held back with the head instead, and a body
+    /// that ends within the limit is sent with a `Content-Length`.
     pub fn start(mut self) -> IoResult<Response<'a, Streaming>> {
-        debug!("writing head: {} {}", self.version, self.status);
-        try!(write!(&mut self.body, "{} {}{}{}", self.version, self.status, CR as char, LF as char));
+        self.run_hooks();
 
-        if !self.headers.has::<common::Date>() {
-            self.headers.set(common::Date(now_utc()));
+        let body_allowed = match self.status as u16 {
+            100...199 | 204 | 304 => false,
+            _ => true
+        };
+
+        if !body_allowed || !self.version.supports_chunked() {
+            self.headers.remove::<common::TransferEncoding>();
         }
 
+        let len = self.headers.get::<common::ContentLength>().map(|cl| **cl);
+        if body_allowed && len.is_none() && self.buffer_limit > 0 &&
+                !self.headers.has::<common::TransferEncoding>() {
+            debug!("buffering up to {} bytes of body", self.buffer_limit);
+            let body = self.body.take();
+            return Ok(self.reframe(body, Some(Vec::new())));
+        }
 
-        let mut chunked = true;
-        let mut len = 0;
+        let chunked = body_allowed && len.is_none();
+        if chunked {
+            self.set_chunked();
+        }
+        try!(self.write_head());
 
-        match self.headers.get::<common::ContentLength>() {
-            Some(cl) => {
-                chunked = false;
-                len = **cl;
-            },
-            None => ()
+        let stream = self.take_stream();
+        let stream = if !body_allowed {
+            EmptyWriter(stream)
+        } else if chunked {
+            unsized_writer(self.version, stream)
+        } else {
+            SizedWriter(stream, len.unwrap())
         };
 
-        // cant do in match above, thanks borrowck
-        if chunked {
-            let encodings = match self.headers.get_mut::<common::TransferEncoding>() {
-                Some(&common::TransferEncoding(ref mut encodings)) => {
-                    //TODO: check if chunked is already in encodings. use HashSet?
-                    encodings.push(common::transfer_encoding::Encoding::Chunked);
-                    false
-                },
-                None => true
-            };
-
-            if encodings {
-                self.headers.set::<common::TransferEncoding>(
-                    common::TransferEncoding(vec![common::transfer_encoding::Encoding::Chunked]))
-            }
-        }
+        // "copy" to change the phantom type
+        Ok(self.reframe(Some(stream), None))
+    }
 
+    /// Consume this Response<Fresh>, writing the Headers and Status without
+    /// any body framing.
+    ///
+    /// This is how to answer a `CONNECT` request: after a successful response
+    /// the rest of the connection is a tunnel, so neither `Content-Length` nor
+    /// `Transfer-Encoding` are sent, and writes go to the stream unchanged.
+    /// The head is flushed immediately.
+    pub fn start_tunnel(mut self) -> IoResult<Response<'a, Streaming>> {
+        self.run_hooks();
 
-        debug!("headers [\n{}]", self.headers);
-        try!(write!(&mut self.body, "{}", self.headers));
+        self.headers.remove::<common::ContentLength>();
+        self.headers.remove::<common::TransferEncoding>();
 
-        try!(self.body.write(LINE_ENDING));
+        try!(self.write_head());
+        try!(self.body().flush());
 
-        let stream = if chunked {
-            ChunkedWriter(self.body.unwrap())
-        } else {
-            SizedWriter(self.body.unwrap(), len)
-        };
+        let stream = ThroughWriter(self.take_stream());
+        Ok(self.reframe(Some(stream), None))
+    }
 
-        // "copy" to change the phantom type
-        Ok(Response {
-            version: self.version,
-            body: stream,
-            status: self.status,
-            headers: self.headers
-        })
+    fn run_hooks(&mut self) {
+        let hooks = mem::replace(&mut self.hooks, Vec::new());
+        for mut hook in hooks.into_iter() {
+            hook.on_start(self);
+        }
     }
 
     /// Get a mutable reference to the status.
     #[inline]
     pub fn status_mut(&mut self) -> &mut status::StatusCode { &mut self.status }
 
+    /// Send `reason` as the reason phrase, instead of the canonical one of
+    /// the status.
+    ///
+    /// The reason phrase is only for people reading the response, so an
+    /// empty one, as in `HTTP/1.1 200 \r\n`, is fine to save some bytes.
+    /// Any CR or LF in `reason` is left out, as it would end the status line.
+    pub fn set_reason(&mut self, reason: &str) {
+        self.reason = Some(reason.chars().filter(|&c| c != '\r' && c != '\n').collect());
+    }
+
     /// Get a mutable reference to the Headers.
     pub fn headers_mut(&mut self) -> &mut header::Headers { &mut self.headers }
+
+    /// Set how much of the body `start` may buffer to send a `Content-Length`.
+    ///
+    /// The default limit of `0` sends the head as soon as the response is
+    /// started, and streams the body chunked, which is what a long-lived
+    /// response such as an event stream wants. A few kilobytes let small
+    /// bodies be sent with a `Content-Length`.
+    pub fn set_buffer_limit(&mut self, limit: uint) {
+        self.buffer_limit = limit;
+    }
+
+    /// Set how many bytes of small writes to a chunked body are collected
+    /// into one chunk.
+    ///
+    /// Collected writes are sent when the chunk is full, and on `flush` and
+    /// `end`. A size of `0` sends a chunk for every write. The default is
+    /// `DEFAULT_CHUNK_SIZE`.
+    pub fn set_chunk_size(&mut self, size: uint) {
+        self.chunk_size = size;
+    }
+
+    /// Send an informational `1xx` response before this one.
+    ///
+    /// The head is written and flushed right away, and this response stays
+    /// fresh, so several can be sent before the final one, such as
+    /// `103 Early Hints` with the `Link` headers of resources the final
+    /// response will need. Clients using HTTP/1.0 do not understand interim
+    /// responses, so nothing is sent to them.
+    ///
+    /// `101 Switching Protocols` is a final response, and is an error here,
+    /// as is any status that is not informational.
+    pub fn send_interim(&mut self, status: status::StatusCode,
+                        headers: &header::Headers) -> IoResult<()> {
+        match status as u16 {
+            100 | 102...199 => (),
+            _ => return Err(IoError {
+                kind: InvalidInput,
+                desc: "Interim responses must have an informational status",
+                detail: Some(status.to_string())
+            })
+        }
+        if !self.version.supports_interim() {
+            debug!("not sending {} to an {} client", status, self.version);
+            return Ok(());
+        }
+
+        debug!("writing interim head: {} {}", self.version, status);
+        let version = self.version;
+        let body = self.body();
+        try!(write!(body, "{} {}{}{}", version, status, CR as char, LF as char));
+        try!(headers.write_to(body));
+        try!(body.write(LINE_ENDING));
+        body.flush()
+    }
+
+    /// Send a `103 Early Hints` response with `links`, so the client can
+    /// start loading resources, such as with `rel="preload"`, while this
+    /// response is prepared.
+    pub fn send_early_hints(&mut self, links: common::Link) -> IoResult<()> {
+        let mut headers = header::Headers::new();
+        headers.set(links);
+        self.send_interim(status::StatusCode::Code103, &headers)
+    }
+
+    /// Set `flag` when the head is written, to whether the connection closes
+    /// after this response.
+    ///
+    /// That is the case if the response has `Connection: close`, or for
+    /// HTTP/1.0 if it doesn't have `Connection: keep-alive`. The server uses
+    /// this to stop reading requests from the connection, such as after a
+    /// body sent to an HTTP/1.0 client without a `Content-Length`, which is
+    /// ended by closing the connection.
+    pub fn set_close_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.close_flag = Some(flag);
+    }
+
+    /// Add a hook to run right before the head of this response is written.
+    ///
+    /// Hooks run in the order they were added.
+    pub fn add_start_hook(&mut self, hook: Box<StartHook + Send>) {
+        self.hooks.push(hook);
+    }
+
+    /// Add a cookie to the `Set-Cookie` header of this response.
+    ///
+    /// Use `header::common::set_cookie::CookieBuilder` to set attributes.
+    pub fn set_cookie(&mut self, cookie: Cookie) {
+        let mut cookie = Some(cookie);
+        if let Some(set_cookie) = self.headers.get_mut::<common::SetCookie>() {
+            set_cookie.push(cookie.take().unwrap());
+        }
+        if let Some(cookie) = cookie {
+            self.headers.set(common::SetCookie(vec![cookie]));
+        }
+    }
 }
 
 impl<'a> Response<'a, Streaming> {
     /// Flushes all writing of a response to the client.
-    pub fn end(self) -> IoResult<()> {
+    ///
+    /// A response that is still buffering has not written anything yet, so
+    /// it must be ended to be sent at all.
+    pub fn end(mut self) -> IoResult<()> {
         debug!("ending");
-        try!(self.body.end());
+        try!(self.write_buffered(true));
+        try!(self.write_chunk());
+        try!(self.body.take().expect(REFRAMING).end());
         Ok(())
     }
+
+    /// Write the head that was held back, and the body buffered so far.
+    ///
+    /// If the body has `ended`, it is sent with a `Content-Length`, and
+    /// otherwise chunked.
+    fn write_buffered(&mut self, ended: bool) -> IoResult<()> {
+        let buf = match self.buffer.take() {
+            Some(buf) => buf,
+            None => return Ok(())
+        };
+        if ended {
+            self.headers.set(common::ContentLength(buf.len()));
+        } else {
+            self.set_chunked();
+        }
+        try!(self.write_head());
+
+        // the body is a ThroughWriter until now
+        let stream = self.take_stream();
+        self.body = Some(if ended {
+            SizedWriter(stream, buf.len())
+        } else {
+            unsized_writer(self.version, stream)
+        });
+
+        if buf.is_empty() {
+            // an empty chunk would be the last-chunk
+            Ok(())
+        } else {
+            self.body().write(buf[])
+        }
+    }
+
+    /// Send the collected small writes as a chunk.
+    fn write_chunk(&mut self) -> IoResult<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = mem::replace(&mut self.chunk, Vec::new());
+        self.body().write(chunk[])
+    }
+}
+
+/// The writer for a body of unknown length.
+fn unsized_writer<W: Writer>(http_version: version::HttpVersion, stream: W) -> HttpWriter<W> {
+    if http_version.supports_chunked() {
+        ChunkedWriter(stream)
+    } else {
+        ThroughWriter(stream)
+    }
 }
 
 impl<'a> Writer for Response<'a, Streaming> {
     fn write(&mut self, msg: &[u8]) -> IoResult<()> {
         debug!("write {} bytes", msg.len());
-        self.body.write(msg)
+        if let Some(ref mut buf) = self.buffer {
+            if buf.len() + msg.len() <= self.buffer_limit {
+                buf.push_all(msg);
+                return Ok(());
+            }
+        }
+        try!(self.write_buffered(false));
+
+        let coalesce = match self.body {
+            Some(ChunkedWriter(_)) => self.chunk_size > 0 && !msg.is_empty(),
+            _ => false
+        };
+        if !coalesce {
+            try!(self.write_chunk());
+            return self.body().write(msg);
+        }
+        if self.chunk.is_empty() && msg.len() >= self.chunk_size {
+            // nothing to collect this with
+            return self.body().write(msg);
+        }
+        self.chunk.push_all(msg);
+        if self.chunk.len() >= self.chunk_size {
+            try!(self.write_chunk());
+        }
+        Ok(())
     }
 
     fn flush(&mut self) -> IoResult<()> {
-        self.body.flush()
+        try!(self.write_buffered(false));
+        try!(self.write_chunk());
+        self.body().flush()
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::io::MemWriter;
+    use std::str::from_utf8;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, SeqCst};
+    use cookie::Cookie;
+    use header::Headers;
+    use header::common::{Connection, ContentLength, Link, SetCookie, TransferEncoding};
+    use header::common::connection::KeepAlive;
+    use header::common::link::LinkValue;
+    use header::common::transfer_encoding::Encoding::Chunked;
+    use net::Fresh;
+    use status::StatusCode::{Code103, Continue, NotFound, NotModified, SwitchingProtocols};
+    use version::HttpVersion::Http10;
+    use super::{Response, StartHook};
+
+    #[test]
+    fn test_start_tunnel_has_no_framing() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.headers_mut().set(ContentLength(5));
+            let mut res = res.start_tunnel().unwrap();
+            res.write(b"raw").unwrap();
+            res.end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(!s.contains("Content-Length"));
+        assert!(!s.contains("Transfer-Encoding"));
+        assert!(s.ends_with("\r\n\r\nraw"));
+    }
+
+    #[test]
+    fn test_reason() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            *res.status_mut() = NotFound;
+            assert_eq!(res.reason(), "Not Found");
+            res.set_reason("Nothing\r\n Here");
+            assert_eq!(res.reason(), "Nothing Here");
+            res.start().unwrap().end().unwrap();
+        }
+        assert!(from_utf8(w.get_ref()).unwrap().starts_with("HTTP/1.1 404 Nothing Here\r\n"));
+
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.set_reason("");
+            res.start().unwrap().end().unwrap();
+        }
+        assert!(from_utf8(w.get_ref()).unwrap().starts_with("HTTP/1.1 200 \r\n"));
+    }
+
+    #[test]
+    fn test_start_writes_head() {
+        let mut w = MemWriter::new();
+        {
+            // not ended, so only what start wrote is sent
+            let _res = Response::new(&mut w).start().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(s.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(s.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_buffered_content_length() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.set_buffer_limit(8192);
+            let mut res = res.start().unwrap();
+            res.write(b"hello ").unwrap();
+            res.write(b"world").unwrap();
+            res.end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.contains("Content-Length: 11\r\n"));
+        assert!(!s.contains("Transfer-Encoding"));
+        assert!(s.ends_with("\r\n\r\nhello world"));
+    }
+
+    #[test]
+    fn test_buffer_limit_chunked() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.set_buffer_limit(4);
+            let mut res = res.start().unwrap();
+            res.write(b"abc").unwrap();
+            res.write(b"de").unwrap();
+            res.end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(!s.contains("Content-Length"));
+        assert!(s.ends_with("\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_chunk_size() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.set_chunk_size(4);
+            let mut res = res.start().unwrap();
+            for part in ["ab", "cd", "ef", "ghijk"].iter() {
+                res.write(part.as_bytes()).unwrap();
+            }
+            res.end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.ends_with("\r\n\r\n4\r\nabcd\r\n7\r\nefghijk\r\n0\r\n\r\n"));
+
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.set_chunk_size(0);
+            let mut res = res.start().unwrap();
+            res.write(b"ab").unwrap();
+            res.write(b"cd").unwrap();
+            res.end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.ends_with("\r\n\r\n2\r\nab\r\n2\r\ncd\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_flush_streams() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.set_buffer_limit(8192);
+            let mut res = res.start().unwrap();
+            res.write(b"event").unwrap();
+            res.flush().unwrap();
+            res.end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(s.ends_with("\r\n\r\n5\r\nevent\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_send_interim() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            let mut hints = Headers::new();
+            hints.set_raw("Link", vec![b"</style.css>; rel=preload".to_vec()]);
+            res.send_interim(Code103, &hints).unwrap();
+            res.send_interim(Code103, &hints).unwrap();
+            assert!(res.send_interim(SwitchingProtocols, &hints).is_err());
+            assert!(res.send_interim(NotFound, &hints).is_err());
+            res.headers_mut().set(ContentLength(0));
+            res.start().unwrap().end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        let interim = "HTTP/1.1 103 Early Hints\r\nLink: </style.css>; rel=preload\r\n\r\n";
+        assert!(s.starts_with(format!("{}{}HTTP/1.1 200 OK\r\n", interim, interim)[]));
+    }
+
+    #[test]
+    fn test_send_early_hints() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            let links = Link(vec![LinkValue::new("/style.css").param("rel", "preload")]);
+            res.send_early_hints(links).unwrap();
+            res.start().unwrap().end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.starts_with("HTTP/1.1 103 Early Hints\r\n\
+                               Link: </style.css>; rel=\"preload\"\r\n\
+                               \r\n\
+                               HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn test_send_interim_http10() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.version = Http10;
+            res.send_interim(Continue, &Headers::new()).unwrap();
+            res.start().unwrap().end().unwrap();
+        }
+        assert!(from_utf8(w.get_ref()).unwrap().starts_with("HTTP/1.0 200 OK\r\n"));
+    }
+
+    #[test]
+    fn test_http10_close_delimited() {
+        let closes = Arc::new(AtomicBool::new(false));
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.version = Http10;
+            res.set_close_flag(closes.clone());
+            res.headers_mut().set(TransferEncoding(vec![Chunked]));
+            let mut res = res.start().unwrap();
+            res.write(b"hello").unwrap();
+            res.end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.starts_with("HTTP/1.0 200 OK\r\n"));
+        assert!(s.contains("Connection: close\r\n"));
+        assert!(!s.contains("Transfer-Encoding"));
+        assert!(s.ends_with("\r\n\r\nhello"));
+        assert!(closes.load(SeqCst));
+    }
+
+    #[test]
+    fn test_http10_buffered() {
+        let closes = Arc::new(AtomicBool::new(true));
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.version = Http10;
+            res.set_buffer_limit(8192);
+            res.set_close_flag(closes.clone());
+            res.headers_mut().set(Connection(vec![KeepAlive]));
+            let mut res = res.start().unwrap();
+            res.write(b"hello").unwrap();
+            res.end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.contains("Content-Length: 5\r\n"));
+        assert!(s.contains("Connection: keep-alive\r\n"));
+        assert!(!closes.load(SeqCst));
+
+        // without keep-alive, a 1.0 connection closes after the response
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.version = Http10;
+            res.set_close_flag(closes.clone());
+            res.start().unwrap().end().unwrap();
+        }
+        assert!(closes.load(SeqCst));
+    }
+
+    #[test]
+    fn test_set_cookie() {
+        let mut w = MemWriter::new();
+        let mut res = Response::new(&mut w);
+        res.set_cookie(Cookie::new("foo".to_string(), "bar".to_string()));
+        res.set_cookie(Cookie::new("baz".to_string(), "quux".to_string()));
+        let cookies = res.headers().get::<SetCookie>().unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[1].name[], "baz");
+    }
+
+    #[test]
+    fn test_start_without_body() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            *res.status_mut() = NotModified;
+            let mut res = res.start().unwrap();
+            assert!(res.write(b"body").is_err());
+            res.end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+        assert!(!s.contains("Transfer-Encoding"));
+        assert!(s.ends_with("\r\n\r\n"));
+    }
+
+    struct NotFoundHook;
+
+    impl StartHook for NotFoundHook {
+        fn on_start(&mut self, res: &mut Response<Fresh>) {
+            *res.status_mut() = NotFound;
+        }
+    }
+
+    #[test]
+    fn test_start_hook() {
+        let mut w = MemWriter::new();
+        {
+            let mut res = Response::new(&mut w);
+            res.add_start_hook(box NotFoundHook);
+            res.start().unwrap().end().unwrap();
+        }
+        let s = from_utf8(w.get_ref()).unwrap();
+        assert!(s.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
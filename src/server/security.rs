@@ -0,0 +1,159 @@
+//! Security headers with safe defaults.
+//!
+//! The `SecureHeaders` middleware adds headers asking browsers to protect a
+//! site's pages: `X-Frame-Options: DENY`, `X-Content-Type-Options: nosniff`
+//! and `Content-Security-Policy: default-src 'self'`, unless configured
+//! otherwise. `Strict-Transport-Security` is only added if one is given,
+//! as it should only be sent by HTTPS servers.
+//!
+//! ```no_run
+//! # use hyper::Server;
+//! # use hyper::Ipv4Addr;
+//! # use hyper::server::{Request, Response};
+//! # fn site(_: Request, res: Response) { res.start().unwrap().end().unwrap(); }
+//! use hyper::header::common::{ContentSecurityPolicy, XFrameOptions};
+//! use hyper::server::security::SecureHeaders;
+//!
+//! let site = SecureHeaders::new(site)
+//!     .frame_options(XFrameOptions::SameOrigin)
+//!     .content_security_policy(ContentSecurityPolicy::new()
+//!         .directive("default-src", &["'self'"])
+//!         .directive("img-src", &["*"]));
+//! Server::http(Ipv4Addr(127, 0, 0, 1), 3000).listen(site).unwrap();
+//! ```
+use header::common::{ContentSecurityPolicy, StrictTransportSecurity};
+use header::common::{XContentTypeOptions, XFrameOptions};
+use net::Fresh;
+use server::{Handler, Request, Response};
+
+/// A middleware `Handler` adding security headers to every response.
+///
+/// The headers are set before the wrapped `Handler` is called, so it can
+/// still change or remove them for a response.
+pub struct SecureHeaders<H> {
+    inner: H,
+    frame_options: Option<XFrameOptions>,
+    nosniff: bool,
+    csp: Option<ContentSecurityPolicy>,
+    hsts: Option<StrictTransportSecurity>
+}
+
+impl<H: Handler> SecureHeaders<H> {
+    /// Wrap a `Handler`, with the default headers.
+    pub fn new(inner: H) -> SecureHeaders<H> {
+        SecureHeaders {
+            inner: inner,
+            frame_options: Some(XFrameOptions::Deny),
+            nosniff: true,
+            csp: Some(ContentSecurityPolicy::new().directive("default-src", &["'self'"])),
+            hsts: None
+        }
+    }
+
+    /// Set the `X-Frame-Options` header.
+    pub fn frame_options(mut self, options: XFrameOptions) -> SecureHeaders<H> {
+        self.frame_options = Some(options);
+        self
+    }
+
+    /// Don't send `X-Frame-Options`.
+    pub fn without_frame_options(mut self) -> SecureHeaders<H> {
+        self.frame_options = None;
+        self
+    }
+
+    /// Don't send `X-Content-Type-Options: nosniff`.
+    pub fn without_nosniff(mut self) -> SecureHeaders<H> {
+        self.nosniff = false;
+        self
+    }
+
+    /// Set the `Content-Security-Policy` header.
+    pub fn content_security_policy(mut self, csp: ContentSecurityPolicy) -> SecureHeaders<H> {
+        self.csp = Some(csp);
+        self
+    }
+
+    /// Don't send a `Content-Security-Policy`.
+    pub fn without_content_security_policy(mut self) -> SecureHeaders<H> {
+        self.csp = None;
+        self
+    }
+
+    /// Send a `Strict-Transport-Security` header, for HTTPS servers.
+    pub fn strict_transport_security(mut self, hsts: StrictTransportSecurity) -> SecureHeaders<H> {
+        self.hsts = Some(hsts);
+        self
+    }
+}
+
+impl<H: Handler> Handler for SecureHeaders<H> {
+    fn handle(&self, req: Request, mut res: Response<Fresh>) {
+        {
+            let headers = res.headers_mut();
+            if let Some(ref options) = self.frame_options {
+                headers.set(options.clone());
+            }
+            if self.nosniff {
+                headers.set(XContentTypeOptions);
+            }
+            if let Some(ref csp) = self.csp {
+                headers.set(csp.clone());
+            }
+            if let Some(hsts) = self.hsts {
+                headers.set(hsts);
+            }
+        }
+        self.inner.handle(req, res);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use header::common::{ContentSecurityPolicy, StrictTransportSecurity, XFrameOptions};
+    use mock::handle;
+    use net::Fresh;
+    use server::{Handler, Request, Response};
+    use super::SecureHeaders;
+
+    fn run<H: Handler>(handler: &H) -> String {
+        handle(handler, "GET / HTTP/1.1\r\n\r\n")
+    }
+
+    fn hello(_: Request, res: Response<Fresh>) {
+        res.start().unwrap().end().unwrap();
+    }
+
+    fn framed(_: Request, mut res: Response<Fresh>) {
+        res.headers_mut().remove::<XFrameOptions>();
+        res.start().unwrap().end().unwrap();
+    }
+
+    #[test]
+    fn test_defaults() {
+        let res = run(&SecureHeaders::new(hello));
+        assert!(res[].contains("X-Frame-Options: DENY\r\n"));
+        assert!(res[].contains("X-Content-Type-Options: nosniff\r\n"));
+        assert!(res[].contains("Content-Security-Policy: default-src 'self'\r\n"));
+        assert!(!res[].contains("Strict-Transport-Security"));
+    }
+
+    #[test]
+    fn test_configured() {
+        let secure = SecureHeaders::new(hello)
+            .frame_options(XFrameOptions::SameOrigin)
+            .without_nosniff()
+            .content_security_policy(ContentSecurityPolicy::new().directive("img-src", &["*"]))
+            .strict_transport_security(StrictTransportSecurity::including_subdomains(3600));
+        let res = run(&secure);
+        assert!(res[].contains("X-Frame-Options: SAMEORIGIN\r\n"));
+        assert!(!res[].contains("X-Content-Type-Options"));
+        assert!(res[].contains("Content-Security-Policy: img-src *\r\n"));
+        assert!(res[].contains("Strict-Transport-Security: max-age=3600; includeSubDomains\r\n"));
+
+        let res = run(&SecureHeaders::new(framed).without_content_security_policy());
+        assert!(!res[].contains("X-Frame-Options"));
+        assert!(!res[].contains("Content-Security-Policy"));
+    }
+}
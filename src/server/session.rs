@@ -0,0 +1,348 @@
+//! Sessions stored in a signed cookie.
+//!
+//! The `Sessions` middleware keeps a small map of strings for each client in
+//! a cookie. The cookie is signed with HMAC, so clients cannot change it, and
+//! can also be encrypted, so they cannot read it either.
+//!
+//! ```no_run
+//! # use hyper::Server;
+//! # use hyper::Ipv4Addr;
+//! # use hyper::server::{Request, Response};
+//! use hyper::server::session::{Session, Sessions};
+//!
+//! fn visits(req: Request, res: Response) {
+//!     let session = req.extensions.get::<Session>().unwrap();
+//!     let count = session.get("visits").and_then(|n| n[].parse()).unwrap_or(0u) + 1;
+//!     session.insert("visits", count.to_string()[]);
+//!     res.start().unwrap().end().unwrap();
+//! }
+//!
+//! let sessions = Sessions::new(visits, b"some secret key material")
+//!     .encrypted()
+//!     .max_age(24 * 60 * 60);
+//! Server::http(Ipv4Addr(127, 0, 0, 1), 3000).listen(sessions).unwrap();
+//! ```
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use cookie::{Cookie, CookieJar};
+use time::get_time;
+
+use header::common::Cookies;
+use net::Fresh;
+use percent_encoding::{form_decode, form_encode};
+use server::{Handler, Request, Response};
+use server::response::StartHook;
+
+/// The name of the session cookie, unless another is configured.
+pub const DEFAULT_NAME: &'static str = "hyper_session";
+
+/// The session of the client making a request.
+///
+/// The `Sessions` middleware puts this in the `extensions` of every request.
+/// Any change is saved to the session cookie when the response is started,
+/// so a `Session` must be changed before calling `Response::start`.
+#[deriving(Clone)]
+pub struct Session {
+    data: Arc<Mutex<SessionData>>
+}
+
+struct SessionData {
+    map: HashMap<String, String>,
+    changed: bool
+}
+
+impl Session {
+    fn new(map: HashMap<String, String>) -> Session {
+        Session {
+            data: Arc::new(Mutex::new(SessionData {
+                map: map,
+                changed: false
+            }))
+        }
+    }
+
+    /// Get the value of `key` in this session.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.data.lock().map.get(key).cloned()
+    }
+
+    /// Set `key` to `value`, returning the previous value, if any.
+    pub fn insert(&self, key: &str, value: &str) -> Option<String> {
+        let mut data = self.data.lock();
+        data.changed = true;
+        data.map.insert(key.to_string(), value.to_string())
+    }
+
+    /// Remove `key` from this session, returning its value, if any.
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut data = self.data.lock();
+        let old = data.map.remove(key);
+        if old.is_some() {
+            data.changed = true;
+        }
+        old
+    }
+
+    /// Remove everything from this session.
+    ///
+    /// An empty session is saved by removing the cookie.
+    pub fn clear(&self) {
+        let mut data = self.data.lock();
+        data.changed = true;
+        data.map.clear();
+    }
+
+    /// Returns whether this session has no values.
+    pub fn is_empty(&self) -> bool {
+        self.data.lock().map.is_empty()
+    }
+}
+
+/// A middleware `Handler` that gives every request a `Session`.
+///
+/// The key material given to `new` is used to sign the cookie, and to
+/// encrypt it when `encrypted` is set. Changing the key invalidates every
+/// existing session.
+pub struct Sessions<H> {
+    inner: H,
+    config: Arc<Config>
+}
+
+#[deriving(Clone)]
+struct Config {
+    key: Vec<u8>,
+    name: String,
+    path: String,
+    encrypted: bool,
+    secure: bool,
+    max_age: Option<u64>
+}
+
+impl<H: Handler> Sessions<H> {
+    /// Wrap a `Handler`, signing session cookies with `key`.
+    pub fn new(inner: H, key: &[u8]) -> Sessions<H> {
+        Sessions {
+            inner: inner,
+            config: Arc::new(Config {
+                key: key.to_vec(),
+                name: DEFAULT_NAME.to_string(),
+                path: "/".to_string(),
+                encrypted: false,
+                secure: false,
+                max_age: None
+            })
+        }
+    }
+
+    /// Set the name of the session cookie.
+    pub fn name(mut self, name: &str) -> Sessions<H> {
+        self.config.make_unique().name = name.to_string();
+        self
+    }
+
+    /// Set the `Path` of the session cookie. Defaults to `/`.
+    pub fn path(mut self, path: &str) -> Sessions<H> {
+        self.config.make_unique().path = path.to_string();
+        self
+    }
+
+    /// Encrypt the session cookie, as well as signing it.
+    pub fn encrypted(mut self) -> Sessions<H> {
+        self.config.make_unique().encrypted = true;
+        self
+    }
+
+    /// Only send the session cookie over HTTPS.
+    pub fn secure(mut self) -> Sessions<H> {
+        self.config.make_unique().secure = true;
+        self
+    }
+
+    /// Expire sessions `secs` seconds after they were last changed.
+    ///
+    /// Besides setting `Max-Age` on the cookie, the expiry is stored in the
+    /// signed value, so an expired cookie is not accepted even if a client
+    /// keeps sending it. Without this, the cookie lasts until the browser
+    /// is closed.
+    pub fn max_age(mut self, secs: u64) -> Sessions<H> {
+        self.config.make_unique().max_age = Some(secs);
+        self
+    }
+}
+
+impl<H: Handler> Handler for Sessions<H> {
+    fn handle(&self, mut req: Request, mut res: Response<Fresh>) {
+        let session = Session::new(load(&*self.config, &req));
+        res.add_start_hook(box SaveSession {
+            session: session.clone(),
+            config: self.config.clone()
+        });
+        req.extensions.insert(session);
+        self.inner.handle(req, res);
+    }
+}
+
+struct SaveSession {
+    session: Session,
+    config: Arc<Config>
+}
+
+impl StartHook for SaveSession {
+    fn on_start(&mut self, res: &mut Response<Fresh>) {
+        let data = self.session.data.lock();
+        if !data.changed {
+            return;
+        }
+
+        let config = &*self.config;
+        let mut cookie = if data.map.is_empty() {
+            let mut cookie = Cookie::new(config.name.clone(), String::new());
+            cookie.max_age = Some(0);
+            cookie
+        } else {
+            let pairs: Vec<(String, String)> = data.map.iter().map(|(k, v)| {
+                (k.clone(), v.clone())
+            }).collect();
+            let expires = match config.max_age {
+                Some(secs) => get_time().sec + secs as i64,
+                None => 0
+            };
+            let value = format!("{}:{}", expires, form_encode(pairs[]));
+
+            let jar = CookieJar::new(config.key[]);
+            let mut cookie = Cookie::new(config.name.clone(), value);
+            cookie.max_age = config.max_age;
+            if config.encrypted {
+                jar.encrypted().add(cookie);
+            } else {
+                jar.signed().add(cookie);
+            }
+            match jar.delta().pop() {
+                Some(cookie) => cookie,
+                None => return
+            }
+        };
+        cookie.path = Some(config.path.clone());
+        cookie.httponly = true;
+        cookie.secure = config.secure;
+        res.set_cookie(cookie);
+    }
+}
+
+/// Read the session values from the request cookie.
+///
+/// A missing, tampered or expired cookie is an empty session.
+fn load(config: &Config, req: &Request) -> HashMap<String, String> {
+    let cookie = req.headers.get::<Cookies>().and_then(|cookies| {
+        let jar = cookies.to_cookie_jar(config.key[]);
+        if config.encrypted {
+            jar.encrypted().find(config.name[])
+        } else {
+            jar.signed().find(config.name[])
+        }
+    });
+
+    let mut map = HashMap::new();
+    let cookie = match cookie {
+        Some(cookie) => cookie,
+        None => return map
+    };
+    let (expires, pairs) = match cookie.value[].find(':') {
+        Some(idx) => (cookie.value[..idx].parse::<i64>(), cookie.value[idx + 1..]),
+        None => return map
+    };
+    // A cookie issued before `max_age` was set has no expiry.
+    match expires {
+        Some(0) if config.max_age.is_none() => (),
+        Some(expires) if expires > get_time().sec => (),
+        _ => {
+            debug!("session cookie expired or invalid");
+            return map;
+        }
+    }
+    for (key, value) in form_decode(pairs.as_bytes()).into_iter() {
+        map.insert(key, value);
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+
+    use mock::handle;
+    use net::Fresh;
+    use server::{Handler, Request, Response};
+    use super::{Session, Sessions};
+
+    fn run<H: Handler>(handler: &H, cookie: Option<&str>) -> String {
+        let head = match cookie {
+            Some(cookie) => format!("GET / HTTP/1.1\r\nCookie: {}\r\n\r\n", cookie),
+            None => "GET / HTTP/1.1\r\n\r\n".to_string()
+        };
+        handle(handler, head[])
+    }
+
+    fn session_cookie(res: &str) -> Option<String> {
+        res.lines().find(|line| line.starts_with("Set-Cookie: ")).map(|line| {
+            line["Set-Cookie: ".len()..].split(';').next().unwrap().to_string()
+        })
+    }
+
+    fn count(req: Request, res: Response<Fresh>) {
+        {
+            let session = req.extensions.get::<Session>().unwrap();
+            let n = session.get("n").and_then(|n| n[].parse()).unwrap_or(0u);
+            session.insert("n", (n + 1).to_string()[]);
+        }
+        res.start().unwrap().end().unwrap();
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let sessions = Sessions::new(count, b"secret").name("sid");
+
+        let first = session_cookie(run(&sessions, None)[]).unwrap();
+        assert!(first[].starts_with("sid="));
+
+        let second = session_cookie(run(&sessions, Some(first[]))[]).unwrap();
+        let check = |&: req: Request, res: Response<Fresh>| {
+            assert_eq!(req.extensions.get::<Session>().unwrap().get("n"), Some("2".to_string()));
+            res.start().unwrap().end().unwrap();
+        };
+        run(&Sessions::new(check, b"secret").name("sid"), Some(second[]));
+    }
+
+    #[test]
+    fn test_session_tampered() {
+        let sessions = Sessions::new(count, b"secret").encrypted();
+        let cookie = session_cookie(run(&sessions, None)[]).unwrap();
+
+        fn check(req: Request, res: Response<Fresh>) {
+            assert!(req.extensions.get::<Session>().unwrap().is_empty());
+            res.start().unwrap().end().unwrap();
+        }
+        run(&Sessions::new(check, b"other key").encrypted(), Some(cookie[]));
+        run(&Sessions::new(check, b"secret").encrypted(),
+            Some(format!("{}x", cookie)[]));
+    }
+
+    #[test]
+    fn test_session_without_expiry_rejected_with_max_age() {
+        let cookie = session_cookie(run(&Sessions::new(count, b"secret"), None)[]).unwrap();
+
+        fn check(req: Request, res: Response<Fresh>) {
+            assert!(req.extensions.get::<Session>().unwrap().is_empty());
+            res.start().unwrap().end().unwrap();
+        }
+        run(&Sessions::new(check, b"secret").max_age(60), Some(cookie[]));
+    }
+
+    #[test]
+    fn test_unchanged_session_not_sent() {
+        let noop = |&: _: Request, res: Response<Fresh>| {
+            res.start().unwrap().end().unwrap();
+        };
+        assert_eq!(session_cookie(run(&Sessions::new(noop, b"secret"), None)[]), None);
+    }
+}
@@ -2,6 +2,8 @@
 use std::fmt;
 use std::mem::transmute;
 
+use serialize::{Encodable, Encoder, Decodable, Decoder};
+
 // shamelessly lifted from Teepee. I tried a few schemes, this really
 // does seem like the best.
 
@@ -30,7 +32,10 @@ pub enum StatusCode {
     SwitchingProtocols = 101,
     /// 102 Processing
     Processing = 102,
-    /// 103 (unregistered)
+    /// 103 Early Hints
+    ///
+    /// Keeps the name it had while unregistered, so existing code using it
+    /// still compiles.
     Code103 = 103,
     /// 104 (unregistered)
     Code104 = 104,
@@ -1047,7 +1052,7 @@ impl StatusCode {
             StatusCode::Continue => Some("Continue"),
             StatusCode::SwitchingProtocols => Some("Switching Protocols"),
             StatusCode::Processing => Some("Processing"),
-            StatusCode::Code103 => None,
+            StatusCode::Code103 => Some("Early Hints"),
             StatusCode::Code104 => None,
             StatusCode::Code105 => None,
             StatusCode::Code106 => None,
@@ -1567,6 +1572,51 @@ impl StatusCode {
             StatusClass::ServerError
         }
     }
+
+    /// Whether the status code is registered, and so has a canonical reason.
+    ///
+    /// An unregistered code is understood by its class, as `class().default_code()`:
+    ///
+    /// ```rust
+    /// # use hyper::status::StatusCode::{Code299, Ok};
+    /// assert!(Ok.is_registered());
+    /// assert!(!Code299.is_registered());
+    /// assert!(Code299.is_success());
+    /// ```
+    #[inline]
+    pub fn is_registered(&self) -> bool {
+        self.canonical_reason().is_some()
+    }
+
+    /// Check if class is Informational.
+    #[inline]
+    pub fn is_informational(&self) -> bool {
+        self.class() == StatusClass::Informational
+    }
+
+    /// Check if class is Success.
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        self.class() == StatusClass::Success
+    }
+
+    /// Check if class is Redirection.
+    #[inline]
+    pub fn is_redirection(&self) -> bool {
+        self.class() == StatusClass::Redirection
+    }
+
+    /// Check if class is ClientError.
+    #[inline]
+    pub fn is_client_error(&self) -> bool {
+        self.class() == StatusClass::ClientError
+    }
+
+    /// Check if class is ServerError.
+    #[inline]
+    pub fn is_server_error(&self) -> bool {
+        self.class() == StatusClass::ServerError
+    }
 }
 
 impl Copy for StatusCode {}
@@ -1658,6 +1708,23 @@ impl ToPrimitive for StatusCode {
     }
 }
 
+/// A `StatusCode` is encoded as its number.
+impl<E, S: Encoder<E>> Encodable<S, E> for StatusCode {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
+        s.emit_u16(*self as u16)
+    }
+}
+
+impl<E, D: Decoder<E>> Decodable<D, E> for StatusCode {
+    fn decode(d: &mut D) -> Result<StatusCode, E> {
+        let code = try!(d.read_u16());
+        match FromPrimitive::from_u16(code) {
+            Some(status) => Ok(status),
+            None => Err(d.error(format!("invalid status code: {}", code)[]))
+        }
+    }
+}
+
 /// The class of an HTTP `Status-Code`.
 ///
 /// [RFC 2616, section 6.1.1 (Status Code and Reason
@@ -1763,3 +1830,17 @@ impl ToPrimitive for StatusClass {
         Some(*self as u64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serialize::json;
+    use super::StatusCode;
+    use super::StatusCode::{NotFound, Code499};
+
+    #[test]
+    fn test_serialize() {
+        assert_eq!(json::encode(&NotFound)[], "404");
+        assert_eq!(json::decode::<StatusCode>("499").unwrap(), Code499);
+        assert!(json::decode::<StatusCode>("600").is_err());
+    }
+}
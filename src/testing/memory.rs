@@ -0,0 +1,353 @@
+//! Connections that stay in memory, for wiring a `Server` to a `Client`
+//! without binding TCP ports.
+//!
+//! A `MemoryListener` bound to an address can be connected to with a
+//! `MemoryConnector`, by the port it was bound to. Port `0` gets a free one,
+//! which `Listening::socket` names.
+//!
+//! ```
+//! # use hyper::server::{Request, Response};
+//! # fn hello(_: Request, res: Response) {
+//! #     let mut res = res.start().unwrap();
+//! #     res.write(b"Hello World!").unwrap();
+//! #     res.end().unwrap();
+//! # }
+//! use hyper::{Client, Ipv4Addr, Server};
+//! use hyper::testing::{MemoryAcceptor, MemoryConnector, MemoryListener, MemoryStream};
+//!
+//! let mut listening = Server::http(Ipv4Addr(127, 0, 0, 1), 0)
+//!     .listen_network::<_, MemoryStream, MemoryAcceptor, MemoryListener>(hello, 1).unwrap();
+//!
+//! let mut client = Client::with_connector(MemoryConnector);
+//! let url = format!("http://127.0.0.1:{}/", listening.socket.port);
+//! let mut res = client.get(url[]).send().unwrap();
+//! assert_eq!(res.read_to_string().unwrap()[], "Hello World!");
+//! listening.close().unwrap();
+//! ```
+use std::cmp::min;
+use std::collections::HashMap;
+use std::comm::{channel, Sender, Receiver};
+use std::fmt;
+use std::io::{IoResult, IoError, Listener, Acceptor, EndOfFile, BrokenPipe, ConnectionRefused,
+              InvalidInput, PathAlreadyExists, standard_error};
+use std::io::net::ip::{Ipv4Addr, Port, SocketAddr, ToSocketAddr};
+use std::mem;
+use std::slice::bytes::copy_memory;
+use std::sync::{Arc, Mutex, Once, ONCE_INIT};
+use std::sync::atomic::{AtomicUint, INIT_ATOMIC_UINT, SeqCst};
+
+use net::{NetworkAcceptor, NetworkConnector, NetworkListener, NetworkStream};
+
+/// The listeners bound in this process, by port.
+type Registry = Mutex<HashMap<Port, (SocketAddr, Sender<Option<MemoryStream>>)>>;
+
+static REGISTRY_INIT: Once = ONCE_INIT;
+static mut REGISTRY: *const Registry = 0 as *const Registry;
+
+fn registry() -> &'static Registry {
+    unsafe {
+        REGISTRY_INIT.doit(|| {
+            REGISTRY = mem::transmute(box Mutex::new(HashMap::new()));
+        });
+        &*REGISTRY
+    }
+}
+
+static NEXT_PORT: AtomicUint = INIT_ATOMIC_UINT;
+
+/// A port from the dynamic range, not bound by a listener yet.
+fn free_port(bound: &HashMap<Port, (SocketAddr, Sender<Option<MemoryStream>>)>) -> Port {
+    loop {
+        let port = (49152 + NEXT_PORT.fetch_add(1, SeqCst) % 16384) as Port;
+        if !bound.contains_key(&port) {
+            return port;
+        }
+    }
+}
+
+/// The bytes sent to one end of a connection, and not read yet.
+struct Inbox {
+    rx: Receiver<Vec<u8>>,
+    buf: Vec<u8>,
+    pos: uint,
+    eof: bool
+}
+
+/// Ends the stream of the peer once the last clone of an end is dropped.
+struct Hangup(Sender<Vec<u8>>);
+
+impl Drop for Hangup {
+    fn drop(&mut self) {
+        let _ = self.0.send_opt(Vec::new());
+    }
+}
+
+/// One end of an in-memory connection.
+///
+/// The bytes written to one end are read from the other. Clones share the
+/// same end, like clones of a `TcpStream`. An empty message on the channel
+/// ends the stream, which is sent when the other end is dropped, or when
+/// `close_read` is called.
+#[deriving(Clone)]
+pub struct MemoryStream {
+    inbox: Arc<Mutex<Inbox>>,
+    outbox: Sender<Vec<u8>>,
+    wake: Sender<Vec<u8>>,
+    _hangup: Arc<Mutex<Hangup>>,
+    peer: SocketAddr
+}
+
+impl MemoryStream {
+    /// A connected pair of streams, the first at `a` and the second at `b`.
+    pub fn pair(a: SocketAddr, b: SocketAddr) -> (MemoryStream, MemoryStream) {
+        let (a_tx, a_rx) = channel();
+        let (b_tx, b_rx) = channel();
+        (MemoryStream::end(a_rx, a_tx.clone(), b_tx.clone(), b),
+         MemoryStream::end(b_rx, b_tx, a_tx, a))
+    }
+
+    fn end(rx: Receiver<Vec<u8>>, wake: Sender<Vec<u8>>, outbox: Sender<Vec<u8>>,
+           peer: SocketAddr) -> MemoryStream {
+        MemoryStream {
+            inbox: Arc::new(Mutex::new(Inbox {
+                rx: rx,
+                buf: Vec::new(),
+                pos: 0,
+                eof: false
+            })),
+            _hangup: Arc::new(Mutex::new(Hangup(outbox.clone()))),
+            outbox: outbox,
+            wake: wake,
+            peer: peer
+        }
+    }
+}
+
+impl fmt::Show for MemoryStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MemoryStream {{ peer: {} }}", self.peer)
+    }
+}
+
+impl Reader for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        let mut inbox = self.inbox.lock();
+        while inbox.pos == inbox.buf.len() {
+            if inbox.eof {
+                return Err(standard_error(EndOfFile));
+            }
+            match inbox.rx.recv_opt() {
+                Ok(bytes) => if bytes.is_empty() {
+                    inbox.eof = true;
+                } else {
+                    inbox.buf = bytes;
+                    inbox.pos = 0;
+                },
+                Err(()) => inbox.eof = true
+            }
+        }
+        let pos = inbox.pos;
+        let n = min(buf.len(), inbox.buf.len() - pos);
+        copy_memory(buf, inbox.buf[pos..pos + n]);
+        inbox.pos += n;
+        Ok(n)
+    }
+}
+
+impl Writer for MemoryStream {
+    fn write(&mut self, msg: &[u8]) -> IoResult<()> {
+        if msg.is_empty() {
+            return Ok(());
+        }
+        self.outbox.send_opt(msg.to_vec()).map_err(|_| standard_error(BrokenPipe))
+    }
+}
+
+impl NetworkStream for MemoryStream {
+    fn peer_name(&mut self) -> IoResult<SocketAddr> {
+        Ok(self.peer)
+    }
+
+    fn close_read(&mut self) -> IoResult<()> {
+        let _ = self.wake.send_opt(Vec::new());
+        Ok(())
+    }
+}
+
+/// A listener for in-memory connections, found by a `MemoryConnector` by
+/// the port it is bound to.
+pub struct MemoryListener {
+    addr: SocketAddr,
+    tx: Sender<Option<MemoryStream>>,
+    rx: Receiver<Option<MemoryStream>>
+}
+
+impl Listener<MemoryStream, MemoryAcceptor> for MemoryListener {
+    fn listen(self) -> IoResult<MemoryAcceptor> {
+        Ok(MemoryAcceptor {
+            addr: self.addr,
+            tx: self.tx,
+            rx: Arc::new(Mutex::new(self.rx))
+        })
+    }
+}
+
+impl NetworkListener<MemoryStream, MemoryAcceptor> for MemoryListener {
+    fn bind<To: ToSocketAddr>(addr: To) -> IoResult<MemoryListener> {
+        let mut addr = try!(addr.to_socket_addr());
+        let mut bound = registry().lock();
+        if addr.port == 0 {
+            addr.port = free_port(&*bound);
+        } else if bound.contains_key(&addr.port) {
+            return Err(IoError {
+                kind: PathAlreadyExists,
+                desc: "Address already in use",
+                detail: Some(addr.to_string())
+            });
+        }
+        let (tx, rx) = channel();
+        bound.insert(addr.port, (addr, tx.clone()));
+        Ok(MemoryListener {
+            addr: addr,
+            tx: tx,
+            rx: rx
+        })
+    }
+
+    fn bind_with_ssl<To: ToSocketAddr>(addr: To, cert: Path, key: Path) -> IoResult<MemoryListener> {
+        let _ = (addr, cert, key);
+        Err(IoError {
+            kind: InvalidInput,
+            desc: "MemoryListener does not support SSL",
+            detail: None
+        })
+    }
+
+    fn socket_name(&mut self) -> IoResult<SocketAddr> {
+        Ok(self.addr)
+    }
+}
+
+/// Accepts the connections made to a `MemoryListener`.
+#[deriving(Clone)]
+pub struct MemoryAcceptor {
+    addr: SocketAddr,
+    tx: Sender<Option<MemoryStream>>,
+    rx: Arc<Mutex<Receiver<Option<MemoryStream>>>>
+}
+
+impl Acceptor<MemoryStream> for MemoryAcceptor {
+    fn accept(&mut self) -> IoResult<MemoryStream> {
+        match self.rx.lock().recv_opt() {
+            Ok(Some(stream)) => Ok(stream),
+            _ => {
+                // wake the next clone waiting to accept, so it ends too
+                let _ = self.tx.send_opt(None);
+                Err(standard_error(EndOfFile))
+            }
+        }
+    }
+}
+
+impl NetworkAcceptor<MemoryStream> for MemoryAcceptor {
+    fn close(&mut self) -> IoResult<()> {
+        registry().lock().remove(&self.addr.port);
+        let _ = self.tx.send_opt(None);
+        Ok(())
+    }
+}
+
+/// A connector making in-memory connections to `MemoryListener`s.
+///
+/// Only the port is used to find the listener, so any host connects to it.
+#[deriving(Copy)]
+pub struct MemoryConnector;
+
+impl NetworkConnector<MemoryStream> for MemoryConnector {
+    fn connect(&mut self, host: &str, port: Port, scheme: &str) -> IoResult<MemoryStream> {
+        let mut bound = registry().lock();
+        let (addr, tx) = match bound.get(&port) {
+            Some(&(addr, ref tx)) => (addr, tx.clone()),
+            None => return Err(IoError {
+                kind: ConnectionRefused,
+                desc: "No MemoryListener is bound to the port",
+                detail: Some(format!("{}://{}:{}", scheme, host, port))
+            })
+        };
+        let local = SocketAddr { ip: Ipv4Addr(127, 0, 0, 1), port: free_port(&*bound) };
+        let (client, server) = MemoryStream::pair(local, addr);
+        match tx.send_opt(Some(server)) {
+            Ok(()) => Ok(client),
+            Err(_) => {
+                bound.remove(&port);
+                Err(standard_error(ConnectionRefused))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Listener, Acceptor, EndOfFile, ConnectionRefused};
+    use std::io::net::ip::Ipv4Addr;
+
+    use client::Client;
+    use net::{Fresh, NetworkAcceptor, NetworkConnector, NetworkListener, NetworkStream};
+    use server::{Request, Response, Server};
+    use super::{MemoryAcceptor, MemoryConnector, MemoryListener, MemoryStream};
+
+    fn echo(mut req: Request, res: Response<Fresh>) {
+        let body = req.read_to_end().unwrap();
+        let mut res = res.start().unwrap();
+        res.write(body[]).unwrap();
+        res.end().unwrap();
+    }
+
+    #[test]
+    fn test_pair() {
+        let (mut a, mut b) = MemoryStream::pair(sock!("127.0.0.1:1"), sock!("127.0.0.1:2"));
+        assert_eq!(b.peer_name().unwrap(), sock!("127.0.0.1:1"));
+        a.write(b"hello ").unwrap();
+        a.clone().write(b"world").unwrap();
+        drop(a);
+        assert_eq!(b.read_to_end().unwrap(), b"hello world".to_vec());
+
+        let (_a, mut b) = MemoryStream::pair(sock!("127.0.0.1:1"), sock!("127.0.0.1:2"));
+        b.clone().close_read().unwrap();
+        assert_eq!(b.read(&mut [0u8, ..4]).unwrap_err().kind, EndOfFile);
+    }
+
+    #[test]
+    fn test_listener() {
+        let mut first = NetworkListener::<MemoryStream, MemoryAcceptor>::bind(("127.0.0.1", 0u16)).unwrap();
+        let addr = first.socket_name().unwrap();
+        assert!(addr.port != 0);
+        assert!(NetworkListener::<MemoryStream, MemoryAcceptor>::bind(addr).is_err());
+
+        let mut acceptor = first.listen().unwrap();
+        let mut client = MemoryConnector.connect("localhost", addr.port, "http").unwrap();
+        let mut server = acceptor.accept().unwrap();
+        client.write(b"ping").unwrap();
+        assert_eq!(server.read_exact(4).unwrap(), b"ping".to_vec());
+
+        acceptor.close().unwrap();
+        assert_eq!(acceptor.accept().unwrap_err().kind, EndOfFile);
+        assert_eq!(MemoryConnector.connect("localhost", addr.port, "http").unwrap_err().kind,
+                   ConnectionRefused);
+    }
+
+    #[test]
+    fn test_server_and_client() {
+        let mut listening = Server::http(Ipv4Addr(127, 0, 0, 1), 0)
+            .listen_network::<_, MemoryStream, MemoryAcceptor, MemoryListener>(echo, 2).unwrap();
+
+        let mut client = Client::with_connector(MemoryConnector);
+        let url = format!("http://127.0.0.1:{}/echo", listening.socket.port);
+        for body in ["one", "two"].iter() {
+            let mut res = client.post(url[]).body(*body).send().unwrap();
+            assert_eq!(res.read_to_string().unwrap()[], *body);
+        }
+        listening.close().unwrap();
+        listening.await();
+    }
+}
@@ -0,0 +1,106 @@
+//! Tools for testing clients and servers built on hyper.
+//!
+//! These are streams and connectors that stand in for the network, so tests
+//! don't depend on a server being up, or on what it answers today, and
+//! `serve`, which runs a handler on a real port for the length of a test.
+use std::io::net::ip::{Ipv4Addr, SocketAddr};
+
+use HttpResult;
+use net::HttpAcceptor;
+use server::{Handler, Listening, Server};
+
+pub use self::memory::{MemoryStream, MemoryListener, MemoryAcceptor, MemoryConnector};
+pub use self::replay::{RecordingStream, RecordingConnector, ReplayStream, ReplayConnector};
+
+pub mod memory;
+pub mod replay;
+
+/// The threads a `TestServer` handles connections with.
+const THREADS: uint = 4;
+
+/// Serve `handler` on a free port of `127.0.0.1`, on background threads.
+///
+/// The server is shut down when the returned `TestServer` is dropped.
+///
+/// ```
+/// # use hyper::server::{Request, Response};
+/// # fn hello(_: Request, res: Response) {
+/// #     let mut res = res.start().unwrap();
+/// #     res.write(b"Hello World!").unwrap();
+/// #     res.end().unwrap();
+/// # }
+/// use hyper::Client;
+/// use hyper::testing::serve;
+///
+/// let server = serve(hello).unwrap();
+/// let mut res = Client::new().get(server.url("/")[]).send().unwrap();
+/// assert_eq!(res.read_to_string().unwrap()[], "Hello World!");
+/// ```
+pub fn serve<H: Handler>(handler: H) -> HttpResult<TestServer> {
+    let listening = try!(Server::http(Ipv4Addr(127, 0, 0, 1), 0).listen_threads(handler, THREADS));
+    Ok(TestServer {
+        addr: listening.socket,
+        listening: listening
+    })
+}
+
+/// A server started by `serve`, which is closed when this is dropped.
+pub struct TestServer {
+    /// The address the server is bound to.
+    pub addr: SocketAddr,
+    listening: Listening<HttpAcceptor>
+}
+
+impl TestServer {
+    /// The `http` URL of `path` on this server.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Err(e) = self.listening.close() {
+            error!("closing test server on {} failed: {}", self.addr, e);
+        }
+        self.listening.await();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::net::tcp::TcpStream;
+
+    use client::Client;
+    use net::Fresh;
+    use server::{Request, Response};
+    use uri::RequestUri::AbsolutePath;
+    use super::serve;
+
+    fn path(req: Request, res: Response<Fresh>) {
+        let path = match req.uri {
+            AbsolutePath(ref path) => path.clone(),
+            _ => String::new()
+        };
+        let mut res = res.start().unwrap();
+        res.write(path.as_bytes()).unwrap();
+        res.end().unwrap();
+    }
+
+    #[test]
+    fn test_serve() {
+        let server = serve(path).unwrap();
+        let addr = server.addr;
+        assert!(addr.port != 0);
+        assert_eq!(server.url("/a")[], format!("http://127.0.0.1:{}/a", addr.port)[]);
+
+        let mut client = Client::new();
+        for p in ["/one", "/two?x=1"].iter() {
+            let mut res = client.get(server.url(*p)[]).send().unwrap();
+            assert_eq!(res.read_to_string().unwrap()[], *p);
+        }
+
+        drop(server);
+        assert!(TcpStream::connect(addr).is_err());
+    }
+}
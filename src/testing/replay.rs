@@ -0,0 +1,346 @@
+//! Recording the bytes of real connections, and replaying them later.
+//!
+//! A `RecordingConnector` wraps the connector a `Client` would use, and
+//! writes everything sent and received on its connections to a file. A
+//! `ReplayConnector` reads that file back, and serves the responses that
+//! were received without touching the network, so a test of a client can
+//! run against the answers of a real server.
+//!
+//! ```no_run
+//! use hyper::Client;
+//! use hyper::net::HttpConnector;
+//! use hyper::testing::{RecordingConnector, ReplayConnector};
+//!
+//! let path = Path::new("tests/fixtures/example.http");
+//!
+//! // once, against the real server
+//! let recorder = RecordingConnector::new(HttpConnector(None), &path).unwrap();
+//! Client::with_connector(recorder).get("http://example.domain/").send().unwrap();
+//!
+//! // and in every test run afterwards
+//! let replay = ReplayConnector::open(&path).unwrap();
+//! let mut res = Client::with_connector(replay).get("http://example.domain/").send().unwrap();
+//! println!("{}", res.read_to_string().unwrap());
+//! ```
+//!
+//! The file is a sequence of records, each a tag byte followed by a
+//! big-endian `u32` length and that many bytes: `+` starts a connection, `<`
+//! holds bytes read from the peer, and `>` bytes written to it.
+use std::fmt;
+use std::io::{File, IoResult, IoError, MemReader, MemWriter, BufReader, EndOfFile, InvalidInput,
+              ConnectionRefused};
+use std::io::net::ip::{Port, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+use net::{NetworkConnector, NetworkStream};
+
+const CONNECTION: u8 = b'+';
+const READ: u8 = b'<';
+const WRITE: u8 = b'>';
+
+fn write_record(log: &Mutex<File>, tag: u8, bytes: &[u8]) -> IoResult<()> {
+    let mut file = log.lock();
+    try!(file.write_u8(tag));
+    try!(file.write_be_u32(bytes.len() as u32));
+    try!(file.write(bytes));
+    file.flush()
+}
+
+/// A stream writing everything read from and written to another stream to
+/// a file, as it passes through.
+///
+/// Clones of the stream record to the same file.
+#[deriving(Clone)]
+pub struct RecordingStream<S> {
+    inner: S,
+    log: Arc<Mutex<File>>
+}
+
+impl<S: NetworkStream> RecordingStream<S> {
+    /// Record `inner` to a new file at `path`, replacing any file there.
+    pub fn create(inner: S, path: &Path) -> IoResult<RecordingStream<S>> {
+        let log = Arc::new(Mutex::new(try!(File::create(path))));
+        try!(write_record(&*log, CONNECTION, &[]));
+        Ok(RecordingStream {
+            inner: inner,
+            log: log
+        })
+    }
+
+    /// Get a reference to the recorded stream.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwrap the recorded stream.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Reader> Reader for RecordingStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        let n = try!(self.inner.read(buf));
+        try!(write_record(&*self.log, READ, buf[..n]));
+        Ok(n)
+    }
+}
+
+impl<S: Writer> Writer for RecordingStream<S> {
+    fn write(&mut self, msg: &[u8]) -> IoResult<()> {
+        try!(self.inner.write(msg));
+        write_record(&*self.log, WRITE, msg)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: NetworkStream + Clone> NetworkStream for RecordingStream<S> {
+    fn peer_name(&mut self) -> IoResult<SocketAddr> {
+        self.inner.peer_name()
+    }
+
+    fn close_read(&mut self) -> IoResult<()> {
+        self.inner.close_read()
+    }
+
+    fn peer_certificate(&self) -> Option<Vec<u8>> {
+        self.inner.peer_certificate()
+    }
+
+    fn set_write_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.inner.set_write_timeout(timeout_ms)
+    }
+}
+
+/// A connector recording every connection another connector makes to one
+/// file, in the order they were made.
+pub struct RecordingConnector<C> {
+    inner: C,
+    log: Arc<Mutex<File>>
+}
+
+impl<C> RecordingConnector<C> {
+    /// Record the connections of `inner` to a new file at `path`, replacing
+    /// any file there.
+    pub fn new(inner: C, path: &Path) -> IoResult<RecordingConnector<C>> {
+        Ok(RecordingConnector {
+            inner: inner,
+            log: Arc::new(Mutex::new(try!(File::create(path))))
+        })
+    }
+}
+
+impl<C: NetworkConnector<S>, S: NetworkStream + Clone> NetworkConnector<RecordingStream<S>> for RecordingConnector<C> {
+    fn connect(&mut self, host: &str, port: Port, scheme: &str) -> IoResult<RecordingStream<S>> {
+        let stream = try!(self.inner.connect(host, port, scheme));
+        try!(write_record(&*self.log, CONNECTION, &[]));
+        Ok(RecordingStream {
+            inner: stream,
+            log: self.log.clone()
+        })
+    }
+}
+
+/// A stream serving the bytes a recorded connection read.
+///
+/// What is written to it is kept, so a test can compare it to what was
+/// written when recording.
+pub struct ReplayStream {
+    read: MemReader,
+    written: MemWriter,
+    recorded: Vec<u8>
+}
+
+impl ReplayStream {
+    /// Replay the first connection recorded in the file at `path`.
+    pub fn open(path: &Path) -> IoResult<ReplayStream> {
+        let data = try!(File::open(path).read_to_end());
+        match try!(parse_recording(data[])).into_iter().next() {
+            Some(stream) => Ok(stream),
+            None => Err(IoError {
+                kind: InvalidInput,
+                desc: "Recording has no connections",
+                detail: None
+            })
+        }
+    }
+
+    /// A stream that reads `read`, and expects to be sent `written`.
+    pub fn new(read: Vec<u8>, written: Vec<u8>) -> ReplayStream {
+        ReplayStream {
+            read: MemReader::new(read),
+            written: MemWriter::new(),
+            recorded: written
+        }
+    }
+
+    /// The bytes written to this stream so far.
+    pub fn written(&self) -> &[u8] {
+        self.written.get_ref()
+    }
+
+    /// The bytes written to the connection when it was recorded.
+    pub fn recorded(&self) -> &[u8] {
+        self.recorded[]
+    }
+}
+
+/// Split a recording into a stream for each of its connections.
+fn parse_recording(data: &[u8]) -> IoResult<Vec<ReplayStream>> {
+    let mut reader = BufReader::new(data);
+    let mut streams = Vec::new();
+    let (mut read, mut written) = (Vec::new(), Vec::new());
+    let mut started = false;
+    loop {
+        let tag = match reader.read_u8() {
+            Ok(tag) => tag,
+            Err(ref e) if e.kind == EndOfFile => break,
+            Err(e) => return Err(e)
+        };
+        let len = try!(reader.read_be_u32()) as uint;
+        let bytes = try!(reader.read_exact(len));
+        match tag {
+            CONNECTION => {
+                if started {
+                    streams.push(ReplayStream::new(read, written));
+                    read = Vec::new();
+                    written = Vec::new();
+                }
+            },
+            READ => read.push_all(bytes[]),
+            WRITE => written.push_all(bytes[]),
+            _ => return Err(IoError {
+                kind: InvalidInput,
+                desc: "Unknown record in recording",
+                detail: Some(format!("tag {}", tag))
+            })
+        }
+        started = true;
+    }
+    if started {
+        streams.push(ReplayStream::new(read, written));
+    }
+    Ok(streams)
+}
+
+impl Clone for ReplayStream {
+    fn clone(&self) -> ReplayStream {
+        ReplayStream {
+            read: MemReader::new(self.read.get_ref().to_vec()),
+            written: MemWriter::from_vec(self.written.get_ref().to_vec()),
+            recorded: self.recorded.clone()
+        }
+    }
+}
+
+impl fmt::Show for ReplayStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ReplayStream {{ read: {}, written: {} }}",
+               self.read.get_ref().len(), self.written.get_ref().len())
+    }
+}
+
+impl Reader for ReplayStream {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        self.read.read(buf)
+    }
+}
+
+impl Writer for ReplayStream {
+    fn write(&mut self, msg: &[u8]) -> IoResult<()> {
+        self.written.write(msg)
+    }
+}
+
+impl NetworkStream for ReplayStream {
+    fn peer_name(&mut self) -> IoResult<SocketAddr> {
+        Ok("127.0.0.1:0".parse().unwrap())
+    }
+}
+
+/// A connector handing out the connections of a recording, one for each
+/// call to `connect`, in the order they were recorded.
+///
+/// Connecting after the last of them fails with `ConnectionRefused`.
+pub struct ReplayConnector {
+    streams: Vec<ReplayStream>
+}
+
+impl ReplayConnector {
+    /// Replay the connections recorded in the file at `path`.
+    pub fn open(path: &Path) -> IoResult<ReplayConnector> {
+        let data = try!(File::open(path).read_to_end());
+        let mut streams = try!(parse_recording(data[]));
+        streams.reverse();
+        Ok(ReplayConnector { streams: streams })
+    }
+}
+
+impl NetworkConnector<ReplayStream> for ReplayConnector {
+    fn connect(&mut self, host: &str, port: Port, scheme: &str) -> IoResult<ReplayStream> {
+        match self.streams.pop() {
+            Some(stream) => Ok(stream),
+            None => Err(IoError {
+                kind: ConnectionRefused,
+                desc: "No more recorded connections",
+                detail: Some(format!("{}://{}:{}", scheme, host, port))
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{TempDir, ConnectionRefused};
+
+    use client::Client;
+    use mock::MockStream;
+    use net::NetworkConnector;
+    use super::{RecordingStream, RecordingConnector, ReplayStream, ReplayConnector};
+
+    mock_connector!(MockRecorded {
+        "http://example.domain" => "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello"
+    });
+
+    #[test]
+    fn test_record_and_replay_stream() {
+        let dir = TempDir::new("hyper-replay").unwrap();
+        let path = dir.path().join("stream.http");
+        {
+            let mut stream = RecordingStream::create(MockStream::with_input(b"pong"), &path).unwrap();
+            stream.write(b"ping").unwrap();
+            assert_eq!(stream.read_to_end().unwrap(), b"pong".to_vec());
+        }
+
+        let mut replay = ReplayStream::open(&path).unwrap();
+        assert_eq!(replay.recorded(), b"ping");
+        assert_eq!(replay.read_to_end().unwrap(), b"pong".to_vec());
+        replay.write(b"ping").unwrap();
+        assert_eq!(replay.written(), replay.recorded());
+    }
+
+    #[test]
+    fn test_record_and_replay_client() {
+        let dir = TempDir::new("hyper-replay").unwrap();
+        let path = dir.path().join("client.http");
+        {
+            let recorder = RecordingConnector::new(MockRecorded, &path).unwrap();
+            let mut client = Client::with_connector(recorder);
+            let mut res = client.get("http://example.domain/").send().unwrap();
+            assert_eq!(res.read_to_string().unwrap()[], "hello");
+        }
+
+        let mut connector = ReplayConnector::open(&path).unwrap();
+        let replay = connector.connect("example.domain", 80, "http").unwrap();
+        assert!(replay.recorded().starts_with(b"GET / HTTP/1.1\r\n"));
+        assert_eq!(connector.connect("example.domain", 80, "http").unwrap_err().kind,
+                   ConnectionRefused);
+
+        let mut client = Client::with_connector(ReplayConnector::open(&path).unwrap());
+        let mut res = client.get("http://example.domain/").send().unwrap();
+        assert_eq!(res.read_to_string().unwrap()[], "hello");
+    }
+}
@@ -1,5 +1,26 @@
 //! HTTP RequestUris
-use url::Url;
+use url::{Url, UrlParser};
+use url::ParseError as UrlError;
+
+use header::Header;
+use header::common::Host;
+use percent_encoding::{form_decode, form_encode};
+
+/// Resolve `reference`, such as the target of a relative `Location` or a
+/// link in a response, against the URL it came from, as RFC 3986 section 5.2
+/// says.
+///
+/// ```
+/// use hyper::Url;
+/// use hyper::uri::resolve;
+///
+/// let base = Url::parse("http://example.domain/a/b?q").unwrap();
+/// assert_eq!(resolve(&base, "../c").unwrap().serialize()[], "http://example.domain/c");
+/// assert_eq!(resolve(&base, "//other.domain/").unwrap().serialize()[], "http://other.domain/");
+/// ```
+pub fn resolve(base: &Url, reference: &str) -> Result<Url, UrlError> {
+    UrlParser::new().base_url(base).parse(reference)
+}
 
 /// The Request-URI of a Request's StartLine.
 ///
@@ -45,3 +66,160 @@ pub enum RequestUri {
     Star,
 }
 
+impl RequestUri {
+    /// The host and port named by an absolute or authority form target.
+    ///
+    /// A server must use this instead of the `Host` header when there is
+    /// one, such as in the requests a proxy receives.
+    pub fn host(&self) -> Option<Host> {
+        match *self {
+            RequestUri::AbsoluteUri(ref url) => Host::from_url(url),
+            RequestUri::Authority(ref authority) => {
+                Header::parse_header([authority.as_bytes().to_vec()][])
+            },
+            RequestUri::AbsolutePath(_) | RequestUri::Star => None
+        }
+    }
+
+    /// The path of an origin or absolute form target, still percent-encoded.
+    pub fn path(&self) -> Option<String> {
+        match *self {
+            RequestUri::AbsolutePath(ref path) => match path.find('?') {
+                Some(i) => Some(path[..i].to_string()),
+                None => Some(path.clone())
+            },
+            RequestUri::AbsoluteUri(ref url) => url.serialize_path(),
+            RequestUri::Authority(_) | RequestUri::Star => None
+        }
+    }
+
+    /// The query of an origin or absolute form target, without the `?`.
+    pub fn query(&self) -> Option<&str> {
+        match *self {
+            RequestUri::AbsolutePath(ref path) => path.find('?').map(|i| path[i + 1..]),
+            RequestUri::AbsoluteUri(ref url) => url.query.as_ref().map(|query| query[]),
+            RequestUri::Authority(_) | RequestUri::Star => None
+        }
+    }
+
+    /// The key and value pairs of the query, decoded.
+    ///
+    /// A `+` is a space, and percent-escapes are decoded as UTF-8. Keys that
+    /// are repeated are all kept, in the order they came in, so `a=1&a=2`
+    /// has the pairs `("a", "1")` and `("a", "2")`.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        match self.query() {
+            Some(query) => form_decode(query.as_bytes()),
+            None => Vec::new()
+        }
+    }
+
+    /// Replace the query with `pairs`, encoded so that `query_pairs` gives
+    /// them back.
+    ///
+    /// Without any pairs, the query is removed. An authority or star target
+    /// has no query, and is left alone.
+    pub fn set_query_pairs(&mut self, pairs: &[(String, String)]) {
+        let query = if pairs.is_empty() {
+            None
+        } else {
+            Some(form_encode(pairs))
+        };
+        match *self {
+            RequestUri::AbsolutePath(ref mut path) => {
+                if let Some(i) = path.find('?') {
+                    path.truncate(i);
+                }
+                if let Some(query) = query {
+                    path.push('?');
+                    path.push_str(query[]);
+                }
+            },
+            RequestUri::AbsoluteUri(ref mut url) => url.query = query,
+            RequestUri::Authority(_) | RequestUri::Star => ()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+    use header::common::Host;
+    use super::resolve;
+    use super::RequestUri::{AbsolutePath, AbsoluteUri, Authority, Star};
+
+    #[test]
+    fn test_host() {
+        let uri = AbsoluteUri(Url::parse("http://example.domain:8080/path").unwrap());
+        assert_eq!(uri.host(), Some(Host::new("example.domain", Some(8080))));
+        let uri = Authority("example.domain:443".to_string());
+        assert_eq!(uri.host(), Some(Host::new("example.domain", Some(443))));
+        assert_eq!(AbsolutePath("/path".to_string()).host(), None);
+        assert_eq!(Star.host(), None);
+    }
+
+    #[test]
+    fn test_resolve() {
+        // the examples of RFC 3986, section 5.4
+        let base = Url::parse("http://a/b/c/d;p?q").unwrap();
+        let examples = [
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g/"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../g", "http://a/g"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("g/../h", "http://a/b/c/h"),
+            ("https://other/", "https://other/")
+        ];
+        for &(reference, expected) in examples.iter() {
+            assert_eq!(resolve(&base, reference).unwrap().serialize()[], expected);
+        }
+    }
+
+    #[test]
+    fn test_query_pairs() {
+        let uri = AbsolutePath("/search?q=two+words&tag=a%26b&tag=%E2%9C%93&empty".to_string());
+        assert_eq!(uri.query(), Some("q=two+words&tag=a%26b&tag=%E2%9C%93&empty"));
+        assert_eq!(uri.query_pairs(), vec![
+            ("q".to_string(), "two words".to_string()),
+            ("tag".to_string(), "a&b".to_string()),
+            ("tag".to_string(), "\u2713".to_string()),
+            ("empty".to_string(), "".to_string())
+        ]);
+
+        let uri = AbsoluteUri(Url::parse("http://example.domain/?a=1&a=2").unwrap());
+        assert_eq!(uri.query_pairs(), vec![("a".to_string(), "1".to_string()),
+                                           ("a".to_string(), "2".to_string())]);
+        assert_eq!(AbsolutePath("/".to_string()).query(), None);
+        assert_eq!(Star.query_pairs(), vec![]);
+    }
+
+    #[test]
+    fn test_set_query_pairs() {
+        let pairs = vec![("q".to_string(), "two words".to_string()),
+                         ("tag".to_string(), "a&b".to_string())];
+        let mut uri = AbsolutePath("/search?old=1".to_string());
+        uri.set_query_pairs(pairs[]);
+        assert_eq!(uri, AbsolutePath("/search?q=two+words&tag=a%26b".to_string()));
+        assert_eq!(uri.query_pairs(), pairs);
+
+        uri.set_query_pairs(&[]);
+        assert_eq!(uri, AbsolutePath("/search".to_string()));
+
+        let mut uri = AbsoluteUri(Url::parse("http://example.domain/search").unwrap());
+        uri.set_query_pairs(pairs[]);
+        assert_eq!(uri.query_pairs(), pairs);
+    }
+}
@@ -2,8 +2,14 @@
 //!
 //! Instead of relying on typo-prone Strings, use expected HTTP versions as
 //! the `HttpVersion` enum.
+//!
+//! What a message may use depends on its version, so code writing messages
+//! asks the version, such as with `supports_chunked`, instead of assuming
+//! HTTP/1.1.
 use std::fmt;
 
+use serialize::{Encodable, Encoder, Decodable, Decoder};
+
 use self::HttpVersion::{Http09, Http10, Http11, Http20};
 
 /// Represents a version of the HTTP spec.
@@ -19,6 +25,40 @@ pub enum HttpVersion {
     Http20
 }
 
+impl HttpVersion {
+    /// Whether a body can be sent with `Transfer-Encoding: chunked`.
+    ///
+    /// HTTP/1.0 has no chunks, and HTTP/2.0 frames bodies itself.
+    pub fn supports_chunked(&self) -> bool {
+        *self == Http11
+    }
+
+    /// Whether trailer headers can follow a body.
+    pub fn supports_trailers(&self) -> bool {
+        match *self {
+            Http11 | Http20 => true,
+            Http09 | Http10 => false
+        }
+    }
+
+    /// Whether informational `1xx` responses, such as `100 Continue`, can be
+    /// sent before the final response.
+    pub fn supports_interim(&self) -> bool {
+        match *self {
+            Http11 | Http20 => true,
+            Http09 | Http10 => false
+        }
+    }
+
+    /// Whether the connection can switch to another protocol with the
+    /// `Upgrade` header.
+    ///
+    /// HTTP/2.0 does not allow `Upgrade`.
+    pub fn supports_upgrade(&self) -> bool {
+        *self == Http11
+    }
+}
+
 impl fmt::Show for HttpVersion {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -29,3 +69,38 @@ impl fmt::Show for HttpVersion {
         }.fmt(fmt)
     }
 }
+
+/// An `HttpVersion` is encoded as it is written in a message, like
+/// `HTTP/1.1`.
+impl<E, S: Encoder<E>> Encodable<S, E> for HttpVersion {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
+        s.emit_str(self.to_string()[])
+    }
+}
+
+impl<E, D: Decoder<E>> Decodable<D, E> for HttpVersion {
+    fn decode(d: &mut D) -> Result<HttpVersion, E> {
+        let version = try!(d.read_str());
+        match version[] {
+            "HTTP/0.9" => Ok(Http09),
+            "HTTP/1.0" => Ok(Http10),
+            "HTTP/1.1" => Ok(Http11),
+            "HTTP/2.0" => Ok(Http20),
+            _ => Err(d.error(format!("invalid HTTP version: {}", version)[]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serialize::json;
+    use super::HttpVersion;
+    use super::HttpVersion::{Http10, Http11};
+
+    #[test]
+    fn test_serialize() {
+        assert_eq!(json::encode(&Http11)[], "\"HTTP/1.1\"");
+        assert_eq!(json::decode::<HttpVersion>("\"HTTP/1.0\"").unwrap(), Http10);
+        assert!(json::decode::<HttpVersion>("\"HTTP/3\"").is_err());
+    }
+}